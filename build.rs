@@ -0,0 +1,47 @@
+// Generates the lookup table behind `MountOption::explain()` and the constants in `options::names` from `data/mount_options.tsv`.
+
+fn main() {
+	let source = std::path::Path::new("data/mount_options.tsv");
+	println!("cargo:rerun-if-changed={}", source.display());
+	let text = std::fs::read_to_string(source).expect("couldn't read data/mount_options.tsv");
+	let mut entries: std::vec::Vec<(&str, &str)> = text.lines()
+		.filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+		.map(|line| {
+			let mut fields = line.splitn(2, '\t');
+			match (fields.next(), fields.next()) {
+				(Some(name), Some(description)) => (name, description),
+				_ => panic!("malformed line in data/mount_options.tsv: {:?}", line)
+			}
+		})
+		.collect();
+	// Sorted so that the table can be binary searched.
+	entries.sort_unstable();
+	for pair in entries.windows(2) {
+		if pair[0].0 == pair[1].0 {
+			panic!("option {} is explained twice in data/mount_options.tsv", pair[0].0);
+		}
+	}
+	let mut table = std::string::String::from("&[\n");
+	for (name, description) in &entries {
+		table.push_str(&format!("\t({:?}, {:?}),\n", name, description));
+	}
+	table.push(']');
+	let out = std::path::PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+	std::fs::write(out.join("option_explanations.rs"), table).expect("couldn't write option_explanations.rs");
+	// One constant per option, named after the option in upper case, e.g. NOSUID for nosuid and NETDEV for _netdev.
+	let mut names = std::string::String::new();
+	let mut constants = std::collections::BTreeSet::new();
+	for (name, description) in &entries {
+		let constant: std::string::String = name.trim_start_matches('_').chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+		if !constants.insert(constant.clone()) {
+			panic!("option {} has the same constant name as another option in data/mount_options.tsv", name);
+		}
+		names.push_str(&format!("/// `{}`: {}\npub const {}: &str = {:?};\n", name, description, constant, name));
+	}
+	names.push_str("\n/// Every option name above, sorted.  This is a curated selection, so an option missing from it may still be valid.\npub const ALL: &[&str] = &[\n");
+	for (name, _) in &entries {
+		names.push_str(&format!("\t{:?},\n", name));
+	}
+	names.push_str("];\n");
+	std::fs::write(out.join("option_names.rs"), names).expect("couldn't write option_names.rs");
+}
@@ -0,0 +1,91 @@
+//! Handles the quirks of mount tables captured from Android devices, e.g. with `adb shell cat /proc/mounts`.
+//!
+//! Android mount tables parse like any other, but differ in a few ways worth knowing about.  Shared storage under `/storage/emulated` is an `sdcardfs` view of `/data/media` (or a FUSE one since Android 11) whose ownership and permissions come from options such as `fsuid=`, `gid=`, and `mask=` rather than from the files underneath.  Data partitions are typically f2fs with option lists far longer than on desktop Linux, so [Limits::UNTRUSTED](crate::limits::Limits::UNTRUSTED) rather than tighter custom limits should be used for untrusted captures.  Labels are set with `context=` options in Android's single-user SELinux policy, e.g. `u:object_r:firmware_file:s0`, which [Mount::selinux()] parses.  Finally, some Android versions deny apps access to `/proc/mounts`, in which case [ProcMounts](crate::source::ProcMounts) falls back to `/proc/self/mounts`.
+
+use super::Mount;
+use super::options::OptionValueError;
+
+/// The ownership and permissions an `sdcardfs` mount imposes on the files it exposes, see [Mount::sdcardfs_options()].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub struct SdcardfsOptions {
+	/// The user that owns the files on the lower filesystem, from `fsuid=`, typically 1023 (media_rw).
+	pub fsuid: std::option::Option<u32>,
+	/// The group that owns the files on the lower filesystem, from `fsgid=`, typically 1023 (media_rw).
+	pub fsgid: std::option::Option<u32>,
+	/// The group apps see as the owner of every file, from `gid=`, e.g. 1015 (sdcard_rw) or 9997 (everybody).
+	pub gid: std::option::Option<u32>,
+	/// The permission bits cleared from every file, from the octal `mask=`.
+	pub mask: std::option::Option<u32>,
+	/// True if each Android user gets a separate view of the storage (`multiuser`).
+	pub multiuser: bool,
+	/// True if files are owned by per-app groups derived from the package that created them (`derive_gid`).
+	pub derive_gid: bool,
+}
+
+impl Mount {
+	/// The ownership and permissions of an `sdcardfs` mount, from its options.  Returns `Ok(None)` for other filesystem types and an error if a numeric option isn't a number.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let mount = nom_tutorial::parsers::parse("/data/media /mnt/runtime/write/emulated sdcardfs rw,nosuid,nodev,noexec,noatime,fsuid=1023,fsgid=1023,gid=9997,multiuser,mask=7,derive_gid,default_normal 0 0", Format::Mounts).unwrap();
+	/// let options = mount.sdcardfs_options().unwrap().unwrap();
+	/// assert_eq!((options.gid, options.mask), (Some(9997), Some(0o7)));
+	/// assert!(options.multiuser && options.derive_gid);
+	/// ```
+	pub fn sdcardfs_options(&self) -> std::result::Result<std::option::Option<SdcardfsOptions>, OptionValueError> {
+		if self.file_system_type != "sdcardfs" {
+			return Ok(None);
+		}
+		let decimal = |name: &str| self.last_option(name).map(|option| option.decimal_value()).transpose();
+		let flag = |name: &str| self.mount_options().any(|option| option.name == name);
+		Ok(Some(SdcardfsOptions {
+			fsuid: decimal("fsuid")?,
+			fsgid: decimal("fsgid")?,
+			gid: decimal("gid")?,
+			mask: self.last_option("mask").map(|option| option.octal_value()).transpose()?,
+			multiuser: flag("multiuser"),
+			derive_gid: flag("derive_gid"),
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::model::Format;
+
+	// Excerpt of `/proc/mounts` from a Pixel running Android 10, as seen by `adb shell`.
+	const CAPTURE: &str = "\
+		/dev/block/dm-0 / ext4 ro,seclabel,nodev,relatime,discard 0 0\n\
+		tmpfs /dev tmpfs rw,seclabel,nosuid,relatime,size=1842364k,nr_inodes=460591,mode=755 0 0\n\
+		/dev/block/bootdevice/by-name/modem_a /vendor/firmware_mnt vfat ro,context=u:object_r:firmware_file:s0,relatime,uid=1000,gid=1000,fmask=0337,dmask=0227,codepage=437,iocharset=iso8859-1,shortname=lower,errors=remount-ro 0 0\n\
+		/dev/block/dm-5 /data f2fs rw,lazytime,seclabel,nosuid,nodev,noatime,background_gc=on,discard,no_heap,user_xattr,inline_xattr,acl,inline_data,inline_dentry,flush_merge,extent_cache,mode=adaptive,active_logs=6,reserve_root=32768,resuid=0,resgid=1065,inlinecrypt,alloc_mode=default,fsync_mode=nobarrier 0 0\n\
+		/data/media /mnt/runtime/default/emulated sdcardfs rw,nosuid,nodev,noexec,noatime,fsuid=1023,fsgid=1023,gid=1015,multiuser,mask=6,derive_gid,default_normal 0 0\n\
+		/data/media /storage/emulated sdcardfs rw,nosuid,nodev,noexec,noatime,fsuid=1023,fsgid=1023,gid=1015,multiuser,mask=6,derive_gid,default_normal 0 0\n";
+
+	fn capture() -> std::vec::Vec<Mount> {
+		crate::snapshot::MountSnapshot::parse(CAPTURE, Format::Mounts).unwrap().into_mounts()
+	}
+
+	// The whole capture parses, including the long f2fs option list, which fits within the untrusted limits.
+	#[test]
+	fn test_android_capture() {
+		let mounts = capture();
+		assert_eq!(mounts.len(), 6);
+		assert_eq!(mounts[3].options.len(), 24);
+		assert!(mounts.iter().all(|mount| mount.options.len() <= crate::limits::Limits::UNTRUSTED.max_options));
+		let firmware = mounts[2].selinux().unwrap();
+		assert_eq!(firmware.context.unwrap().to_string(), "u:object_r:firmware_file:s0");
+		assert!(!firmware.seclabel);
+		assert!(mounts[0].selinux().unwrap().seclabel);
+	}
+
+	#[test]
+	fn test_sdcardfs_options() {
+		let mounts = capture();
+		assert_eq!(mounts[0].sdcardfs_options(), Ok(None));
+		assert_eq!(mounts[5].sdcardfs_options(), Ok(Some(SdcardfsOptions { fsuid: Some(1023), fsgid: Some(1023), gid: Some(1015), mask: Some(0o6), multiuser: true, derive_gid: true })));
+		let bad = Mount { file_system_type: "sdcardfs".to_string(), options: vec!["gid=sdcard_rw".to_string()], ..Default::default() };
+		assert_eq!(bad.sdcardfs_options().unwrap_err().name, "gid");
+	}
+}
@@ -0,0 +1,83 @@
+//! [proptest](https://docs.rs/proptest) support.  Enabled by the `proptest` feature.
+//!
+//! Implements [Arbitrary] for [Mount], generating devices, mount points, and options that are full of characters needing escaping (spaces, tabs, newlines, and backslashes), and provides [roundtrip()] for checking that a serialization layer preserves every `Mount` it is given.
+//! # Examples
+//! ```
+//! # use nom_tutorial::Mount;
+//! # use nom_tutorial::arbitrary::roundtrip;
+//! # use proptest::prelude::*;
+//! proptest!(|(mount in any::<Mount>())| {
+//!     // A trivial "serialization layer" that just clones the mount.
+//!     roundtrip(&mount, |m| m.clone(), |m| Ok::<_, std::convert::Infallible>(m))?;
+//! });
+//! ```
+
+use super::Mount;
+use proptest::prelude::*;
+
+// Characters that `/proc/mounts` must escape, mixed in with ordinary ones.
+const FIELD_REGEX: &str = "[a-zA-Z0-9/._=:@ \\t\\n\\\\\u{e9}\u{4e2d}-]{1,24}";
+// Options additionally may not contain commas because they are comma-separated.
+const OPTION_REGEX: &str = "[a-zA-Z0-9/._=:@ \\t\\n\\\\-]{1,16}";
+// Filesystem types are not unescaped by the parser, so keep them simple.
+const FS_TYPE_REGEX: &str = "[a-z0-9_.]{1,12}";
+
+impl Arbitrary for Mount {
+	type Parameters = ();
+	type Strategy = BoxedStrategy<Mount>;
+
+	fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+		(
+			FIELD_REGEX,
+			FIELD_REGEX,
+			FS_TYPE_REGEX,
+			// The kernel always prints at least one option and an empty option list cannot be parsed.
+			proptest::collection::vec(OPTION_REGEX, 1..8),
+		).prop_map(|(device, mount_point, file_system_type, options)| Mount {
+			device,
+			mount_point,
+			file_system_type,
+			options
+		}).boxed()
+	}
+}
+
+/// Checks that `decode(encode(mount))` reproduces `mount` exactly.  Returns a [TestCaseError] describing the mismatch or decoding error otherwise, so it can be used with `?` inside `proptest!`.
+pub fn roundtrip<T, E, Enc, Dec>(mount: &Mount, encode: Enc, decode: Dec) -> std::result::Result<(), TestCaseError>
+where
+	E: std::fmt::Display,
+	Enc: FnOnce(&Mount) -> T,
+	Dec: FnOnce(T) -> std::result::Result<Mount, E>,
+{
+	match decode(encode(mount)) {
+		Ok(decoded) => {
+			prop_assert_eq!(&decoded, mount);
+			Ok(())
+		},
+		Err(e) => Err(TestCaseError::fail(format!("failed to decode {:?}: {}", mount, e)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Parses a single line with the crate's own parser.
+	fn parse(line: std::string::String) -> std::result::Result<Mount, crate::ParseError> {
+		crate::parsers::parse(&line, crate::model::Format::Mounts)
+	}
+
+	proptest! {
+		#[test]
+		fn test_proc_line_roundtrip(mount in any::<Mount>()) {
+			roundtrip(&mount, Mount::to_proc_line, parse)?;
+		}
+	}
+
+	#[test]
+	fn test_roundtrip_detects_mismatch() {
+		let mount = Mount { device: "a".to_string(), ..Default::default() };
+		assert!(roundtrip(&mount, |m| m.clone(), |_| Ok::<_, std::convert::Infallible>(Mount::default())).is_err());
+		assert!(roundtrip(&mount, |m| m.clone(), |_| Err("nope")).is_err());
+	}
+}
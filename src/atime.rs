@@ -0,0 +1,85 @@
+//! Resolves the access time options of a mount into what actually happens when a file is read.
+//!
+//! Six options interact: `noatime` turns updates off, `relatime` only updates the access time if it is older than the modification or change time or more than a day old, `strictatime` updates it on every access, `atime` and its `no` counterparts undo the others, `nodiratime` turns updates off for directories only, and `lazytime` keeps updates in memory until the inode is written for another reason.  The kernel lists `noatime` or `relatime` in `/proc/mounts` if it is set, and neither for strict updates.
+
+use super::Mount;
+
+/// When the access time of a file is updated, see [Mount::atime_mode()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AtimeMode {
+	/// Access times are never updated (`noatime`).
+	Never,
+	/// Access times are updated if they are older than the modification or change time, or more than a day old (`relatime`).
+	Relative {
+		/// False if directories are excluded (`nodiratime`).
+		directories: bool,
+		/// True if updates are only written to disk along with other changes to the inode (`lazytime`).
+		lazy: bool,
+	},
+	/// Access times are updated on every access (`strictatime`).
+	Strict {
+		/// False if directories are excluded (`nodiratime`).
+		directories: bool,
+		/// True if updates are only written to disk along with other changes to the inode (`lazytime`).
+		lazy: bool,
+	},
+}
+
+impl Mount {
+	/// Resolves the mount's access time options into a single [AtimeMode].  Options are applied in order, so later ones override earlier ones as they do for mount(8).  A mount without any of them has strict access times, which is how the kernel reports them in `/proc/mounts`; note that the kernel defaults to `relatime` when it is asked to mount a filesystem without any access time option.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::atime::AtimeMode;
+	/// let mount = |options: &[&str]| Mount { options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() };
+	/// assert_eq!(mount(&["rw", "relatime"]).atime_mode(), AtimeMode::Relative { directories: true, lazy: false });
+	/// assert_eq!(mount(&["rw", "noatime", "nodiratime"]).atime_mode(), AtimeMode::Never);
+	/// assert_eq!(mount(&["rw", "nodiratime", "lazytime"]).atime_mode(), AtimeMode::Strict { directories: false, lazy: true });
+	/// ```
+	pub fn atime_mode(&self) -> AtimeMode {
+		resolve(self.options.iter().map(|option| &option[..]))
+	}
+}
+
+// Applies the access time options among `options` in order, see [Mount::atime_mode()].
+pub(crate) fn resolve<'a, I: IntoIterator<Item = &'a str>>(options: I) -> AtimeMode {
+	let (mut never, mut relative, mut directories, mut lazy) = (false, false, true, false);
+	for option in options {
+		match option {
+			"noatime" => never = true,
+			"atime" => never = false,
+			"relatime" | "nostrictatime" => relative = true,
+			"norelatime" => relative = false,
+			"strictatime" => (never, relative) = (false, false),
+			"nodiratime" => directories = false,
+			"diratime" => directories = true,
+			"lazytime" => lazy = true,
+			"nolazytime" => lazy = false,
+			_ => ()
+		}
+	}
+	match (never, relative) {
+		(true, _) => AtimeMode::Never,
+		(false, true) => AtimeMode::Relative { directories, lazy },
+		(false, false) => AtimeMode::Strict { directories, lazy },
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures::mount;
+
+	// Later options override earlier ones, `atime` only undoes `noatime`, and `strictatime` undoes both `noatime` and `relatime`.
+	#[test]
+	fn test_atime_mode() {
+		let mode = |options: &[&str]| mount("/dev/sda1", "/", "ext4", options).atime_mode();
+		assert_eq!(mode(&["rw"]), AtimeMode::Strict { directories: true, lazy: false });
+		assert_eq!(mode(&["noatime", "atime", "relatime"]), AtimeMode::Relative { directories: true, lazy: false });
+		assert_eq!(mode(&["relatime", "strictatime"]), AtimeMode::Strict { directories: true, lazy: false });
+		assert_eq!(mode(&["strictatime", "nostrictatime", "lazytime", "nolazytime"]), AtimeMode::Relative { directories: true, lazy: false });
+		assert_eq!(mode(&["nodiratime", "diratime", "relatime", "noatime"]), AtimeMode::Never);
+		assert_eq!(mode(&["noatime", "strictatime"]), AtimeMode::Strict { directories: true, lazy: false });
+		assert_eq!(mode(&["strictatime", "noatime"]), AtimeMode::Never);
+	}
+}
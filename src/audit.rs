@@ -0,0 +1,170 @@
+//! Cross-checks the live mount table against `/etc/fstab` and ranks what is wrong by how urgently it needs attention.
+//!
+//! [fstab_verify](crate::fstab_verify) looks at `fstab` alone.  The findings here need both the file and the mounts, because they are about the system having drifted away from what the file asks for, whether through a manual remount or because the kernel stepped in after an error.
+
+use super::MountEntry;
+use super::fstab::{option_drift, Fstab, OptionDrift};
+
+/// How urgently a [Finding] needs attention.  Severities are ordered, so the most urgent findings sort last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+	/// Worth knowing, but often intended or harmless.
+	Low,
+	/// Something is probably broken and losing data or availability.
+	High,
+}
+
+/// A problem found by [audit()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Finding {
+	/// A filesystem that `fstab` mounts read-write is read-only at the superblock level.  Unless someone remounted it by hand, the filesystem found corruption and remounted itself read-only, e.g. ext4 with `errors=remount-ro`, and writes to it are failing.
+	ErrorRemountedReadOnly {
+		/// The mount point of the filesystem.
+		mount_point: std::string::String,
+		/// The device of the filesystem, as in the mount table.
+		device: std::string::String,
+		/// The value of the `errors=` option in effect, e.g. remount-ro, if the mount has one.
+		errors: std::option::Option<std::string::String>,
+	},
+	/// The options in effect differ from those requested in `fstab`, see [option_drift()].
+	OptionDrift(OptionDrift),
+}
+
+impl Finding {
+	/// How urgently the finding needs attention.
+	pub fn severity(&self) -> Severity {
+		match self {
+			Finding::ErrorRemountedReadOnly { .. } => Severity::High,
+			Finding::OptionDrift(_) => Severity::Low,
+		}
+	}
+}
+
+impl std::fmt::Display for Finding {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Finding::ErrorRemountedReadOnly { mount_point, device, errors } => {
+				write!(f, "{} ({}) is read-write in fstab but its filesystem is read-only", mount_point, device)?;
+				match errors {
+					Some(errors) => write!(f, ", probably remounted after an error (errors={})", errors),
+					None => write!(f, ", probably remounted after an error")
+				}
+			},
+			Finding::OptionDrift(drift) => {
+				write!(f, "{}: options differ from fstab", drift.mount_point)?;
+				if !drift.missing.is_empty() {
+					write!(f, ", missing {}", drift.missing.join(","))?;
+				}
+				if !drift.unexpected.is_empty() {
+					write!(f, ", unexpected {}", drift.unexpected.join(","))?;
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
+// Filesystem types that can only be mounted read-only, so being read-only means nothing.
+const READ_ONLY_TYPES: &[&str] = &["cramfs", "erofs", "iso9660", "squashfs"];
+
+// True if the superblock of `entry` is read-only.  Without mountinfo, the merged options of `/proc/mounts` are all there is to go on.
+fn superblock_read_only(entry: &MountEntry) -> bool {
+	let options = entry.super_options().unwrap_or(&entry.options);
+	options.iter().any(|option| option == "ro")
+}
+
+/// Finds filesystems that `fstab` mounts read-write but that are read-only at the superblock level, the sign of a filesystem that remounted itself read-only after an error.  A read-only bind mount of a read-write filesystem doesn't count, since only its mount is read-only, but telling the two apart needs entries read from mountinfo.  Where several filesystems are mounted at the same mount point, the one mounted last is checked.
+/// # Examples
+/// ```
+/// # use nom_tutorial::audit::{error_remounts, Finding};
+/// # use nom_tutorial::fstab::Fstab;
+/// # use nom_tutorial::model::Format;
+/// let fstab = Fstab::parse("/dev/sda2 /srv ext4 defaults,errors=remount-ro 0 2\n").unwrap();
+/// let line = "36 1 8:2 / /srv rw,relatime shared:1 - ext4 /dev/sda2 ro,errors=remount-ro";
+/// let live = [nom_tutorial::parsers::parse_entry(line, Format::MountInfo).unwrap()];
+/// assert!(matches!(&error_remounts(&fstab, &live)[..], [Finding::ErrorRemountedReadOnly { errors: Some(errors), .. }] if errors == "remount-ro"));
+/// ```
+pub fn error_remounts<I>(fstab: &Fstab, live: I) -> std::vec::Vec<Finding>
+where
+	I: IntoIterator,
+	I::Item: std::borrow::Borrow<MountEntry>,
+{
+	let live: std::vec::Vec<I::Item> = live.into_iter().collect();
+	fstab.entries()
+		.filter(|entry| !entry.options.iter().any(|option| option == "ro") && !READ_ONLY_TYPES.contains(&&entry.file_system_type[..]))
+		.filter_map(|entry| live.iter().map(std::borrow::Borrow::<MountEntry>::borrow).filter(|mount| mount.mount_point == entry.mount_point).last())
+		.filter(|mount| superblock_read_only(mount))
+		.map(|mount| Finding::ErrorRemountedReadOnly {
+			mount_point: mount.mount_point.clone(),
+			device: mount.device.clone(),
+			errors: mount.mount_options().find(|option| option.name == "errors").and_then(|option| option.value).map(str::to_string)
+		})
+		.collect()
+}
+
+/// Runs every check of this module, returning the findings with the most urgent first and in `fstab` order within each severity.  A mount point that remounted itself read-only is only reported as such, not also as option drift.
+/// # Examples
+/// ```
+/// # use nom_tutorial::audit::{audit, Severity};
+/// # use nom_tutorial::fstab::Fstab;
+/// # use nom_tutorial::{Mount, MountEntry};
+/// let fstab = Fstab::parse("/dev/sda1 / ext4 defaults,noatime 0 1\n/dev/sda2 /srv ext4 defaults 0 2\n").unwrap();
+/// let mount = |mount_point: &str, options: &[&str]| MountEntry::from(Mount { mount_point: mount_point.to_string(), options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() });
+/// let findings = audit(&fstab, &[mount("/", &["rw", "relatime"]), mount("/srv", &["ro", "relatime"])]);
+/// assert_eq!(findings.iter().map(|finding| finding.severity()).collect::<Vec<_>>(), [Severity::High, Severity::Low]);
+/// ```
+pub fn audit<I>(fstab: &Fstab, live: I) -> std::vec::Vec<Finding>
+where
+	I: IntoIterator,
+	I::Item: std::borrow::Borrow<MountEntry>,
+{
+	let live: std::vec::Vec<I::Item> = live.into_iter().collect();
+	let live: std::vec::Vec<&MountEntry> = live.iter().map(std::borrow::Borrow::borrow).collect();
+	let mut findings = error_remounts(fstab, live.iter().copied());
+	let remounted: std::vec::Vec<std::string::String> = findings.iter().filter_map(|finding| match finding {
+		Finding::ErrorRemountedReadOnly { mount_point, .. } => Some(mount_point.clone()),
+		_ => None
+	}).collect();
+	findings.extend(option_drift(fstab, live.iter().map(|entry| &entry.mount))
+		.into_iter()
+		.filter(|drift| !remounted.contains(&drift.mount_point))
+		.map(Finding::OptionDrift));
+	// The sort is stable, so findings of equal severity stay in fstab order.
+	findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity()));
+	findings
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Only a read-only superblock counts, not a read-only mount of a read-write one, and entries that ask for ro are fine.
+	#[test]
+	fn test_error_remounts() {
+		let fstab = Fstab::parse("/dev/sda1 / ext4 errors=remount-ro 0 1\n\
+			/dev/sda2 /srv ext4 defaults 0 2\n\
+			/srv/pub /export none bind,ro 0 0\n\
+			/dev/sr0 /media/cdrom iso9660 user,noauto 0 0\n\
+			/dev/sda3 /archive xfs ro 0 2\n").unwrap();
+		let live: std::vec::Vec<MountEntry> = [
+			"22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 ro,errors=remount-ro",
+			"23 22 8:2 / /srv ro,relatime shared:2 - ext4 /dev/sda2 rw",
+			"24 22 8:2 /pub /export ro,relatime shared:2 - ext4 /dev/sda2 rw",
+			"25 22 11:0 / /media/cdrom ro,relatime - iso9660 /dev/sr0 ro",
+			"26 22 8:3 / /archive ro,relatime - xfs /dev/sda3 ro",
+		].iter().map(|line| crate::parsers::parse_entry(line, crate::model::Format::MountInfo).unwrap()).collect();
+		assert_eq!(error_remounts(&fstab, &live), vec![Finding::ErrorRemountedReadOnly {
+			mount_point: "/".to_string(),
+			device: "/dev/sda1".to_string(),
+			errors: Some("remount-ro".to_string())
+		}]);
+	}
+
+	#[test]
+	fn test_display() {
+		let finding = Finding::ErrorRemountedReadOnly { mount_point: "/srv".to_string(), device: "/dev/sda2".to_string(), errors: None };
+		assert_eq!(finding.to_string(), "/srv (/dev/sda2) is read-write in fstab but its filesystem is read-only, probably remounted after an error");
+		let drift = OptionDrift { mount_point: "/".to_string(), missing: vec!["noatime".to_string()], unexpected: vec![] };
+		assert_eq!(Finding::OptionDrift(drift).to_string(), "/: options differ from fstab, missing noatime");
+	}
+}
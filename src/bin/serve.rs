@@ -0,0 +1,198 @@
+//! HTTP server exposing the mount table as JSON, so that fleet tooling can scrape the mount state of many machines.
+//!
+//! Usage: `serve [ADDRESS]`, listening on 127.0.0.1:9850 by default.
+//!
+//! - `GET /mounts` returns `{"taken_at":SECONDS,"mounts":[...]}`, where each mount is an object as written by [write_json()](nom_tutorial::jsonl::write_json).  `taken_at` is when the mount table was last seen to change, in seconds since the Unix epoch, not when it was last polled: the table is read every second but a snapshot is only recorded when it differs from the previous one.
+//! - `GET /mounts/diff?since=SECONDS` returns `{"since":SECONDS,"taken_at":SECONDS,"events":[...]}`, the changes between the mount table as it was at `since` and now, where `taken_at` is again the time of the last change.  Each event is `{"event":"added","mount":{...}}`, `{"event":"removed","mount":{...}}`, or `{"event":"changed","old":{...},"new":{...}}`.  Answers 410 Gone if `since` is older than the history kept by the server.
+
+extern crate nom_tutorial;
+use nom_tutorial::BoxError;
+use nom_tutorial::history::SnapshotHistory;
+use nom_tutorial::jsonl::write_json;
+use nom_tutorial::snapshot::MountSnapshot;
+use nom_tutorial::watch::MountEvent;
+
+/// Where to listen if no address is given.
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9850";
+
+/// How often the mount table is re-read.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How far back `/mounts/diff` can look.
+const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// The most snapshots kept.  A snapshot is only kept when the mount table changes, so this bounds memory on machines where it changes constantly.
+const CAPACITY: usize = 4096;
+
+/// Formats a time as seconds since the Unix epoch, with nanosecond precision.
+fn seconds(time: std::time::SystemTime) -> std::string::String {
+	let since_epoch = time.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+	format!("{}.{:09}", since_epoch.as_secs(), since_epoch.subsec_nanos())
+}
+
+/// Parses the `since` parameter of a query string, in seconds since the Unix epoch.
+fn parse_since(query: &str) -> std::result::Result<std::time::SystemTime, BoxError> {
+	let value = query.split('&').find_map(|pair| pair.strip_prefix("since=")).ok_or("missing parameter: since")?;
+	let since: f64 = value.parse().map_err(|_| format!("since must be a number of seconds, found {:?}", value))?;
+	std::time::Duration::try_from_secs_f64(since).ok()
+		.and_then(|since| std::time::UNIX_EPOCH.checked_add(since))
+		.ok_or_else(|| format!("since must be a non-negative number of seconds that can be represented as a time, found {:?}", value).into())
+}
+
+/// Renders the body of `GET /mounts`.
+fn render_mounts(snapshot: &MountSnapshot) -> std::result::Result<std::vec::Vec<u8>, BoxError> {
+	let mut body = format!("{{\"taken_at\":{},\"mounts\":[", seconds(snapshot.taken_at())).into_bytes();
+	for (i, mount) in snapshot.iter().enumerate() {
+		if i > 0 {
+			body.push(b',');
+		}
+		write_json(mount, &mut body)?;
+	}
+	body.extend_from_slice(b"]}");
+	Ok(body)
+}
+
+/// Renders the body of `GET /mounts/diff`.
+fn render_diff(since: std::time::SystemTime, latest: &MountSnapshot, events: &[MountEvent]) -> std::result::Result<std::vec::Vec<u8>, BoxError> {
+	let mut body = format!("{{\"since\":{},\"taken_at\":{},\"events\":[", seconds(since), seconds(latest.taken_at())).into_bytes();
+	for (i, event) in events.iter().enumerate() {
+		if i > 0 {
+			body.push(b',');
+		}
+		match event {
+			MountEvent::Added(mount) | MountEvent::Removed(mount) => {
+				let name = if matches!(event, MountEvent::Added(_)) { "added" } else { "removed" };
+				body.extend_from_slice(format!("{{\"event\":\"{}\",\"mount\":", name).as_bytes());
+				write_json(mount, &mut body)?;
+			},
+			MountEvent::Changed { old, new } => {
+				body.extend_from_slice(b"{\"event\":\"changed\",\"old\":");
+				write_json(old, &mut body)?;
+				body.extend_from_slice(b",\"new\":");
+				write_json(new, &mut body)?;
+			}
+		}
+		body.push(b'}');
+	}
+	body.extend_from_slice(b"]}");
+	Ok(body)
+}
+
+/// Answers a request with a status code and a JSON or plain text body.
+fn respond(request: tiny_http::Request, status: u16, body: std::result::Result<std::vec::Vec<u8>, BoxError>) -> std::result::Result<(), BoxError> {
+	let (status, content_type, body) = match body {
+		Ok(body) => (status, "application/json", body),
+		Err(e) => (if status == 200 { 500 } else { status }, "text/plain; charset=utf-8", format!("{}\n", e).into_bytes())
+	};
+	let header = tiny_http::Header::from_bytes("Content-Type", content_type).map_err(|_| "invalid header")?;
+	request.respond(tiny_http::Response::from_data(body).with_status_code(status).with_header(header))?;
+	Ok(())
+}
+
+/// Works out the status code and body of the answer to `GET url` from the history.
+fn route(url: &str, history: &SnapshotHistory) -> (u16, std::result::Result<std::vec::Vec<u8>, BoxError>) {
+	let (path, query) = url.split_once('?').unwrap_or((url, ""));
+	let latest = match history.latest() {
+		Some(latest) => latest,
+		None => return (503, Err("no snapshot taken yet".into()))
+	};
+	match path {
+		"/mounts" => (200, render_mounts(latest)),
+		"/mounts/diff" => {
+			let since = match parse_since(query) {
+				Ok(since) => since,
+				Err(e) => return (400, Err(e))
+			};
+			match history.diff(since, latest.taken_at()) {
+				Some(events) => (200, render_diff(since, latest, &events)),
+				None => (410, Err(format!("no history as far back as {}", seconds(since)).into()))
+			}
+		},
+		_ => (404, Err(format!("not found: {}", path).into()))
+	}
+}
+
+/// Handles a single request against the history.  The history is only locked while the body is rendered, not while it is sent to a possibly slow client.
+fn handle(request: tiny_http::Request, history: &std::sync::Mutex<SnapshotHistory>) -> std::result::Result<(), BoxError> {
+	if *request.method() != tiny_http::Method::Get {
+		return respond(request, 405, Err("only GET is supported".into()));
+	}
+	let (status, body) = route(request.url(), &history.lock().unwrap_or_else(std::sync::PoisonError::into_inner));
+	respond(request, status, body)
+}
+
+/// Keeps `history` up to date, adding a snapshot whenever the mount table changes.
+fn record(history: std::sync::Arc<std::sync::Mutex<SnapshotHistory>>) {
+	loop {
+		std::thread::sleep(POLL_INTERVAL);
+		match nom_tutorial::snapshot() {
+			Ok(snapshot) => {
+				// A panic elsewhere can't leave the history half-updated, so keep recording rather than serving stale data forever.
+				let mut history = history.lock().unwrap_or_else(|poisoned| {
+					eprintln!("history lock poisoned, recovering");
+					poisoned.into_inner()
+				});
+				if history.latest().map(MountSnapshot::mounts) != Some(snapshot.mounts()) {
+					history.push(snapshot);
+				}
+			},
+			Err(e) => eprintln!("error reading the mount table: {}", e)
+		}
+	}
+}
+
+/// Serves the mount table over HTTP until interrupted.
+fn main() -> std::result::Result<(), BoxError> {
+	let mut args = std::env::args().skip(1);
+	let address = match (args.next(), args.next()) {
+		(address, None) => address.unwrap_or_else(|| DEFAULT_ADDRESS.to_string()),
+		(_, Some(arg)) => return Err(format!("unrecognized argument: {}", arg).into())
+	};
+	let mut history = SnapshotHistory::new(CAPACITY).with_max_age(MAX_AGE);
+	history.push(nom_tutorial::snapshot()?);
+	let history = std::sync::Arc::new(std::sync::Mutex::new(history));
+	let recorder = std::sync::Arc::clone(&history);
+	std::thread::spawn(move || record(recorder));
+	let server = tiny_http::Server::http(&address)?;
+	eprintln!("listening on http://{}", address);
+	for request in server.incoming_requests() {
+		if let Err(e) = handle(request, &history) {
+			eprintln!("error answering request: {}", e);
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_since() {
+		assert_eq!(parse_since("since=1.5").unwrap(), std::time::UNIX_EPOCH + std::time::Duration::from_millis(1500));
+		assert_eq!(parse_since("a=b&since=0").unwrap(), std::time::UNIX_EPOCH);
+		for query in ["", "since=", "since=soon", "since=-1", "since=NaN", "since=inf", "since=1e19", "since=1e20"] {
+			assert!(parse_since(query).is_err(), "{}", query);
+		}
+	}
+
+	// Every path answers with the right status, and an empty history with 503.
+	#[test]
+	fn test_route() {
+		let minutes = |n: u64| std::time::UNIX_EPOCH + std::time::Duration::from_secs(60 * n);
+		let mut history = SnapshotHistory::new(10);
+		assert_eq!(route("/mounts", &history).0, 503);
+		history.push(MountSnapshot::with_time(vec![], minutes(1)));
+		history.push(MountSnapshot::with_time(vec![nom_tutorial::Mount { device: "/dev/sda1".to_string(), mount_point: "/".to_string(), ..Default::default() }], minutes(2)));
+		let (status, body) = route("/mounts", &history);
+		assert_eq!(status, 200);
+		assert!(std::string::String::from_utf8(body.unwrap()).unwrap().starts_with("{\"taken_at\":120.000000000,\"mounts\":[{"));
+		let (status, body) = route("/mounts/diff?since=60", &history);
+		assert_eq!(status, 200);
+		assert!(std::string::String::from_utf8(body.unwrap()).unwrap().contains("\"event\":\"added\""));
+		assert_eq!(route("/mounts/diff?since=0", &history).0, 410);
+		assert_eq!(route("/mounts/diff?since=1e20", &history).0, 400);
+		assert_eq!(route("/mounts/diff", &history).0, 400);
+		assert_eq!(route("/elsewhere", &history).0, 404);
+	}
+}
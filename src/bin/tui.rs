@@ -0,0 +1,193 @@
+//! Interactive mount browser.  Lists the mounted filesystems, updates the list as filesystems are mounted and unmounted, filters by type or option, and shows the details of the selected mount.
+//!
+//! Keys: Up/Down or j/k to move, Enter to show details, / to filter, Esc to close details or clear the filter, q to quit.
+
+extern crate nom_tutorial;
+use nom_tutorial::{BoxError, Mount};
+use nom_tutorial::watch::{MountEvent, Watcher};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+
+/// How often the watcher re-reads the mount table.
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How long to wait for a key press before checking the watcher again.
+const INPUT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// State of the browser.
+struct App {
+	/// Every mount, in mount table order.
+	mounts: std::vec::Vec<Mount>,
+	/// Only mounts whose filesystem type or one of whose options contains this are listed.
+	filter: std::string::String,
+	/// True while the user is typing the filter.
+	editing_filter: bool,
+	/// True while the details of the selected mount are shown.
+	details: bool,
+	/// Selection within the filtered list.
+	list: ListState,
+	/// The last error reported by the watcher, shown in the status line.
+	error: std::option::Option<std::string::String>,
+}
+
+impl App {
+	fn new(mounts: std::vec::Vec<Mount>) -> App {
+		let mut list = ListState::default();
+		list.select(Some(0));
+		App { mounts, filter: std::string::String::new(), editing_filter: false, details: false, list, error: None }
+	}
+
+	/// The mounts that pass the filter.
+	fn visible(&self) -> std::vec::Vec<&Mount> {
+		self.mounts.iter().filter(|mount| {
+			self.filter.is_empty()
+				|| mount.file_system_type.contains(&self.filter[..])
+				|| mount.options.iter().any(|option| option.contains(&self.filter[..]))
+		}).collect()
+	}
+
+	/// Updates the list with a change reported by the watcher.
+	fn apply(&mut self, event: MountEvent) {
+		match event {
+			MountEvent::Added(entry) => self.mounts.push(entry.mount),
+			MountEvent::Removed(entry) => if let Some(index) = self.mounts.iter().position(|m| *m == entry.mount) {
+				self.mounts.remove(index);
+			},
+			MountEvent::Changed { old, new } => if let Some(m) = self.mounts.iter_mut().find(|m| **m == old.mount) {
+				*m = new.mount;
+			}
+		}
+	}
+
+	/// Moves the selection by `offset`, staying within the filtered list.
+	fn select(&mut self, offset: isize) {
+		let count = self.visible().len();
+		let current = self.list.selected().unwrap_or(0) as isize;
+		self.list.select(Some((current + offset).clamp(0, count.saturating_sub(1) as isize) as usize));
+	}
+
+	/// Handles a key press.  Returns false when it is time to quit.
+	fn key(&mut self, code: KeyCode) -> bool {
+		if self.editing_filter {
+			match code {
+				KeyCode::Enter => self.editing_filter = false,
+				KeyCode::Esc => { self.editing_filter = false; self.filter.clear(); },
+				KeyCode::Backspace => { self.filter.pop(); },
+				KeyCode::Char(c) => self.filter.push(c),
+				_ => ()
+			}
+			self.list.select(Some(0));
+			return true;
+		}
+		match code {
+			KeyCode::Char('q') => return false,
+			KeyCode::Down | KeyCode::Char('j') => self.select(1),
+			KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+			KeyCode::Enter => self.details = !self.visible().is_empty(),
+			KeyCode::Char('/') => { self.editing_filter = true; self.details = false; },
+			KeyCode::Esc if self.details => self.details = false,
+			KeyCode::Esc => { self.filter.clear(); self.list.select(Some(0)); },
+			_ => ()
+		}
+		true
+	}
+
+	fn draw(&mut self, frame: &mut ratatui::Frame) {
+		let [list_area, status_area] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+		let visible = self.visible();
+		let items: std::vec::Vec<ListItem> = visible.iter()
+			.map(|mount| ListItem::new(format!("{:<30} {:<10} {}", mount.mount_point, mount.file_system_type, mount.device)))
+			.collect();
+		let title = format!(" Mounts ({} of {}) ", visible.len(), self.mounts.len());
+		let selected = self.list.selected().and_then(|index| visible.get(index)).map(|mount| (*mount).clone());
+		let list = List::new(items)
+			.block(Block::default().borders(Borders::ALL).title(title))
+			.highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+		frame.render_stateful_widget(list, list_area, &mut self.list);
+
+		let status = if self.editing_filter {
+			format!("Filter by type or option: {}_", self.filter)
+		} else if let Some(error) = &self.error {
+			format!("Error: {}", error)
+		} else if !self.filter.is_empty() {
+			format!("Filter: {}  (Esc to clear)  q quit", self.filter)
+		} else {
+			"Enter details  / filter  q quit".to_string()
+		};
+		frame.render_widget(Paragraph::new(status), status_area);
+
+		if let (true, Some(mount)) = (self.details, selected) {
+			let area = list_area.inner(ratatui::layout::Margin { horizontal: 4, vertical: 2 });
+			frame.render_widget(Clear, area);
+			let details = Paragraph::new(detail_lines(&mount))
+				.block(Block::default().borders(Borders::ALL).title(format!(" {} ", mount.mount_point)))
+				.wrap(Wrap { trim: false });
+			frame.render_widget(details, area);
+		}
+	}
+}
+
+/// Describes a mount in full for the details popup.
+fn detail_lines(mount: &Mount) -> std::vec::Vec<Line<'static>> {
+	let mut lines = vec![
+		Line::from(format!("Device:      {}", mount.device)),
+		Line::from(format!("Type:        {}", mount.file_system_type)),
+	];
+	match mount.usage() {
+		Ok(usage) => lines.push(Line::from(format!("Usage:       {} of {} used, {} available", size(usage.used_bytes()), size(usage.total_bytes), size(usage.available_bytes)))),
+		Err(e) => lines.push(Line::from(format!("Usage:       unavailable ({})", e)))
+	}
+	lines.push(Line::from(""));
+	lines.push(Line::from("Options:"));
+	for option in mount.mount_options() {
+		lines.push(Line::from(match option.description() {
+			Some(description) => format!("  {:<24} {}", option.to_string(), description),
+			None => format!("  {}", option)
+		}));
+	}
+	lines
+}
+
+/// Formats a number of bytes with a binary unit.
+fn size(bytes: u64) -> std::string::String {
+	let units = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+	let mut value = bytes as f64;
+	let mut unit = 0;
+	while value >= 1024.0 && unit < units.len() - 1 {
+		value /= 1024.0;
+		unit += 1;
+	}
+	format!("{:.1} {}", value, units[unit])
+}
+
+/// Runs the browser until the user quits.
+fn run(terminal: &mut ratatui::DefaultTerminal, app: &mut App, watcher: &Watcher) -> std::result::Result<(), BoxError> {
+	loop {
+		while let Some(event) = watcher.try_recv() {
+			match event {
+				Ok(event) => app.apply(event.event),
+				Err(e) => app.error = Some(e.to_string())
+			}
+		}
+		terminal.draw(|frame| app.draw(frame))?;
+		if event::poll(INPUT_TIMEOUT)? {
+			if let Event::Key(key) = event::read()? {
+				if key.kind == KeyEventKind::Press && !app.key(key.code) {
+					return Ok(());
+				}
+			}
+		}
+	}
+}
+
+fn main() -> std::result::Result<(), BoxError> {
+	let mut app = App::new(nom_tutorial::snapshot()?.into_mounts());
+	let watcher = Watcher::new(WATCH_INTERVAL)?;
+	let mut terminal = ratatui::init();
+	let result = run(&mut terminal, &mut app, &watcher);
+	ratatui::restore();
+	result
+}
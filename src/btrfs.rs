@@ -0,0 +1,157 @@
+//! Identifies which btrfs subvolume a mount exposes.
+//!
+//! A btrfs filesystem can be mounted several times from the same device, once per subvolume, e.g. `/@` on `/` and `/@home` on `/home`.  The kernel records the subvolume of each mount in its `subvol=` and `subvolid=` options, escaping characters such as commas and spaces in the path.
+//!
+//! A btrfs filesystem can also span several devices, of which the mount table only shows one.  The others are listed in sysfs, in `/sys/fs/btrfs/<uuid>/devices`, see [Mount::all_devices()].
+
+use super::Mount;
+use super::options::OptionValueError;
+
+/// The subvolume of a btrfs mount, see [Mount::btrfs_subvol()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BtrfsSubvol<'a> {
+	/// The path of the subvolume from the root of the filesystem, unescaped, e.g. /@home
+	pub path: std::option::Option<&'a str>,
+	/// The ID of the subvolume, e.g. 257.  The top-level subvolume is always 5.
+	pub id: std::option::Option<u64>,
+}
+
+impl Mount {
+	/// The subvolume of a btrfs mount, from its `subvol=` and `subvolid=` options.  Returns `Ok(None)` for other filesystem types or if neither option is set, and an error if the subvolume ID isn't a number.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let mount = nom_tutorial::parsers::parse("/dev/nvme0n1p3 /home btrfs rw,relatime,subvolid=257,subvol=/@home\\054old 0 0", Format::Mounts).unwrap();
+	/// let subvol = mount.btrfs_subvol().unwrap().unwrap();
+	/// assert_eq!((subvol.path, subvol.id), (Some("/@home,old"), Some(257)));
+	/// ```
+	pub fn btrfs_subvol(&self) -> std::result::Result<std::option::Option<BtrfsSubvol<'_>>, OptionValueError> {
+		if self.file_system_type != "btrfs" {
+			return Ok(None);
+		}
+		let path = self.mount_options().filter(|option| option.name == "subvol").last().and_then(|option| option.value);
+		let id = match self.mount_options().filter(|option| option.name == "subvolid").last() {
+			Some(option) => Some(option.value.unwrap_or_default().parse::<u64>().map_err(|e| OptionValueError::new(&option, Some(e)))?),
+			None => None
+		};
+		Ok(if path.is_none() && id.is_none() { None } else { Some(BtrfsSubvol { path, id }) })
+	}
+}
+
+/// A btrfs filesystem and the devices it spans, as listed in sysfs.
+#[cfg(feature = "io")]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BtrfsFilesystem {
+	/// The UUID of the filesystem, e.g. 1c3f4a1e-8b9e-4a3c-9d2f-6f0e5a7b8c9d
+	pub uuid: std::string::String,
+	/// The kernel names of the member devices in alphabetical order, e.g. ["sda2", "sdb2"]
+	pub devices: std::vec::Vec<std::string::String>,
+}
+
+#[cfg(feature = "io")]
+impl BtrfsFilesystem {
+	/// Finds the btrfs filesystem that the device with the given kernel name, e.g. sda2 or dm-0, belongs to.  Returns `None` if no mounted btrfs filesystem contains the device.
+	pub fn find(device: &str) -> std::result::Result<std::option::Option<BtrfsFilesystem>, std::io::Error> {
+		BtrfsFilesystem::find_from(std::path::Path::new(crate::sysfs::SYSFS_PATH), device)
+	}
+
+	/// Like [BtrfsFilesystem::find()], but with sysfs mounted at `sysfs` instead of `/sys`, e.g. in a container or a saved copy.
+	pub fn find_from(sysfs: &std::path::Path, device: &str) -> std::result::Result<std::option::Option<BtrfsFilesystem>, std::io::Error> {
+		let filesystems = match std::fs::read_dir(sysfs.join("fs/btrfs")) {
+			Ok(filesystems) => filesystems,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e)
+		};
+		for filesystem in filesystems {
+			let filesystem = filesystem?;
+			// Besides a directory per filesystem there are entries such as the `features` directory, none of which have a `devices` directory.
+			if !filesystem.file_type()?.is_dir() {
+				continue;
+			}
+			let devices = match std::fs::read_dir(filesystem.path().join("devices")) {
+				Ok(devices) => devices,
+				Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+				Err(e) => return Err(e)
+			};
+			let mut devices = devices.map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned())).collect::<std::result::Result<std::vec::Vec<_>, _>>()?;
+			if devices.iter().any(|name| name == device) {
+				devices.sort();
+				return Ok(Some(BtrfsFilesystem { uuid: filesystem.file_name().to_string_lossy().into_owned(), devices }));
+			}
+		}
+		Ok(None)
+	}
+}
+
+#[cfg(feature = "io")]
+impl Mount {
+	/// Every device the mounted filesystem spans.  For btrfs these are read from sysfs, see [BtrfsFilesystem::find()], and returned as paths such as /dev/sdb2, while for other filesystems, or if sysfs doesn't know the device, this is just [Mount::device].
+	/// # Examples
+	/// ```no_run
+	/// for mount in nom_tutorial::mounts().unwrap() {
+	///     let mount = mount.unwrap();
+	///     println!("{} is on {}", mount.mount_point, mount.all_devices().unwrap().join(", "));
+	/// }
+	/// ```
+	pub fn all_devices(&self) -> std::result::Result<std::vec::Vec<std::string::String>, std::io::Error> {
+		if self.file_system_type == "btrfs" {
+			// Resolve links such as /dev/disk/by-uuid/... or /dev/mapper/... to the kernel's name for the device.
+			let name = match std::fs::canonicalize(&self.device) {
+				Ok(path) => path.file_name().map(|name| name.to_string_lossy().into_owned()),
+				Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+				Err(e) => return Err(e)
+			};
+			if let Some(filesystem) = name.map(|name| BtrfsFilesystem::find(&name)).transpose()?.flatten() {
+				return Ok(filesystem.devices.iter().map(|device| format!("/dev/{}", device)).collect());
+			}
+		}
+		Ok(vec![self.device.clone()])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Two subvolumes of the same device are told apart, and other filesystems have none.
+	#[test]
+	fn test_btrfs_subvol() {
+		let mount = |fs_type: &str, options: &[&str]| Mount { device: "/dev/sda2".to_string(), file_system_type: fs_type.to_string(), options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() };
+		assert_eq!(mount("btrfs", &["subvolid=256", "subvol=/@"]).btrfs_subvol(), Ok(Some(BtrfsSubvol { path: Some("/@"), id: Some(256) })));
+		assert_eq!(mount("btrfs", &["subvolid=5"]).btrfs_subvol(), Ok(Some(BtrfsSubvol { path: None, id: Some(5) })));
+		assert_eq!(mount("btrfs", &["rw"]).btrfs_subvol(), Ok(None));
+		assert_eq!(mount("ext4", &["subvol=/@"]).btrfs_subvol(), Ok(None));
+		assert_eq!(mount("btrfs", &["subvolid=top"]).btrfs_subvol().unwrap_err().name, "subvolid");
+	}
+
+	// Commas in a subvolume path survive a round trip through the mount table format.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_subvol_comma_roundtrip() {
+		let mount = Mount { device: "/dev/sda2".to_string(), mount_point: "/a".to_string(), file_system_type: "btrfs".to_string(), options: vec!["subvol=/a,b".to_string()] };
+		let parsed = crate::Mounts::from_source(&crate::source::MockSource::new(vec![mount.clone()])).unwrap().into_iter().next().unwrap().unwrap();
+		assert_eq!(parsed, mount);
+	}
+
+	// Every member of a multi-device filesystem is found from any one of them.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_find_from() {
+		let sysfs = std::env::temp_dir().join(format!("nom-tutorial-btrfs-{}", std::process::id()));
+		let filesystem = sysfs.join("fs/btrfs/1c3f4a1e-8b9e-4a3c-9d2f-6f0e5a7b8c9d");
+		for device in ["sdb2", "sda2", "dm-0"] {
+			std::fs::create_dir_all(filesystem.join("devices").join(device)).unwrap();
+		}
+		std::fs::create_dir_all(sysfs.join("fs/btrfs/features")).unwrap();
+		std::fs::write(sysfs.join("fs/btrfs/features/raid1c34"), "0\n").unwrap();
+		let found = BtrfsFilesystem::find_from(&sysfs, "sdb2");
+		let missing = BtrfsFilesystem::find_from(&sysfs, "sdc1");
+		std::fs::remove_dir_all(&sysfs).unwrap();
+		let found = found.unwrap().unwrap();
+		assert_eq!(found.uuid, "1c3f4a1e-8b9e-4a3c-9d2f-6f0e5a7b8c9d");
+		assert_eq!(found.devices, ["dm-0", "sda2", "sdb2"]);
+		assert_eq!(missing.unwrap(), None);
+		let tmpfs = Mount { device: "tmpfs".to_string(), file_system_type: "tmpfs".to_string(), ..Default::default() };
+		assert_eq!(tmpfs.all_devices().unwrap(), ["tmpfs"]);
+	}
+}
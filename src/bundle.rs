@@ -0,0 +1,138 @@
+//! Reads the mount tables captured in an extracted sosreport or similar support bundle.
+//!
+//! Support bundles mirror the paths of the files they collect, so a bundle taken from a broken machine contains `proc/mounts`, `proc/self/mountinfo`, and `etc/fstab` relative to its root.  Bundles are usually extracted from a tarball whose only entry is a directory named after the host, e.g. `sosreport-web01-2024-03-01-abcdef`; [SupportBundle::open()] looks inside such a directory too.  Needs the `io` feature.
+
+use super::{BoxError, Mount, MountEntry};
+use super::fstab::Fstab;
+use super::model::Format;
+
+/// Error reading one of the files in a support bundle, identifying which one.
+#[derive(Debug)]
+pub struct BundleError {
+	/// The file that couldn't be read or parsed.
+	pub path: std::path::PathBuf,
+	// The underlying error.
+	source: BoxError,
+}
+
+impl std::fmt::Display for BundleError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}: {}", self.path.display(), self.source)
+	}
+}
+
+impl std::error::Error for BundleError {
+	fn source(&self) -> std::option::Option<&(dyn std::error::Error + 'static)> {
+		Some(&*self.source)
+	}
+}
+
+/// The mount tables found in a support bundle.  A file missing from the bundle is `None`.
+#[derive(Clone, Debug, Default)]
+pub struct SupportBundle {
+	/// The directory the files were found in.
+	pub root: std::path::PathBuf,
+	/// The contents of `proc/mounts`.
+	pub mounts: std::option::Option<std::vec::Vec<Mount>>,
+	/// The contents of `proc/self/mountinfo`.
+	pub mountinfo: std::option::Option<std::vec::Vec<MountEntry>>,
+	/// The contents of `etc/fstab`.
+	pub fstab: std::option::Option<Fstab>,
+}
+
+// The files looked for in a bundle, relative to its root.
+const MOUNTS_PATH: &str = "proc/mounts";
+const MOUNTINFO_PATH: &str = "proc/self/mountinfo";
+const FSTAB_PATH: &str = "etc/fstab";
+
+impl SupportBundle {
+	/// Reads the mount tables from the bundle extracted to `path`.  If `path` contains none of the files but has exactly one subdirectory, that subdirectory is searched instead.  Returns an error if a file exists but can't be read or parsed.
+	/// # Examples
+	/// ```no_run
+	/// # use nom_tutorial::bundle::SupportBundle;
+	/// let bundle = SupportBundle::open("/tmp/sosreport-web01-2024-03-01-abcdef").unwrap();
+	/// for mount in bundle.active_mounts().unwrap_or_default() {
+	///     println!("{}", mount);
+	/// }
+	/// ```
+	pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<SupportBundle, BundleError> {
+		let root = find_root(path.as_ref()).map_err(|e| BundleError { path: path.as_ref().to_path_buf(), source: e.into() })?;
+		Ok(SupportBundle {
+			mounts: read_file(&root, MOUNTS_PATH, |text| Ok(crate::snapshot::MountSnapshot::parse(text, Format::Mounts)?.into_mounts()))?,
+			mountinfo: read_file(&root, MOUNTINFO_PATH, |text| Ok(crate::snapshot::MountSnapshot::parse(text, Format::MountInfo)?.into_entries()))?,
+			fstab: read_file(&root, FSTAB_PATH, |text| Ok(Fstab::parse(text)?))?,
+			root,
+		})
+	}
+
+	/// The mounts that were active when the bundle was taken, from `proc/self/mountinfo` if it was collected since it has more detail, otherwise from `proc/mounts`.
+	pub fn active_mounts(&self) -> std::option::Option<std::vec::Vec<MountEntry>> {
+		self.mountinfo.clone().or_else(|| self.mounts.clone().map(|mounts| mounts.into_iter().map(MountEntry::from).collect()))
+	}
+
+	/// True if the bundle contains none of the files.
+	pub fn is_empty(&self) -> bool {
+		self.mounts.is_none() && self.mountinfo.is_none() && self.fstab.is_none()
+	}
+}
+
+// True if `directory` contains any of the files looked for.
+fn has_files(directory: &std::path::Path) -> bool {
+	[MOUNTS_PATH, MOUNTINFO_PATH, FSTAB_PATH].iter().any(|file| directory.join(file).is_file())
+}
+
+// Returns `path`, or its only subdirectory if the files are in there instead.
+fn find_root(path: &std::path::Path) -> std::result::Result<std::path::PathBuf, std::io::Error> {
+	if has_files(path) {
+		return Ok(path.to_path_buf());
+	}
+	let mut subdirectories = std::vec::Vec::new();
+	for entry in std::fs::read_dir(path)? {
+		let entry = entry?;
+		if entry.file_type()?.is_dir() {
+			subdirectories.push(entry.path());
+		}
+	}
+	Ok(match &subdirectories[..] {
+		[only] if has_files(only) => only.clone(),
+		_ => path.to_path_buf()
+	})
+}
+
+// Reads and parses `root/file` with `parse`, or returns `None` if it doesn't exist.
+fn read_file<T, F: Fn(&str) -> std::result::Result<T, BoxError>>(root: &std::path::Path, file: &str, parse: F) -> std::result::Result<std::option::Option<T>, BundleError> {
+	let path = root.join(file);
+	let text = match std::fs::read_to_string(&path) {
+		Ok(text) => text,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+		Err(e) => return Err(BundleError { path, source: e.into() })
+	};
+	parse(&text).map(Some).map_err(|source| BundleError { path, source })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Files are found inside the single top-level directory of an extracted tarball, missing files are `None`, and a corrupt file is reported by path.
+	#[test]
+	fn test_open_nested_bundle() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-bundle-{}", std::process::id()));
+		let root = directory.join("sosreport-web01-2024-03-01-abcdef");
+		std::fs::create_dir_all(root.join("proc/self")).unwrap();
+		std::fs::create_dir_all(root.join("etc")).unwrap();
+		std::fs::write(root.join("proc/mounts"), "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n").unwrap();
+		std::fs::write(root.join("etc/fstab"), "# static\n/dev/sda1 / ext4 defaults 0 1\n").unwrap();
+		let bundle = SupportBundle::open(&directory);
+		std::fs::write(root.join("proc/self/mountinfo"), "garbage\n").unwrap();
+		let error = SupportBundle::open(&directory).unwrap_err();
+		std::fs::remove_dir_all(&directory).unwrap();
+		let bundle = bundle.unwrap();
+		assert_eq!(bundle.root, root);
+		assert!(bundle.mountinfo.is_none());
+		assert_eq!(bundle.active_mounts().unwrap().len(), 2);
+		assert_eq!(bundle.fstab.unwrap().entries().count(), 1);
+		assert_eq!(error.path, root.join("proc/self/mountinfo"));
+		assert!(std::error::Error::source(&error).unwrap().downcast_ref::<crate::ParseError>().is_some());
+	}
+}
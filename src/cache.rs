@@ -0,0 +1,114 @@
+//! Caches the parsed mount table so that frequent lookups don't re-read `/proc/mounts` every time.
+
+use super::{BoxError, MountEntry};
+use super::watch::{ReadTable, Watcher};
+
+// State guarded by the mutex in `CachedMounts`.
+struct Inner {
+	read: ReadTable,
+	watcher: std::option::Option<Watcher>,
+	mounts: std::sync::Arc<std::vec::Vec<MountEntry>>,
+	refreshed: std::option::Option<std::time::Instant>,
+}
+
+/// A cached copy of the mount table that refreshes itself when it is older than a time-to-live (TTL).
+///
+/// The table is read lazily on the first call to [CachedMounts::get()] and then served from memory until the TTL expires.  If constructed with [CachedMounts::with_watcher()] the cache is also refreshed as soon as the watcher reports a change, so the TTL can be long without serving stale data.  `CachedMounts` is `Send + Sync` and can be shared between threads, e.g. in an `Arc`.
+/// # Examples
+/// ```
+/// # use nom_tutorial::cache::CachedMounts;
+/// let cache = CachedMounts::new(std::time::Duration::from_secs(5));
+/// for mount in cache.get().unwrap().iter() {
+///     println!("{}", mount);
+/// }
+/// ```
+pub struct CachedMounts {
+	ttl: std::time::Duration,
+	inner: std::sync::Mutex<Inner>,
+}
+
+impl CachedMounts {
+	/// Caches `/proc/mounts` for up to `ttl`.
+	pub fn new(ttl: std::time::Duration) -> CachedMounts {
+		CachedMounts::with_reader(ttl, std::boxed::Box::new(super::watch::read_mounts))
+	}
+
+	/// Caches `/proc/mounts` for up to `ttl`, refreshing early whenever `watcher` reports a change.
+	pub fn with_watcher(ttl: std::time::Duration, watcher: Watcher) -> CachedMounts {
+		let cache = CachedMounts::new(ttl);
+		cache.inner.lock().unwrap().watcher = Some(watcher);
+		cache
+	}
+
+	/// Caches the mount table returned by `read` for up to `ttl`.
+	pub fn with_reader(ttl: std::time::Duration, read: ReadTable) -> CachedMounts {
+		CachedMounts {
+			ttl,
+			inner: std::sync::Mutex::new(Inner {
+				read,
+				watcher: None,
+				mounts: std::sync::Arc::new(std::vec::Vec::new()),
+				refreshed: None
+			})
+		}
+	}
+
+	/// Returns the cached mount table, re-reading it first if it has expired or changed.  If re-reading fails the error is returned and the next call will try again.
+	pub fn get(&self) -> std::result::Result<std::sync::Arc<std::vec::Vec<MountEntry>>, BoxError> {
+		let mut inner = self.inner.lock().unwrap();
+		let mut stale = match inner.refreshed {
+			Some(refreshed) => refreshed.elapsed() >= self.ttl,
+			None => true
+		};
+		if let Some(watcher) = &inner.watcher {
+			// Drain every pending notification; any one of them makes the cache stale.
+			while watcher.try_recv().is_some() {
+				stale = true;
+			}
+		}
+		if stale {
+			inner.refreshed = None;
+			inner.mounts = std::sync::Arc::new((inner.read)()?);
+			inner.refreshed = Some(std::time::Instant::now());
+		}
+		Ok(inner.mounts.clone())
+	}
+
+	/// Forces the next call to [CachedMounts::get()] to re-read the mount table.
+	pub fn invalidate(&self) {
+		self.inner.lock().unwrap().refreshed = None;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures::mount;
+
+	// Returns a cache whose reader counts how many times it was called.
+	fn counting_cache(ttl: std::time::Duration) -> (CachedMounts, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+		let reads = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+		let reader_reads = reads.clone();
+		let cache = CachedMounts::with_reader(ttl, std::boxed::Box::new(move || {
+			reader_reads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+			Ok(vec![mount("/dev/sda1", "/", "ext4", &["rw"]).into()])
+		}));
+		(cache, reads)
+	}
+
+	#[test]
+	fn test_cache_ttl() {
+		let (cache, reads) = counting_cache(std::time::Duration::from_secs(3600));
+		assert_eq!(cache.get().unwrap().len(), 1);
+		cache.get().unwrap();
+		assert_eq!(reads.load(std::sync::atomic::Ordering::SeqCst), 1);
+		cache.invalidate();
+		cache.get().unwrap();
+		assert_eq!(reads.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+		let (cache, reads) = counting_cache(std::time::Duration::from_secs(0));
+		cache.get().unwrap();
+		cache.get().unwrap();
+		assert_eq!(reads.load(std::sync::atomic::Ordering::SeqCst), 2);
+	}
+}
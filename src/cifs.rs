@@ -0,0 +1,95 @@
+//! Decomposes CIFS/SMB mounts into the server, share, and credentials they were mounted with.
+//!
+//! The device of a CIFS mount is a UNC path written with forward slashes, `//server/share`, optionally followed by a path within the share.  The user and domain the share was mounted as are in the `username=` and `domain=` options.
+
+use super::Mount;
+
+/// The endpoint and identity of a CIFS mount, see [Mount::cifs_info()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CifsInfo<'a> {
+	/// The host name or address of the server, e.g. fileserver
+	pub server: &'a str,
+	/// The name of the share, e.g. Shared Docs
+	pub share: &'a str,
+	/// The directory within the share that is mounted, without a leading slash, or `None` if the whole share is mounted.
+	pub prefix_path: std::option::Option<&'a str>,
+	/// The Windows domain or workgroup of the user, from the `domain=` option.
+	pub domain: std::option::Option<&'a str>,
+	/// The user the share was mounted as, from the `username=` option.
+	pub username: std::option::Option<&'a str>,
+}
+
+impl<'a> CifsInfo<'a> {
+	/// Splits a UNC path such as `//server/share/sub/dir` into the server, share, and prefix path, leaving the domain and username empty.  Returns `None` if `device` isn't a UNC path.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::cifs::CifsInfo;
+	/// let info = CifsInfo::parse("//fileserver/projects/2024").unwrap();
+	/// assert_eq!((info.server, info.share, info.prefix_path), ("fileserver", "projects", Some("2024")));
+	/// assert_eq!(CifsInfo::parse("/dev/sda1"), None);
+	/// ```
+	pub fn parse(device: &'a str) -> std::option::Option<CifsInfo<'a>> {
+		parsers::unc_path(device).ok().map(|(_, (server, share, prefix_path))| CifsInfo { server, share, prefix_path, domain: None, username: None })
+	}
+}
+
+impl Mount {
+	/// The server, share, and credentials of a `cifs` or `smb3` mount.  Returns `None` for other filesystem types, or if the device isn't a UNC path, see [CifsInfo::parse()].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount {
+	///     device: "//fileserver/Shared Docs".to_string(),
+	///     file_system_type: "cifs".to_string(),
+	///     options: vec!["rw".to_string(), "username=alice".to_string(), "domain=CORP".to_string()],
+	///     ..Default::default()
+	/// };
+	/// let info = mount.cifs_info().unwrap();
+	/// assert_eq!((info.server, info.share), ("fileserver", "Shared Docs"));
+	/// assert_eq!((info.domain, info.username), (Some("CORP"), Some("alice")));
+	/// ```
+	pub fn cifs_info(&self) -> std::option::Option<CifsInfo<'_>> {
+		match &self.file_system_type[..] {
+			"cifs" | "smb3" => {
+				let option = |name: &str| self.mount_options().filter(|option| option.name == name).last().and_then(|option| option.value);
+				CifsInfo::parse(&self.device).map(|info| CifsInfo { domain: option("domain"), username: option("username"), ..info })
+			},
+			_ => None
+		}
+	}
+}
+
+mod parsers {
+	// Parses one non-empty component of a UNC path.
+	fn component(i: &str) -> nom::IResult<&str, &str> {
+		nom::bytes::complete::is_not("/")(i)
+	}
+
+	// Parses a UNC path such as `//server/share/sub/dir` into the server, the share, and the rest of the path.  A trailing slash after the share is ignored.
+	pub fn unc_path(i: &str) -> nom::IResult<&str, (&str, &str, std::option::Option<&str>)> {
+		let (i, _) = nom::bytes::complete::tag("//")(i)?;
+		let (i, server) = component(i)?;
+		let (i, _) = nom::character::complete::char('/')(i)?;
+		let (i, share) = component(i)?;
+		let (i, prefix_path) = nom::combinator::all_consuming(nom::combinator::opt(nom::sequence::preceded(
+			nom::character::complete::char('/'),
+			nom::combinator::rest
+		)))(i)?;
+		Ok((i, (server, share, prefix_path.filter(|path| !path.is_empty()))))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_unc_path() {
+			assert_eq!(unc_path("//server/share"), Ok(("", ("server", "share", None))));
+			assert_eq!(unc_path("//server/share/"), Ok(("", ("server", "share", None))));
+			assert_eq!(unc_path("//10.0.0.5/share/a/b"), Ok(("", ("10.0.0.5", "share", Some("a/b")))));
+			assert!(unc_path("//server").is_err());
+			assert!(unc_path("//server//share").is_err());
+			assert!(unc_path("server/share").is_err());
+		}
+	}
+}
@@ -0,0 +1,94 @@
+//! Command line parsing for the `nom-tutorial` binary.
+
+use nom_tutorial::{BoxError, Mount};
+use nom_tutorial::filter::{Field, FilterExpr};
+use nom_tutorial::sort::SortKey;
+use nom_tutorial::watch::Action;
+
+/// Where to read the mount table from.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum Input {
+	/// The live mount table, `/proc/mounts`.
+	#[default]
+	Proc,
+	/// A mount table piped in on standard input.
+	Stdin,
+	/// A mount table saved to a file, e.g. a capture from another machine.
+	File(std::path::PathBuf),
+}
+
+impl Input {
+	/// Reads and parses the whole mount table.
+	pub fn read(&self) -> std::result::Result<std::vec::Vec<Mount>, BoxError> {
+		match self {
+			Input::Proc => nom_tutorial::mounts()?.into_iter().collect(),
+			Input::Stdin => {
+				let mut contents = std::string::String::new();
+				std::io::Read::read_to_string(&mut std::io::stdin(), &mut contents)?;
+				nom_tutorial::Mounts::from_source(&nom_tutorial::source::StringSource::new(contents))?.into_iter().collect()
+			},
+			Input::File(path) => nom_tutorial::Mounts::from_source(&nom_tutorial::source::FileSource::new(path))?.into_iter().collect()
+		}
+	}
+}
+
+/// Command line options.
+#[derive(Default)]
+pub struct Options {
+	/// Where to read the mount table from.
+	pub input: Input,
+	/// Print Prometheus metrics instead of the mount list.
+	pub metrics: bool,
+	/// Print the mounts as JSON Lines instead of the mount list.
+	pub jsonl: bool,
+	/// Print changes to the mount table as they happen, limited to these actions, instead of the mount list.
+	pub poll: std::option::Option<std::vec::Vec<Action>>,
+	/// Sort the mounts by this key instead of listing them in kernel order.
+	pub sort: std::option::Option<SortKey>,
+	/// List the mounts in reverse order.
+	pub reverse: bool,
+	/// Only list the mounts that match.
+	pub filter: std::vec::Vec<FilterExpr>,
+}
+
+impl Options {
+	/// Parses options from the command line arguments, excluding the program name.
+	pub fn parse<I: Iterator<Item = std::string::String>>(mut args: I) -> std::result::Result<Options, BoxError> {
+		let mut options = Options::default();
+		while let Some(arg) = args.next() {
+			match &arg[..] {
+				"--metrics" => options.metrics = true,
+				"--jsonl" => options.jsonl = true,
+				"--stdin" | "-" => options.input = Input::Stdin,
+				"--sort" => options.sort = Some(args.next().ok_or("--sort requires a key")?.parse()?),
+				_ if arg.starts_with("--sort=") => options.sort = Some(arg["--sort=".len()..].parse()?),
+				"--reverse" => options.reverse = true,
+				"--match-source" => options.filter.push(regex(Field::Source, &args.next().ok_or("--match-source requires a regular expression")?)?),
+				"--filter" => options.filter.push(args.next().ok_or("--filter requires an expression")?.parse()?),
+				"--glob" => options.filter.push(FilterExpr::Glob(Field::Target, args.next().ok_or("--glob requires a pattern")?)),
+				"--match-target" => options.filter.push(regex(Field::Target, &args.next().ok_or("--match-target requires a regular expression")?)?),
+				"--poll" => options.poll = Some(Action::ALL.to_vec()),
+				_ if arg.starts_with("--poll=") => {
+					options.poll = Some(arg["--poll=".len()..].split(',').map(str::parse).collect::<std::result::Result<_, BoxError>>()?);
+				},
+				_ if !arg.starts_with('-') && options.input == Input::Proc => options.input = Input::File(arg.into()),
+				_ => return Err(format!("unrecognized argument: {}", arg).into())
+			}
+		}
+		if options.poll.is_some() && options.input != Input::Proc {
+			return Err("--poll can only watch the live mount table".into());
+		}
+		Ok(options)
+	}
+}
+
+/// Compiles a filter for `--match-source` or `--match-target`.
+#[cfg(feature = "regex")]
+fn regex(field: Field, pattern: &str) -> std::result::Result<FilterExpr, BoxError> {
+	Ok(FilterExpr::Regex(field, regex::Regex::new(pattern)?))
+}
+
+#[cfg(not(feature = "regex"))]
+fn regex(_field: Field, _pattern: &str) -> std::result::Result<FilterExpr, BoxError> {
+	Err("regular expressions require nom-tutorial to be built with the `regex` feature".into())
+}
@@ -0,0 +1,171 @@
+//! Resolves device-mapper devices between their kernel names and the names they were given.
+//!
+//! A device-mapper device, such as an LVM logical volume or a LUKS container, is known to the kernel as `/dev/dm-N`, but udev also links it as `/dev/mapper/<name>` and, for LVM, as `/dev/<vg>/<lv>`.  The mount table shows whichever of these was passed to mount(8), so the same volume can appear under different names on different machines.  The kernel exposes each device's name in `/sys/block/dm-N/dm/name`.  LVM builds that name from the volume group and logical volume, doubling any dashes within them, e.g. `vg--data-root` for the volume `root` in the group `vg-data`.
+
+use super::Mount;
+use super::sysfs::SYSFS_PATH;
+
+/// A device-mapper device, see [DmDevice::resolve()].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DmDevice {
+	/// The kernel's name for the device, e.g. dm-0
+	pub kernel_name: std::string::String,
+	/// The device-mapper name, e.g. vg--data-root
+	pub name: std::string::String,
+	/// The device-mapper UUID, which starts with the subsystem that created the device, e.g. LVM- or CRYPT-.
+	pub uuid: std::option::Option<std::string::String>,
+}
+
+impl DmDevice {
+	/// Resolves a device path from the mount table, in any of the forms `/dev/dm-N`, `/dev/mapper/<name>`, or `/dev/<vg>/<lv>`.  Returns `None` if the path isn't a device-mapper device that sysfs knows about.
+	pub fn resolve(device: &str) -> std::result::Result<std::option::Option<DmDevice>, std::io::Error> {
+		DmDevice::resolve_from(std::path::Path::new(SYSFS_PATH), device)
+	}
+
+	/// Like [DmDevice::resolve()], but with sysfs mounted at `sysfs` instead of `/sys`, e.g. in a container or a saved copy.
+	pub fn resolve_from(sysfs: &std::path::Path, device: &str) -> std::result::Result<std::option::Option<DmDevice>, std::io::Error> {
+		if let Some(kernel_name) = device.strip_prefix("/dev/").filter(|name| name.starts_with("dm-")) {
+			return DmDevice::read(sysfs, kernel_name);
+		}
+		let name = match device.strip_prefix("/dev/mapper/") {
+			Some(name) => name.to_string(),
+			None => match device.strip_prefix("/dev/").and_then(|path| path.split_once('/')) {
+				// Only LVM creates links of the form /dev/<vg>/<lv>, which are checked against the UUID below.
+				Some((vg, lv)) if !lv.contains('/') => format!("{}-{}", vg.replace('-', "--"), lv.replace('-', "--")),
+				_ => return Ok(None)
+			}
+		};
+		let block = match std::fs::read_dir(sysfs.join("block")) {
+			Ok(block) => block,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e)
+		};
+		for entry in block {
+			let kernel_name = entry?.file_name().to_string_lossy().into_owned();
+			if !kernel_name.starts_with("dm-") {
+				continue;
+			}
+			if let Some(dm) = DmDevice::read(sysfs, &kernel_name)? {
+				if dm.name == name && (device.starts_with("/dev/mapper/") || dm.lvm_names().is_some()) {
+					return Ok(Some(dm));
+				}
+			}
+		}
+		Ok(None)
+	}
+
+	// Reads the name and UUID of the device with the given kernel name, e.g. dm-0.
+	fn read(sysfs: &std::path::Path, kernel_name: &str) -> std::result::Result<std::option::Option<DmDevice>, std::io::Error> {
+		let dm = sysfs.join("block").join(kernel_name).join("dm");
+		let name = match std::fs::read_to_string(dm.join("name")) {
+			Ok(name) => name.trim_end().to_string(),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e)
+		};
+		let uuid = match std::fs::read_to_string(dm.join("uuid")) {
+			Ok(uuid) if uuid.trim().is_empty() => None,
+			Ok(uuid) => Some(uuid.trim_end().to_string()),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+			Err(e) => return Err(e)
+		};
+		Ok(Some(DmDevice { kernel_name: kernel_name.to_string(), name, uuid }))
+	}
+
+	/// The path of the device under its kernel name, e.g. /dev/dm-0
+	pub fn kernel_path(&self) -> std::string::String {
+		format!("/dev/{}", self.kernel_name)
+	}
+
+	/// The path of the device under its device-mapper name, e.g. /dev/mapper/vg--data-root
+	pub fn mapper_path(&self) -> std::string::String {
+		format!("/dev/mapper/{}", self.name)
+	}
+
+	/// The volume group and logical volume of an LVM device, with the doubled dashes undone, e.g. `("vg-data", "root")`.  Returns `None` for devices that LVM didn't create.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::devmapper::DmDevice;
+	/// let dm = DmDevice { kernel_name: "dm-1".to_string(), name: "vg--data-root".to_string(), uuid: Some("LVM-abc".to_string()) };
+	/// assert_eq!(dm.lvm_names(), Some(("vg-data".to_string(), "root".to_string())));
+	/// ```
+	pub fn lvm_names(&self) -> std::option::Option<(std::string::String, std::string::String)> {
+		if !self.uuid.as_ref()?.starts_with("LVM-") {
+			return None;
+		}
+		// The separator is the first dash that isn't doubled.
+		let bytes = self.name.as_bytes();
+		let mut i = 0;
+		while i < bytes.len() {
+			if bytes[i] == b'-' {
+				if bytes.get(i + 1) != Some(&b'-') {
+					let (vg, lv) = (&self.name[..i], &self.name[i + 1..]);
+					return Some((vg.replace("--", "-"), lv.replace("--", "-")));
+				}
+				i += 1;
+			}
+			i += 1;
+		}
+		None
+	}
+}
+
+impl Mount {
+	/// The device-mapper device the mount is on, see [DmDevice::resolve()].
+	pub fn dm_device(&self) -> std::result::Result<std::option::Option<DmDevice>, std::io::Error> {
+		DmDevice::resolve(&self.device)
+	}
+
+	/// The device in the form `/dev/mapper/<name>`, which names the same volume the same way no matter which alias was passed to mount(8).  Devices other than device-mapper devices are returned unchanged.
+	/// # Examples
+	/// ```no_run
+	/// for mount in nom_tutorial::mounts().unwrap() {
+	///     let mount = mount.unwrap();
+	///     println!("{} is mounted from {}", mount.mount_point, mount.friendly_device().unwrap());
+	/// }
+	/// ```
+	pub fn friendly_device(&self) -> std::result::Result<std::string::String, std::io::Error> {
+		Ok(match self.dm_device()? {
+			Some(dm) => dm.mapper_path(),
+			None => self.device.clone()
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Every alias of an LVM volume resolves to the same device, while a LUKS container only has its mapper name.
+	#[test]
+	fn test_resolve_from() {
+		let sysfs = std::env::temp_dir().join(format!("nom-tutorial-devmapper-{}", std::process::id()));
+		for (kernel_name, name, uuid) in [("dm-0", "luks-root", "CRYPT-LUKS2-1234"), ("dm-1", "vg--data-root", "LVM-5678")] {
+			let dm = sysfs.join("block").join(kernel_name).join("dm");
+			std::fs::create_dir_all(&dm).unwrap();
+			std::fs::write(dm.join("name"), format!("{}\n", name)).unwrap();
+			std::fs::write(dm.join("uuid"), format!("{}\n", uuid)).unwrap();
+		}
+		std::fs::create_dir_all(sysfs.join("block/sda")).unwrap();
+		let resolve = |device: &str| DmDevice::resolve_from(&sysfs, device).unwrap().map(|dm| dm.kernel_name);
+		let results = [
+			resolve("/dev/dm-1"),
+			resolve("/dev/mapper/vg--data-root"),
+			resolve("/dev/vg-data/root"),
+			resolve("/dev/mapper/luks-root"),
+			resolve("/dev/luks/root"),
+			resolve("/dev/dm-7"),
+			resolve("/dev/sda1"),
+		];
+		std::fs::remove_dir_all(&sysfs).unwrap();
+		let dm = |name: &str| Some(name.to_string());
+		assert_eq!(results, [dm("dm-1"), dm("dm-1"), dm("dm-1"), dm("dm-0"), None, None, None]);
+	}
+
+	#[test]
+	fn test_lvm_names() {
+		let dm = |name: &str, uuid: &str| DmDevice { kernel_name: "dm-0".to_string(), name: name.to_string(), uuid: Some(uuid.to_string()) };
+		assert_eq!(dm("vg0-swap--1", "LVM-x").lvm_names(), Some(("vg0".to_string(), "swap-1".to_string())));
+		assert_eq!(dm("luks-root", "CRYPT-LUKS2-x").lvm_names(), None);
+		assert_eq!(dm("novg", "LVM-x").lvm_names(), None);
+	}
+}
@@ -0,0 +1,120 @@
+//! Interprets the journalling and error handling options of ext2, ext3, and ext4, see `ext4(5)`.
+//!
+//! These options take a value from a short list, and the kernel refuses to mount a filesystem with a value it doesn't know.  A typo such as `data=writback` in `fstab` therefore only shows up at the next boot, which is why [fstab_verify](crate::fstab_verify) checks them with [Ext4Options::parse()].
+
+use super::Mount;
+use super::options::{MountOption, OptionValueError};
+
+/// How file data is written relative to the journal, from the `data=` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataMode {
+	/// File data is written to the journal along with the metadata (`data=journal`).  Safest and slowest.
+	Journal,
+	/// File data is written before the metadata that refers to it is committed (`data=ordered`).  The default.
+	Ordered,
+	/// File data may be written after the metadata (`data=writeback`), so files can contain stale data after a crash.
+	Writeback,
+}
+
+/// What the filesystem does when it finds an error, from the `errors=` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorBehavior {
+	/// Log the error and carry on (`errors=continue`).
+	Continue,
+	/// Remount the filesystem read-only (`errors=remount-ro`), see [audit::error_remounts()](crate::audit::error_remounts).
+	RemountReadOnly,
+	/// Panic the kernel (`errors=panic`).
+	Panic,
+}
+
+/// The journalling and error handling options of an ext2, ext3, or ext4 mount, see [Mount::ext4_options()].  Options that aren't given are `None`, leaving the choice to the defaults recorded in the superblock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Ext4Options {
+	/// The `data=` option.
+	pub data: std::option::Option<DataMode>,
+	/// The `errors=` option.
+	pub errors: std::option::Option<ErrorBehavior>,
+	/// True for `journal_checksum` and false for `nojournal_checksum`.
+	pub journal_checksum: std::option::Option<bool>,
+	/// True if commit blocks are written without waiting for the journal blocks before them (`journal_async_commit`), which implies `journal_checksum`.
+	pub journal_async_commit: bool,
+}
+
+impl Ext4Options {
+	/// Interprets the ext4 options among `options`, ignoring the others.  Later options override earlier ones.  Returns an error for a value that the kernel would refuse.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::ext4::{DataMode, Ext4Options};
+	/// assert_eq!(Ext4Options::parse(&["rw", "data=writeback"]).unwrap().data, Some(DataMode::Writeback));
+	/// assert!(Ext4Options::parse(&["data=writback"]).is_err());
+	/// ```
+	pub fn parse<S: AsRef<str>>(options: &[S]) -> std::result::Result<Ext4Options, OptionValueError> {
+		let mut parsed = Ext4Options::default();
+		for option in options.iter().map(|option| MountOption::parse(option.as_ref())) {
+			let invalid = || OptionValueError::new(&option, None);
+			match option.name {
+				"data" => parsed.data = Some(match option.value {
+					Some("journal") => DataMode::Journal,
+					Some("ordered") => DataMode::Ordered,
+					Some("writeback") => DataMode::Writeback,
+					_ => return Err(invalid())
+				}),
+				"errors" => parsed.errors = Some(match option.value {
+					Some("continue") => ErrorBehavior::Continue,
+					Some("remount-ro") => ErrorBehavior::RemountReadOnly,
+					Some("panic") => ErrorBehavior::Panic,
+					_ => return Err(invalid())
+				}),
+				"journal_checksum" => parsed.journal_checksum = Some(true),
+				"nojournal_checksum" => parsed.journal_checksum = Some(false),
+				"journal_async_commit" => parsed.journal_async_commit = true,
+				_ => ()
+			}
+		}
+		Ok(parsed)
+	}
+}
+
+impl Mount {
+	/// The journalling and error handling options of an ext2, ext3, or ext4 mount.  Returns `Ok(None)` for other filesystem types, and an error if an option has a value the kernel doesn't know.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::ext4::{DataMode, ErrorBehavior};
+	/// let mount = Mount { file_system_type: "ext4".to_string(), options: vec!["rw".to_string(), "errors=remount-ro".to_string(), "data=ordered".to_string()], ..Default::default() };
+	/// let options = mount.ext4_options().unwrap().unwrap();
+	/// assert_eq!((options.data, options.errors), (Some(DataMode::Ordered), Some(ErrorBehavior::RemountReadOnly)));
+	/// ```
+	pub fn ext4_options(&self) -> std::result::Result<std::option::Option<Ext4Options>, OptionValueError> {
+		match &self.file_system_type[..] {
+			"ext2" | "ext3" | "ext4" => Ext4Options::parse(&self.options).map(Some),
+			_ => Ok(None)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		assert_eq!(Ext4Options::parse(&["data=journal", "journal_checksum", "errors=panic", "nojournal_checksum", "journal_async_commit"]), Ok(Ext4Options {
+			data: Some(DataMode::Journal),
+			errors: Some(ErrorBehavior::Panic),
+			journal_checksum: Some(false),
+			journal_async_commit: true
+		}));
+		assert_eq!(Ext4Options::parse::<&str>(&[]), Ok(Ext4Options::default()));
+		let error = Ext4Options::parse(&["errors=remount_ro"]).unwrap_err();
+		assert_eq!((&error.name[..], error.value.as_deref()), ("errors", Some("remount_ro")));
+		assert!(Ext4Options::parse(&["data"]).is_err());
+	}
+
+	#[test]
+	fn test_ext4_options() {
+		let mount = |file_system_type: &str| crate::test_fixtures::mount("/dev/sda1", "/", file_system_type, &["data=writeback"]);
+		assert_eq!(mount("ext3").ext4_options().unwrap().unwrap().data, Some(DataMode::Writeback));
+		assert_eq!(mount("xfs").ext4_options(), Ok(None));
+	}
+}
@@ -0,0 +1,177 @@
+//! Cross-checks mount tables against the filesystem types the kernel supports, as listed in `/proc/filesystems`.
+//!
+//! A line of `/proc/mounts` that was corrupted or truncated can still parse if the damage happens to leave four whitespace-separated fields.  Such lines usually end up with a nonsense filesystem type, which [validate()] catches because the kernel can only mount types it has registered.
+
+use super::{BoxError, Mount};
+
+/// Where the kernel lists the filesystem types it supports.
+pub const FILESYSTEMS_PATH: &str = "/proc/filesystems";
+
+/// A filesystem type registered with the kernel.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileSystem {
+	/// The name of the type, e.g. ext4
+	pub name: std::string::String,
+	/// True if the filesystem isn't backed by a block device, e.g. for tmpfs and proc.
+	pub nodev: bool,
+}
+
+/// The set of filesystem types the kernel supports.
+///
+/// Filesystems built as modules only appear once their module is loaded, but every type that is currently mounted is necessarily registered, so a mount whose type is missing from the list was not read correctly.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct KnownFileSystems {
+	file_systems: std::collections::BTreeMap<std::string::String, FileSystem>,
+}
+
+impl KnownFileSystems {
+	/// Parses the contents of `/proc/filesystems`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::filesystems::KnownFileSystems;
+	/// let known = KnownFileSystems::parse("nodev\tproc\n\text4\n").unwrap();
+	/// assert!(known.contains("ext4"));
+	/// assert!(known.get("proc").unwrap().nodev);
+	/// assert!(!known.contains("ext9"));
+	/// ```
+	pub fn parse(text: &str) -> std::result::Result<KnownFileSystems, BoxError> {
+		let mut file_systems = std::collections::BTreeMap::new();
+		for (number, line) in text.lines().enumerate() {
+			if line.trim().is_empty() {
+				continue;
+			}
+			match parsers::parse_line(line) {
+				Ok((_, file_system)) => { file_systems.insert(file_system.name.clone(), file_system); },
+				Err(e) => return Err(crate::ParseError::new(line, e).with_line_number(number + 1).into())
+			}
+		}
+		Ok(KnownFileSystems { file_systems })
+	}
+
+	/// Reads and parses [FILESYSTEMS_PATH].
+	#[cfg(feature = "io")]
+	pub fn read() -> std::result::Result<KnownFileSystems, BoxError> {
+		KnownFileSystems::read_from(std::path::Path::new(FILESYSTEMS_PATH))
+	}
+
+	/// Like [KnownFileSystems::read()], but reads `path` instead of [FILESYSTEMS_PATH], e.g. a copy saved from another machine.
+	#[cfg(feature = "io")]
+	pub fn read_from(path: &std::path::Path) -> std::result::Result<KnownFileSystems, BoxError> {
+		KnownFileSystems::parse(&std::fs::read_to_string(path)?)
+	}
+
+	/// Looks up a filesystem type by name.  FUSE subtypes such as `fuse.sshfs` are looked up as `fuse`, which is what the kernel registers.
+	pub fn get(&self, fs_type: &str) -> std::option::Option<&FileSystem> {
+		self.file_systems.get(fs_type).or_else(|| {
+			let base = fs_type.split('.').next()?;
+			self.file_systems.get(base)
+		})
+	}
+
+	/// True if the kernel supports the filesystem type, see [KnownFileSystems::get()].
+	pub fn contains(&self, fs_type: &str) -> bool {
+		self.get(fs_type).is_some()
+	}
+
+	/// Iterates over the known filesystem types in alphabetical order.
+	pub fn iter(&self) -> impl Iterator<Item = &FileSystem> {
+		self.file_systems.values()
+	}
+
+	/// Returns the mounts whose filesystem type the kernel doesn't support, along with their positions in `mounts`.
+	pub fn unknown<'a>(&self, mounts: &'a [Mount]) -> std::vec::Vec<UnknownType<'a>> {
+		mounts.iter().enumerate()
+			.filter(|(_, mount)| !self.contains(&mount.file_system_type))
+			.map(|(index, mount)| UnknownType { index, mount })
+			.collect()
+	}
+}
+
+/// A mount whose filesystem type isn't registered with the kernel, see [validate()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownType<'a> {
+	/// Position of the mount in the table that was validated.
+	pub index: usize,
+	/// The offending mount.
+	pub mount: &'a Mount,
+}
+
+impl std::fmt::Display for UnknownType<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "mount {} ({}) has unknown filesystem type {:?}", self.index, self.mount.mount_point, self.mount.file_system_type)
+	}
+}
+
+/// Strict mode: checks every mount's filesystem type against [FILESYSTEMS_PATH] and returns the ones the kernel doesn't know about.  An empty result means the table passed.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::filesystems;
+/// let mounts: Vec<_> = nom_tutorial::mounts().unwrap().into_iter().map(|m| m.unwrap()).collect();
+/// for unknown in filesystems::validate(&mounts).unwrap() {
+///     eprintln!("{}", unknown);
+/// }
+/// ```
+#[cfg(feature = "io")]
+pub fn validate(mounts: &[Mount]) -> std::result::Result<std::vec::Vec<UnknownType<'_>>, BoxError> {
+	Ok(KnownFileSystems::read()?.unknown(mounts))
+}
+
+mod parsers {
+	use super::FileSystem;
+
+	// Parses a line such as `nodev\tproc` or `\text4`.
+	pub fn parse_line(i: &str) -> nom::IResult<&str, FileSystem> {
+		let (i, (nodev, _, name)) = nom::combinator::all_consuming(nom::sequence::tuple((
+			nom::combinator::opt(nom::bytes::complete::tag("nodev")),
+			nom::character::complete::space1,
+			crate::parsers::not_whitespace
+		)))(i)?;
+		Ok((i, FileSystem { name: name.to_string(), nodev: nodev.is_some() }))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_parse_line() {
+			assert_eq!(parse_line("nodev\tsysfs"), Ok(("", FileSystem { name: "sysfs".to_string(), nodev: true })));
+			assert_eq!(parse_line("\text4"), Ok(("", FileSystem { name: "ext4".to_string(), nodev: false })));
+			assert!(parse_line("ext4").is_err());
+			assert!(parse_line("\text4 trailing").is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A truncated line that still parses is caught by its filesystem type, and FUSE subtypes are accepted.
+	#[test]
+	fn test_unknown() {
+		let known = KnownFileSystems::parse("nodev\tproc\n\text4\nnodev\tfuse\n").unwrap();
+		let mount = |file_system_type: &str| Mount {
+			device: "dev".to_string(),
+			mount_point: "/mnt".to_string(),
+			file_system_type: file_system_type.to_string(),
+			options: vec!["rw".to_string()]
+		};
+		let mounts = [mount("ext4"), mount("fuse.sshfs"), mount("ex"), mount("proc")];
+		assert_eq!(known.unknown(&mounts), vec![UnknownType { index: 2, mount: &mounts[2] }]);
+	}
+
+	// A saved copy of /proc/filesystems validates a mount table read from the same machine.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_read_from() {
+		let path = std::env::temp_dir().join(format!("nom-tutorial-filesystems-{}", std::process::id()));
+		std::fs::write(&path, "nodev\tsysfs\nnodev\ttmpfs\nnodev\tproc\n\text4\n\tvfat\nnodev\tfuse\n").unwrap();
+		let known = KnownFileSystems::read_from(&path);
+		std::fs::remove_file(&path).unwrap();
+		let table = "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\nsysfs /sys sysfs rw 0 0\ntmpfs /run tmpfs rw 0 0\n/dev/sda2 /boot/efi vfat rw 0 0\nsshfs#host: /mnt fuse.sshfs rw 0 0\n";
+		let mounts: std::vec::Vec<Mount> = table.lines().map(|line| crate::parsers::parse(line, crate::model::Format::Mounts).unwrap()).collect();
+		assert_eq!(known.unwrap().unknown(&mounts), vec![]);
+		assert!(KnownFileSystems::read_from(&path).is_err());
+	}
+}
@@ -0,0 +1,491 @@
+//! Selecting mounts by their fields, as done by the filter options of `nom-tutorial`.
+//!
+//! Filters can be built in code, or parsed from a small expression language that fits in a single command line argument or config file entry:
+//!
+//! ```text
+//! fstype==ext4 && !opt(ro) && target^="/srv"
+//! ```
+//!
+//! A comparison is a field (`source`, `target`, `fstype`, or `fsfamily`), an operator, and a value, which is quoted if it contains spaces or punctuation.  The operators are `==` and `!=` for equality, `^=`, `$=`, and `*=` for starts with, ends with, and contains, and `=~` for a regular expression (with the `regex` feature).  `opt(name)` is true if the mount has the option `name`, with or without a value.  `owner(1000)` or `owner(alice)` is true if the mount belongs to the user with that ID or name, see [FilterExpr::OwnedBy].  Conditions are combined with `!`, `&&`, `||`, and parentheses, with the usual precedence.  Negations and parentheses can be nested at most 64 deep, so that a hostile expression can't overflow the stack.
+
+use super::Mount;
+
+/// A field of a [Mount] that a [FilterExpr] can test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+	/// [Mount::device], called the source by `findmnt`.
+	Source,
+	/// [Mount::mount_point], called the target by `findmnt`.
+	Target,
+	/// [Mount::file_system_type]
+	FsType,
+	/// [Mount::fs_family()], e.g. fuse for every FUSE filesystem.
+	FsFamily,
+}
+
+impl Field {
+	/// The value of this field of `mount`.
+	pub fn value(self, mount: &Mount) -> &str {
+		match self {
+			Field::Source => &mount.device,
+			Field::Target => &mount.mount_point,
+			Field::FsType => &mount.file_system_type,
+			Field::FsFamily => mount.fs_family(),
+		}
+	}
+}
+
+/// A condition on a mount.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::filter::{Field, FilterExpr};
+/// # #[cfg(feature = "regex")] {
+/// let filter = FilterExpr::All(vec![
+///     FilterExpr::Regex(Field::Target, regex::Regex::new("^/var/lib/kubelet/pods/.*").unwrap()),
+///     FilterExpr::Equals(Field::FsType, "tmpfs".to_string()),
+/// ]);
+/// let mount = Mount { mount_point: "/var/lib/kubelet/pods/1234/volumes".to_string(), file_system_type: "tmpfs".to_string(), ..Default::default() };
+/// assert!(filter.matches(&mount));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum FilterExpr {
+	/// True if the field is exactly this string.
+	Equals(Field, std::string::String),
+	/// True if the field starts with this string.
+	StartsWith(Field, std::string::String),
+	/// True if the field ends with this string.
+	EndsWith(Field, std::string::String),
+	/// True if the field contains this string.
+	Contains(Field, std::string::String),
+	/// True if the mount has this option.  An option name without a value also matches the option with any value, so `size` matches `size=1G`.
+	HasOption(std::string::String),
+	/// True if the mount belongs to the user with ID `uid` or called `name`, going by the options that record an owner: `uid=` for filesystems such as vfat whose files all belong to one user, `user_id=` for FUSE filesystems, and `user=` for mounts made by a user through `/etc/fstab`, which mount(8) records in utab.  The kernel doesn't report `user=`, so merge utab into the table first to see those mounts, see [utab::merge()](crate::utab::merge).
+	OwnedBy {
+		/// The user ID to match against `uid=` and `user_id=`.
+		uid: std::option::Option<u32>,
+		/// The user name to match against `user=`.
+		name: std::option::Option<std::string::String>,
+	},
+	/// True if the regular expression matches anywhere in the field.  Anchor it with `^` and `$` to match the whole field.
+	#[cfg(feature = "regex")]
+	Regex(Field, regex::Regex),
+	/// True if the whole field matches the shell-style pattern, see [glob_match()].
+	Glob(Field, std::string::String),
+	/// True if every expression is true, including when there are none.
+	All(std::vec::Vec<FilterExpr>),
+	/// True if any expression is true, which is never the case when there are none.
+	Any(std::vec::Vec<FilterExpr>),
+	/// True if the expression is false.
+	Not(std::boxed::Box<FilterExpr>),
+}
+
+impl FilterExpr {
+	/// Evaluates the condition against `mount`.
+	pub fn matches(&self, mount: &Mount) -> bool {
+		match self {
+			FilterExpr::Equals(field, value) => field.value(mount) == value,
+			FilterExpr::StartsWith(field, value) => field.value(mount).starts_with(&value[..]),
+			FilterExpr::EndsWith(field, value) => field.value(mount).ends_with(&value[..]),
+			FilterExpr::Contains(field, value) => field.value(mount).contains(&value[..]),
+			FilterExpr::HasOption(name) => mount.options.iter().any(|option| option == name || option.strip_prefix(&name[..]).is_some_and(|rest| rest.starts_with('='))),
+			FilterExpr::OwnedBy { uid, name } => {
+				let has_uid = |id: std::result::Result<std::option::Option<u32>, crate::options::OptionValueError>| uid.is_some() && id.ok().flatten() == *uid;
+				has_uid(mount.uid()) || has_uid(mount.fuse_user_id()) || name.as_ref().is_some_and(|name| mount.user_name() == Some(&name[..]))
+			},
+			#[cfg(feature = "regex")]
+			FilterExpr::Regex(field, regex) => regex.is_match(field.value(mount)),
+			FilterExpr::Glob(field, pattern) => glob_match(pattern, field.value(mount)),
+			FilterExpr::All(exprs) => exprs.iter().all(|expr| expr.matches(mount)),
+			FilterExpr::Any(exprs) => exprs.iter().any(|expr| expr.matches(mount)),
+			FilterExpr::Not(expr) => !expr.matches(mount),
+		}
+	}
+
+	/// A filter for the mounts of the user running this process, see [FilterExpr::OwnedBy].  The user's name is looked up in `/etc/passwd`, and if it isn't there only the ID is matched.
+	/// # Examples
+	/// ```no_run
+	/// # use nom_tutorial::filter::FilterExpr;
+	/// let mut mounts = nom_tutorial::utab::merge(&nom_tutorial::snapshot().unwrap().into_mounts(), &nom_tutorial::utab::read().unwrap());
+	/// FilterExpr::current_user().unwrap().retain(&mut mounts);
+	/// for mount in mounts {
+	///     println!("{}", mount);
+	/// }
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn current_user() -> std::result::Result<FilterExpr, crate::BoxError> {
+		let uid = real_uid(&std::fs::read_to_string("/proc/self/status")?)?;
+		let name = match std::fs::read_to_string("/etc/passwd") {
+			Ok(passwd) => user_name(&passwd, uid),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+			Err(e) => return Err(e.into())
+		};
+		Ok(FilterExpr::OwnedBy { uid: Some(uid), name })
+	}
+
+	/// Keeps only the mounts in `mounts` that match.
+	pub fn retain(&self, mounts: &mut std::vec::Vec<Mount>) {
+		mounts.retain(|mount| self.matches(mount));
+	}
+}
+
+impl std::str::FromStr for FilterExpr {
+	type Err = crate::ParseError;
+
+	/// Parses an expression in the language described in the [module documentation](self).
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::filter::FilterExpr;
+	/// let filter: FilterExpr = "fstype==ext4 && !opt(ro) && target^=\"/srv\"".parse().unwrap();
+	/// let mount = Mount { mount_point: "/srv/www".to_string(), file_system_type: "ext4".to_string(), options: vec!["rw".to_string()], ..Default::default() };
+	/// assert!(filter.matches(&mount));
+	/// assert!("fstype==".parse::<FilterExpr>().is_err());
+	/// ```
+	fn from_str(s: &str) -> std::result::Result<FilterExpr, crate::ParseError> {
+		match parsers::parse_filter(s) {
+			Ok((_, expr)) => Ok(expr),
+			Err(e) => Err(crate::ParseError::new(s, e))
+		}
+	}
+}
+
+impl Default for FilterExpr {
+	/// The filter that matches every mount.
+	fn default() -> FilterExpr {
+		FilterExpr::All(std::vec::Vec::new())
+	}
+}
+
+// Finds the real user ID in the contents of `/proc/self/status`, the first of the IDs on the `Uid:` line.
+#[cfg(feature = "io")]
+fn real_uid(status: &str) -> std::result::Result<u32, crate::BoxError> {
+	Ok(status.lines()
+		.find_map(|line| line.strip_prefix("Uid:"))
+		.and_then(|ids| ids.split_whitespace().next())
+		.ok_or("no Uid: line in /proc/self/status")?
+		.parse()?)
+}
+
+// Looks up the name of the user with ID `uid` in the contents of `/etc/passwd`, whose lines are `name:password:uid:...`.
+#[cfg(feature = "io")]
+fn user_name(passwd: &str, uid: u32) -> std::option::Option<std::string::String> {
+	passwd.lines().find_map(|line| {
+		let mut fields = line.split(':');
+		let name = fields.next()?;
+		(fields.nth(1)?.parse() == Ok(uid)).then(|| name.to_string())
+	})
+}
+
+/// Matches `text` against a shell-style glob `pattern`, treating `/` as a path separator.
+///
+/// `*` matches any run of characters other than `/`, `**` matches anything including `/` (and `**/` may also match nothing), `?` matches one character other than `/`, and `[abc]`, `[a-z]`, or `[!abc]` match one character from, or not from, a set.  Any other character, including a `[` without a closing `]`, matches itself.  The whole of `text` must match.
+/// # Examples
+/// ```
+/// # use nom_tutorial::filter::glob_match;
+/// assert!(glob_match("/mnt/*/data", "/mnt/disk1/data"));
+/// assert!(!glob_match("/mnt/*/data", "/mnt/a/b/data"));
+/// assert!(glob_match("/mnt/**/data", "/mnt/a/b/data"));
+/// assert!(glob_match("/dev/sd[a-c]?", "/dev/sdb1"));
+/// ```
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+	let pattern: std::vec::Vec<char> = pattern.chars().collect();
+	let text: std::vec::Vec<char> = text.chars().collect();
+	glob_match_chars(&pattern, &text)
+}
+
+// Recursive matcher behind [glob_match()].
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+	match pattern {
+		[] => text.is_empty(),
+		['*', '*', rest @ ..] => {
+			if let ['/', after_slash @ ..] = rest {
+				if glob_match_chars(after_slash, text) {
+					return true;
+				}
+			}
+			(0..=text.len()).any(|skip| glob_match_chars(rest, &text[skip..]))
+		},
+		['*', rest @ ..] => {
+			let component = text.iter().position(|&c| c == '/').unwrap_or(text.len());
+			(0..=component).any(|skip| glob_match_chars(rest, &text[skip..]))
+		},
+		['?', rest @ ..] => matches!(text, [c, ..] if *c != '/') && glob_match_chars(rest, &text[1..]),
+		['[', class @ ..] => match parse_class(class) {
+			Some((negated, set, rest)) => match text {
+				[c, remaining @ ..] if *c != '/' && class_contains(set, *c) != negated => glob_match_chars(rest, remaining),
+				_ => false
+			},
+			None => matches!(text, ['[', ..]) && glob_match_chars(class, &text[1..])
+		},
+		[literal, rest @ ..] => matches!(text, [c, ..] if c == literal) && glob_match_chars(rest, &text[1..])
+	}
+}
+
+// Splits the body of a `[...]` class (after the `[`) into whether it is negated, its members, and the rest of the pattern.  Returns `None` if the class isn't closed.
+fn parse_class(class: &[char]) -> std::option::Option<(bool, &[char], &[char])> {
+	let (negated, body) = match class {
+		['!', body @ ..] => (true, body),
+		body => (false, body)
+	};
+	// A `]` right at the start is a member rather than the end of the class.
+	let end = body.iter().skip(1).position(|&c| c == ']')? + 1;
+	Some((negated, &body[..end], &body[end + 1..]))
+}
+
+// True if `c` is one of the members of a class, which may include ranges like `a-z`.
+fn class_contains(set: &[char], c: char) -> bool {
+	let mut i = 0;
+	while i < set.len() {
+		if i + 2 < set.len() && set[i + 1] == '-' {
+			if set[i] <= c && c <= set[i + 2] {
+				return true;
+			}
+			i += 3;
+		} else {
+			if set[i] == c {
+				return true;
+			}
+			i += 1;
+		}
+	}
+	false
+}
+
+mod parsers {
+	use super::{Field, FilterExpr};
+	use nom::bytes::complete::tag;
+	use nom::character::complete::{char, multispace0};
+
+	// Wraps a parser to skip whitespace around it.
+	fn ws<'a, O, F: Fn(&'a str) -> nom::IResult<&'a str, O>>(parser: F) -> impl Fn(&'a str) -> nom::IResult<&'a str, O> {
+		nom::sequence::delimited(multispace0, parser, multispace0)
+	}
+
+	fn field(i: &str) -> nom::IResult<&str, Field> {
+		nom::branch::alt((
+			nom::combinator::value(Field::Source, tag("source")),
+			nom::combinator::value(Field::Target, tag("target")),
+			nom::combinator::value(Field::FsType, tag("fstype")),
+			nom::combinator::value(Field::FsFamily, tag("fsfamily")),
+		))(i)
+	}
+
+	// A double-quoted string in which `\"` and `\\` stand for a quote and a backslash.
+	fn quoted(i: &str) -> nom::IResult<&str, std::string::String> {
+		let escaped = nom::bytes::complete::escaped_transform(nom::bytes::complete::is_not("\\\""), '\\', nom::branch::alt((
+			nom::combinator::value("\\", tag("\\")),
+			nom::combinator::value("\"", tag("\"")),
+		)));
+		nom::sequence::delimited(char('"'), nom::combinator::map(nom::combinator::opt(escaped), std::option::Option::unwrap_or_default), char('"'))(i)
+	}
+
+	// A quoted string, or a bare word that runs until whitespace or a character with a meaning in the language.
+	fn value(i: &str) -> nom::IResult<&str, std::string::String> {
+		nom::branch::alt((quoted, nom::combinator::map(nom::bytes::complete::is_not(" \t\r\n()&|!\""), str::to_string)))(i)
+	}
+
+	// A comparison such as `fstype==ext4`.
+	fn comparison(i: &str) -> nom::IResult<&str, FilterExpr> {
+		let (i, field) = ws(field)(i)?;
+		let (i, operator) = nom::branch::alt((tag("=="), tag("!="), tag("^="), tag("$="), tag("*="), tag("=~")))(i)?;
+		let (rest, value) = ws(value)(i)?;
+		let expr = match operator {
+			"==" => FilterExpr::Equals(field, value),
+			"!=" => FilterExpr::Not(std::boxed::Box::new(FilterExpr::Equals(field, value))),
+			"^=" => FilterExpr::StartsWith(field, value),
+			"$=" => FilterExpr::EndsWith(field, value),
+			"*=" => FilterExpr::Contains(field, value),
+			_ => regex(field, &value).ok_or(nom::Err::Failure((i, nom::error::ErrorKind::MapRes)))?
+		};
+		Ok((rest, expr))
+	}
+
+	#[cfg(feature = "regex")]
+	fn regex(field: Field, pattern: &str) -> std::option::Option<FilterExpr> {
+		regex::Regex::new(pattern).ok().map(|regex| FilterExpr::Regex(field, regex))
+	}
+
+	// Regular expressions are a syntax error without the `regex` feature.
+	#[cfg(not(feature = "regex"))]
+	fn regex(_field: Field, _pattern: &str) -> std::option::Option<FilterExpr> {
+		None
+	}
+
+	// `opt(name)`
+	fn option(i: &str) -> nom::IResult<&str, FilterExpr> {
+		nom::combinator::map(
+			nom::sequence::delimited(ws(tag("opt(")), ws(value), char(')')),
+			FilterExpr::HasOption
+		)(i)
+	}
+
+	// `owner(1000)` or `owner(alice)`
+	fn owner(i: &str) -> nom::IResult<&str, FilterExpr> {
+		nom::combinator::map(
+			nom::sequence::delimited(ws(tag("owner(")), ws(value), char(')')),
+			|user| match user.parse() {
+				Ok(uid) => FilterExpr::OwnedBy { uid: Some(uid), name: None },
+				Err(_) => FilterExpr::OwnedBy { uid: None, name: Some(user) }
+			}
+		)(i)
+	}
+
+	// How deeply negations and parentheses can be nested before parsing fails, which keeps the recursion off the end of the stack.
+	pub const MAX_DEPTH: usize = 64;
+
+	// A negation, a parenthesized expression, or a single condition, `depth` levels of nesting down.
+	fn unary(i: &str, depth: usize) -> nom::IResult<&str, FilterExpr> {
+		if depth > MAX_DEPTH {
+			return Err(nom::Err::Failure((i, nom::error::ErrorKind::TooLarge)));
+		}
+		ws(nom::branch::alt((
+			nom::combinator::map(nom::sequence::preceded(char('!'), |i| unary(i, depth + 1)), |expr| FilterExpr::Not(std::boxed::Box::new(expr))),
+			nom::sequence::delimited(char('('), |i| any(i, depth + 1), char(')')),
+			option,
+			owner,
+			comparison,
+		)))(i)
+	}
+
+	// Conditions joined by `&&`, which binds more tightly than `||`.
+	fn all(i: &str, depth: usize) -> nom::IResult<&str, FilterExpr> {
+		nom::combinator::map(nom::multi::separated_nonempty_list(tag("&&"), |i| unary(i, depth)), |mut exprs| {
+			if exprs.len() == 1 { exprs.remove(0) } else { FilterExpr::All(exprs) }
+		})(i)
+	}
+
+	// Conditions joined by `||`.
+	fn any(i: &str, depth: usize) -> nom::IResult<&str, FilterExpr> {
+		nom::combinator::map(nom::multi::separated_nonempty_list(tag("||"), |i| all(i, depth)), |mut exprs| {
+			if exprs.len() == 1 { exprs.remove(0) } else { FilterExpr::Any(exprs) }
+		})(i)
+	}
+
+	// Parses a whole filter expression.
+	pub fn parse_filter(i: &str) -> nom::IResult<&str, FilterExpr> {
+		nom::combinator::all_consuming(|i| any(i, 0))(i)
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_quoted() {
+			assert_eq!(quoted("\"a \\\"b\\\\\" rest"), Ok((" rest", "a \"b\\".to_string())));
+			assert_eq!(quoted("\"\""), Ok(("", std::string::String::new())));
+		}
+
+		#[test]
+		fn test_precedence() {
+			match parse_filter("fstype==a || fstype==b && !opt(ro)") {
+				Ok(("", FilterExpr::Any(exprs))) => assert!(matches!(&exprs[1], FilterExpr::All(all) if all.len() == 2)),
+				result => panic!("unexpected {:?}", result)
+			}
+		}
+
+		// Nesting past the limit is a parse error rather than a stack overflow.
+		#[test]
+		fn test_max_depth() {
+			let nested = |depth: usize| format!("{}{}fstype==ext4{}", "!".repeat(depth), "(".repeat(depth), ")".repeat(depth));
+			assert!(parse_filter(&nested(MAX_DEPTH / 2)).is_ok());
+			assert!(matches!(parse_filter(&nested(MAX_DEPTH)), Err(nom::Err::Failure((_, nom::error::ErrorKind::TooLarge)))));
+			assert!(parse_filter(&"!".repeat(100_000)).is_err());
+			assert!(parse_filter(&"(".repeat(100_000)).is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_equals() {
+		let mount = Mount { device: "tmpfs".to_string(), file_system_type: "tmpfs".to_string(), ..Default::default() };
+		assert!(FilterExpr::Equals(Field::FsType, "tmpfs".to_string()).matches(&mount));
+		assert!(!FilterExpr::Equals(Field::FsType, "tmp".to_string()).matches(&mount));
+	}
+
+	// Every FUSE filesystem is in the fuse family, whatever its subtype and whether or not it is backed by a block device.
+	#[test]
+	fn test_fs_family() {
+		let filter: FilterExpr = "fsfamily==fuse".parse().unwrap();
+		let mount = |fs_type: &str| Mount { file_system_type: fs_type.to_string(), ..Default::default() };
+		assert!(filter.matches(&mount("fuse.sshfs")));
+		assert!(filter.matches(&mount("fuse")));
+		assert!(filter.matches(&mount("fuseblk")));
+		assert!(!filter.matches(&mount("fusectl")));
+	}
+
+	#[test]
+	fn test_glob_match() {
+		assert!(glob_match("/var/lib/docker/*", "/var/lib/docker/overlay2"));
+		assert!(!glob_match("/var/lib/docker/*", "/var/lib/docker/overlay2/abc/merged"));
+		assert!(glob_match("/var/lib/docker/**/merged", "/var/lib/docker/overlay2/abc/merged"));
+		assert!(glob_match("/**", "/"));
+		assert!(glob_match("/mnt/[!a]*", "/mnt/backup"));
+		assert!(!glob_match("/mnt/[!a]*", "/mnt/archive"));
+		assert!(glob_match("/mnt/[]x]", "/mnt/]"));
+		assert!(glob_match("/mnt/[ab", "/mnt/[ab"));
+		assert!(!glob_match("/mnt/?", "/mnt/ab"));
+		let mount = Mount { mount_point: "/mnt/usb/data".to_string(), ..Default::default() };
+		assert!(FilterExpr::Glob(Field::Target, "/mnt/*/data".to_string()).matches(&mount));
+	}
+
+	#[test]
+	fn test_parse() {
+		let mount = Mount {
+			device: "/dev/sda1".to_string(),
+			mount_point: "/srv/my data".to_string(),
+			file_system_type: "ext4".to_string(),
+			options: vec!["rw".to_string(), "commit=30".to_string()]
+		};
+		let matches = |expr: &str| expr.parse::<FilterExpr>().unwrap().matches(&mount);
+		assert!(matches("fstype==ext4 && !opt(ro) && target^=\"/srv\""));
+		assert!(matches("opt(commit) && opt(commit=30) && !opt(comm)"));
+		assert!(matches("(fstype==xfs || fstype==ext4) && source$=sda1 && target*=\"my data\""));
+		assert!(!matches("!(fstype!=ext4)  &&  fstype == xfs"));
+		let error = "fstype==ext4 &&".parse::<FilterExpr>().unwrap_err();
+		assert_eq!(error.offset, 13);
+	}
+
+	// Each of the options that record an owner counts, and a user ID never matches a user name.
+	#[test]
+	fn test_owned_by() {
+		let mount = |option: &str| crate::test_fixtures::mount("/dev/sdb1", "/media/usb", "vfat", &["rw", option]);
+		let matches = |expr: &str, option: &str| expr.parse::<FilterExpr>().unwrap().matches(&mount(option));
+		assert!(matches("owner(1000)", "uid=1000"));
+		assert!(matches("owner(1000)", "user_id=1000"));
+		assert!(!matches("owner(1000)", "uid=0"));
+		assert!(matches("owner(alice)", "user=alice"));
+		assert!(!matches("owner(alice)", "user"));
+		assert!(!matches("owner(1000)", "user=1000"));
+		let both = FilterExpr::OwnedBy { uid: Some(1000), name: Some("alice".to_string()) };
+		assert!(both.matches(&mount("user=alice")) && both.matches(&mount("uid=1000")) && !both.matches(&mount("uid=wheel")));
+	}
+
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_user_name() {
+		let passwd = "root:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000::/home/alice:/bin/sh\nbroken\n";
+		assert_eq!(user_name(passwd, 1000).as_deref(), Some("alice"));
+		assert_eq!(user_name(passwd, 1001), None);
+		assert_eq!(real_uid("Name:\tbash\nUid:\t1000\t0\t0\t0\nGid:\t1000\t1000\t1000\t1000\n").unwrap(), 1000);
+		assert!(real_uid("Name:\tbash\n").is_err());
+	}
+
+	#[cfg(feature = "regex")]
+	#[test]
+	fn test_regex() {
+		let mount = Mount { device: "/dev/sda1".to_string(), mount_point: "/srv/data".to_string(), ..Default::default() };
+		let regex = |field, pattern| FilterExpr::Regex(field, regex::Regex::new(pattern).unwrap());
+		assert!(regex(Field::Target, "^/srv/").matches(&mount));
+		assert!(!regex(Field::Source, "^/srv/").matches(&mount));
+		assert!(!FilterExpr::All(vec![regex(Field::Target, "data"), regex(Field::Source, "sdb")]).matches(&mount));
+		assert!(FilterExpr::default().matches(&mount));
+	}
+}
@@ -0,0 +1,919 @@
+//! Parses `/etc/fstab` into a document model that preserves comments, blank lines, and whitespace.
+//!
+//! Unlike `/proc/mounts`, `fstab` is written by humans who care about its layout.  [Fstab] keeps every line of the original file, and every entry remembers its original text and spacing, so that a parse-edit-write cycle reproduces the file byte-for-byte except for the entries that were actually edited.
+//! # Examples
+//! ```
+//! # use nom_tutorial::fstab::Fstab;
+//! let text = "# <file system> <mount point> <type> <options> <dump> <pass>\n\
+//!             UUID=1234-ABCD  /boot/efi  vfat  umask=0077  0  1\n\
+//!             \n\
+//!             /dev/sda2       /          ext4  defaults    0  1\n";
+//! let mut fstab = Fstab::parse(text).unwrap();
+//! assert_eq!(fstab.to_string(), text);
+//! fstab.entries_mut().nth(1).unwrap().options = vec!["defaults".to_string(), "noatime".to_string()];
+//! assert!(fstab.to_string().ends_with("/dev/sda2       /          ext4  defaults,noatime    0  1\n"));
+//! ```
+
+use super::Mount;
+
+/// Error parsing an `fstab` file, identifying where the problem is.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+	/// The line number, starting from 1.
+	pub line: usize,
+	/// The column (in characters) where the offending field starts, starting from 1.
+	pub column: usize,
+	/// Description of the problem.
+	pub message: std::string::String,
+}
+
+impl std::fmt::Display for ParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+	}
+}
+
+impl std::error::Error for ParseError {}
+
+/// A single filesystem described by a line in `fstab`.  See `man 5 fstab`.
+#[derive(Clone, Default, Debug)]
+pub struct FstabEntry {
+	/// The block device or remote filesystem to mount, e.g. /dev/sda1 or UUID=...  (`fs_spec`)
+	pub device: std::string::String,
+	/// Where to mount the filesystem, e.g. /mnt/disk  (`fs_file`)
+	pub mount_point: std::string::String,
+	/// The filesystem type, e.g. ext4  (`fs_vfstype`)
+	pub file_system_type: std::string::String,
+	/// The mount options, e.g. ["defaults", "noatime"]  (`fs_mntops`)
+	pub options: std::vec::Vec<std::string::String>,
+	/// Whether the filesystem should be backed up by dump(8).  (`fs_freq`)
+	pub dump: u32,
+	/// The order in which fsck(8) checks the filesystem, 0 to skip.  (`fs_passno`)
+	pub pass: u32,
+	// How the entry was originally written, if it was parsed rather than constructed.
+	layout: std::option::Option<std::boxed::Box<Layout>>,
+}
+
+// The original text of a parsed entry.
+#[derive(Clone, Debug)]
+struct Layout {
+	// Whitespace before the first field.
+	leading: std::string::String,
+	// The raw text of each field and the whitespace following it.  There are 4 to 6 fields because dump and pass are optional.
+	fields: std::vec::Vec<(std::string::String, std::string::String)>,
+	// The decoded values as parsed, used to tell which fields have been edited since.
+	original: [std::string::String; 6],
+}
+
+impl FstabEntry {
+	/// Creates a new entry with dump and pass set to 0.
+	pub fn new(device: &str, mount_point: &str, file_system_type: &str, options: &[&str]) -> FstabEntry {
+		FstabEntry {
+			device: device.to_string(),
+			mount_point: mount_point.to_string(),
+			file_system_type: file_system_type.to_string(),
+			options: options.iter().map(|o| o.to_string()).collect(),
+			..Default::default()
+		}
+	}
+
+	// Decoded values of the six fields, in order, for comparison with `Layout::original`.
+	fn values(&self) -> [std::string::String; 6] {
+		[
+			self.device.clone(),
+			self.mount_point.clone(),
+			self.file_system_type.clone(),
+			self.options.join(","),
+			self.dump.to_string(),
+			self.pass.to_string(),
+		]
+	}
+
+	// Escaped text of the six fields, in order, as they would be written for a new entry.
+	fn escaped_values(&self) -> [std::string::String; 6] {
+		let options: std::vec::Vec<std::string::String> = self.options.iter().map(|o| escape(o)).collect();
+		[
+			escape(&self.device),
+			escape(&self.mount_point),
+			escape(&self.file_system_type),
+			crate::model::join_options(&options),
+			self.dump.to_string(),
+			self.pass.to_string(),
+		]
+	}
+}
+
+impl PartialEq for FstabEntry {
+	/// Entries are equal if their values are equal, regardless of how they were written.
+	fn eq(&self, other: &FstabEntry) -> bool {
+		self.values() == other.values()
+	}
+}
+
+impl Eq for FstabEntry {}
+
+impl std::fmt::Display for FstabEntry {
+	/// Writes the entry as a line of `fstab` without the newline.  Parsed entries keep their original spacing, and fields that have not been edited keep their original text.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let values = self.values();
+		let escaped = self.escaped_values();
+		match &self.layout {
+			Some(layout) => {
+				write!(f, "{}", layout.leading)?;
+				for (i, (raw, separator)) in layout.fields.iter().enumerate() {
+					if values[i] == layout.original[i] {
+						write!(f, "{}{}", raw, separator)?;
+					} else {
+						write!(f, "{}{}", escaped[i], separator)?;
+					}
+				}
+				// Dump and pass were omitted but have since been set.
+				let written = layout.fields.len();
+				if written < 6 && (self.dump != 0 || self.pass != 0) {
+					if written < 5 {
+						write!(f, " {}", escaped[4])?;
+					}
+					write!(f, " {}", escaped[5])?;
+				}
+				Ok(())
+			},
+			None => write!(f, "{}", escaped.join(" "))
+		}
+	}
+}
+
+impl From<&FstabEntry> for Mount {
+	/// Converts to the [Mount] the entry describes.  Dump and pass are discarded.
+	fn from(entry: &FstabEntry) -> Mount {
+		Mount {
+			device: entry.device.clone(),
+			mount_point: entry.mount_point.clone(),
+			file_system_type: entry.file_system_type.clone(),
+			options: entry.options.clone()
+		}
+	}
+}
+
+// Escapes whitespace and backslashes in an fstab field.
+fn escape(field: &str) -> std::string::String {
+	field.replace('\\', "\\134").replace(' ', "\\040").replace('\t', "\\011").replace('\n', "\\012")
+}
+
+/// A line of an `fstab` file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Line {
+	/// A filesystem entry.
+	Entry(FstabEntry),
+	/// A comment, stored verbatim including any leading whitespace and the `#`.
+	Comment(std::string::String),
+	/// A line containing only whitespace, stored verbatim.
+	Blank(std::string::String),
+}
+
+impl std::fmt::Display for Line {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Line::Entry(entry) => write!(f, "{}", entry),
+			Line::Comment(text) | Line::Blank(text) => write!(f, "{}", text)
+		}
+	}
+}
+
+/// An `fstab` file.  Formatting it with `Display` (or `to_string()`) reproduces the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Fstab {
+	/// Every line in the file, in order.
+	pub lines: std::vec::Vec<Line>,
+	// Whether the last line was terminated by a newline.
+	trailing_newline: bool,
+}
+
+impl Fstab {
+	/// Parses the contents of an `fstab` file.
+	pub fn parse(text: &str) -> std::result::Result<Fstab, ParseError> {
+		let trailing_newline = text.ends_with('\n');
+		let body = if trailing_newline { &text[..text.len() - 1] } else { text };
+		let mut lines = std::vec::Vec::new();
+		if !text.is_empty() {
+			for (number, line) in body.split('\n').enumerate() {
+				lines.push(parsers::parse_line(line).map_err(|(column, message)| ParseError { line: number + 1, column, message })?);
+			}
+		}
+		Ok(Fstab { lines, trailing_newline })
+	}
+
+	/// Reads and parses the `fstab` file at `path`, e.g. `/etc/fstab`.
+	#[cfg(feature = "io")]
+	pub fn read<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<Fstab, super::BoxError> {
+		Ok(Fstab::parse(&std::fs::read_to_string(path)?)?)
+	}
+
+	/// Iterates over the filesystem entries, skipping comments and blank lines.
+	pub fn entries(&self) -> impl Iterator<Item = &FstabEntry> {
+		self.lines.iter().filter_map(|line| match line {
+			Line::Entry(entry) => Some(entry),
+			_ => None
+		})
+	}
+
+	/// Iterates mutably over the filesystem entries, skipping comments and blank lines.
+	pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut FstabEntry> {
+		self.lines.iter_mut().filter_map(|line| match line {
+			Line::Entry(entry) => Some(entry),
+			_ => None
+		})
+	}
+
+	/// Returns the first entry for `mount_point`, if any.
+	pub fn entry_for(&self, mount_point: &str) -> std::option::Option<&FstabEntry> {
+		self.entries().find(|entry| entry.mount_point == mount_point)
+	}
+
+	/// Appends `entry` to the end of the file.  No attempt is made to detect an existing entry for the same mount point; call [Fstab::remove_entry_for()] first to replace one.
+	pub fn add_entry(&mut self, entry: FstabEntry) {
+		// If the file didn't end in a newline the new entry still goes on its own line, and now the file does end in one.
+		self.trailing_newline = true;
+		self.lines.push(Line::Entry(entry));
+	}
+
+	/// Removes every entry for `mount_point` and returns them.  Surrounding comments and blank lines are left alone.
+	pub fn remove_entry_for(&mut self, mount_point: &str) -> std::vec::Vec<FstabEntry> {
+		let mut removed = std::vec::Vec::new();
+		let mut kept = std::vec::Vec::with_capacity(self.lines.len());
+		for line in self.lines.drain(..) {
+			match line {
+				Line::Entry(entry) if entry.mount_point == mount_point => removed.push(entry),
+				line => kept.push(line)
+			}
+		}
+		self.lines = kept;
+		removed
+	}
+
+	/// Replaces the options of every entry for `mount_point`.  Returns `false` if there is no such entry.
+	pub fn set_options(&mut self, mount_point: &str, options: &[&str]) -> bool {
+		let mut found = false;
+		for entry in self.entries_mut().filter(|entry| entry.mount_point == mount_point) {
+			entry.options = options.iter().map(|o| o.to_string()).collect();
+			found = true;
+		}
+		found
+	}
+
+	/// Atomically writes the file to `path`.
+	///
+	/// The contents are written to a temporary file with a unique name in the same directory, flushed to disk, and then renamed over `path`, so readers (including the system at boot) see either the old file or the new one, never a partial write.  If `path` already exists its permissions are copied to the new file.
+	/// # Examples
+	/// ```no_run
+	/// # use nom_tutorial::fstab::{Fstab, FstabEntry};
+	/// let mut fstab = Fstab::read("/etc/fstab").unwrap();
+	/// fstab.remove_entry_for("/data");
+	/// fstab.add_entry(FstabEntry::new("/dev/sdb1", "/data", "xfs", &["defaults", "noatime"]));
+	/// fstab.save("/etc/fstab").unwrap();
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::result::Result<(), std::io::Error> {
+		use std::io::Write;
+		let path = path.as_ref();
+		let directory = match path.parent() {
+			Some(parent) if !parent.as_os_str().is_empty() => parent,
+			_ => std::path::Path::new(".")
+		};
+		// Every save gets its own temporary file, so saves from several threads don't collide.
+		static SAVES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+		let (temp_path, mut file) = loop {
+			let mut temp_name = std::ffi::OsString::from(".");
+			temp_name.push(path.file_name().unwrap_or_else(|| std::ffi::OsStr::new("fstab")));
+			temp_name.push(format!(".tmp.{}.{}", std::process::id(), SAVES.fetch_add(1, std::sync::atomic::Ordering::Relaxed)));
+			let temp_path = directory.join(temp_name);
+			match std::fs::OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+				Ok(file) => break (temp_path, file),
+				// Left behind by a process that crashed and had the same ID.
+				Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+				Err(e) => return Err(e)
+			}
+		};
+		let result = (|| {
+			file.write_all(self.to_string().as_bytes())?;
+			if let Ok(metadata) = std::fs::metadata(path) {
+				file.set_permissions(metadata.permissions())?;
+			}
+			file.sync_all()?;
+			std::fs::rename(&temp_path, path)?;
+			// Make the rename itself durable.
+			std::fs::File::open(directory)?.sync_all()
+		})();
+		if result.is_err() {
+			let _ = std::fs::remove_file(&temp_path);
+		}
+		result
+	}
+}
+
+/// What a [Duplicate] has in common.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DuplicateKind {
+	/// Several entries mount something at the same mount point.
+	MountPoint(std::string::String),
+	/// The same device is declared with different filesystem types.
+	Device(std::string::String),
+}
+
+/// A set of conflicting entries found by [Fstab::duplicates()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Duplicate {
+	/// The mount point or device the entries share.
+	pub kind: DuplicateKind,
+	/// Line numbers of all the conflicting entries, starting from 1.
+	pub lines: std::vec::Vec<usize>,
+}
+
+impl std::fmt::Display for Duplicate {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let lines: std::vec::Vec<std::string::String> = self.lines.iter().map(|line| line.to_string()).collect();
+		match &self.kind {
+			DuplicateKind::MountPoint(mount_point) => write!(f, "mount point {} is declared on lines {}", mount_point, lines.join(", ")),
+			DuplicateKind::Device(device) => write!(f, "device {} is declared with conflicting filesystem types on lines {}", device, lines.join(", "))
+		}
+	}
+}
+
+// Swap entries don't have a real mount point.
+fn is_swap_mount_point(mount_point: &str) -> bool {
+	mount_point == "none" || mount_point == "swap"
+}
+
+// Pseudo-filesystems like `tmpfs` or `proc` use an arbitrary name as their device, which may legitimately repeat.  Real devices are paths (`/dev/sda1`, `//server/share`, `host:/export`) or tags (`UUID=...`).
+fn is_real_device(device: &str) -> bool {
+	device.contains('/') || device.contains('=')
+}
+
+impl Fstab {
+	/// Finds entries that conflict with each other: entries sharing a mount point (other than swap), and real devices declared more than once with different filesystem types.  Bind mounts are excluded from the device check since their "device" is a directory that is expected to be reused.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::fstab::Fstab;
+	/// let fstab = Fstab::parse("/dev/sda1 /data ext4 defaults\n/dev/sdb1 /data xfs defaults\n").unwrap();
+	/// let duplicates = fstab.duplicates();
+	/// assert_eq!(duplicates[0].lines, vec![1, 2]);
+	/// ```
+	pub fn duplicates(&self) -> std::vec::Vec<Duplicate> {
+		// Group line numbers by key, remembering the order in which keys were first seen.
+		let mut mount_points: std::vec::Vec<(&str, std::vec::Vec<usize>)> = std::vec::Vec::new();
+		let mut devices: std::vec::Vec<(&str, std::vec::Vec<(usize, &str)>)> = std::vec::Vec::new();
+		for (index, line) in self.lines.iter().enumerate() {
+			let entry = match line {
+				Line::Entry(entry) => entry,
+				_ => continue
+			};
+			let number = index + 1;
+			if !is_swap_mount_point(&entry.mount_point) {
+				match mount_points.iter_mut().find(|(key, _)| *key == entry.mount_point) {
+					Some((_, lines)) => lines.push(number),
+					None => mount_points.push((&entry.mount_point, vec![number]))
+				}
+			}
+			let bind = entry.options.iter().any(|o| o == "bind" || o == "rbind");
+			if is_real_device(&entry.device) && !bind {
+				match devices.iter_mut().find(|(key, _)| *key == entry.device) {
+					Some((_, lines)) => lines.push((number, &entry.file_system_type)),
+					None => devices.push((&entry.device, vec![(number, &entry.file_system_type)]))
+				}
+			}
+		}
+		let mut duplicates: std::vec::Vec<Duplicate> = mount_points.into_iter()
+			.filter(|(_, lines)| lines.len() > 1)
+			.map(|(mount_point, lines)| Duplicate { kind: DuplicateKind::MountPoint(mount_point.to_string()), lines })
+			.collect();
+		duplicates.extend(devices.into_iter()
+			.filter(|(_, lines)| lines.iter().any(|(_, fs_type)| *fs_type != lines[0].1))
+			.map(|(device, lines)| Duplicate {
+				kind: DuplicateKind::Device(device.to_string()),
+				lines: lines.into_iter().map(|(number, _)| number).collect()
+			}));
+		duplicates
+	}
+}
+
+/// A single difference between two versions of an `fstab` file, see [diff()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FstabEvent {
+	/// An entry was added.
+	Added(FstabEntry),
+	/// An entry was removed.
+	Removed(FstabEntry),
+	/// An entry with the same device and mount point was edited, e.g. its options changed.
+	Changed {
+		/// The entry as it was before the edit.
+		old: FstabEntry,
+		/// The entry as it is after the edit.
+		new: FstabEntry
+	},
+}
+
+/// Computes the differences between the entries of two versions of an `fstab` file.  Comments, blank lines, and layout are ignored.
+///
+/// Entries are matched up the same way as [watch::diff()](crate::watch::diff): by device and mount point, with duplicates matched in order.  Events for entries in `new` come first in file order, followed by removals in the order they appear in `old`.
+/// # Examples
+/// ```
+/// # use nom_tutorial::fstab::{diff, Fstab, FstabEvent};
+/// let old = Fstab::parse("/dev/sda1 / ext4 defaults 0 1\n").unwrap();
+/// let new = Fstab::parse("# root\n/dev/sda1  /  ext4  defaults,noatime  0 1\n").unwrap();
+/// match &diff(&old, &new)[..] {
+///     [FstabEvent::Changed { new, .. }] => assert_eq!(new.options, ["defaults", "noatime"]),
+///     events => panic!("unexpected {:?}", events)
+/// }
+/// ```
+pub fn diff(old: &Fstab, new: &Fstab) -> std::vec::Vec<FstabEvent> {
+	let mut unmatched = std::collections::HashMap::<(&str, &str), std::collections::VecDeque<&FstabEntry>>::new();
+	for entry in old.entries() {
+		unmatched.entry((&entry.device[..], &entry.mount_point[..])).or_default().push_back(entry);
+	}
+	let mut events = std::vec::Vec::new();
+	for entry in new.entries() {
+		match unmatched.get_mut(&(&entry.device[..], &entry.mount_point[..])).and_then(|candidates| candidates.pop_front()) {
+			Some(previous) => if previous != entry {
+				events.push(FstabEvent::Changed { old: previous.clone(), new: entry.clone() });
+			},
+			None => events.push(FstabEvent::Added(entry.clone()))
+		}
+	}
+	for entry in old.entries() {
+		if let Some(candidates) = unmatched.get_mut(&(&entry.device[..], &entry.mount_point[..])) {
+			if candidates.front().is_some_and(|candidate| std::ptr::eq(*candidate, entry)) {
+				candidates.pop_front();
+				events.push(FstabEvent::Removed(entry.clone()));
+			}
+		}
+	}
+	events
+}
+
+/// How [merge()] resolves an entry in the overrides for a mount point that the base already has an entry for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergePolicy {
+	/// The override entry replaces the base entry.
+	#[default]
+	PreferOverrides,
+	/// The base entry is kept and the override entry is dropped.
+	PreferBase,
+	/// The override entry replaces the base entry, but keeps the base entry's options.  Options are matched by name, so `size=2G` in the override replaces `size=1G` in the base, and an option replaces its counterparts too, so `ro` replaces `rw` and `noatime` replaces `relatime`.  Options only in the override are added at the end.
+	MergeOptions,
+}
+
+// The name of an option, without its value.
+fn option_name(option: &str) -> &str {
+	option.split('=').next().unwrap_or(option)
+}
+
+// True if `a` and `b` set the same thing, so that one replaces the other: they have the same name, or they undo each other like `rw` and `ro` or `noatime` and `atime`.
+fn replaces_option(a: &str, b: &str) -> bool {
+	let (a, b) = (option_name(a), option_name(b));
+	let directory = |option: &str| option.ends_with("diratime");
+	a == b
+		|| (["ro", "rw"].contains(&a) && ["ro", "rw"].contains(&b))
+		|| crate::options::OPPOSITES.iter().any(|&(set, unset)| (a == set && b == unset) || (a == unset && b == set))
+		|| (crate::options::ATIME_OPTIONS.contains(&a) && crate::options::ATIME_OPTIONS.contains(&b) && directory(a) == directory(b))
+}
+
+/// Merges two `fstab` files, e.g. a distribution's defaults and a site's overrides, into one.
+///
+/// The result is `base` with the entries of `overrides` merged in.  An override entry for a mount point that `base` already has is resolved by `policy` and takes the place of the base entry; any other override entry is added at the end.  Swap entries never conflict.  Comments and blank lines from both files are kept: those in `base` stay where they are, and those in `overrides` stay with the entry that follows them.
+/// # Examples
+/// ```
+/// # use nom_tutorial::fstab::{merge, Fstab, MergePolicy};
+/// let base = Fstab::parse("/dev/sda1 / ext4 defaults 0 1\ntmpfs /tmp tmpfs mode=1777,size=1G\n").unwrap();
+/// let overrides = Fstab::parse("# bigger /tmp\ntmpfs /tmp tmpfs size=4G\n/dev/sdb1 /data xfs noatime\n").unwrap();
+/// let merged = merge(&base, &overrides, MergePolicy::MergeOptions).to_string();
+/// assert_eq!(merged, "/dev/sda1 / ext4 defaults 0 1\n# bigger /tmp\ntmpfs /tmp tmpfs mode=1777,size=4G\n/dev/sdb1 /data xfs noatime\n");
+/// ```
+pub fn merge(base: &Fstab, overrides: &Fstab, policy: MergePolicy) -> Fstab {
+	// Lines from `overrides` to insert before each line of `base`, and the entry to put in its place, if any.
+	let mut before: std::vec::Vec<std::vec::Vec<Line>> = vec![std::vec::Vec::new(); base.lines.len()];
+	let mut replaced: std::vec::Vec<std::option::Option<FstabEntry>> = vec![None; base.lines.len()];
+	let mut appended = std::vec::Vec::new();
+	let mut pending = std::vec::Vec::new();
+	for line in &overrides.lines {
+		let entry = match line {
+			Line::Entry(entry) => entry,
+			line => { pending.push(line.clone()); continue; }
+		};
+		let conflict = base.lines.iter().enumerate().position(|(index, line)| match line {
+			Line::Entry(other) => replaced[index].is_none() && other.mount_point == entry.mount_point && !is_swap_mount_point(&entry.mount_point),
+			_ => false
+		});
+		match conflict {
+			Some(index) => {
+				let base_entry = match &base.lines[index] {
+					Line::Entry(base_entry) => base_entry,
+					_ => unreachable!()
+				};
+				before[index].append(&mut pending);
+				replaced[index] = Some(match policy {
+					MergePolicy::PreferOverrides => entry.clone(),
+					MergePolicy::PreferBase => base_entry.clone(),
+					MergePolicy::MergeOptions => {
+						let mut merged = entry.clone();
+						let mut used = vec![false; entry.options.len()];
+						merged.options = std::vec::Vec::with_capacity(base_entry.options.len() + entry.options.len());
+						for option in &base_entry.options {
+							match entry.options.iter().position(|o| replaces_option(o, option)) {
+								Some(index) if !used[index] => {
+									used[index] = true;
+									merged.options.push(entry.options[index].clone());
+								},
+								Some(_) => (),
+								None => merged.options.push(option.clone())
+							}
+						}
+						merged.options.extend(entry.options.iter().zip(used).filter(|(_, used)| !used).map(|(option, _)| option.clone()));
+						merged
+					}
+				});
+			},
+			None => {
+				appended.append(&mut pending);
+				appended.push(line.clone());
+			}
+		}
+	}
+	appended.append(&mut pending);
+	let mut lines = std::vec::Vec::with_capacity(base.lines.len() + overrides.lines.len());
+	for ((line, mut before), replacement) in base.lines.iter().zip(before).zip(replaced) {
+		lines.append(&mut before);
+		lines.push(replacement.map(Line::Entry).unwrap_or_else(|| line.clone()));
+	}
+	let trailing_newline = if appended.is_empty() { base.trailing_newline } else { overrides.trailing_newline };
+	lines.append(&mut appended);
+	Fstab { lines, trailing_newline }
+}
+
+/// How the options of a mount differ from what its `fstab` entry requests, see [option_drift()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionDrift {
+	/// The mount point shared by the entry and the mount.
+	pub mount_point: std::string::String,
+	/// Options the entry requests that aren't in effect, in normalized form.
+	pub missing: std::vec::Vec<std::string::String>,
+	/// Options in effect that the entry doesn't request, in normalized form.  These are often defaults added by the filesystem, such as `seclabel` or `data=ordered`.
+	pub unexpected: std::vec::Vec<std::string::String>,
+}
+
+// Options that only mean something to mount(8) or systemd and never show up in the mount table.
+const USERSPACE_OPTIONS: &[&str] = &["_netdev", "auto", "bind", "comment", "group", "loop", "noauto", "nofail", "nouser", "owner", "rbind", "user", "users"];
+
+// The options an entry asks the kernel for, with the defaults mount(8) and the kernel fill in: `rw`, and `relatime` unless another access time option is given.
+fn requested_options(entry: &FstabEntry) -> std::vec::Vec<std::string::String> {
+	let options: std::vec::Vec<&str> = ["rw", "relatime"].iter().copied()
+		.chain(entry.options.iter().map(|option| &option[..]))
+		.filter(|option| {
+			let name = option_name(option);
+			!name.starts_with("x-") && !name.starts_with("X-") && !USERSPACE_OPTIONS.contains(&name)
+		})
+		.collect();
+	crate::options::normalize_options(&options)
+}
+
+/// Compares the options requested in `fstab` with those in effect for each mount point that is both in `fstab` and mounted, after normalizing both with [normalize_options()](crate::options::normalize_options).  Returns the mount points whose options differ, in file order.  Options that only mount(8) or systemd understand, such as `noauto`, `nofail`, or `x-systemd.automount`, are ignored, and an entry without `ro` or an access time option is taken to request `rw` and `relatime`, which is what the kernel does.  If several filesystems are mounted at the same mount point, the one mounted last, which hides the others, is compared.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::fstab::{option_drift, Fstab};
+/// let fstab = Fstab::parse("/dev/sda2 / ext4 defaults,noatime 0 1\n/dev/sda3 /home ext4 defaults,nofail 0 2\n").unwrap();
+/// let mount = |mount_point: &str, options: &[&str]| Mount { mount_point: mount_point.to_string(), options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() };
+/// let live = [mount("/", &["rw", "relatime"]), mount("/home", &["rw", "relatime"])];
+/// let drift = option_drift(&fstab, &live);
+/// assert_eq!(drift.len(), 1);
+/// assert_eq!((&drift[0].missing[..], &drift[0].unexpected[..]), (&["noatime".to_string()][..], &["relatime".to_string()][..]));
+/// ```
+pub fn option_drift<I>(fstab: &Fstab, live: I) -> std::vec::Vec<OptionDrift>
+where
+	I: IntoIterator,
+	I::Item: std::borrow::Borrow<Mount>,
+{
+	let mut mounted = std::collections::HashMap::<std::string::String, std::vec::Vec<std::string::String>>::new();
+	for mount in live {
+		let mount = std::borrow::Borrow::<Mount>::borrow(&mount);
+		mounted.insert(mount.mount_point.clone(), mount.normalized_options());
+	}
+	fstab.entries()
+		.filter(|entry| !is_swap_mount_point(&entry.mount_point))
+		.filter_map(|entry| {
+			let active = mounted.get(&entry.mount_point)?;
+			let requested = requested_options(entry);
+			let missing: std::vec::Vec<_> = requested.iter().filter(|option| !active.contains(option)).cloned().collect();
+			let unexpected: std::vec::Vec<_> = active.iter().filter(|option| !requested.contains(option)).cloned().collect();
+			if missing.is_empty() && unexpected.is_empty() {
+				None
+			} else {
+				Some(OptionDrift { mount_point: entry.mount_point.clone(), missing, unexpected })
+			}
+		})
+		.collect()
+}
+
+impl std::fmt::Display for Fstab {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (i, line) in self.lines.iter().enumerate() {
+			if i > 0 {
+				writeln!(f)?;
+			}
+			write!(f, "{}", line)?;
+		}
+		if self.trailing_newline {
+			writeln!(f)?;
+		}
+		Ok(())
+	}
+}
+
+// Parsers for fstab lines, built on the same escape handling as `/proc/mounts`.
+mod parsers {
+	use super::{FstabEntry, Layout, Line};
+
+	// Leading whitespace, followed by each field with its trailing whitespace.
+	type Fields<'a> = (&'a str, std::vec::Vec<(&'a str, &'a str)>);
+
+	// Splits an entry into its leading whitespace and each field with its trailing whitespace.
+	fn fields(i: &str) -> nom::IResult<&str, Fields<'_>> {
+		nom::combinator::all_consuming(nom::sequence::pair(
+			nom::character::complete::space0,
+			nom::multi::many1(nom::sequence::pair(crate::parsers::not_whitespace, nom::character::complete::space0))
+		))(i)
+	}
+
+	// Decodes escapes in an entire field.
+	fn unescape(field: &str) -> std::option::Option<std::string::String> {
+		nom::combinator::all_consuming(crate::parsers::transform_escaped)(field).ok().map(|(_, s)| s)
+	}
+
+	// Parses one line of fstab.  On failure returns the 1-based column of the offending field and a description.
+	pub fn parse_line(line: &str) -> std::result::Result<Line, (usize, std::string::String)> {
+		let trimmed = line.trim_start_matches([' ', '\t']);
+		if trimmed.is_empty() {
+			return Ok(Line::Blank(line.to_string()));
+		}
+		if trimmed.starts_with('#') {
+			return Ok(Line::Comment(line.to_string()));
+		}
+		let (leading, fields) = match fields(line) {
+			Ok((_, parsed)) => parsed,
+			Err(_) => return Err((1, "unable to split line into fields".to_string()))
+		};
+		// Column of field `n`, counted in characters.
+		let column = |n: usize| -> usize {
+			let offset = fields[n].0.as_ptr() as usize - line.as_ptr() as usize;
+			line[..offset].chars().count() + 1
+		};
+		if fields.len() < 4 {
+			// Point just past the end of the line, where the missing field should be.
+			return Err((line.chars().count() + 1, format!("expected at least 4 fields but found {}", fields.len())));
+		}
+		if fields.len() > 6 {
+			return Err((column(6), format!("expected at most 6 fields but found {}", fields.len())));
+		}
+		let mut decoded: std::vec::Vec<std::string::String> = std::vec::Vec::with_capacity(3);
+		for (n, name) in ["device", "mount point", "filesystem type"].iter().enumerate() {
+			match unescape(fields[n].0) {
+				Some(value) => decoded.push(value),
+				None => return Err((column(n), format!("invalid escape sequence in {}", name)))
+			}
+		}
+		let options = match nom::combinator::all_consuming(crate::parsers::mount_opts)(fields[3].0) {
+			Ok((_, options)) => options,
+			Err(_) => return Err((column(3), "invalid mount options".to_string()))
+		};
+		let mut numbers = [0u32; 2];
+		for (n, name) in ["dump", "pass"].iter().enumerate() {
+			if let Some((raw, _)) = fields.get(4 + n) {
+				numbers[n] = raw.parse().map_err(|_| (column(4 + n), format!("{} must be a non-negative integer, found {:?}", name, raw)))?;
+			}
+		}
+		let mut entry = FstabEntry {
+			device: decoded.remove(0),
+			mount_point: decoded.remove(0),
+			file_system_type: decoded.remove(0),
+			options,
+			dump: numbers[0],
+			pass: numbers[1],
+			layout: None
+		};
+		entry.layout = Some(std::boxed::Box::new(Layout {
+			leading: leading.to_string(),
+			fields: fields.iter().map(|(raw, separator)| (raw.to_string(), separator.to_string())).collect(),
+			original: entry.values()
+		}));
+		Ok(Line::Entry(entry))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_fields() {
+			assert_eq!(fields("  a\tb  c d"), Ok(("", ("  ", vec![("a", "\t"), ("b", "  "), ("c", " "), ("d", "")]))));
+		}
+
+		#[test]
+		fn test_parse_line() {
+			assert_eq!(parse_line(" \t"), Ok(Line::Blank(" \t".to_string())));
+			assert_eq!(parse_line("  # comment"), Ok(Line::Comment("  # comment".to_string())));
+			match parse_line("/dev/sda1 /mnt/my\\040disk ext4 defaults,noatime") {
+				Ok(Line::Entry(entry)) => {
+					assert_eq!(entry.mount_point, "/mnt/my disk");
+					assert_eq!(entry.options, vec!["defaults", "noatime"]);
+					assert_eq!((entry.dump, entry.pass), (0, 0));
+				},
+				other => panic!("unexpected {:?}", other)
+			}
+		}
+
+		#[test]
+		fn test_parse_line_errors() {
+			assert_eq!(parse_line("/dev/sda1 /mnt ext4"), Err((20, "expected at least 4 fields but found 3".to_string())));
+			assert_eq!(parse_line("/dev/sda1 /mnt ext4 defaults 0 x"), Err((32, "pass must be a non-negative integer, found \"x\"".to_string())));
+			assert_eq!(parse_line("/dev/sda1 /mnt\\q ext4 defaults").unwrap_err().0, 11);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const FSTAB: &str = "# /etc/fstab: static file system information.\n\
+		#\n\
+		UUID=0a1b2c3d\t/\text4\terrors=remount-ro\t0\t1\n\
+		\n\
+		   /dev/sdb1  /mnt/my\\040data   xfs   defaults,noatime   0 2   \n\
+		tmpfs /tmp tmpfs mode=1777";
+
+	#[test]
+	fn test_diff() {
+		let old = Fstab::parse(FSTAB).unwrap();
+		let mut new = old.clone();
+		new.set_options("/tmp", &["mode=1777", "size=1G"]);
+		new.remove_entry_for("/");
+		new.add_entry(FstabEntry::new("/dev/sdc1", "/backup", "ext4", &["noauto"]));
+		let tmp = |options: &[&str]| FstabEntry::new("tmpfs", "/tmp", "tmpfs", options);
+		assert_eq!(diff(&old, &new), vec![
+			FstabEvent::Changed { old: tmp(&["mode=1777"]), new: tmp(&["mode=1777", "size=1G"]) },
+			FstabEvent::Added(FstabEntry::new("/dev/sdc1", "/backup", "ext4", &["noauto"])),
+			FstabEvent::Removed(old.entry_for("/").unwrap().clone())
+		]);
+		assert_eq!(diff(&old, &old), vec![]);
+	}
+
+	// Parsing and writing without edits reproduces the file exactly.
+	#[test]
+	fn test_roundtrip() {
+		let fstab = Fstab::parse(FSTAB).unwrap();
+		assert_eq!(fstab.to_string(), FSTAB);
+		assert_eq!(fstab.entries().count(), 3);
+		let with_newline = format!("{}\n", FSTAB);
+		assert_eq!(Fstab::parse(&with_newline).unwrap().to_string(), with_newline);
+		assert_eq!(Fstab::parse("").unwrap().to_string(), "");
+		let mut fstab = Fstab::parse("").unwrap();
+		fstab.add_entry(FstabEntry::new("/dev/sdb1", "/data", "btrfs", &["subvol=/a,b"]));
+		assert_eq!(Fstab::parse(&fstab.to_string()).unwrap().entry_for("/data").unwrap().options, ["subvol=/a,b"]);
+	}
+
+	// Only edited fields are rewritten.
+	#[test]
+	fn test_edit_preserves_layout() {
+		let mut fstab = Fstab::parse(FSTAB).unwrap();
+		{
+			let entry = fstab.entries_mut().nth(1).unwrap();
+			entry.mount_point = "/mnt/new data".to_string();
+			entry.pass = 0;
+		}
+		fstab.entries_mut().nth(2).unwrap().pass = 2;
+		assert_eq!(fstab.to_string(), FSTAB
+			.replace("/mnt/my\\040data   xfs   defaults,noatime   0 2", "/mnt/new\\040data   xfs   defaults,noatime   0 0")
+			.replace("tmpfs /tmp tmpfs mode=1777", "tmpfs /tmp tmpfs mode=1777 0 2"));
+	}
+
+	#[test]
+	fn test_editing() {
+		let mut fstab = Fstab::parse(FSTAB).unwrap();
+		assert!(fstab.set_options("/tmp", &["mode=1777", "nosuid"]));
+		assert!(!fstab.set_options("/nonexistent", &["ro"]));
+		assert_eq!(fstab.remove_entry_for("/mnt/my data").len(), 1);
+		fstab.add_entry(FstabEntry::new("/dev/sdc1", "/data", "ext4", &["defaults"]));
+		assert_eq!(fstab.entry_for("/data").unwrap().device, "/dev/sdc1");
+		assert_eq!(fstab.to_string(), "# /etc/fstab: static file system information.\n\
+			#\n\
+			UUID=0a1b2c3d\t/\text4\terrors=remount-ro\t0\t1\n\
+			\n\
+			tmpfs /tmp tmpfs mode=1777,nosuid\n\
+			/dev/sdc1 /data ext4 defaults 0 0\n");
+	}
+
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_save() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-fstab-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let path = directory.join("fstab");
+		std::fs::write(&path, "old contents\n").unwrap();
+		let fstab = Fstab::parse(FSTAB).unwrap();
+		fstab.save(&path).unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), FSTAB);
+		// Only the saved file remains, no temporary files.
+		assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+
+	// Saves from several threads at once all succeed, and one of them wins.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_save_concurrently() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-fstab-threads-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let path = directory.join("fstab");
+		let fstab = Fstab::parse(FSTAB).unwrap();
+		let results: std::vec::Vec<_> = std::thread::scope(|scope| {
+			let saves: std::vec::Vec<_> = (0..4).map(|_| scope.spawn(|| fstab.save(&path))).collect();
+			saves.into_iter().map(|save| save.join().unwrap()).collect()
+		});
+		let contents = std::fs::read_to_string(&path);
+		let files = std::fs::read_dir(&directory).unwrap().count();
+		std::fs::remove_dir_all(&directory).unwrap();
+		assert!(results.iter().all(|result| result.is_ok()));
+		assert_eq!(contents.unwrap(), FSTAB);
+		assert_eq!(files, 1);
+	}
+
+	#[test]
+	fn test_duplicates() {
+		let fstab = Fstab::parse("# comment\n\
+			UUID=abc / ext4 defaults 0 1\n\
+			UUID=abc /alt xfs defaults 0 2\n\
+			/dev/sdb1 /data ext4 defaults\n\
+			/dev/sdb2 /data ext4 defaults\n\
+			/dev/sdc1 none swap sw\n\
+			/dev/sdc2 none swap sw\n\
+			/data /srv/data none bind\n\
+			/data /srv/data2 none bind\n\
+			tmpfs /tmp tmpfs defaults\n\
+			tmpfs /var/tmp tmpfs defaults\n").unwrap();
+		assert_eq!(fstab.duplicates(), vec![
+			Duplicate { kind: DuplicateKind::MountPoint("/data".to_string()), lines: vec![4, 5] },
+			Duplicate { kind: DuplicateKind::Device("UUID=abc".to_string()), lines: vec![2, 3] },
+		]);
+	}
+
+	#[test]
+	fn test_merge() {
+		let base = Fstab::parse(FSTAB).unwrap();
+		let overrides = Fstab::parse("# site overrides\n\
+			\n\
+			# keep /tmp small\n\
+			tmpfs /tmp tmpfs size=512M\n\
+			/dev/sdc1 none swap sw\n\
+			# trailing comment\n").unwrap();
+		let tmp = |options: &[&str]| FstabEntry::new("tmpfs", "/tmp", "tmpfs", options);
+		let merged = merge(&base, &overrides, MergePolicy::MergeOptions);
+		assert_eq!(merged.entry_for("/tmp"), Some(&tmp(&["mode=1777", "size=512M"])));
+		assert_eq!(merge(&base, &overrides, MergePolicy::PreferOverrides).entry_for("/tmp"), Some(&tmp(&["size=512M"])));
+		assert_eq!(merge(&base, &overrides, MergePolicy::PreferBase).entry_for("/tmp"), Some(&tmp(&["mode=1777"])));
+		let counterparts = Fstab::parse("tmpfs /tmp tmpfs ro,noatime\n").unwrap();
+		let writable = Fstab::parse("tmpfs /tmp tmpfs rw,nosuid,relatime,nodiratime\n").unwrap();
+		assert_eq!(merge(&writable, &counterparts, MergePolicy::MergeOptions).entry_for("/tmp"), Some(&tmp(&["ro", "nosuid", "noatime", "nodiratime"])));
+		assert_eq!(merged.to_string(), format!("{}\n\
+			# site overrides\n\
+			\n\
+			# keep /tmp small\n\
+			tmpfs /tmp tmpfs mode=1777,size=512M\n\
+			/dev/sdc1 none swap sw\n\
+			# trailing comment\n", FSTAB.strip_suffix("tmpfs /tmp tmpfs mode=1777").unwrap().strip_suffix('\n').unwrap()));
+	}
+
+	// mount(8)-only options and kernel defaults don't count as drift, but a dropped noatime does.
+	#[test]
+	fn test_option_drift() {
+		let fstab = Fstab::parse(FSTAB).unwrap();
+		let mount = |mount_point: &str, options: &[&str]| crate::test_fixtures::mount("", mount_point, "", options);
+		let live = [
+			mount("/", &["rw", "relatime", "errors=remount-ro"]),
+			mount("/mnt/my data", &["rw", "relatime", "attr2", "inode64"]),
+			mount("/tmp", &["rw", "nosuid", "relatime"]),
+			mount("/tmp", &["rw", "relatime", "mode=1777"]),
+			mount("/proc", &["rw", "nosuid"]),
+		];
+		assert_eq!(option_drift(&fstab, &live), vec![OptionDrift {
+			mount_point: "/mnt/my data".to_string(),
+			missing: vec!["noatime".to_string()],
+			unexpected: vec!["attr2".to_string(), "inode64".to_string(), "relatime".to_string()]
+		}]);
+		let fstab = Fstab::parse("server:/export /srv nfs ro,noauto,_netdev,x-systemd.automount 0 0\n").unwrap();
+		assert_eq!(option_drift(&fstab, [mount("/srv", &["ro", "relatime"])]), vec![]);
+	}
+
+	#[test]
+	fn test_parse_error_line_number() {
+		let error = Fstab::parse("# ok\n/dev/sda1 /\n").unwrap_err();
+		assert_eq!((error.line, error.column), (2, 12));
+	}
+}
@@ -0,0 +1,190 @@
+//! Strict checking of `fstab` files, as done by `nom-tutorial verify`.
+//!
+//! [Fstab::parse()] stops at the first line it can't parse, which is what a program editing the file wants.  Someone checking a file by hand wants to hear about every problem at once, including the ones that parse fine but will fail at boot: options the filesystem doesn't understand, mount points that don't exist, and conflicting entries.
+
+use super::fstab::{Duplicate, Fstab, Line, ParseError};
+
+/// Options that the kernel or mount(8) accept for every filesystem type.
+const COMMON_OPTIONS: &[&str] = &[
+	"_netdev", "async", "atime", "auto", "bind", "context", "defaults", "defcontext", "dev", "diratime", "dirsync", "exec", "fscontext", "group",
+	"iversion", "lazytime", "loud", "mand", "noatime", "noauto", "nodev", "nodiratime", "noexec", "nofail", "noiversion", "nolazytime", "nomand",
+	"norelatime", "nostrictatime", "nosuid", "nosymfollow", "nouser", "owner", "private", "rbind", "relatime", "remount", "ro", "rootcontext",
+	"rprivate", "rshared", "rslave", "runbindable", "rw", "shared", "silent", "slave", "strictatime", "suid", "sync", "unbindable", "user", "users",
+];
+
+/// Options specific to each filesystem type, from the kernel documentation and `nfs(5)`.  The XFS options are listed by [xfs](crate::xfs) next to the code that interprets them.  Other types that aren't listed aren't checked, since their options can't be known.
+const FS_OPTIONS: &[(&str, &[&str])] = &[
+	("btrfs", &["acl", "autodefrag", "barrier", "commit", "compress", "compress-force", "degraded", "device", "discard", "noacl", "noautodefrag", "nobarrier", "nodatacow", "nodatasum", "nodiscard", "nossd", "space_cache", "ssd", "ssd_spread", "subvol", "subvolid", "user_subvol_rm_allowed"]),
+	("ext2", &["acl", "errors", "grpid", "grpquota", "noacl", "nogrpid", "nouser_xattr", "quota", "resgid", "resuid", "sb", "user_xattr", "usrquota"]),
+	("ext3", &["acl", "barrier", "commit", "data", "errors", "grpid", "grpquota", "journal_dev", "noacl", "nobarrier", "nogrpid", "nouser_xattr", "quota", "resgid", "resuid", "sb", "user_xattr", "usrquota"]),
+	("ext4", &["abort", "acl", "auto_da_alloc", "barrier", "block_validity", "bsdgroups", "commit", "data", "data_err", "dax", "debug", "delalloc", "dioread_lock", "dioread_nolock", "discard", "errors", "grpid", "grpjquota", "grpquota", "i_version", "init_itable", "inlinecrypt", "inode_readahead_blks", "journal_async_commit", "journal_checksum", "journal_dev", "journal_ioprio", "journal_path", "jqfmt", "max_batch_time", "mb_optimize_scan", "min_batch_time", "no_mbcache", "no_prefetch_block_bitmaps", "noacl", "noauto_da_alloc", "nobarrier", "noblock_validity", "nodelalloc", "nodiscard", "nogrpid", "noinit_itable", "noload", "nombcache", "noquota", "norecovery", "nouid32", "nouser_xattr", "prefetch_block_bitmaps", "prjquota", "quota", "resgid", "resize", "resuid", "sb", "stripe", "sysvgroups", "test_dummy_encryption", "user_xattr", "usrjquota", "usrquota"]),
+	("iso9660", &["block", "check", "cruft", "gid", "iocharset", "map", "mode", "norock", "nojoliet", "session", "uid", "unhide", "utf8"]),
+	("nfs", &["ac", "acdirmax", "acdirmin", "acl", "acregmax", "acregmin", "actimeo", "addr", "bg", "clientaddr", "cto", "fg", "fsc", "hard", "intr", "local_lock", "lock", "lookupcache", "max_connect", "migration", "mountaddr", "mounthost", "mountport", "mountproto", "mountvers", "namlen", "nconnect", "nfsvers", "noac", "noacl", "nocto", "nofsc", "nointr", "nolock", "nomigration", "nordirplus", "noresvport", "nosharecache", "nosoftreval", "notrunkdiscovery", "port", "proto", "rdirplus", "rdma", "resvport", "retrans", "retry", "rsize", "sec", "sharecache", "sloppy", "soft", "softerr", "softreval", "tcp", "timeo", "trunkdiscovery", "udp", "vers", "write", "wsize", "xprtsec"]),
+	("nfs4", &["ac", "acdirmax", "acdirmin", "acregmax", "acregmin", "actimeo", "addr", "bg", "clientaddr", "cto", "fg", "fsc", "hard", "intr", "lookupcache", "max_connect", "migration", "minorversion", "nconnect", "nfsvers", "noac", "nocto", "nofsc", "nointr", "nomigration", "nordirplus", "noresvport", "nosharecache", "nosoftreval", "notrunkdiscovery", "port", "proto", "rdirplus", "rdma", "resvport", "retrans", "retry", "rsize", "sec", "sharecache", "sloppy", "soft", "softerr", "softreval", "tcp", "timeo", "trunkdiscovery", "vers", "write", "wsize", "xprtsec"]),
+	("ntfs3", &["acl", "discard", "dmask", "fmask", "force", "gid", "hidden", "iocharset", "noacl", "prealloc", "showmeta", "sparse", "sys_immutable", "uid", "umask"]),
+	("swap", &["discard", "pri", "sw"]),
+	("tmpfs", &["gid", "huge", "inode64", "mode", "mpol", "noswap", "nr_blocks", "nr_inodes", "size", "uid"]),
+	("vfat", &["allow_utime", "blocksize", "check", "codepage", "conv", "cvf_format", "cvf_option", "debug", "discard", "dmask", "dos1xfloppy", "dots", "dotsOK", "errors", "fat", "flush", "fmask", "gid", "iocharset", "nfs", "nocase", "nodots", "nonumtail", "posix", "quiet", "rodir", "shortname", "showexec", "sys_immutable", "time_offset", "tz", "uid", "umask", "uni_xlate", "usefree", "utf8"]),
+];
+
+/// A problem found by [verify()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Finding {
+	/// A line that couldn't be parsed.
+	Syntax(ParseError),
+	/// An option that the entry's filesystem type doesn't accept.
+	UnknownOption {
+		/// The line number, starting from 1.
+		line: usize,
+		/// The filesystem type of the entry.
+		file_system_type: std::string::String,
+		/// The option as written, including any value.
+		option: std::string::String,
+	},
+	/// An option that the entry's filesystem type accepts, but not with this value, e.g. `data=writback` for ext4.
+	InvalidValue {
+		/// The line number, starting from 1.
+		line: usize,
+		/// The filesystem type of the entry.
+		file_system_type: std::string::String,
+		/// The option as written, including the value.
+		option: std::string::String,
+	},
+	/// The mount point of an entry isn't an existing directory.
+	MissingMountPoint {
+		/// The line number, starting from 1.
+		line: usize,
+		/// The mount point of the entry.
+		mount_point: std::string::String,
+	},
+	/// Entries that conflict with each other, see [Fstab::duplicates()].
+	Duplicate(Duplicate),
+}
+
+impl std::fmt::Display for Finding {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Finding::Syntax(error) => write!(f, "{}", error),
+			Finding::UnknownOption { line, file_system_type, option } => write!(f, "line {}: unknown option {:?} for {}", line, option, file_system_type),
+			Finding::InvalidValue { line, file_system_type, option } => write!(f, "line {}: invalid value in {:?} for {}", line, option, file_system_type),
+			Finding::MissingMountPoint { line, mount_point } => write!(f, "line {}: mount point {} is not a directory", line, mount_point),
+			Finding::Duplicate(duplicate) => write!(f, "{}", duplicate)
+		}
+	}
+}
+
+/// True if `option` is valid for `file_system_type`, or if the type isn't one whose options are known.  Options starting with `x-` or `X-` and `comment=` are for userspace tools and are always valid.
+/// # Examples
+/// ```
+/// # use nom_tutorial::fstab_verify::is_known_option;
+/// assert!(is_known_option("ext4", "errors=remount-ro"));
+/// assert!(is_known_option("ext4", "x-systemd.automount"));
+/// assert!(is_known_option("ext4", "X-mount.mkdir"));
+/// assert!(!is_known_option("ext4", "umask=0077"));
+/// assert!(is_known_option("fuse.sshfs", "IdentityFile=/root/.ssh/id_rsa"));
+/// ```
+pub fn is_known_option(file_system_type: &str, option: &str) -> bool {
+	let name = option.split('=').next().unwrap_or(option);
+	if name.starts_with("x-") || name.starts_with("X-") || name == "comment" || COMMON_OPTIONS.contains(&name) {
+		return true;
+	}
+	if file_system_type == "xfs" {
+		return crate::xfs::is_option(name);
+	}
+	match FS_OPTIONS.iter().find(|(fs_type, _)| *fs_type == file_system_type) {
+		Some((_, options)) => options.contains(&name),
+		None => true
+	}
+}
+
+/// True if `option` has a value that `file_system_type` accepts, or if the type or option isn't one whose values are known.  Checks the options interpreted by [Ext4Options](crate::ext4::Ext4Options) and [XfsOptions](crate::xfs::XfsOptions).
+/// # Examples
+/// ```
+/// # use nom_tutorial::fstab_verify::is_valid_value;
+/// assert!(is_valid_value("ext4", "data=writeback"));
+/// assert!(!is_valid_value("ext4", "data=writback"));
+/// assert!(!is_valid_value("xfs", "logbufs=0"));
+/// ```
+pub fn is_valid_value(file_system_type: &str, option: &str) -> bool {
+	match file_system_type {
+		"ext2" | "ext3" | "ext4" => crate::ext4::Ext4Options::parse(&[option]).is_ok(),
+		"xfs" => crate::xfs::XfsOptions::parse(&[option]).is_ok(),
+		_ => true
+	}
+}
+
+/// Checks the contents of an `fstab` file for every problem that can be found without mounting anything.  An empty result means the file passed.
+/// # Examples
+/// ```
+/// # use nom_tutorial::fstab_verify::{verify, Finding};
+/// let findings = verify("/dev/sda1 / ext4 defaults 0 1\n/dev/sda2 /\n/dev/sda3 / xfs bogus\n");
+/// assert!(matches!(&findings[0], Finding::Syntax(error) if error.line == 2));
+/// assert!(matches!(&findings[1], Finding::UnknownOption { line: 3, .. }));
+/// assert!(matches!(&findings[2], Finding::Duplicate(_)));
+/// ```
+pub fn verify(text: &str) -> std::vec::Vec<Finding> {
+	let mut findings = std::vec::Vec::new();
+	// Parse each line separately so that one bad line doesn't hide the rest, keeping a placeholder for bad lines so that line numbers still match.
+	let mut fstab = Fstab::default();
+	for (index, line) in text.lines().enumerate() {
+		let number = index + 1;
+		match Fstab::parse(line) {
+			Ok(parsed) if parsed.lines.is_empty() => fstab.lines.push(Line::Blank(std::string::String::new())),
+			Ok(parsed) => fstab.lines.extend(parsed.lines),
+			Err(error) => {
+				findings.push(Finding::Syntax(ParseError { line: number, ..error }));
+				fstab.lines.push(Line::Blank(std::string::String::new()));
+			}
+		}
+	}
+	for (index, line) in fstab.lines.iter().enumerate() {
+		let entry = match line {
+			Line::Entry(entry) => entry,
+			_ => continue
+		};
+		findings.extend(entry.options.iter()
+			.filter(|option| !is_known_option(&entry.file_system_type, option))
+			.map(|option| Finding::UnknownOption { line: index + 1, file_system_type: entry.file_system_type.clone(), option: option.clone() }));
+		findings.extend(entry.options.iter()
+			.filter(|option| !is_valid_value(&entry.file_system_type, option))
+			.map(|option| Finding::InvalidValue { line: index + 1, file_system_type: entry.file_system_type.clone(), option: option.clone() }));
+		if entry.file_system_type != "swap" && entry.mount_point != "none" && !std::path::Path::new(&entry.mount_point).is_dir() {
+			findings.push(Finding::MissingMountPoint { line: index + 1, mount_point: entry.mount_point.clone() });
+		}
+	}
+	findings.extend(fstab.duplicates().into_iter().map(Finding::Duplicate));
+	findings
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_verify() {
+		let text = "# comment\n\
+			\n\
+			UUID=1234 / ext4 errors=remount-ro,umask=0077 0 1\n\
+			/dev/sda2 none swap sw,pri=1\n\
+			tmpfs /nonexistent/directory tmpfs size=1G\n\
+			/dev/sdb1 /tmp xfs logbufs=9 0 2\n";
+		assert_eq!(verify(text), vec![
+			Finding::UnknownOption { line: 3, file_system_type: "ext4".to_string(), option: "umask=0077".to_string() },
+			Finding::MissingMountPoint { line: 5, mount_point: "/nonexistent/directory".to_string() },
+			Finding::InvalidValue { line: 6, file_system_type: "xfs".to_string(), option: "logbufs=9".to_string() },
+		]);
+	}
+	// Every spelling of the XFS quota options is known.
+	#[test]
+	fn test_xfs_quota_options() {
+		let text = "/dev/sdb1 / xfs uquota,uqnoenforce,qnoenforce,gqnoenforce,pqnoenforce 0 2\n";
+		assert_eq!(verify(text), vec![]);
+	}
+	// Userspace options in either case, propagation flags and the less common vfat options are all known.
+	#[test]
+	fn test_less_common_options() {
+		let text = "/dev/sdc1 /tmp vfat X-mount.mkdir,x-systemd.automount,rshared,utf8,shortname=mixed,iocharset=iso8859-1,codepage=437,errors=remount-ro,nonumtail,time_offset=60 0 2\n\
+			/dev/sdd1 / ext4 private,slave,unbindable 0 2\n";
+		assert_eq!(verify(text), vec![]);
+	}
+}
@@ -0,0 +1,197 @@
+//! Watches an `fstab` file for edits using inotify.
+//!
+//! Where [watch::Watcher](crate::watch::Watcher) reports changes to the mounts that are active right now, [FstabWatcher] reports changes to the mounts that are configured, so a long-running drift detector can react to both.  Every time the file is written the new version is re-parsed and compared to the previous one with [fstab::diff()](crate::fstab::diff).
+
+use super::BoxError;
+use super::fstab::{Fstab, FstabEvent};
+
+// How long the watcher thread waits for inotify before checking whether it should stop.
+const POLL_TIMEOUT_MS: libc::c_int = 100;
+
+// The inotify events that mean the file may have new contents.  Editors and [Fstab::save()] usually replace the file by renaming a temporary file over it, so the directory is watched rather than the file itself.
+const MASK: u32 = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE | libc::IN_DELETE | libc::IN_MOVED_FROM;
+
+// Owns an inotify file descriptor and closes it when dropped.
+struct Inotify(libc::c_int);
+
+impl Inotify {
+	// Creates a non-blocking inotify instance watching `directory`.
+	fn watch(directory: &std::path::Path) -> std::result::Result<Inotify, std::io::Error> {
+		use std::os::unix::ffi::OsStrExt;
+		// Safe because inotify_init1() only takes flags.
+		let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+		if fd < 0 {
+			return Err(std::io::Error::last_os_error());
+		}
+		let inotify = Inotify(fd);
+		let directory = std::ffi::CString::new(directory.as_os_str().as_bytes()).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+		// Safe because `fd` is an open inotify instance and `directory` is a valid NUL-terminated string.
+		if unsafe { libc::inotify_add_watch(fd, directory.as_ptr(), MASK) } < 0 {
+			return Err(std::io::Error::last_os_error());
+		}
+		Ok(inotify)
+	}
+
+	// Waits up to [POLL_TIMEOUT_MS] for events and returns the names of the files they concern.  Errors other than a signal or a spurious wakeup mean the descriptor is unusable and won't go away by retrying.
+	fn read_names(&self) -> std::result::Result<std::vec::Vec<std::ffi::OsString>, std::io::Error> {
+		use std::os::unix::ffi::OsStrExt;
+		let transient = |error: std::io::Error| match error.kind() {
+			std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted => Ok(std::vec::Vec::new()),
+			_ => Err(error)
+		};
+		let mut poll = libc::pollfd { fd: self.0, events: libc::POLLIN, revents: 0 };
+		// Safe because `poll` is a single valid pollfd that outlives the call.
+		match unsafe { libc::poll(&mut poll, 1, POLL_TIMEOUT_MS) } {
+			0 => return Ok(std::vec::Vec::new()),
+			ready if ready < 0 => return transient(std::io::Error::last_os_error()),
+			_ => ()
+		}
+		let mut buffer = [0u8; 4096];
+		// Safe because the kernel writes at most `buffer.len()` bytes into `buffer`.
+		let length = unsafe { libc::read(self.0, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+		if length < 0 {
+			return transient(std::io::Error::last_os_error());
+		}
+		// Each event is a fixed-size header followed by a NUL-padded name of `len` bytes.
+		let header = std::mem::size_of::<libc::inotify_event>();
+		let mut names = std::vec::Vec::new();
+		let mut offset = 0;
+		while offset + header <= length as usize {
+			// Safe because the loop condition guarantees a whole header lies within the bytes the kernel wrote.
+			let event = unsafe { std::ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event) };
+			let name = &buffer[offset + header..offset + header + event.len as usize];
+			let name = &name[..name.iter().position(|&byte| byte == 0).unwrap_or(name.len())];
+			names.push(std::ffi::OsStr::from_bytes(name).to_os_string());
+			offset += header + event.len as usize;
+		}
+		Ok(names)
+	}
+}
+
+impl Drop for Inotify {
+	fn drop(&mut self) {
+		// Safe because the descriptor is owned by this struct and not used after it is dropped.
+		unsafe { libc::close(self.0) };
+	}
+}
+
+/// Watches an `fstab` file for edits.
+///
+/// A background thread waits for inotify to report that the file was written, created, replaced, or deleted, then re-parses it and sends an [FstabEvent] for every entry that differs from the previous version.  If the file can't be read or parsed the error is sent instead and the last good version is kept for comparison.  If inotify itself fails the error is sent once and the thread exits, since retrying would only fail again.  The thread also exits when the `FstabWatcher` is dropped.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::fstab_watch::FstabWatcher;
+/// for event in FstabWatcher::new("/etc/fstab").unwrap() {
+///     println!("{:?}", event.unwrap());
+/// }
+/// ```
+pub struct FstabWatcher {
+	receiver: std::sync::mpsc::Receiver<std::result::Result<FstabEvent, BoxError>>,
+	stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl FstabWatcher {
+	/// Starts watching the `fstab` file at `path`.  Fails if the file can't be read and parsed initially or if inotify can't watch its directory.
+	pub fn new<P: Into<std::path::PathBuf>>(path: P) -> std::result::Result<FstabWatcher, BoxError> {
+		let path = path.into();
+		let name = path.file_name().ok_or_else(|| format!("{} is not a file", path.display()))?.to_os_string();
+		let directory = match path.parent() {
+			Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+			_ => std::path::PathBuf::from(".")
+		};
+		let inotify = Inotify::watch(&directory)?;
+		let mut previous = Fstab::read(&path)?;
+		let (sender, receiver) = std::sync::mpsc::channel();
+		let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+		let thread_stop = stop.clone();
+		std::thread::spawn(move || {
+			#[cfg(feature = "tracing")]
+			let _span = tracing::debug_span!("watch_fstab", path = %path.display()).entered();
+			let mut failed = false;
+			while !failed && !thread_stop.load(std::sync::atomic::Ordering::Relaxed) {
+				let events = match inotify.read_names() {
+					Ok(names) if !names.contains(&name) => continue,
+					Ok(_) => match Fstab::read(&path) {
+						Ok(current) => {
+							let events = super::fstab::diff(&previous, &current);
+							previous = current;
+							events.into_iter().map(Ok).collect()
+						},
+						Err(e) => vec![Err(e)]
+					},
+					Err(e) => {
+						failed = true;
+						vec![Err(e.into())]
+					}
+				};
+				for event in events {
+					#[cfg(feature = "tracing")]
+					match &event {
+						Ok(event) => tracing::debug!(?event, "fstab changed"),
+						Err(e) => tracing::debug!(error = %e, "failed to read fstab"),
+					}
+					// The receiver has hung up, nobody is listening anymore.
+					if sender.send(event).is_err() {
+						return;
+					}
+				}
+			}
+		});
+		Ok(FstabWatcher { receiver, stop })
+	}
+
+	/// Blocks until the next event is available.  Returns `None` if the watcher thread has exited.
+	pub fn recv(&self) -> std::option::Option<std::result::Result<FstabEvent, BoxError>> {
+		self.receiver.recv().ok()
+	}
+
+	/// Returns the next event if one is available without blocking.
+	pub fn try_recv(&self) -> std::option::Option<std::result::Result<FstabEvent, BoxError>> {
+		self.receiver.try_recv().ok()
+	}
+
+	/// Waits up to `timeout` for the next event.  Returns `Err` if the timeout elapsed or the watcher thread has exited.
+	pub fn recv_timeout(&self, timeout: std::time::Duration) -> std::result::Result<std::result::Result<FstabEvent, BoxError>, std::sync::mpsc::RecvTimeoutError> {
+		self.receiver.recv_timeout(timeout)
+	}
+}
+
+impl std::iter::Iterator for FstabWatcher {
+	type Item = std::result::Result<FstabEvent, BoxError>;
+
+	/// Blocks until the next event, see [FstabWatcher::recv()].
+	fn next(&mut self) -> std::option::Option<Self::Item> {
+		self.recv()
+	}
+}
+
+// Once the background thread has exited the channel stays disconnected, so `recv()` keeps returning `None`.
+impl std::iter::FusedIterator for FstabWatcher {}
+
+impl Drop for FstabWatcher {
+	fn drop(&mut self) {
+		self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fstab::FstabEntry;
+
+	// Saving the file atomically, as editors do, is noticed even though it replaces the watched file.
+	#[test]
+	fn test_watch_save() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-fstab-watch-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let path = directory.join("fstab");
+		std::fs::write(&path, "/dev/sda1 / ext4 defaults 0 1\n").unwrap();
+		let watcher = FstabWatcher::new(&path).unwrap();
+		let mut fstab = Fstab::read(&path).unwrap();
+		fstab.add_entry(FstabEntry::new("/dev/sdb1", "/data", "xfs", &["defaults"]));
+		fstab.save(&path).unwrap();
+		let event = watcher.recv_timeout(std::time::Duration::from_secs(5));
+		std::fs::remove_dir_all(&directory).unwrap();
+		assert_eq!(event.unwrap().unwrap(), FstabEvent::Added(FstabEntry::new("/dev/sdb1", "/data", "xfs", &["defaults"])));
+	}
+}
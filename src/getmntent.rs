@@ -0,0 +1,153 @@
+//! Reads mount tables with the C library's `getmntent_r(3)` instead of this crate's parsers.  Enabled by the `getmntent` feature.
+//!
+//! Every C program that lists mounts goes through `getmntent(3)`, so its results are a sanity baseline for the nom parsers, and it copes with any differences in the table format of the platform's C library.  It only understands the format of `/proc/mounts` and `/etc/fstab`, not mountinfo.  [GetmntentSource] plugs it into [Mounts](crate::Mounts), so that the backend can be chosen at runtime.
+
+use super::Mount;
+use super::source::MountSource;
+
+// Large enough for the longest line the kernel writes for an overlay mount with many layers.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+// Copies a string from a `struct mntent`.
+fn string(pointer: *const libc::c_char) -> std::string::String {
+	if pointer.is_null() {
+		std::string::String::new()
+	} else {
+		// Safe because getmntent_r() returns NUL-terminated strings that live as long as its buffer.
+		unsafe { std::ffi::CStr::from_ptr(pointer) }.to_string_lossy().into_owned()
+	}
+}
+
+/// Reads the mount table at `path`, in the format of `/proc/mounts` or `/etc/fstab`, with `getmntent_r(3)`.  The C library undoes the escaping of spaces and other special characters itself.
+///
+/// `getmntent_r(3)` takes any line with at least four words for an entry, so a file that isn't a mount table at all can look like one.  Entries whose mount point isn't an absolute path are therefore rejected as invalid data, except for swap entries in `fstab`, whose mount point is conventionally `none` or `swap`.
+pub fn read<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<std::vec::Vec<Mount>, std::io::Error> {
+	use std::os::unix::ffi::OsStrExt;
+	let c_path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+		.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+	// Safe because both arguments are valid NUL-terminated strings.
+	let file = unsafe { libc::setmntent(c_path.as_ptr(), b"r\0".as_ptr() as *const libc::c_char) };
+	if file.is_null() {
+		return Err(std::io::Error::last_os_error());
+	}
+	let mut mounts = std::vec::Vec::new();
+	let mut buffer = vec![0 as libc::c_char; BUFFER_SIZE];
+	let mut entry = std::mem::MaybeUninit::<libc::mntent>::uninit();
+	loop {
+		// Safe because `file` is open, and `entry` and `buffer` are large enough for the result.  getmntent_r() returns NULL at the end of the file.
+		let result = unsafe { libc::getmntent_r(file, entry.as_mut_ptr(), buffer.as_mut_ptr(), buffer.len() as libc::c_int) };
+		if result.is_null() {
+			break;
+		}
+		// Safe because getmntent_r() returned a pointer to the filled-in `entry`.
+		let entry = unsafe { &*result };
+		let mount = Mount {
+			device: string(entry.mnt_fsname),
+			mount_point: string(entry.mnt_dir),
+			file_system_type: string(entry.mnt_type),
+			options: string(entry.mnt_opts).split(',').filter(|o| !o.is_empty()).map(|o| o.to_string()).collect()
+		};
+		if !mount.mount_point.starts_with('/') && mount.file_system_type != "swap" {
+			// Safe because `file` was opened by setmntent() and isn't used afterwards.
+			unsafe { libc::endmntent(file) };
+			return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("mount point {:?} of entry {} is not an absolute path", mount.mount_point, mounts.len() + 1)));
+		}
+		mounts.push(mount);
+	}
+	// Safe because `file` was opened by setmntent() and isn't used afterwards.
+	unsafe { libc::endmntent(file) };
+	Ok(mounts)
+}
+
+/// Reads `/proc/mounts` with `getmntent_r(3)`, the counterpart of [mounts()](super::mounts).
+pub fn mounts() -> std::result::Result<std::vec::Vec<Mount>, std::io::Error> {
+	read("/proc/mounts")
+}
+
+/// A [MountSource] that reads the table with `getmntent_r(3)`, for choosing between the C library and this crate's parsers at runtime.  The mounts are served as lines of `/proc/mounts`, escaped again with [Mount::to_proc_line()].
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mounts;
+/// # use nom_tutorial::getmntent::GetmntentSource;
+/// let use_libc = std::env::var_os("USE_LIBC").is_some();
+/// let count = if use_libc {
+///     Mounts::from_source(&GetmntentSource::proc_mounts()).unwrap().into_iter().count()
+/// } else {
+///     nom_tutorial::mounts().unwrap().into_iter().count()
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GetmntentSource {
+	path: std::path::PathBuf
+}
+
+impl GetmntentSource {
+	/// Reads the mount table at `path`.
+	pub fn new<P: Into<std::path::PathBuf>>(path: P) -> GetmntentSource {
+		GetmntentSource { path: path.into() }
+	}
+
+	/// Reads `/proc/mounts`.
+	pub fn proc_mounts() -> GetmntentSource {
+		GetmntentSource::new("/proc/mounts")
+	}
+}
+
+impl MountSource for GetmntentSource {
+	type Reader = std::io::Cursor<std::string::String>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		let mut contents = std::string::String::new();
+		for mount in read(&self.path)? {
+			contents.push_str(&mount.to_proc_line());
+			contents.push('\n');
+		}
+		Ok(std::io::Cursor::new(contents))
+	}
+
+	fn remaining_lines(&self, reader: &Self::Reader) -> std::option::Option<usize> {
+		Some(reader.get_ref().lines().count())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The C library and the nom parser agree on every fixture.
+	#[test]
+	fn test_mounts_match() {
+		use crate::test_fixtures;
+		let copy = std::env::temp_dir().join(format!("nom-tutorial-getmntent-{}", std::process::id()));
+		std::fs::write(&copy, test_fixtures::table(test_fixtures::ALL)).unwrap();
+		let reference = read(&copy);
+		std::fs::remove_file(&copy).unwrap();
+		assert_eq!(reference.unwrap(), test_fixtures::parsed(test_fixtures::ALL));
+	}
+
+	// Escaped spaces survive the trip through the C library and back.
+	#[test]
+	fn test_source_escapes() {
+		let path = std::env::temp_dir().join(format!("nom-tutorial-getmntent-escapes-{}", std::process::id()));
+		std::fs::write(&path, "/dev/sda1 /mnt/my\\040disk ext4 rw,noatime 0 0\n").unwrap();
+		let mounts: std::vec::Vec<Mount> = crate::Mounts::from_source(&GetmntentSource::new(&path)).unwrap().into_iter().map(|m| m.unwrap()).collect();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(mounts.len(), 1);
+		assert_eq!(mounts[0].mount_point, "/mnt/my disk");
+		assert_eq!(mounts[0].options, ["rw", "noatime"]);
+		assert!(read("/this/path/does/not/exist").is_err());
+	}
+
+	// Text that merely has four words on a line isn't a mount table, but an fstab swap entry is.
+	#[test]
+	fn test_read_rejects_relative_mount_points() {
+		let path = std::env::temp_dir().join(format!("nom-tutorial-getmntent-relative-{}", std::process::id()));
+		std::fs::write(&path, "not a mount table\n").unwrap();
+		let garbage = read(&path);
+		std::fs::write(&path, "/dev/sda2 none swap sw 0 0\n").unwrap();
+		let swap = read(&path);
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(garbage.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+		assert_eq!(swap.unwrap()[0].mount_point, "none");
+	}
+}
@@ -0,0 +1,158 @@
+//! Detects hung network mounts without hanging the caller.  Enabled by the `statvfs` feature.
+//!
+//! When an NFS server goes away, every `stat()` or `statfs()` on a hard mount blocks until it comes back, and the process is stuck in uninterruptible sleep.  [Mount::health_check()] makes that call on a helper thread and stops waiting after a timeout.  The helper thread can't be cancelled, so it stays blocked until the kernel answers; checking the mount again meanwhile waits for the same thread rather than starting another.
+
+use super::Mount;
+
+/// The outcome of [Mount::health_check()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Health {
+	/// `statfs()` answered within half the timeout.
+	Healthy,
+	/// `statfs()` answered, but only after more than half the timeout had passed, which is how a struggling server usually shows up before it stops answering altogether.  Holds how long the call took.
+	Slow(std::time::Duration),
+	/// `statfs()` didn't answer within the timeout, or failed with an error that means the server or FUSE daemon is gone: `ESTALE`, `ENOTCONN`, `ETIMEDOUT`, or `EIO`.
+	Stale,
+}
+
+// Classifies the result of a `statfs()` call that took `elapsed` out of `timeout`.
+fn classify<T>(result: std::result::Result<T, std::io::Error>, elapsed: std::time::Duration, timeout: std::time::Duration) -> std::result::Result<Health, std::io::Error> {
+	match result {
+		Ok(_) if elapsed > timeout / 2 => Ok(Health::Slow(elapsed)),
+		Ok(_) => Ok(Health::Healthy),
+		Err(e) => match e.raw_os_error() {
+			Some(libc::ESTALE) | Some(libc::ENOTCONN) | Some(libc::ETIMEDOUT) | Some(libc::EIO) => Ok(Health::Stale),
+			_ => Err(e)
+		}
+	}
+}
+
+// A check running on a helper thread, shared by every caller that checks the same path before it finishes.
+#[derive(Default)]
+struct InFlight {
+	result: std::sync::Mutex<std::option::Option<std::result::Result<(), std::io::Error>>>,
+	done: std::sync::Condvar,
+}
+
+// The checks whose helper thread hasn't finished yet, by path.
+fn in_flight() -> &'static std::sync::Mutex<std::collections::HashMap<std::path::PathBuf, std::sync::Arc<InFlight>>> {
+	static IN_FLIGHT: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<std::path::PathBuf, std::sync::Arc<InFlight>>>> = std::sync::OnceLock::new();
+	IN_FLIGHT.get_or_init(Default::default)
+}
+
+// A copy of `e`, which can't be cloned, for each caller sharing a check.
+fn copy_error(e: &std::io::Error) -> std::io::Error {
+	match e.raw_os_error() {
+		Some(code) => std::io::Error::from_raw_os_error(code),
+		None => std::io::Error::new(e.kind(), e.to_string())
+	}
+}
+
+/// Calls `statvfs(3)` on `path` on a helper thread, giving up after `timeout`.  Errors that don't point to a dead server, such as a missing path or a permission error, are returned as they are.
+///
+/// At most one helper thread per path is running at any time.  If a check of `path` is still outstanding, e.g. because the server stopped answering and an earlier check timed out, this waits for that check instead of starting another thread that would block as well, so checking a hung mount repeatedly doesn't pile up threads.
+pub fn check<P: AsRef<std::path::Path>>(path: P, timeout: std::time::Duration) -> std::result::Result<Health, std::io::Error> {
+	check_with(path.as_ref(), timeout, |path| crate::usage::statvfs(path).map(|_| ()))
+}
+
+// Like [check()], but calls `call` instead of `statvfs(3)`.
+fn check_with<F: FnOnce(&std::path::Path) -> std::result::Result<(), std::io::Error> + Send + 'static>(path: &std::path::Path, timeout: std::time::Duration, call: F) -> std::result::Result<Health, std::io::Error> {
+	let start = std::time::Instant::now();
+	let check = {
+		let mut checks = in_flight().lock().unwrap();
+		match checks.get(path) {
+			Some(check) => check.clone(),
+			None => {
+				let check = std::sync::Arc::new(InFlight::default());
+				let (thread_check, thread_path) = (check.clone(), path.to_path_buf());
+				std::thread::Builder::new().name("nom-tutorial-health".to_string()).spawn(move || {
+					let result = call(&thread_path);
+					// Forget the check before publishing the result, so that a caller who sees the result and checks again starts afresh.
+					in_flight().lock().unwrap().remove(&thread_path);
+					*thread_check.result.lock().unwrap() = Some(result);
+					thread_check.done.notify_all();
+				})?;
+				checks.insert(path.to_path_buf(), check.clone());
+				check
+			}
+		}
+	};
+	let result = check.done.wait_timeout_while(check.result.lock().unwrap(), timeout, |result| result.is_none()).unwrap().0;
+	match &*result {
+		Some(Ok(())) => classify(Ok(()), start.elapsed(), timeout),
+		Some(Err(e)) => classify(Err::<(), _>(copy_error(e)), start.elapsed(), timeout),
+		None => Ok(Health::Stale)
+	}
+}
+
+impl Mount {
+	/// Checks whether the filesystem still answers by calling `statvfs(3)` on its mount point, waiting at most `timeout`, see [check()].
+	/// # Examples
+	/// ```no_run
+	/// # use nom_tutorial::health::Health;
+	/// for mount in nom_tutorial::mounts().unwrap() {
+	///     let mount = mount.unwrap();
+	///     if mount.file_system_type.starts_with("nfs") {
+	///         if let Ok(Health::Stale) = mount.health_check(std::time::Duration::from_secs(5)) {
+	///             println!("{} is not responding", mount.mount_point);
+	///         }
+	///     }
+	/// }
+	/// ```
+	pub fn health_check(&self, timeout: std::time::Duration) -> std::result::Result<Health, std::io::Error> {
+		check(&self.mount_point, timeout)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_classify() {
+		let timeout = std::time::Duration::from_secs(4);
+		let ok = || Ok::<(), std::io::Error>(());
+		assert_eq!(classify(ok(), std::time::Duration::from_secs(1), timeout).unwrap(), Health::Healthy);
+		assert_eq!(classify(ok(), std::time::Duration::from_secs(3), timeout).unwrap(), Health::Slow(std::time::Duration::from_secs(3)));
+		assert_eq!(classify(Err::<(), _>(std::io::Error::from_raw_os_error(libc::ESTALE)), timeout, timeout).unwrap(), Health::Stale);
+		assert_eq!(classify(Err::<(), _>(std::io::Error::from_raw_os_error(libc::EACCES)), timeout, timeout).unwrap_err().raw_os_error(), Some(libc::EACCES));
+	}
+
+	#[test]
+	fn test_check() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-health-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let health = check(&directory, std::time::Duration::from_secs(30));
+		std::fs::remove_dir_all(&directory).unwrap();
+		assert_eq!(health.unwrap(), Health::Healthy);
+		assert_eq!(check("/this/path/does/not/exist", std::time::Duration::from_secs(30)).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+	}
+
+	// A check that is still hanging is waited for again rather than started twice, and a new one starts once it finishes.
+	#[test]
+	fn test_check_hung() {
+		let path = std::path::Path::new("/nom-tutorial-health-hung");
+		let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+		let (release, released) = std::sync::mpsc::channel::<()>();
+		let released = std::sync::Arc::new(std::sync::Mutex::new(released));
+		let hanging = || {
+			let (calls, released) = (calls.clone(), released.clone());
+			move |_: &std::path::Path| {
+				calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+				let _ = released.lock().unwrap().recv();
+				Ok(())
+			}
+		};
+		let timeout = std::time::Duration::from_millis(20);
+		assert_eq!(check_with(path, timeout, hanging()).unwrap(), Health::Stale);
+		assert_eq!(check_with(path, timeout, hanging()).unwrap(), Health::Stale);
+		assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+		release.send(()).unwrap();
+		while in_flight().lock().unwrap().contains_key(path) {
+			std::thread::sleep(std::time::Duration::from_millis(1));
+		}
+		drop(release);
+		assert_eq!(check_with(path, std::time::Duration::from_secs(30), hanging()).unwrap(), Health::Healthy);
+		assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+	}
+}
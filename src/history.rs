@@ -0,0 +1,239 @@
+//! Records a timestamped log of changes to the mount table.
+//!
+//! [MountHistory] logs the individual changes reported by a [Watcher](super::watch::Watcher), while [SnapshotHistory] keeps whole [MountSnapshot]s so that the table can be looked up as it was at a past time.  [MountHistory] needs the `watch` feature.
+
+#[cfg(feature = "watch")]
+use super::BoxError;
+use super::snapshot::MountSnapshot;
+use super::watch::{Action, MountEvent};
+#[cfg(feature = "watch")]
+use super::watch::Watcher;
+
+/// A [MountEvent] along with the time it was observed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+	/// When the change was observed.  Since the mount table is polled this is up to one polling interval after the change actually happened.
+	pub time: std::time::SystemTime,
+	/// What kind of change this is.
+	pub action: Action,
+	/// What changed.
+	pub event: MountEvent,
+}
+
+/// Runs a [Watcher] in the background and keeps a bounded, in-memory log of every change it reports.
+///
+/// The log holds at most `capacity` entries; once full the oldest entries are discarded.  Errors reported by the watcher are not recorded.  Recording stops when the `MountHistory` is dropped.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::history::MountHistory;
+/// let history = MountHistory::new(std::time::Duration::from_secs(1), 1000).unwrap();
+/// // ... some time later ...
+/// let hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+/// for entry in history.between(hour_ago, std::time::SystemTime::now()) {
+///     println!("{:?} {}: {:?}", entry.time, entry.action, entry.event);
+/// }
+/// ```
+#[cfg(feature = "watch")]
+pub struct MountHistory {
+	entries: std::sync::Arc<std::sync::Mutex<std::collections::VecDeque<HistoryEntry>>>,
+	stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+// How often the recording thread checks whether it should stop.
+#[cfg(feature = "watch")]
+const STOP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+#[cfg(feature = "watch")]
+impl MountHistory {
+	/// Starts watching `/proc/mounts` every `interval`, keeping up to `capacity` entries.
+	pub fn new(interval: std::time::Duration, capacity: usize) -> std::result::Result<MountHistory, BoxError> {
+		Ok(MountHistory::from_watcher(Watcher::new(interval)?, capacity))
+	}
+
+	/// Records the events reported by an existing `watcher`, keeping up to `capacity` entries.
+	pub fn from_watcher(watcher: Watcher, capacity: usize) -> MountHistory {
+		let entries = std::sync::Arc::new(std::sync::Mutex::new(std::collections::VecDeque::with_capacity(capacity)));
+		let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+		let thread_entries = entries.clone();
+		let thread_stop = stop.clone();
+		std::thread::spawn(move || {
+			while !thread_stop.load(std::sync::atomic::Ordering::Relaxed) {
+				match watcher.recv_timeout(STOP_CHECK_INTERVAL) {
+					Ok(Ok(watch_event)) => {
+						let mut entries = thread_entries.lock().unwrap();
+						if capacity == 0 {
+							continue;
+						}
+						if entries.len() == capacity {
+							entries.pop_front();
+						}
+						entries.push_back(HistoryEntry { time: watch_event.time, action: watch_event.action, event: watch_event.event });
+					},
+					Ok(Err(_)) | Err(std::sync::mpsc::RecvTimeoutError::Timeout) => (),
+					Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return
+				}
+			}
+		});
+		MountHistory { entries, stop }
+	}
+
+	/// Returns a copy of every retained entry, oldest first.
+	pub fn entries(&self) -> std::vec::Vec<HistoryEntry> {
+		self.entries.lock().unwrap().iter().cloned().collect()
+	}
+
+	/// Returns the retained entries observed at or after `start` and before `end`, oldest first.
+	pub fn between(&self, start: std::time::SystemTime, end: std::time::SystemTime) -> std::vec::Vec<HistoryEntry> {
+		self.entries.lock().unwrap().iter()
+			.filter(|entry| entry.time >= start && entry.time < end)
+			.cloned()
+			.collect()
+	}
+}
+
+#[cfg(feature = "watch")]
+impl Drop for MountHistory {
+	fn drop(&mut self) {
+		self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+	}
+}
+
+/// A bounded, in-memory collection of [MountSnapshot]s ordered by [MountSnapshot::taken_at()], for looking up the mount table as it was at a past time.
+///
+/// At most `capacity` snapshots are kept, and with [SnapshotHistory::with_max_age()] snapshots that are too much older than the newest one are discarded as well.  Unlike [MountHistory] nothing is recorded in the background; snapshots are added with [SnapshotHistory::push()].
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::history::SnapshotHistory;
+/// # use nom_tutorial::snapshot::MountSnapshot;
+/// # use nom_tutorial::watch::MountEvent;
+/// let start = std::time::UNIX_EPOCH;
+/// let minutes = |n: u64| start + std::time::Duration::from_secs(60 * n);
+/// let data = Mount { device: "/dev/sdb1".to_string(), mount_point: "/data".to_string(), ..Default::default() };
+/// let mut history = SnapshotHistory::new(10);
+/// history.push(MountSnapshot::with_time(vec![], minutes(0)));
+/// history.push(MountSnapshot::with_time(vec![data.clone()], minutes(5)));
+/// assert!(history.at(minutes(3)).unwrap().is_empty());
+/// assert_eq!(history.diff(minutes(3), minutes(7)).unwrap(), vec![MountEvent::Added(data.into())]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SnapshotHistory {
+	snapshots: std::collections::VecDeque<MountSnapshot>,
+	capacity: usize,
+	max_age: std::option::Option<std::time::Duration>,
+}
+
+impl SnapshotHistory {
+	/// Creates an empty history that keeps up to `capacity` snapshots.
+	pub fn new(capacity: usize) -> SnapshotHistory {
+		SnapshotHistory { snapshots: std::collections::VecDeque::with_capacity(capacity), capacity, max_age: None }
+	}
+
+	/// Also discards snapshots taken more than `max_age` before the newest one, except for the newest of them, which is still needed to look up the mount table as it was `max_age` ago.  The age is measured from the newest snapshot rather than from now, so a history loaded after a long pause isn't emptied by the first push.
+	pub fn with_max_age(mut self, max_age: std::time::Duration) -> SnapshotHistory {
+		self.max_age = Some(max_age);
+		self.prune();
+		self
+	}
+
+	/// Adds `snapshot`, discarding the oldest snapshots if the history is over capacity or they are older than the maximum age.  A snapshot taken before ones already in the history is inserted in time order.
+	pub fn push(&mut self, snapshot: MountSnapshot) {
+		let index = self.snapshots.iter().rposition(|retained| retained.taken_at() <= snapshot.taken_at()).map_or(0, |index| index + 1);
+		self.snapshots.insert(index, snapshot);
+		self.prune();
+	}
+
+	/// The newest snapshot, if any.
+	pub fn latest(&self) -> std::option::Option<&MountSnapshot> {
+		self.snapshots.back()
+	}
+
+	/// The mount table as it was at `time`, i.e. the newest snapshot taken at or before `time`.  Returns `None` if every retained snapshot was taken after `time`.
+	pub fn at(&self, time: std::time::SystemTime) -> std::option::Option<&MountSnapshot> {
+		self.snapshots.iter().rev().find(|snapshot| snapshot.taken_at() <= time)
+	}
+
+	/// The differences between the mount table as it was at `from` and as it was at `to`, see [SnapshotHistory::at()] and [diff()](crate::watch::diff).  Returns `None` if there is no snapshot as of `from` or `to`.
+	pub fn diff(&self, from: std::time::SystemTime, to: std::time::SystemTime) -> std::option::Option<std::vec::Vec<MountEvent>> {
+		Some(crate::watch::diff(self.at(from)?.mounts(), self.at(to)?.mounts()))
+	}
+
+	/// Iterates over the retained snapshots, oldest first.
+	pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, MountSnapshot> {
+		self.snapshots.iter()
+	}
+
+	/// Number of retained snapshots.
+	pub fn len(&self) -> usize {
+		self.snapshots.len()
+	}
+
+	/// True if no snapshots are retained.
+	pub fn is_empty(&self) -> bool {
+		self.snapshots.is_empty()
+	}
+
+	// Discards snapshots over capacity or older than the maximum age, oldest first.  The oldest snapshot is only too old once the one after it is at least the maximum age, since until then it is the mount table as it was at the cutoff.
+	fn prune(&mut self) {
+		while self.snapshots.len() > self.capacity {
+			self.snapshots.pop_front();
+		}
+		if let (Some(max_age), Some(newest)) = (self.max_age, self.latest().map(MountSnapshot::taken_at)) {
+			while self.snapshots.get(1).is_some_and(|next| newest.duration_since(next.taken_at()).unwrap_or_default() >= max_age) {
+				self.snapshots.pop_front();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures::mount;
+
+	// Each read of the fabricated mount table alternately adds and removes a mount.
+	#[cfg(feature = "watch")]
+	fn flapping_watcher() -> Watcher {
+		let mut counter = 0;
+		Watcher::with_reader(std::time::Duration::from_millis(1), std::boxed::Box::new(move || {
+			counter += 1;
+			Ok(if counter % 2 == 0 { vec![mount("/dev/sda1", "/", "ext4", &["rw"]).into()] } else { vec![] })
+		})).unwrap()
+	}
+
+	#[cfg(feature = "watch")]
+	#[test]
+	fn test_history_bounded() {
+		let start = std::time::SystemTime::now();
+		let history = MountHistory::from_watcher(flapping_watcher(), 3);
+		while history.entries().len() < 3 {
+			std::thread::sleep(std::time::Duration::from_millis(1));
+		}
+		std::thread::sleep(std::time::Duration::from_millis(20));
+		let entries = history.entries();
+		assert_eq!(entries.len(), 3);
+		assert!(entries.windows(2).all(|pair| pair[0].time <= pair[1].time));
+		assert_eq!(history.between(start, std::time::SystemTime::now() + std::time::Duration::from_secs(1)).len(), 3);
+		assert!(history.between(start, start).is_empty());
+	}
+
+	#[test]
+	fn test_snapshot_history_pruning() {
+		let minutes = |n: u64| std::time::UNIX_EPOCH + std::time::Duration::from_secs(60 * n);
+		let snapshot = |n: u64| MountSnapshot::with_time(vec![mount("/dev/sda1", &format!("/mnt{}", n), "ext4", &["rw"])], minutes(n));
+		let mut history = SnapshotHistory::new(3).with_max_age(std::time::Duration::from_secs(600));
+		for n in [0, 2, 1, 4] {
+			history.push(snapshot(n));
+		}
+		let times: std::vec::Vec<_> = history.iter().map(MountSnapshot::taken_at).collect();
+		assert_eq!(times, vec![minutes(1), minutes(2), minutes(4)]);
+		history.push(snapshot(13));
+		assert_eq!(history.len(), 3);
+		assert_eq!(history.at(minutes(3)).unwrap().taken_at(), minutes(2));
+		assert_eq!(history.at(minutes(5)).unwrap().taken_at(), minutes(4));
+		history.push(snapshot(15));
+		assert_eq!(history.iter().map(MountSnapshot::taken_at).collect::<std::vec::Vec<_>>(), vec![minutes(4), minutes(13), minutes(15)]);
+		assert!(history.at(minutes(3)).is_none());
+		assert_eq!(history.diff(minutes(4), minutes(20)).unwrap().len(), 2);
+	}
+}
@@ -0,0 +1,606 @@
+//! Reading mount tables from a [MountSource](source::MountSource) such as `/proc/mounts`.  Enabled by the `io` feature, which is on by default.
+//!
+//! Everything here is re-exported from the crate root, so [Mounts](crate::Mounts) and [mounts()](crate::mounts()) can be used without naming this module.
+
+// Needed to use traits associated with std::io::BufReader.
+use std::io::BufRead;
+
+use super::{limits, source, BoxError, Mount, MountEntry};
+use super::snapshot::MountSnapshot;
+
+/// Structure that accesses `/proc/mounts` and iterates over the contained mounts.
+/// 
+/// You can generate an instance by calling [Mounts::new()] or the convenience method [mounts()].  Instantiation may fail if `/proc/mounts` does not exist or you do not have access to read it.  You can access each individual mount through an iterator with [Mounts::into_iter()](std::iter::IntoIterator::into_iter) for a consuming iterator or [Mounts::iter_mut()] for a mutable iterator.  Note that there is no immutable borrowed iterator `Mounts::iter()`.  An instance of `Mounts` really isn't useful for anything except iterating over the contained mounts.
+///
+/// `Mounts` is generic over the [MountSource](source::MountSource) it reads from, which defaults to `/proc/mounts`.  Use [Mounts::from_source()] to read a mount table from somewhere else, such as a saved file or an in-memory string in a unit test.
+/// # Examples
+/// 
+/// ```
+/// # use nom_tutorial;
+/// for mount in nom_tutorial::mounts().unwrap() {
+///   println!("{}", mount.unwrap());
+/// }
+pub struct Mounts<S: source::MountSource = source::ProcMounts> {
+	reader: S::Reader,
+	format: source::Format,
+	limits: limits::Limits,
+	// Lines left in `reader`, if known, see [source::MountSource::remaining_lines()].
+	remaining: std::option::Option<usize>
+}
+
+impl Mounts {
+	/// Returns a new Mounts instance.  You can also call [mounts()] for convenience.
+	pub fn new() -> std::result::Result<Mounts, std::io::Error> {
+		Mounts::from_source(&source::ProcMounts)
+	}
+}
+
+impl<S: source::MountSource> Mounts<S> {
+	/// Returns a new Mounts instance that reads from `source`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mounts;
+	/// # use nom_tutorial::source::StringSource;
+	/// let source = StringSource::new("/dev/sda1 /mnt/disk ext4 rw 0 0\n");
+	/// let mounts = Mounts::from_source(&source).unwrap();
+	/// assert_eq!(mounts.into_iter().count(), 1);
+	/// ```
+	pub fn from_source(source: &S) -> std::result::Result<Mounts<S>, std::io::Error> {
+		let reader = source.open()?;
+		let remaining = source.remaining_lines(&reader);
+		Ok( Mounts { reader, format: source.format(), limits: limits::Limits::default(), remaining } )
+	}
+
+	/// Applies `limits` to every line, for parsing mount tables that can't be trusted.  Lines that exceed them are returned as a [LimitError](limits::LimitError) and reading continues with the next line.  By default there are no limits.
+	pub fn with_limits(mut self, limits: limits::Limits) -> Mounts<S> {
+		self.limits = limits;
+		self
+	}
+}
+
+impl<S: source::MountSource> IntoIterator for Mounts<S> {
+	type Item = std::result::Result<Mount, BoxError>;
+	type IntoIter = MountsIntoIterator<S::Reader>;
+	
+	/// Consuming iterator, used similarly to mutable iterator.  See [Mounts::iter_mut()] for example.
+	fn into_iter(self) -> Self::IntoIter {
+		MountsIntoIterator { reader: self.reader, format: self.format, limits: self.limits, remaining: self.remaining, done: false }
+	}
+}
+
+impl<'a, S: source::MountSource> IntoIterator for &'a mut Mounts<S> {
+	type Item = std::result::Result<Mount, BoxError>;
+	type IntoIter = MountsIteratorMut<'a, S::Reader>;
+	
+	/// Mutable iterator, see [Mounts::iter_mut()].
+	fn into_iter(self) -> Self::IntoIter {
+		MountsIteratorMut { reader: &mut self.reader, format: self.format, limits: self.limits, remaining: &mut self.remaining }
+	}
+}
+
+/// Consuming iterator for [Mounts].
+///
+/// Once it has returned `None` it keeps returning `None`, even if the reader has more data by then.  Its [size_hint()](Iterator::size_hint) is exact for sources that hold the whole table in memory, such as [SingleReadSource](source::SingleReadSource), so collecting it allocates only once.  For those sources it can also be iterated from the back, see [DoubleEndedIterator::next_back()].
+pub struct MountsIntoIterator<R: BufRead = std::io::BufReader<std::fs::File>> {
+	reader: R,
+	format: source::Format,
+	limits: limits::Limits,
+	remaining: std::option::Option<usize>,
+	done: bool
+}
+
+impl<R: BufRead> MountsIntoIterator<R> {
+	// Shared body of `next()` for this iterator and [MountEntries].
+	fn next_entry(&mut self) -> std::option::Option<std::result::Result<MountEntry, BoxError>> {
+		if self.done {
+			return None;
+		}
+		let (format, limits) = (self.format, self.limits);
+		let next = limits::read_line(&mut self.reader, limits.max_line_length).map(|line| parse_read_line(line, format, &limits));
+		self.done = next.is_none();
+		count_line(&mut self.remaining, &next);
+		next
+	}
+}
+
+impl<R: BufRead> std::iter::Iterator for MountsIntoIterator<R> {
+	type Item = std::result::Result<Mount, BoxError>;
+	
+	/// Returns the next line in `/proc/mounts` as a [Mount].  If there is a problem reading or parsing `/proc/mounts` returns an error.  In the case of a parsing error the error is a [ParseError](crate::ParseError), which uses [nom::Err::to_owned()] to allow the underlying nom error to outlive `line`.  See [Mounts::iter_mut()] for an analagous example using a mutable iterator.
+	fn next(&mut self) -> std::option::Option<Self::Item> {
+		self.next_entry().map(|entry| entry.map(Mount::from))
+	}
+
+	fn size_hint(&self) -> (usize, std::option::Option<usize>) {
+		if self.done {
+			return (0, Some(0));
+		}
+		size_hint(self.remaining)
+	}
+}
+
+impl<R: BufRead> std::iter::FusedIterator for MountsIntoIterator<R> {}
+
+impl std::iter::DoubleEndedIterator for MountsIntoIterator<std::io::Cursor<std::string::String>> {
+	/// Returns the last line not yet read as a [Mount], for sources that hold the whole table in memory such as [StringSource](source::StringSource) and [SingleReadSource](source::SingleReadSource).  Iterating in reverse lists the most recent mounts first, which is a good order for unmounting them, without collecting the table first.  The front and the back can be read alternately until they meet.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mounts;
+	/// # use nom_tutorial::source::StringSource;
+	/// let source = StringSource::new("/dev/sda1 / ext4 rw 0 0\n/dev/sdb1 /mnt ext4 rw 0 0\n");
+	/// let mount_points: Vec<String> = Mounts::from_source(&source).unwrap().into_iter().rev().map(|m| m.unwrap().mount_point).collect();
+	/// assert_eq!(mount_points, ["/mnt", "/"]);
+	/// ```
+	fn next_back(&mut self) -> std::option::Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		let position = self.reader.position() as usize;
+		let text = self.reader.get_ref();
+		let unread = text.get(position..).unwrap_or_default();
+		let unread = unread.strip_suffix('\n').unwrap_or(unread);
+		if unread.is_empty() {
+			self.done = true;
+			return None;
+		}
+		let start = unread.rfind('\n').map_or(0, |newline| newline + 1);
+		let line = &unread[start..];
+		// Strip a `\r` like `limits::read_line()` does from the front.
+		let line = line.strip_suffix('\r').unwrap_or(line);
+		let (format, limits) = (self.format, self.limits);
+		let line = if line.len() > limits.max_line_length {
+			Err(limits::ReadError::Limit(limits::LimitError { kind: limits::LimitKind::LineLength, limit: limits.max_line_length }))
+		} else {
+			Ok(line.to_string())
+		};
+		// Cutting the line off the end of the buffer leaves the rest for `next()`.
+		self.reader.get_mut().truncate(position + start);
+		let next = Some(parse_read_line(line, format, &limits).map(Mount::from));
+		count_line(&mut self.remaining, &next);
+		next
+	}
+}
+
+// Keeps track of the lines left after the iterator returned `next`.
+fn count_line<T>(remaining: &mut std::option::Option<usize>, next: &std::option::Option<T>) {
+	*remaining = match next {
+		Some(_) => remaining.map(|remaining| remaining.saturating_sub(1)),
+		None => Some(0)
+	};
+}
+
+// The size hint for an iterator with `remaining` lines left, if known.
+fn size_hint(remaining: std::option::Option<usize>) -> (usize, std::option::Option<usize>) {
+	match remaining {
+		Some(remaining) => (remaining, Some(remaining)),
+		None => (0, None)
+	}
+}
+
+// Shared body of the iterators' `next()` methods.  Takes a line as read by [limits::read_line()] and parses it into a [MountEntry] according to `format`, converting any kind of failure into a [BoxError].
+fn parse_read_line(line: std::result::Result<std::string::String, limits::ReadError>, format: source::Format, limits: &limits::Limits) -> std::result::Result<MountEntry, BoxError> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::debug_span!("parse_line").entered();
+	let line = match line {
+		Ok(line) => line,
+		Err(limits::ReadError::Io(e)) => {
+			#[cfg(feature = "tracing")]
+			tracing::debug!(error = %e, "failed to read line");
+			return Err(e.into());
+		},
+		Err(limits::ReadError::Limit(e)) => {
+			#[cfg(feature = "tracing")]
+			tracing::debug!(error = %e, "line exceeds limits");
+			return Err(e.into());
+		}
+	};
+	match crate::parsers::parse_entry(&line, format) {
+		Ok(m) => {
+			if let Err(e) = limits.check(&m) {
+				#[cfg(feature = "tracing")]
+				tracing::debug!(error = %e, "mount exceeds limits");
+				return Err(e.into());
+			}
+			#[cfg(feature = "tracing")]
+			tracing::debug!(device = %m.device, mount_point = %m.mount_point, "parsed line");
+			Ok(m)
+		},
+		Err(e) => {
+			#[cfg(feature = "tracing")]
+			tracing::debug!(line = %line, error = %e, "failed to parse line");
+			Err(e.into())
+		}
+	}
+}
+
+/// Consuming iterator over the lines of [Mounts] as [MountEntry]s, which keep the [MountInfo](crate::MountInfo) of mount tables in the format of `/proc/self/mountinfo`, see [Mounts::entries()].
+pub struct MountEntries<R: BufRead = std::io::BufReader<std::fs::File>> {
+	inner: MountsIntoIterator<R>
+}
+
+impl<R: BufRead> std::iter::Iterator for MountEntries<R> {
+	type Item = std::result::Result<MountEntry, BoxError>;
+
+	/// Returns the next line as a [MountEntry], with the same errors as [MountsIntoIterator].
+	fn next(&mut self) -> std::option::Option<Self::Item> {
+		self.inner.next_entry()
+	}
+
+	fn size_hint(&self) -> (usize, std::option::Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl<R: BufRead> std::iter::FusedIterator for MountEntries<R> {}
+
+/// Iterator over the mounts of [Mounts] that logs the lines it can't read or parse instead of returning them, see [Mounts::logged()].  Requires the `log` feature.
+#[cfg(feature = "log")]
+pub struct LoggedMounts<R: BufRead = std::io::BufReader<std::fs::File>> {
+	inner: MountsIntoIterator<R>,
+	line_number: usize
+}
+
+#[cfg(feature = "log")]
+impl<R: BufRead> std::iter::Iterator for LoggedMounts<R> {
+	type Item = Mount;
+
+	/// Returns the next mount that could be parsed, logging a warning for every line skipped on the way.  Stops after logging an I/O error other than invalid UTF-8, since the reader can't be trusted to make progress after that.
+	fn next(&mut self) -> std::option::Option<Mount> {
+		loop {
+			self.line_number += 1;
+			match self.inner.next()? {
+				Ok(mount) => return Some(mount),
+				Err(e) => {
+					log::warn!("skipping line {} of mount table: {}", self.line_number, e);
+					if matches!(e.downcast_ref::<std::io::Error>(), Some(e) if e.kind() != std::io::ErrorKind::InvalidData) {
+						self.inner.done = true;
+						return None;
+					}
+				}
+			}
+		}
+	}
+
+	fn size_hint(&self) -> (usize, std::option::Option<usize>) {
+		(0, self.inner.size_hint().1)
+	}
+}
+
+#[cfg(feature = "log")]
+impl<R: BufRead> std::iter::FusedIterator for LoggedMounts<R> {}
+
+/// Mutable iterator for `Mounts`.
+pub struct MountsIteratorMut<'a, R: BufRead = std::io::BufReader<std::fs::File>> {
+	reader: &'a mut R,
+	format: source::Format,
+	limits: limits::Limits,
+	remaining: &'a mut std::option::Option<usize>
+}
+
+impl<'a, R: BufRead> std::iter::Iterator for MountsIteratorMut<'a, R> {
+	type Item = std::result::Result<Mount, BoxError>;
+	
+	// Returns the next line in `/proc/mounts` as a [Mount].  See [Mounts::iter_mut()] for an example.
+	fn next(&mut self) -> std::option::Option<Self::Item> {
+		let (format, limits) = (self.format, self.limits);
+		let next = limits::read_line(&mut self.reader, limits.max_line_length).map(|line| parse_read_line(line, format, &limits).map(Mount::from));
+		count_line(self.remaining, &next);
+		next
+	}
+
+	fn size_hint(&self) -> (usize, std::option::Option<usize>) {
+		size_hint(*self.remaining)
+	}
+}
+
+impl<'a, S: source::MountSource> Mounts<S> {
+	// There is no non-mutable iterator.
+	
+	/// Mutable iterator.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial;
+	/// let mut iter = nom_tutorial::mounts().expect("Couldn't access /proc/mounts.").into_iter();
+	/// match iter.next() {
+	///     Some(m) => match m {
+	///         Ok(m) => eprintln!("Here is a mounted filesystem: {}", m),
+	///         Err(e) => eprintln!("There was an error parsing the next line in /proc/mounts: {}", e)
+	///     },
+	///     None => eprintln!("There are no mounted filesystems.")
+	/// }
+	/// ```
+	pub fn iter_mut(&'a mut self) -> MountsIteratorMut<'a, S::Reader> {
+		self.into_iter()
+	}
+
+	/// Consuming iterator like [Mounts::into_iter()](std::iter::IntoIterator::into_iter) that returns [MountEntry]s, which keep the [MountInfo](crate::MountInfo) of each line when reading a mount table in the format of `/proc/self/mountinfo`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mounts;
+	/// # use nom_tutorial::source::StringSource;
+	/// let source = StringSource::mountinfo("36 35 98:0 / /mnt rw,noatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro\n");
+	/// let entry = Mounts::from_source(&source).unwrap().entries().next().unwrap().unwrap();
+	/// assert_eq!(entry.mountinfo.unwrap().mount_id, 36);
+	/// ```
+	pub fn entries(self) -> MountEntries<S::Reader> {
+		MountEntries { inner: self.into_iter() }
+	}
+}
+
+#[cfg(feature = "log")]
+impl<S: source::MountSource> Mounts<S> {
+	/// Consuming iterator that yields plain [Mount]s, for applications where a best-effort list is good enough.  Lines that can't be read or parsed are logged at warn level with the [log](https://docs.rs/log) crate and skipped.  Requires the `log` feature.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mounts;
+	/// # use nom_tutorial::source::StringSource;
+	/// let source = StringSource::new("/dev/sda1 / ext4 rw 0 0\ngarbage\nproc /proc proc rw 0 0\n");
+	/// let mounts: Vec<_> = Mounts::from_source(&source).unwrap().logged().collect();
+	/// assert_eq!(mounts.len(), 2);
+	/// ```
+	pub fn logged(self) -> LoggedMounts<S::Reader> {
+		LoggedMounts { inner: self.into_iter(), line_number: 0 }
+	}
+}
+
+/// Convenience method equivalent to `Mounts::new()`.
+pub fn mounts() -> std::result::Result<Mounts, std::io::Error> {
+	Mounts::new()
+}
+
+/// Reads `/proc/mounts` into an owned [MountSnapshot] that can be iterated over repeatedly and shared between threads.
+///
+/// The table is read with a single `read()` call before it is parsed, see [SingleReadSource](source::SingleReadSource), so the snapshot never mixes the table from before and after a concurrent mount or unmount.
+/// # Examples
+/// ```
+/// # use nom_tutorial;
+/// let snapshot = std::sync::Arc::new(nom_tutorial::snapshot().unwrap());
+/// let shared = std::sync::Arc::clone(&snapshot);
+/// std::thread::spawn(move || for mount in shared.iter() { println!("{}", mount); }).join().unwrap();
+/// ```
+pub fn snapshot() -> std::result::Result<MountSnapshot, BoxError> {
+	MountSnapshot::from_source(&source::SingleReadSource::proc_mounts())
+}
+
+/// Like [snapshot()], but reads `/proc/mounts` again until two consecutive reads agree, so the snapshot is self-consistent even if the kernel's single read was interrupted by a change.  Returns an error if the table is still changing after `max_retries` re-reads, see [MountSnapshot::from_source_consistent()](MountSnapshot::from_source_consistent).
+pub fn snapshot_consistent(max_retries: usize) -> std::result::Result<MountSnapshot, BoxError> {
+	MountSnapshot::from_source_consistent(&source::SingleReadSource::proc_mounts(), max_retries)
+}
+
+/// The mount tables tried by [snapshot_with_fallback()], in order.
+pub const FALLBACK_PATHS: [&str; 3] = ["/proc/self/mounts", "/proc/mounts", "/etc/mtab"];
+
+/// Like [snapshot()], but keeps going down a chain of sources until one can be read and parsed, so the crate works in sandboxes that mask some of them.  Tries each of [FALLBACK_PATHS] in turn: `/proc/self/mounts`, `/proc/mounts`, and the `/etc/mtab` that older systems still keep as a regular file.  With the `getmntent` feature the same paths are then tried once more with the C library's `getmntent_r(3)`, which copes with formats that differ subtly from the kernel's.  A table without any mounts counts as a failure too, since every running system has at least a root filesystem, so an empty `/etc/mtab` doesn't hide a readable table further down the chain.  The source that worked is recorded in [MountSnapshot::source()].  If every source fails the error lists them all.
+/// # Examples
+/// ```no_run
+/// let snapshot = nom_tutorial::snapshot_with_fallback().unwrap();
+/// println!("read {} mounts from {}", snapshot.len(), snapshot.source().unwrap());
+/// ```
+pub fn snapshot_with_fallback() -> std::result::Result<MountSnapshot, BoxError> {
+	let paths: std::vec::Vec<&std::path::Path> = FALLBACK_PATHS.iter().map(std::path::Path::new).collect();
+	snapshot_from_chain(&paths)
+}
+
+// Tries the sources of the fallback chain for `paths`, see [snapshot_with_fallback()].
+fn snapshot_from_chain(paths: &[&std::path::Path]) -> std::result::Result<MountSnapshot, BoxError> {
+	use super::snapshot::SnapshotSource;
+	let mut errors = std::vec::Vec::new();
+	for path in paths {
+		match MountSnapshot::from_source(&source::SingleReadSource::new(*path)) {
+			Ok(snapshot) if snapshot.is_empty() => errors.push(format!("{}: no mounts", path.display())),
+			Ok(snapshot) => return Ok(snapshot.with_source(SnapshotSource::File(path.to_path_buf()))),
+			Err(e) => errors.push(format!("{}: {}", path.display(), e))
+		}
+	}
+	#[cfg(feature = "getmntent")]
+	for path in paths {
+		match MountSnapshot::from_source(&crate::getmntent::GetmntentSource::new(*path)) {
+			Ok(snapshot) if snapshot.is_empty() => errors.push(format!("{} via getmntent: no mounts", path.display())),
+			Ok(snapshot) => return Ok(snapshot.with_source(SnapshotSource::Getmntent(path.to_path_buf()))),
+			Err(e) => errors.push(format!("{} via getmntent: {}", path.display(), e))
+		}
+	}
+	Err(format!("no mount table could be read ({})", errors.join("; ")).into())
+}
+
+/// Convenience method equivalent to `Mounts::from_source(&source::ProcMountInfo)`.  Iterate over [Mounts::entries()] to keep the [MountInfo](crate::MountInfo) of each mount.
+/// # Examples
+/// ```
+/// # use nom_tutorial;
+/// for entry in nom_tutorial::mountinfo().unwrap().entries() {
+///     let entry = entry.unwrap();
+///     println!("{} has superblock options {:?}", entry.mount_point, entry.super_options().unwrap());
+/// }
+/// ```
+pub fn mountinfo() -> std::result::Result<Mounts<source::ProcMountInfo>, std::io::Error> {
+	Mounts::from_source(&source::ProcMountInfo)
+}
+
+/// A line of a mount table that [parse_all()] couldn't turn into a [Mount].
+#[derive(Debug)]
+pub struct LineError {
+	/// The line number, starting from 1.
+	pub line_number: usize,
+	/// Why the line was rejected, usually a [ParseError](crate::ParseError).  Lines that can't be read, e.g. because they aren't valid UTF-8, give a [std::io::Error].
+	pub error: BoxError,
+}
+
+impl std::fmt::Display for LineError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "line {}: {}", self.line_number, self.error)
+	}
+}
+
+impl std::error::Error for LineError {
+	fn source(&self) -> std::option::Option<&(dyn std::error::Error + 'static)> {
+		Some(&*self.error)
+	}
+}
+
+/// Parses every line of a mount table in the format of `/proc/mounts`, never stopping at the first bad line.  Returns the mounts that could be parsed along with a [LineError] for each line that couldn't, which is what an audit wants: as much data as possible plus a list of defects.  Reading only stops early if `reader` itself fails, in which case the I/O error is the last [LineError].
+/// # Examples
+/// ```
+/// # use nom_tutorial::io::parse_all;
+/// let (mounts, errors) = parse_all("/dev/sda1 / ext4 rw 0 0\ngarbage\nproc /proc proc rw 0 0\n".as_bytes());
+/// assert_eq!(mounts.len(), 2);
+/// assert_eq!(errors[0].line_number, 2);
+/// ```
+pub fn parse_all<R: BufRead>(mut reader: R) -> (std::vec::Vec<Mount>, std::vec::Vec<LineError>) {
+	let (mut mounts, mut errors) = (std::vec::Vec::new(), std::vec::Vec::new());
+	let limits = limits::Limits::default();
+	let mut line_number = 0;
+	while let Some(line) = limits::read_line(&mut reader, limits.max_line_length) {
+		line_number += 1;
+		// A line that isn't UTF-8 has been consumed, but any other I/O error means the reader is broken.
+		let fatal = matches!(&line, Err(limits::ReadError::Io(e)) if e.kind() != std::io::ErrorKind::InvalidData);
+		match parse_read_line(line, source::Format::Mounts, &limits) {
+			Ok(entry) => mounts.push(entry.mount),
+			Err(error) => errors.push(LineError { line_number, error })
+		}
+		if fatal {
+			break;
+		}
+	}
+	(mounts, errors)
+}
+
+impl Mount {
+	/// The major and minor number of the block device the mount is backed by, read with `stat()` if [Mount::device] is a device node such as /dev/sda1.  Returns `None` otherwise, including on targets without Unix device nodes, such as WASI, and for ZFS, whose device is a dataset name rather than a path, see [Mount::zfs_dataset()].  [MountEntry::device_number()] knows the numbers of every mount read from mountinfo.
+	pub fn device_number(&self) -> std::option::Option<(u32, u32)> {
+		if self.is_zfs() {
+			return None;
+		}
+		block_device_number(std::path::Path::new(&self.device))
+	}
+}
+
+impl MountEntry {
+	/// Like [Mount::device_number()], but falls back to the numbers in [MountInfo](crate::MountInfo), which for filesystems without a backing device are anonymous numbers with major 0.  Returns `None` if neither is available.
+	pub fn device_number(&self) -> std::option::Option<(u32, u32)> {
+		self.mount.device_number().or_else(|| self.mountinfo.as_ref().map(|info| (info.major, info.minor)))
+	}
+}
+
+// The major and minor number of the block device node at `path`, or `None` if `path` isn't one.
+pub(crate) fn block_device_number(path: &std::path::Path) -> std::option::Option<(u32, u32)> {
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::{FileTypeExt, MetadataExt};
+		if let Ok(metadata) = std::fs::metadata(path) {
+			if metadata.file_type().is_block_device() {
+				return Some(split_device_number(metadata.rdev()));
+			}
+		}
+	}
+	#[cfg(not(unix))]
+	let _ = path;
+	None
+}
+
+// Splits a dev_t in the glibc encoding into its major and minor number, see makedev(3).  The minor number has 8 bits at the bottom and 24 above the 12 bits of the major number, which has its remaining 20 bits in the top half.
+#[cfg(unix)]
+fn split_device_number(rdev: u64) -> (u32, u32) {
+	let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & 0xfffff000);
+	let minor = (rdev & 0xff) | ((rdev >> 12) & 0xffffff00);
+	(major as u32, minor as u32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// In-memory sources give an exact size hint that counts down, a finished iterator stays finished, and only a file read in one go has an exact hint.
+	#[test]
+	fn test_size_hint_and_fused() {
+		let source = source::StringSource::new("/dev/sda1 / ext4 rw 0 0\nbad line\nproc /proc proc rw 0 0");
+		let mut mounts = Mounts::from_source(&source).unwrap();
+		let mut iter = mounts.iter_mut();
+		assert_eq!(iter.size_hint(), (3, Some(3)));
+		assert!(iter.next().unwrap().is_ok());
+		let mut iter = mounts.into_iter();
+		assert_eq!(iter.size_hint(), (2, Some(2)));
+		assert!(iter.next().unwrap().is_err());
+		assert_eq!(iter.by_ref().count(), 1);
+		assert_eq!(iter.size_hint(), (0, Some(0)));
+		assert!(iter.next().is_none());
+		let path = std::env::temp_dir().join(format!("nom-tutorial-size-hint-{}", std::process::id()));
+		std::fs::write(&path, "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n").unwrap();
+		let streamed = Mounts::from_source(&source::FileSource::new(&path)).unwrap().into_iter().size_hint();
+		let read_whole = Mounts::from_source(&source::SingleReadSource::new(&path)).unwrap().into_iter().size_hint();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(streamed, (0, None));
+		assert_eq!(read_whole, (2, Some(2)));
+	}
+
+	// The chain skips sources that are missing, can't be parsed, or are empty, and reports every failure if none works.
+	#[test]
+	fn test_snapshot_from_chain() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-chain-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let (missing, bad, empty, good) = (directory.join("missing"), directory.join("bad"), directory.join("empty"), directory.join("good"));
+		std::fs::write(&bad, "not a mount table\n").unwrap();
+		std::fs::write(&empty, "").unwrap();
+		std::fs::write(&good, "/dev/sda1 / ext4 rw 0 0\n").unwrap();
+		let found = snapshot_from_chain(&[&missing, &bad, &empty, &good]);
+		let failed = snapshot_from_chain(&[&missing, &bad, &empty]);
+		std::fs::remove_dir_all(&directory).unwrap();
+		let found = found.unwrap();
+		assert_eq!(found.len(), 1);
+		assert_eq!(found.source(), Some(&crate::snapshot::SnapshotSource::File(good)));
+		let failed = failed.unwrap_err().to_string();
+		assert!(failed.contains("missing: ") && failed.contains("bad: ") && failed.contains("empty: no mounts"), "{}", failed);
+	}
+
+	// Bad lines are reported with their line numbers and don't stop parsing, not even invalid UTF-8.
+	#[test]
+	fn test_parse_all() {
+		let table: &[u8] = b"/dev/sda1 / ext4 rw 0 0\n/dev/sda2 /mnt\n\xff\xfe\ntmpfs /tmp tmpfs rw 0 0";
+		let (mounts, errors) = parse_all(table);
+		assert_eq!(mounts.iter().map(|m| &m.mount_point[..]).collect::<std::vec::Vec<_>>(), ["/", "/tmp"]);
+		assert_eq!(errors.iter().map(|e| e.line_number).collect::<std::vec::Vec<_>>(), [2, 3]);
+		assert!(errors[0].error.downcast_ref::<crate::ParseError>().is_some());
+		assert_eq!(errors[1].error.downcast_ref::<std::io::Error>().unwrap().kind(), std::io::ErrorKind::InvalidData);
+		assert!(errors[0].to_string().starts_with("line 2: "));
+	}
+
+	// Both ends of a buffered table can be read until they meet, with limits and the size hint kept the same as from the front.
+	#[test]
+	fn test_next_back() {
+		let source = source::StringSource::new("/dev/sda1 / ext4 rw 0 0\n/dev/sdb1 /a/very/long/mount/point ext4 rw 0 0\ntmpfs /tmp tmpfs rw 0 0\n/dev/sdc1 /mnt ext4 rw 0 0");
+		let mut iter = Mounts::from_source(&source).unwrap().with_limits(limits::Limits { max_line_length: 40, ..limits::Limits::UNLIMITED }).into_iter();
+		assert_eq!(iter.size_hint(), (4, Some(4)));
+		assert_eq!(iter.next_back().unwrap().unwrap().mount_point, "/mnt");
+		assert_eq!(iter.next().unwrap().unwrap().mount_point, "/");
+		assert_eq!(iter.next_back().unwrap().unwrap().mount_point, "/tmp");
+		assert_eq!(iter.size_hint(), (1, Some(1)));
+		assert!(iter.next_back().unwrap().unwrap_err().downcast_ref::<limits::LimitError>().is_some());
+		assert!(iter.next().is_none());
+		assert!(iter.next_back().is_none());
+	}
+
+	// The high bits of the major number don't leak into the minor number or the other way around.
+	#[cfg(unix)]
+	#[test]
+	fn test_split_device_number() {
+		let (major, minor): (u64, u64) = (0x12345, 0x123456);
+		let rdev = (minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12) | ((major & !0xfff) << 32);
+		assert_eq!(split_device_number(rdev), (0x12345, 0x123456));
+		assert_eq!(split_device_number(0x811), (8, 17));
+	}
+
+	// The logging iterator skips bad lines, but gives up on a reader that keeps failing.
+	#[cfg(feature = "log")]
+	#[test]
+	fn test_logged() {
+		let source = source::StringSource::new("garbage\n/dev/sda1 / ext4 rw 0 0\n\n");
+		assert_eq!(Mounts::from_source(&source).unwrap().logged().map(|m| m.mount_point).collect::<std::vec::Vec<_>>(), ["/"]);
+		struct Broken;
+		impl std::io::Read for Broken {
+			fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+				Err(std::io::Error::other("broken"))
+			}
+		}
+		let inner = MountsIntoIterator { reader: std::io::BufReader::new(Broken), format: source::Format::Mounts, limits: limits::Limits::default(), remaining: None, done: false };
+		assert_eq!(LoggedMounts { inner, line_number: 0 }.count(), 0);
+	}
+}
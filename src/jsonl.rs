@@ -0,0 +1,127 @@
+//! Writes mounts as [JSON Lines](https://jsonlines.org/), one JSON object per line, the format log pipelines such as Vector, Fluent Bit, or `jq -c` ingest.
+//!
+//! Each mount is written as soon as it is produced, so a mount table can be streamed from [Mounts](crate::Mounts) without collecting it first.  The objects have the same fields as the `serde` representation of [Mount](crate::Mount), plus the [MountInfo] of the [MountEntry] or null, but no serde support is needed to write them.
+
+use super::{MountEntry, MountInfo};
+use std::borrow::Borrow;
+
+// Writes `value` as a JSON string, escaping quotes, backslashes, and control characters.
+fn write_string<W: std::io::Write>(w: &mut W, value: &str) -> std::io::Result<()> {
+	w.write_all(b"\"")?;
+	for c in value.chars() {
+		match c {
+			'"' => w.write_all(b"\\\"")?,
+			'\\' => w.write_all(b"\\\\")?,
+			'\n' => w.write_all(b"\\n")?,
+			'\r' => w.write_all(b"\\r")?,
+			'\t' => w.write_all(b"\\t")?,
+			c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+			c => write!(w, "{}", c)?
+		}
+	}
+	w.write_all(b"\"")
+}
+
+// Writes a list of strings as a JSON array.
+fn write_strings<W: std::io::Write>(w: &mut W, values: &[std::string::String]) -> std::io::Result<()> {
+	w.write_all(b"[")?;
+	for (i, value) in values.iter().enumerate() {
+		if i > 0 {
+			w.write_all(b",")?;
+		}
+		write_string(w, value)?;
+	}
+	w.write_all(b"]")
+}
+
+// Writes the mountinfo fields as a JSON object.
+fn write_mountinfo<W: std::io::Write>(w: &mut W, info: &MountInfo) -> std::io::Result<()> {
+	write!(w, "{{\"mount_id\":{},\"parent_id\":{},\"major\":{},\"minor\":{},\"root\":", info.mount_id, info.parent_id, info.major, info.minor)?;
+	write_string(w, &info.root)?;
+	w.write_all(b",\"vfs_options\":")?;
+	write_strings(w, &info.vfs_options)?;
+	w.write_all(b",\"optional_fields\":")?;
+	write_strings(w, &info.optional_fields)?;
+	w.write_all(b",\"super_options\":")?;
+	write_strings(w, &info.super_options)?;
+	w.write_all(b"}")
+}
+
+/// Writes `mount` to `w` as a single JSON object, without a trailing newline, for embedding in larger documents.  Mounts without [MountInfo] have `"mountinfo":null`.
+pub fn write_json<W: std::io::Write>(mount: &MountEntry, mut w: W) -> std::io::Result<()> {
+	w.write_all(b"{\"device\":")?;
+	write_string(&mut w, &mount.device)?;
+	w.write_all(b",\"mount_point\":")?;
+	write_string(&mut w, &mount.mount_point)?;
+	w.write_all(b",\"file_system_type\":")?;
+	write_string(&mut w, &mount.file_system_type)?;
+	w.write_all(b",\"options\":")?;
+	write_strings(&mut w, &mount.options)?;
+	w.write_all(b",\"mountinfo\":")?;
+	match &mount.mountinfo {
+		Some(info) => write_mountinfo(&mut w, info)?,
+		None => w.write_all(b"null")?
+	}
+	w.write_all(b"}")
+}
+
+/// Writes a JSON object for each mount to `w`, each on its own line, see [write_json()].
+///
+/// `w` isn't buffered here, so wrap it in a [BufWriter](std::io::BufWriter) when writing to a file or socket.
+/// # Examples
+/// ```
+/// # use nom_tutorial::{Mount, MountEntry};
+/// let mount = Mount { device: "tmpfs".to_string(), mount_point: "/tmp".to_string(), file_system_type: "tmpfs".to_string(), options: vec!["rw".to_string()] };
+/// let mut out = Vec::new();
+/// nom_tutorial::jsonl::write_jsonl(&[MountEntry::from(mount)], &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "{\"device\":\"tmpfs\",\"mount_point\":\"/tmp\",\"file_system_type\":\"tmpfs\",\"options\":[\"rw\"],\"mountinfo\":null}\n");
+/// ```
+pub fn write_jsonl<I, W>(mounts: I, mut w: W) -> std::io::Result<()>
+where
+	I: IntoIterator,
+	I::Item: std::borrow::Borrow<MountEntry>,
+	W: std::io::Write,
+{
+	for mount in mounts {
+		write_json(mount.borrow(), &mut w)?;
+		w.write_all(b"\n")?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Mount;
+
+	// Every line is valid JSON on its own, even with characters that need escaping.
+	#[test]
+	fn test_write_jsonl() {
+		let info = MountInfo { mount_id: 36, parent_id: 35, major: 98, minor: 0, root: "/".to_string(), vfs_options: vec!["rw".to_string()], optional_fields: vec![], super_options: vec!["errors=remount-ro".to_string()] };
+		let mounts = [
+			MountEntry { mount: Mount { device: "/dev/sda1".to_string(), mount_point: "/mnt/\"a\\b\"\n\u{1}".to_string(), file_system_type: "ext4".to_string(), options: vec!["rw".to_string()] }, mountinfo: Some(info) },
+			MountEntry::default(),
+		];
+		let mut out = std::vec::Vec::new();
+		write_jsonl(mounts.iter(), &mut out).unwrap();
+		let out = std::string::String::from_utf8(out).unwrap();
+		let lines: std::vec::Vec<serde_json::Value> = out.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0]["mount_point"], mounts[0].mount_point);
+		assert_eq!(lines[0]["mountinfo"]["super_options"][0], "errors=remount-ro");
+		assert_eq!(lines[0]["mountinfo"]["optional_fields"], serde_json::json!([]));
+		assert!(lines[1]["mountinfo"].is_null());
+	}
+
+	// With serde, the lines deserialize back into the same mount and mountinfo.
+	#[cfg(feature = "serde")]
+	#[test]
+	fn test_write_jsonl_serde() {
+		let entry = MountEntry { mount: Mount { device: "a b".to_string(), ..Default::default() }, mountinfo: Some(MountInfo::default()) };
+		let mut out = std::vec::Vec::new();
+		write_jsonl(vec![entry.clone()], &mut out).unwrap();
+		let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+		assert_eq!(serde_json::from_value::<Mount>(value.clone()).unwrap(), entry.mount);
+		assert_eq!(serde_json::from_value::<Option<MountInfo>>(value["mountinfo"].clone()).unwrap(), entry.mountinfo);
+	}
+}
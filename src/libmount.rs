@@ -0,0 +1,270 @@
+//! Reads mount tables with util-linux's libmount instead of this crate's parsers.  Enabled by the `libmount` feature.
+//!
+//! libmount is the reference implementation used by mount(8) and findmnt(8).  It is loaded at runtime with `dlopen(3)`, so the crate builds without the libmount development files and only fails when the functions here are called on a system without `libmount.so.1`.  The results use the same [Mount] type as the rest of the crate, and [validate()] compares the two implementations on the same file.
+
+use super::{BoxError, Mount, MountEntry, MountInfo};
+
+// Opaque libmount types.
+#[repr(C)]
+struct Table { _private: [u8; 0] }
+#[repr(C)]
+struct Iter { _private: [u8; 0] }
+#[repr(C)]
+struct Fs { _private: [u8; 0] }
+
+// MNT_ITER_FORWARD from libmount.h.
+const ITER_FORWARD: libc::c_int = 0;
+
+// Signatures of the libmount functions we use, from libmount.h.
+type NewTableFromFile = unsafe extern "C" fn(*const libc::c_char) -> *mut Table;
+type UnrefTable = unsafe extern "C" fn(*mut Table);
+type NewIter = unsafe extern "C" fn(libc::c_int) -> *mut Iter;
+type FreeIter = unsafe extern "C" fn(*mut Iter);
+type TableNextFs = unsafe extern "C" fn(*mut Table, *mut Iter, *mut *mut Fs) -> libc::c_int;
+type FsGetString = unsafe extern "C" fn(*mut Fs) -> *const libc::c_char;
+type FsGetInt = unsafe extern "C" fn(*mut Fs) -> libc::c_int;
+type FsGetDevno = unsafe extern "C" fn(*mut Fs) -> libc::dev_t;
+
+// The libmount functions we use, resolved with dlsym().
+struct Library {
+	new_table_from_file: NewTableFromFile,
+	unref_table: UnrefTable,
+	new_iter: NewIter,
+	free_iter: FreeIter,
+	table_next_fs: TableNextFs,
+	fs_get_source: FsGetString,
+	fs_get_target: FsGetString,
+	fs_get_fstype: FsGetString,
+	fs_get_options: FsGetString,
+	fs_get_vfs_options: FsGetString,
+	fs_get_fs_options: FsGetString,
+	fs_get_optional_fields: FsGetString,
+	fs_get_root: FsGetString,
+	fs_get_id: FsGetInt,
+	fs_get_parent_id: FsGetInt,
+	fs_get_devno: FsGetDevno,
+}
+
+// Loads libmount once per process.  The library is never closed.
+fn library() -> std::result::Result<&'static Library, BoxError> {
+	static LIBRARY: std::sync::OnceLock<std::result::Result<Library, std::string::String>> = std::sync::OnceLock::new();
+	LIBRARY.get_or_init(load).as_ref().map_err(|e| e.clone().into())
+}
+
+fn load() -> std::result::Result<Library, std::string::String> {
+	// Safe because the name is a valid NUL-terminated string.
+	let handle = unsafe { libc::dlopen(b"libmount.so.1\0".as_ptr() as *const libc::c_char, libc::RTLD_NOW | libc::RTLD_LOCAL) };
+	if handle.is_null() {
+		return Err("unable to load libmount.so.1".to_string());
+	}
+	macro_rules! symbol {
+		($name:literal, $type:ty) => {{
+			// Safe because `handle` is a valid library handle and the name is NUL-terminated.
+			let symbol = unsafe { libc::dlsym(handle, concat!($name, "\0").as_ptr() as *const libc::c_char) };
+			if symbol.is_null() {
+				return Err(format!("libmount.so.1 has no symbol {}", $name));
+			}
+			// Safe because the signatures above match libmount.h.
+			unsafe { std::mem::transmute::<*mut libc::c_void, $type>(symbol) }
+		}};
+	}
+	Ok(Library {
+		new_table_from_file: symbol!("mnt_new_table_from_file", NewTableFromFile),
+		unref_table: symbol!("mnt_unref_table", UnrefTable),
+		new_iter: symbol!("mnt_new_iter", NewIter),
+		free_iter: symbol!("mnt_free_iter", FreeIter),
+		table_next_fs: symbol!("mnt_table_next_fs", TableNextFs),
+		fs_get_source: symbol!("mnt_fs_get_source", FsGetString),
+		fs_get_target: symbol!("mnt_fs_get_target", FsGetString),
+		fs_get_fstype: symbol!("mnt_fs_get_fstype", FsGetString),
+		fs_get_options: symbol!("mnt_fs_get_options", FsGetString),
+		fs_get_vfs_options: symbol!("mnt_fs_get_vfs_options", FsGetString),
+		fs_get_fs_options: symbol!("mnt_fs_get_fs_options", FsGetString),
+		fs_get_optional_fields: symbol!("mnt_fs_get_optional_fields", FsGetString),
+		fs_get_root: symbol!("mnt_fs_get_root", FsGetString),
+		fs_get_id: symbol!("mnt_fs_get_id", FsGetInt),
+		fs_get_parent_id: symbol!("mnt_fs_get_parent_id", FsGetInt),
+		fs_get_devno: symbol!("mnt_fs_get_devno", FsGetDevno),
+	})
+}
+
+// Copies a possibly-NULL C string.
+fn string(pointer: *const libc::c_char) -> std::option::Option<std::string::String> {
+	if pointer.is_null() {
+		None
+	} else {
+		// Safe because libmount returns NUL-terminated strings that live as long as the table.
+		Some(unsafe { std::ffi::CStr::from_ptr(pointer) }.to_string_lossy().into_owned())
+	}
+}
+
+// Splits a possibly-NULL comma-separated option string.
+fn list(pointer: *const libc::c_char, separator: char) -> std::vec::Vec<std::string::String> {
+	string(pointer).map_or_else(std::vec::Vec::new, |s| s.split(separator).filter(|o| !o.is_empty()).map(|o| o.to_string()).collect())
+}
+
+/// Reads the mount table at `path` with libmount.  libmount detects the format itself, so this works for `/proc/mounts`, `/proc/self/mountinfo`, and `/etc/fstab` alike.  Entries read from mountinfo have their [MountInfo] populated.
+pub fn read<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> {
+	use std::os::unix::ffi::OsStrExt;
+	let library = library()?;
+	let c_path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())?;
+	// Safe because `c_path` is a valid NUL-terminated string.  Every pointer obtained below is checked for NULL and freed before returning.
+	unsafe {
+		let table = (library.new_table_from_file)(c_path.as_ptr());
+		if table.is_null() {
+			return Err(format!("libmount was unable to parse {}", path.as_ref().display()).into());
+		}
+		let iter = (library.new_iter)(ITER_FORWARD);
+		if iter.is_null() {
+			(library.unref_table)(table);
+			return Err("libmount was unable to allocate an iterator".into());
+		}
+		let is_mountinfo = path.as_ref().ends_with("mountinfo");
+		let mut mounts = std::vec::Vec::new();
+		let mut fs: *mut Fs = std::ptr::null_mut();
+		let mut result = Ok(());
+		loop {
+			match (library.table_next_fs)(table, iter, &mut fs) {
+				0 => (),
+				1 => break,
+				code => {
+					result = Err(format!("libmount failed to iterate over {}: error {}", path.as_ref().display(), code));
+					break;
+				}
+			}
+			let mountinfo = if is_mountinfo {
+				let devno = (library.fs_get_devno)(fs);
+				Some(MountInfo {
+					mount_id: (library.fs_get_id)(fs) as u32,
+					parent_id: (library.fs_get_parent_id)(fs) as u32,
+					major: libc::major(devno),
+					minor: libc::minor(devno),
+					root: string((library.fs_get_root)(fs)).unwrap_or_default(),
+					vfs_options: list((library.fs_get_vfs_options)(fs), ','),
+					optional_fields: list((library.fs_get_optional_fields)(fs), ' '),
+					super_options: list((library.fs_get_fs_options)(fs), ',')
+				})
+			} else {
+				None
+			};
+			let mount = Mount {
+				device: string((library.fs_get_source)(fs)).unwrap_or_else(|| "none".to_string()),
+				mount_point: string((library.fs_get_target)(fs)).unwrap_or_default(),
+				file_system_type: string((library.fs_get_fstype)(fs)).unwrap_or_default(),
+				options: list((library.fs_get_options)(fs), ',')
+			};
+			mounts.push(MountEntry { mount, mountinfo });
+		}
+		(library.free_iter)(iter);
+		(library.unref_table)(table);
+		result?;
+		Ok(mounts)
+	}
+}
+
+/// Reads `/proc/mounts` with libmount, the counterpart of [mounts()](super::mounts).
+pub fn mounts() -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> {
+	read("/proc/mounts")
+}
+
+/// Reads `/proc/self/mountinfo` with libmount, the counterpart of [mountinfo()](super::mountinfo).
+pub fn mountinfo() -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> {
+	read("/proc/self/mountinfo")
+}
+
+/// A mount on which libmount and this crate's parser disagree, see [validate()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Discrepancy {
+	/// Position of the mount in the table, starting from 0.
+	pub index: usize,
+	/// The mount as parsed by this crate, or `None` if this crate found fewer mounts.
+	pub parsed: std::option::Option<Mount>,
+	/// The mount as read by libmount, or `None` if libmount found fewer mounts.
+	pub libmount: std::option::Option<Mount>,
+}
+
+/// Parses the mount table in `/proc/mounts` format at `path` with both this crate and libmount and returns every mount on which they disagree.  An empty result means the nom parser matches the reference implementation.
+///
+/// Options are compared as sets because libmount may reorder them.
+/// # Examples
+/// ```no_run
+/// let discrepancies = nom_tutorial::libmount::validate("/proc/mounts").unwrap();
+/// assert!(discrepancies.is_empty(), "{:#?}", discrepancies);
+/// ```
+pub fn validate<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<std::vec::Vec<Discrepancy>, BoxError> {
+	// Read the file once so that both parsers see the same contents.
+	let contents = std::fs::read_to_string(path.as_ref())?;
+	let parsed: std::vec::Vec<Mount> = super::Mounts::from_source(&super::source::StringSource::new(contents.clone()))?.into_iter().collect::<std::result::Result<_, _>>()?;
+	let copy = std::env::temp_dir().join(format!("nom-tutorial-libmount-{}-{:?}", std::process::id(), std::thread::current().id()));
+	std::fs::write(&copy, contents)?;
+	let reference = read(&copy);
+	let _ = std::fs::remove_file(&copy);
+	let reference: std::vec::Vec<Mount> = reference?.into_iter().map(Mount::from).collect();
+	let same = |a: &Mount, b: &Mount| {
+		let options = |m: &Mount| m.options.iter().cloned().collect::<std::collections::BTreeSet<_>>();
+		a.device == b.device && a.mount_point == b.mount_point && a.file_system_type == b.file_system_type && options(a) == options(b)
+	};
+	let mut discrepancies = std::vec::Vec::new();
+	for index in 0..parsed.len().max(reference.len()) {
+		match (parsed.get(index), reference.get(index)) {
+			(Some(a), Some(b)) if same(a, b) => (),
+			(a, b) => discrepancies.push(Discrepancy { index, parsed: a.cloned(), libmount: b.cloned() })
+		}
+	}
+	Ok(discrepancies)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures;
+
+	// Whether libmount is installed.  Prints why the calling test is skipped if it isn't.
+	fn have_libmount() -> bool {
+		match library() {
+			Ok(_) => true,
+			Err(e) => {
+				eprintln!("skipped: {}", e);
+				false
+			}
+		}
+	}
+
+	#[test]
+	fn test_validate_fixtures() {
+		if !have_libmount() {
+			return;
+		}
+		let path = std::env::temp_dir().join(format!("nom-tutorial-libmount-fixtures-{}", std::process::id()));
+		std::fs::write(&path, test_fixtures::table(test_fixtures::ALL)).unwrap();
+		let discrepancies = validate(&path);
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(discrepancies.unwrap(), vec![]);
+	}
+
+	#[test]
+	fn test_mountinfo_matches() {
+		if !have_libmount() {
+			return;
+		}
+		let mountinfo = "22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro\n\
+			23 22 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:12 - proc proc rw\n\
+			24 22 0:22 / /run rw,nosuid,nodev,noexec,relatime shared:5 - tmpfs tmpfs rw,size=1631280k,mode=755,inode64\n\
+			45 22 8:17 /backups /media/My\\040Disk rw,nosuid master:3 propagate_from:2 - vfat /dev/sdb1 rw,fmask=0022\n";
+		// libmount only treats a file as mountinfo if it is named that.
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-libmount-mountinfo-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let path = directory.join("mountinfo");
+		std::fs::write(&path, mountinfo).unwrap();
+		let reference = read(&path);
+		std::fs::remove_dir_all(&directory).unwrap();
+		let reference = reference.unwrap();
+		let parsed: std::vec::Vec<MountEntry> = crate::Mounts::from_source(&crate::source::StringSource::mountinfo(mountinfo.to_string())).unwrap().entries().map(|m| m.unwrap()).collect();
+		assert_eq!(reference.len(), parsed.len());
+		for (a, b) in reference.iter().zip(&parsed) {
+			assert_eq!(a.mount_point, b.mount_point);
+			assert_eq!(a.mountinfo.as_ref().unwrap().mount_id, b.mountinfo.as_ref().unwrap().mount_id);
+			assert_eq!(a.super_options(), b.super_options());
+		}
+	}
+}
@@ -0,0 +1,189 @@
+//! Bounds on the size of mount table lines, for parsing files that can't be trusted.
+//!
+//! The kernel never writes absurd lines, but a mount table taken from a support bundle or uploaded by a user might be damaged or malicious.  [Limits] caps how much the parser will read and produce for a single line; a line that exceeds a cap is reported as a [LimitError] and skipped without reading all of it into memory.
+
+#[cfg(feature = "io")]
+use super::MountEntry;
+
+/// Caps on the size of a single mount table line, see [Mounts::with_limits()](crate::Mounts::with_limits).  The default is unlimited, which is fine for the kernel's own tables.
+/// # Examples
+/// ```
+/// # #[cfg(feature = "io")] {
+/// # use nom_tutorial::Mounts;
+/// # use nom_tutorial::limits::{LimitError, LimitKind, Limits};
+/// # use nom_tutorial::source::StringSource;
+/// let source = StringSource::new("/dev/sda1 /mnt ext4 rw,noatime,nosuid 0 0\n");
+/// let limits = Limits { max_options: 2, ..Limits::default() };
+/// let error = Mounts::from_source(&source).unwrap().with_limits(limits).into_iter().next().unwrap().unwrap_err();
+/// assert_eq!(error.downcast_ref::<LimitError>().unwrap().kind, LimitKind::Options);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+	/// Maximum length of a line in bytes, not counting the newline.
+	pub max_line_length: usize,
+	/// Maximum number of options on one mount, counting superblock options separately for mountinfo.
+	pub max_options: usize,
+	/// Maximum length in bytes of any single field after unescaping, e.g. the mount point or one option.
+	pub max_field_size: usize,
+}
+
+impl Limits {
+	/// No limits at all.
+	pub const UNLIMITED: Limits = Limits { max_line_length: usize::MAX, max_options: usize::MAX, max_field_size: usize::MAX };
+
+	/// Limits generous enough for any real mount table: 1 MiB lines and fields, and 1024 options.  Lines and fields are allowed to be this long because the `lowerdir=` option of an overlay mount lists every layer of a container image, which with thousands of layers takes hundreds of KiB, see [FileSource::with_capacity()](crate::source::FileSource::with_capacity).
+	pub const UNTRUSTED: Limits = Limits { max_line_length: 1024 * 1024, max_options: 1024, max_field_size: 1024 * 1024 };
+
+	// Checks the parsed mount against the limits on options and fields.
+	#[cfg(feature = "io")]
+	pub(crate) fn check(&self, mount: &MountEntry) -> std::result::Result<(), LimitError> {
+		let mut option_count = mount.options.len();
+		let mut fields: std::vec::Vec<&str> = vec![&mount.device, &mount.mount_point, &mount.file_system_type];
+		fields.extend(mount.options.iter().map(|option| &option[..]));
+		if let Some(info) = &mount.mountinfo {
+			option_count = option_count.max(info.vfs_options.len() + info.super_options.len());
+			fields.push(&info.root);
+			fields.extend(info.optional_fields.iter().map(|field| &field[..]));
+		}
+		if option_count > self.max_options {
+			return Err(LimitError { kind: LimitKind::Options, limit: self.max_options });
+		}
+		if fields.iter().any(|field| field.len() > self.max_field_size) {
+			return Err(LimitError { kind: LimitKind::FieldSize, limit: self.max_field_size });
+		}
+		Ok(())
+	}
+}
+
+impl Default for Limits {
+	fn default() -> Limits {
+		Limits::UNLIMITED
+	}
+}
+
+/// Which of the [Limits] was exceeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+	/// [Limits::max_line_length]
+	LineLength,
+	/// [Limits::max_options]
+	Options,
+	/// [Limits::max_field_size]
+	FieldSize,
+}
+
+/// A line exceeded one of the [Limits].  Returned inside a [BoxError](crate::BoxError) by the mount iterators, from which it can be recovered with `downcast_ref()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitError {
+	/// The limit that was exceeded.
+	pub kind: LimitKind,
+	/// The value of the limit.
+	pub limit: usize,
+}
+
+impl std::fmt::Display for LimitError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.kind {
+			LimitKind::LineLength => write!(f, "line is longer than {} bytes", self.limit),
+			LimitKind::Options => write!(f, "mount has more than {} options", self.limit),
+			LimitKind::FieldSize => write!(f, "field is longer than {} bytes", self.limit),
+		}
+	}
+}
+
+impl std::error::Error for LimitError {}
+
+// Error reading a line with `read_line()`.
+#[cfg(feature = "io")]
+pub(crate) enum ReadError {
+	Io(std::io::Error),
+	Limit(LimitError),
+}
+
+// Reads the next line from `reader` without its newline or a `\r` before it, reading at most `max_length` bytes of the line into memory.  A longer line is consumed up to and including its newline so that reading can resume with the next line.  Returns `None` at the end of the input.
+#[cfg(feature = "io")]
+pub(crate) fn read_line<R: std::io::BufRead>(reader: &mut R, max_length: usize) -> std::option::Option<std::result::Result<std::string::String, ReadError>> {
+	use std::io::{BufRead, Read};
+	let mut line = std::vec::Vec::new();
+	// Allow two bytes more than the limit to make room for `\r\n`.
+	let read = reader.by_ref().take(max_length.saturating_add(2) as u64).read_until(b'\n', &mut line);
+	match read {
+		Ok(0) => return None,
+		Ok(_) => (),
+		Err(e) => return Some(Err(ReadError::Io(e)))
+	}
+	let complete = line.last() == Some(&b'\n');
+	if complete {
+		line.pop();
+	}
+	// Without a newline the line was either cut off by `take()` or is the last one.
+	let cut = !complete && line.len() > max_length.saturating_add(1);
+	if line.last() == Some(&b'\r') {
+		line.pop();
+	}
+	if line.len() > max_length {
+		// Skip the rest of the line a buffer at a time.
+		if cut {
+			loop {
+				let (skip, found) = match reader.fill_buf() {
+					Ok([]) => break,
+					Ok(buffer) => match buffer.iter().position(|&byte| byte == b'\n') {
+						Some(position) => (position + 1, true),
+						None => (buffer.len(), false)
+					},
+					Err(e) => return Some(Err(ReadError::Io(e)))
+				};
+				reader.consume(skip);
+				if found {
+					break;
+				}
+			}
+		}
+		return Some(Err(ReadError::Limit(LimitError { kind: LimitKind::LineLength, limit: max_length })));
+	}
+	Some(std::string::String::from_utf8(line).map_err(|e| ReadError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))))
+}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+	use super::*;
+
+	// Reading continues normally after a line that is too long.
+	#[test]
+	fn test_read_line_skips_long_line() {
+		let mut reader = std::io::BufReader::with_capacity(4, std::io::Cursor::new("short\nthis line is too long\nok"));
+		assert!(matches!(read_line(&mut reader, 8), Some(Ok(line)) if line == "short"));
+		assert!(matches!(read_line(&mut reader, 8), Some(Err(ReadError::Limit(LimitError { kind: LimitKind::LineLength, limit: 8 })))));
+		assert!(matches!(read_line(&mut reader, 8), Some(Ok(line)) if line == "ok"));
+		assert!(read_line(&mut reader, 8).is_none());
+	}
+
+	// Windows line endings are stripped and don't count towards the limit.
+	#[test]
+	fn test_read_line_crlf() {
+		let mut reader = std::io::Cursor::new("12345678\r\nabc\r\n123456789\r\nok\r");
+		assert!(matches!(read_line(&mut reader, 8), Some(Ok(line)) if line == "12345678"));
+		assert!(matches!(read_line(&mut reader, 8), Some(Ok(line)) if line == "abc"));
+		assert!(matches!(read_line(&mut reader, 8), Some(Err(ReadError::Limit(_)))));
+		assert!(matches!(read_line(&mut reader, 8), Some(Ok(line)) if line == "ok"));
+		assert!(read_line(&mut reader, 8).is_none());
+	}
+
+	// An overlay mount of a container image with thousands of layers is within the limits for untrusted input.
+	#[test]
+	fn test_untrusted_overlay() {
+		let lowerdir: std::vec::Vec<std::string::String> = (0..4000).map(|layer| format!("/var/lib/docker/overlay2/l/LAYER{:05}", layer)).collect();
+		let line = format!("overlay /merged overlay rw,relatime,lowerdir={},upperdir=/upper,workdir=/work 0 0\n", lowerdir.join(":"));
+		let source = crate::source::StringSource::new(line);
+		let mounts = crate::Mounts::from_source(&source).unwrap().with_limits(Limits::UNTRUSTED);
+		assert_eq!(mounts.into_iter().map(|mount| mount.unwrap().options.len()).collect::<std::vec::Vec<_>>(), [5]);
+	}
+
+	#[test]
+	fn test_check_field_size() {
+		let mount = MountEntry::from(crate::Mount { mount_point: "/a/very/long/path".to_string(), ..Default::default() });
+		assert_eq!(Limits { max_field_size: 8, ..Limits::default() }.check(&mount), Err(LimitError { kind: LimitKind::FieldSize, limit: 8 }));
+		assert_eq!(Limits::UNTRUSTED.check(&mount), Ok(()));
+	}
+}
@@ -0,0 +1,279 @@
+//! Finds which mount a path lives on.
+
+use super::{BoxError, Mount, MountEntry};
+
+// Symlinks followed before giving up, the same limit Linux uses.
+const MAX_SYMLINKS: usize = 40;
+
+/// Returns the mount that contains `path`, i.e. the mount whose mount point is the longest prefix of `path`.
+///
+/// The comparison is purely lexical: `.` and `..` components are resolved without touching the filesystem and symlinks are not followed.  When several mounts share the same mount point the last one wins, since later mounts hide earlier ones.  Returns `None` if no mount point is a prefix of `path`, which can only happen if `path` is relative or the table has no root mount.  Use [find_mount_for_path_canonical()] to follow symlinks.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::lookup::find_mount_for_path;
+/// let mount = |mount_point: &str| Mount { mount_point: mount_point.to_string(), ..Default::default() };
+/// let mounts = vec![mount("/"), mount("/var"), mount("/var/lib")];
+/// assert_eq!(find_mount_for_path(&mounts, "/var/log/syslog").unwrap().mount_point, "/var");
+/// assert_eq!(find_mount_for_path(&mounts, "/var/lib/../lib/x").unwrap().mount_point, "/var/lib");
+/// assert_eq!(find_mount_for_path(&mounts, "/variable").unwrap().mount_point, "/");
+/// ```
+pub fn find_mount_for_path<P: AsRef<std::path::Path>>(mounts: &[Mount], path: P) -> std::option::Option<&Mount> {
+	let path = normalize(path.as_ref());
+	let mut best: std::option::Option<(&Mount, usize)> = None;
+	for mount in mounts {
+		let mount_point = std::path::Path::new(&mount.mount_point);
+		if path.starts_with(mount_point) {
+			let depth = mount_point.components().count();
+			if best.is_none_or(|(_, best_depth)| depth >= best_depth) {
+				best = Some((mount, depth));
+			}
+		}
+	}
+	best.map(|(mount, _)| mount)
+}
+
+/// Like [find_mount_for_path()], but first resolves symlinks in `path` component by component like `realpath -m`.
+///
+/// For example, on systems where `/var/run` is a symlink to `/run`, `/var/run/foo` resolves to the tmpfs mounted at `/run` rather than the filesystem containing `/var`.  Components that don't exist are kept as-is, so paths that have not been created yet can still be looked up.  Relative paths are resolved against the current directory.  Fails if a symlink cannot be read or there are too many levels of symlinks.
+pub fn find_mount_for_path_canonical<P: AsRef<std::path::Path>>(mounts: &[Mount], path: P) -> std::result::Result<std::option::Option<&Mount>, std::io::Error> {
+	Ok(find_mount_for_path(mounts, canonicalize(path.as_ref())?))
+}
+
+impl Mount {
+	/// False if the mount is `noexec`, which stops binaries on it from being executed.
+	pub fn allows_exec(&self) -> bool {
+		!self.options.iter().any(|option| option == "noexec")
+	}
+
+	/// False if the mount is `ro`.  For mounts read from mountinfo this covers a read-only superblock as well as a read-only mount, see [MountEntry::effective_read_only()](crate::MountEntry::effective_read_only).
+	pub fn allows_write(&self) -> bool {
+		!self.options.iter().any(|option| option == "ro")
+	}
+
+	/// False if set-user-ID and set-group-ID binaries on the mount run without the privileges of their owner, because it is `nosuid` or they can't be executed at all.
+	pub fn allows_setuid(&self) -> bool {
+		self.allows_exec() && !self.options.iter().any(|option| option == "nosuid")
+	}
+}
+
+/// Checks whether binaries at `path` can be executed, as far as the options of the mount containing it are concerned, e.g. for an installer to check "will my binary run from here?" before copying it.  File permissions and security modules aren't considered.  Reads the current mount table and follows symlinks, see [find_mount_for_path_canonical()].  Fails if no mount contains `path`.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::lookup::{can_execute_at, can_write_at};
+/// if !can_execute_at("/tmp/installer").unwrap() {
+///     eprintln!("/tmp is mounted noexec, unpacking elsewhere");
+/// }
+/// println!("/usr/local is {}", if can_write_at("/usr/local").unwrap() { "writable" } else { "read-only" });
+/// ```
+pub fn can_execute_at<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<bool, BoxError> {
+	covering_mount(path.as_ref(), Mount::allows_exec)
+}
+
+/// Like [can_execute_at()], but checks whether the mount containing `path` is writable.
+pub fn can_write_at<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<bool, BoxError> {
+	covering_mount(path.as_ref(), Mount::allows_write)
+}
+
+/// Like [can_execute_at()], but checks whether set-user-ID and set-group-ID binaries at `path` would gain the privileges of their owner.
+pub fn can_setuid_at<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<bool, BoxError> {
+	covering_mount(path.as_ref(), Mount::allows_setuid)
+}
+
+// Finds the mount containing `path` in the current mount table and applies `check` to it.
+fn covering_mount(path: &std::path::Path, check: fn(&Mount) -> bool) -> std::result::Result<bool, BoxError> {
+	covering_mount_in(&crate::snapshot()?.into_mounts(), path, check)
+}
+
+// Like covering_mount(), but looks in `mounts` instead of the current mount table.
+fn covering_mount_in(mounts: &[Mount], path: &std::path::Path, check: fn(&Mount) -> bool) -> std::result::Result<bool, BoxError> {
+	match find_mount_for_path_canonical(mounts, path)? {
+		Some(mount) => Ok(check(mount)),
+		None => Err(format!("no mount contains {}", path.display()).into())
+	}
+}
+
+/// Returns every mount of the device `spec`, the inverse of [find_mount_for_path()], e.g. to answer "where is /dev/sdb1 mounted?"  A device is often mounted more than once, e.g. for bind mounts or btrfs subvolumes.
+///
+/// `spec` is a device path such as /dev/sdb1 or /dev/mapper/data, a tag as in fstab such as `UUID=...` or `LABEL=...`, or a device number such as `8:17`.  Paths and tags are resolved to the device number of the block device they name, which is compared with [MountEntry::device_number()], so it doesn't matter which alias was used to mount the device.  Mounts whose device is literally `spec` match as well, which covers devices that aren't block devices, such as `server:/export` or `tmpfs`.
+/// # Examples
+/// ```
+/// # use nom_tutorial::{Mount, MountEntry, MountInfo};
+/// # use nom_tutorial::lookup::find_mounts_of_device;
+/// let mount = |device: &str, mount_point: &str, minor: u32| MountEntry {
+///     mount: Mount { device: device.to_string(), mount_point: mount_point.to_string(), ..Default::default() },
+///     mountinfo: Some(MountInfo { major: 259, minor, ..Default::default() }),
+/// };
+/// let mounts = vec![mount("/dev/nvme0n1p2", "/", 2), mount("/dev/nvme0n1p3", "/home", 3), mount("/dev/nvme0n1p2", "/srv", 2)];
+/// let found = find_mounts_of_device(&mounts, "259:2");
+/// assert_eq!(found.iter().map(|m| &m.mount_point[..]).collect::<Vec<_>>(), ["/", "/srv"]);
+/// ```
+pub fn find_mounts_of_device<'a>(mounts: &'a [MountEntry], spec: &str) -> std::vec::Vec<&'a MountEntry> {
+	let number = device_number_of_spec(spec);
+	mounts.iter()
+		.filter(|mount| mount.device == spec || (number.is_some() && mount.device_number() == number))
+		.collect()
+}
+
+/// Like [find_mounts_of_device()], but reads the current mount table from `/proc/self/mountinfo`, so that the device numbers of all mounts are known.
+/// # Examples
+/// ```no_run
+/// for mount in nom_tutorial::lookup::mounts_of_device("UUID=0a1b2c3d-4e5f-6789-abcd-ef0123456789").unwrap() {
+///     println!("mounted on {}", mount.mount_point);
+/// }
+/// ```
+pub fn mounts_of_device(spec: &str) -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> {
+	let mounts: std::vec::Vec<MountEntry> = crate::mountinfo()?.entries().collect::<std::result::Result<_, _>>()?;
+	Ok(find_mounts_of_device(&mounts, spec).into_iter().cloned().collect())
+}
+
+// Resolves a device number such as `8:17`, a tag such as `UUID=...`, or a device path to the major and minor number of the device.
+fn device_number_of_spec(spec: &str) -> std::option::Option<(u32, u32)> {
+	if let Some((major, minor)) = spec.split_once(':') {
+		if let (Ok(major), Ok(minor)) = (major.parse(), minor.parse()) {
+			return Some((major, minor));
+		}
+	}
+	let path = match spec.split_once('=') {
+		Some(("UUID", value)) => format!("/dev/disk/by-uuid/{}", value),
+		Some(("LABEL", value)) => format!("/dev/disk/by-label/{}", value),
+		Some(("PARTUUID", value)) => format!("/dev/disk/by-partuuid/{}", value),
+		Some(("PARTLABEL", value)) => format!("/dev/disk/by-partlabel/{}", value),
+		_ => spec.to_string()
+	};
+	crate::io::block_device_number(std::path::Path::new(&path))
+}
+
+// Lexically resolves `.` and `..` components.
+fn normalize(path: &std::path::Path) -> std::path::PathBuf {
+	let mut normalized = std::path::PathBuf::new();
+	for component in path.components() {
+		match component {
+			std::path::Component::CurDir => (),
+			std::path::Component::ParentDir => { normalized.pop(); },
+			component => normalized.push(component)
+		}
+	}
+	normalized
+}
+
+/// Resolves every symlink in `path`, one component at a time, like `realpath -m`.  Unlike [std::fs::canonicalize()] the path does not need to exist; once a component is missing the rest of the path is taken literally.
+pub fn canonicalize(path: &std::path::Path) -> std::result::Result<std::path::PathBuf, std::io::Error> {
+	let mut resolved = if path.is_absolute() { std::path::PathBuf::from("/") } else { std::env::current_dir()? };
+	let mut pending: std::collections::VecDeque<std::ffi::OsString> = path.components().filter_map(|c| match c {
+		std::path::Component::Normal(name) => Some(name.to_os_string()),
+		std::path::Component::ParentDir => Some("..".into()),
+		_ => None
+	}).collect();
+	let mut symlinks = 0;
+	while let Some(name) = pending.pop_front() {
+		if name == ".." {
+			resolved.pop();
+			continue;
+		}
+		let candidate = resolved.join(&name);
+		match std::fs::symlink_metadata(&candidate) {
+			Ok(metadata) if metadata.file_type().is_symlink() => {
+				symlinks += 1;
+				if symlinks > MAX_SYMLINKS {
+					return Err(std::io::Error::other(format!("too many levels of symbolic links resolving {}", path.display())));
+				}
+				let target = std::fs::read_link(&candidate)?;
+				if target.is_absolute() {
+					resolved = std::path::PathBuf::from("/");
+				}
+				// Splice the link target in front of whatever is left to resolve.
+				for component in target.components().rev() {
+					match component {
+						std::path::Component::Normal(name) => pending.push_front(name.to_os_string()),
+						std::path::Component::ParentDir => pending.push_front("..".into()),
+						_ => ()
+					}
+				}
+			},
+			_ => resolved = candidate
+		}
+	}
+	Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A mount that only has a mount point.
+	fn at(mount_point: &str) -> Mount {
+		crate::test_fixtures::mount("", mount_point, "", &[])
+	}
+
+	// The last of several mounts on the same mount point wins.
+	#[test]
+	fn test_find_mount_overmounted() {
+		let mut mounts = vec![at("/"), at("/data"), at("/data")];
+		mounts[2].device = "top".to_string();
+		assert_eq!(find_mount_for_path(&mounts, "/data/x").unwrap().device, "top");
+		assert!(find_mount_for_path(&mounts, "relative").is_none());
+	}
+
+	// nosuid only matters for binaries that can be executed in the first place.
+	#[test]
+	fn test_allows() {
+		let mount = |options: &[&str]| crate::test_fixtures::mount("tmpfs", "/tmp", "tmpfs", options);
+		let tmp = mount(&["rw", "nosuid", "nodev"]);
+		assert!(tmp.allows_exec() && tmp.allows_write() && !tmp.allows_setuid());
+		let media = mount(&["ro", "noexec"]);
+		assert!(!media.allows_exec() && !media.allows_write() && !media.allows_setuid());
+		assert!(mount(&["rw"]).allows_setuid());
+	}
+
+	// The innermost mount decides, after symlinks are followed, and a path outside every mount is an error.
+	#[test]
+	fn test_covering_mount_in() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-lookup-noexec-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		let noexec = directory.canonicalize().unwrap();
+		let parent = noexec.parent().unwrap().to_path_buf();
+		std::fs::remove_dir_all(&directory).unwrap();
+		let mounts = [
+			crate::test_fixtures::mount("/dev/sda1", "/", "ext4", &["rw"]),
+			crate::test_fixtures::mount("tmpfs", noexec.to_str().unwrap(), "tmpfs", &["rw", "noexec"]),
+		];
+		assert!(!covering_mount_in(&mounts, &noexec.join("installer"), Mount::allows_exec).unwrap());
+		assert!(covering_mount_in(&mounts, &parent, Mount::allows_exec).unwrap());
+		assert!(covering_mount_in(&mounts[1..], &parent, Mount::allows_exec).is_err());
+	}
+
+	// Devices are matched by number whatever name they were mounted under, and by name if they aren't block devices.
+	#[test]
+	fn test_find_mounts_of_device() {
+		let with_number = |device: &str, mount_point: &str, major: u32, minor: u32| MountEntry {
+			mount: crate::test_fixtures::mount(device, mount_point, "", &[]),
+			mountinfo: Some(crate::MountInfo { major, minor, ..Default::default() }),
+		};
+		let mounts = vec![with_number("/dev/nom-tutorial-test-disk", "/mnt/a", 8, 17), with_number("/dev/disk/by-label/b", "/mnt/b", 8, 17), with_number("server:/export", "/net", 0, 52)];
+		let mount_points = |spec: &str| find_mounts_of_device(&mounts, spec).iter().map(|m| m.mount_point.clone()).collect::<std::vec::Vec<_>>();
+		assert_eq!(mount_points("8:17"), ["/mnt/a", "/mnt/b"]);
+		assert_eq!(mount_points("server:/export"), ["/net"]);
+		assert_eq!(mount_points("/dev/nom-tutorial-test-disk"), ["/mnt/a"]);
+		assert!(mount_points("UUID=missing").is_empty());
+	}
+
+	#[test]
+	fn test_canonicalize() {
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-lookup-{}", std::process::id()));
+		let run = directory.join("run");
+		let var = directory.join("var");
+		std::fs::create_dir_all(&run).unwrap();
+		std::fs::create_dir_all(&var).unwrap();
+		std::os::unix::fs::symlink("../run", var.join("run")).unwrap();
+		std::os::unix::fs::symlink("loop", directory.join("loop")).unwrap();
+
+		assert_eq!(canonicalize(&var.join("run/foo/bar")).unwrap(), run.join("foo/bar"));
+		let mounts = vec![at("/"), at(var.to_str().unwrap()), at(run.to_str().unwrap())];
+		assert_eq!(find_mount_for_path(&mounts, var.join("run/foo")).unwrap().mount_point, var.to_str().unwrap());
+		assert_eq!(find_mount_for_path_canonical(&mounts, var.join("run/foo")).unwrap().unwrap().mount_point, run.to_str().unwrap());
+		assert!(canonicalize(&directory.join("loop")).is_err());
+
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}
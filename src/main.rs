@@ -1,10 +1,136 @@
 extern crate nom_tutorial;
-use nom_tutorial::BoxError;
+use nom_tutorial::{BoxError, Mount, MountEntry};
+use nom_tutorial::filter::FilterExpr;
+use nom_tutorial::watch::{Action, MountEvent};
 
-/// Prints a list of mounted filesystems similar to calling `mount` with no arguments.
+mod cli;
+use cli::{Input, Options};
+
+/// How often `--poll` re-reads the mount table.
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// Prints the mounts in the Prometheus text format.
+#[cfg(feature = "metrics")]
+fn print_metrics(mounts: &[Mount]) -> std::result::Result<(), BoxError> {
+	print!("{}", nom_tutorial::metrics::render(mounts));
+	Ok(())
+}
+
+#[cfg(not(feature = "metrics"))]
+fn print_metrics(_mounts: &[Mount]) -> std::result::Result<(), BoxError> {
+	Err("--metrics requires nom-tutorial to be built with the `metrics` feature".into())
+}
+
+/// Reads `/proc/self/mountinfo`, whose mount IDs let a moved mount be told apart from an unmount followed by a mount.
+fn read_mountinfo() -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> {
+	nom_tutorial::mountinfo()?.entries().collect()
+}
+
+/// Mirrors `findmnt --poll`: prints a line for every change to the mount table until interrupted.
+fn poll(actions: &[Action]) -> std::result::Result<(), BoxError> {
+	println!("{:<8} {:<30} {:<30} {:<10} OPTIONS", "ACTION", "TARGET", "SOURCE", "FSTYPE");
+	let mut previous = read_mountinfo()?;
+	loop {
+		std::thread::sleep(POLL_INTERVAL);
+		let current = read_mountinfo()?;
+		for (action, event) in nom_tutorial::watch::classify(nom_tutorial::watch::diff(&previous, &current)) {
+			if !actions.contains(&action) {
+				continue;
+			}
+			let (mount, target) = match event {
+				MountEvent::Changed { old, new } if action == Action::Move => {
+					let target = format!("{} (from {})", new.mount_point, old.mount_point);
+					(new, target)
+				},
+				MountEvent::Added(mount) | MountEvent::Removed(mount) | MountEvent::Changed { new: mount, .. } => {
+					let target = mount.mount_point.clone();
+					(mount, target)
+				}
+			};
+			println!("{:<8} {:<30} {:<30} {:<10} {}", action.name(), target, mount.device, mount.file_system_type, mount.options.join(","));
+		}
+		previous = current;
+	}
+}
+
+/// Checks an `fstab` file for `nom-tutorial verify`, printing every problem found and exiting with status 1 if there are any.
+fn verify<I: Iterator<Item = std::string::String>>(mut args: I) -> std::result::Result<(), BoxError> {
+	let path = match (args.next(), args.next()) {
+		(Some(path), None) => path,
+		(None, _) => "/etc/fstab".to_string(),
+		(Some(_), Some(arg)) => return Err(format!("unrecognized argument: {}", arg).into())
+	};
+	let findings = nom_tutorial::fstab_verify::verify(&std::fs::read_to_string(&path)?);
+	for finding in &findings {
+		println!("{}: {}", path, finding);
+	}
+	if !findings.is_empty() {
+		std::process::exit(1);
+	}
+	Ok(())
+}
+
+/// ANSI escape sequences for `nom-tutorial diff`.
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Compares two saved mount tables for `nom-tutorial diff`, printing removed mounts prefixed with `-` and added mounts with `+` like a unified diff.  A changed mount is printed as its old line followed by its new line.  Colors are used when writing to a terminal, unless `NO_COLOR` is set.
+fn diff<I: Iterator<Item = std::string::String>>(mut args: I) -> std::result::Result<(), BoxError> {
+	let (before, after) = match (args.next(), args.next(), args.next()) {
+		(Some(before), Some(after), None) => (before, after),
+		(_, _, Some(arg)) => return Err(format!("unrecognized argument: {}", arg).into()),
+		_ => return Err("usage: nom-tutorial diff BEFORE AFTER".into())
+	};
+	let old: std::vec::Vec<MountEntry> = Input::File(before.clone().into()).read()?.into_iter().map(MountEntry::from).collect();
+	let new: std::vec::Vec<MountEntry> = Input::File(after.clone().into()).read()?.into_iter().map(MountEntry::from).collect();
+	let color = std::io::IsTerminal::is_terminal(&std::io::stdout()) && std::env::var_os("NO_COLOR").is_none();
+	let paint = |style: &str, text: std::string::String| if color { format!("{}{}{}", style, text, RESET) } else { text };
+	println!("{}", paint(BOLD, format!("--- {}", before)));
+	println!("{}", paint(BOLD, format!("+++ {}", after)));
+	for event in nom_tutorial::watch::diff(&old, &new) {
+		match event {
+			MountEvent::Added(mount) => println!("{}", paint(GREEN, format!("+{}", mount.to_proc_line()))),
+			MountEvent::Removed(mount) => println!("{}", paint(RED, format!("-{}", mount.to_proc_line()))),
+			MountEvent::Changed { old, new } => {
+				println!("{}", paint(RED, format!("-{}", old.to_proc_line())));
+				println!("{}", paint(GREEN, format!("+{}", new.to_proc_line())));
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Prints a list of mounted filesystems similar to calling `mount` with no arguments, or the mounts in a saved mount table given as an argument.
 fn main() -> std::result::Result<(), BoxError> {
-	for mount in nom_tutorial::mounts()? {
-		println!("{}", mount?);
+	let mut args = std::env::args().skip(1).peekable();
+	if args.peek().map(|arg| &arg[..]) == Some("verify") {
+		return verify(args.skip(1));
+	}
+	if args.peek().map(|arg| &arg[..]) == Some("diff") {
+		return diff(args.skip(1));
+	}
+	let options = Options::parse(args)?;
+	if let Some(actions) = &options.poll {
+		return poll(actions);
+	}
+	let mut mounts = options.input.read()?;
+	FilterExpr::All(options.filter).retain(&mut mounts);
+	if let Some(key) = options.sort {
+		nom_tutorial::sort::sort(&mut mounts, key);
+	}
+	if options.reverse {
+		mounts.reverse();
+	}
+	if options.metrics {
+		return print_metrics(&mounts);
+	}
+	if options.jsonl {
+		return Ok(nom_tutorial::jsonl::write_jsonl(mounts.into_iter().map(MountEntry::from), std::io::stdout().lock())?);
+	}
+	for mount in mounts {
+		println!("{}", mount);
 	}
 	Ok(())
 }
@@ -0,0 +1,256 @@
+//! Connects mounts on Linux software RAID (MD) arrays to the state of the array, as listed in `/proc/mdstat`.
+//!
+//! Each array takes up a block of lines in `/proc/mdstat`: a header such as `md0 : active raid1 sdb1[1] sda1[0](F)`, listing the level and the member devices with their role numbers and flags, followed by indented lines with the size and the disk counts, e.g. `[2/1] [U_]` for an array that should have two working disks but has one.
+
+use super::{BoxError, Mount};
+
+/// Where the kernel lists the MD arrays.
+pub const MDSTAT_PATH: &str = "/proc/mdstat";
+
+/// A member device of an [MdArray].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MdMember {
+	/// The kernel's name for the device, e.g. sda1
+	pub device: std::string::String,
+	/// The role number of the device within the array.
+	pub role: u32,
+	/// True if the device has failed, shown as `(F)`.
+	pub faulty: bool,
+	/// True if the device is a spare, shown as `(S)`.
+	pub spare: bool,
+	/// True if reads avoid the device, shown as `(W)`.
+	pub write_mostly: bool,
+}
+
+/// An MD array as listed in `/proc/mdstat`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MdArray {
+	/// The kernel's name for the array, e.g. md0
+	pub name: std::string::String,
+	/// False if the array has been assembled but not started.
+	pub active: bool,
+	/// True if the array is read-only, including `auto-read-only` arrays that haven't been written to yet.
+	pub read_only: bool,
+	/// The RAID level, e.g. raid1, or `None` for inactive arrays.
+	pub level: std::option::Option<std::string::String>,
+	/// The member devices, in the order the kernel lists them.
+	pub members: std::vec::Vec<MdMember>,
+	/// The number of devices the array should have, if the kernel shows it.
+	pub raid_disks: std::option::Option<u32>,
+	/// The number of those devices that are working.
+	pub working_disks: std::option::Option<u32>,
+}
+
+impl MdArray {
+	/// The path of the array's device node, e.g. /dev/md0
+	pub fn device_path(&self) -> std::string::String {
+		format!("/dev/{}", self.name)
+	}
+
+	/// True if fewer devices are working than the array should have.
+	pub fn is_degraded(&self) -> bool {
+		matches!((self.raid_disks, self.working_disks), (Some(raid), Some(working)) if working < raid)
+	}
+
+	/// Reads the array's state from `/sys/block/<name>/md/array_state`, e.g. clean, active, or read-auto, for the finer detail `/proc/mdstat` doesn't show.  Returns `None` if sysfs doesn't know the array.
+	#[cfg(feature = "io")]
+	pub fn array_state(&self) -> std::result::Result<std::option::Option<std::string::String>, std::io::Error> {
+		let path = std::path::Path::new(crate::sysfs::SYSFS_PATH).join("block").join(&self.name).join("md/array_state");
+		match std::fs::read_to_string(path) {
+			Ok(state) => Ok(Some(state.trim_end().to_string())),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(e)
+		}
+	}
+}
+
+/// The contents of `/proc/mdstat`.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct MdStat {
+	/// The arrays, in the order the kernel lists them.
+	pub arrays: std::vec::Vec<MdArray>,
+}
+
+impl MdStat {
+	/// Parses the contents of `/proc/mdstat`.  The `Personalities` and `unused devices` lines and the progress of resyncs are skipped.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::mdraid::MdStat;
+	/// let mdstat = MdStat::parse("Personalities : [raid1]\nmd0 : active raid1 sdb1[1] sda1[0](F)\n      1046528 blocks super 1.2 [2/1] [_U]\n\nunused devices: <none>\n").unwrap();
+	/// let array = mdstat.get("md0").unwrap();
+	/// assert!(array.is_degraded());
+	/// assert!(array.members[1].faulty);
+	/// ```
+	pub fn parse(text: &str) -> std::result::Result<MdStat, BoxError> {
+		let mut arrays: std::vec::Vec<MdArray> = std::vec::Vec::new();
+		for (number, line) in text.lines().enumerate() {
+			if line.starts_with(char::is_whitespace) {
+				// The disk counts are on one of the lines following the header.
+				if let Some(array) = arrays.last_mut() {
+					if let Some((raid, working)) = line.split_whitespace().find_map(|token| parsers::disk_counts(token).ok()).map(|(_, counts)| counts) {
+						array.raid_disks = Some(raid);
+						array.working_disks = Some(working);
+					}
+				}
+			} else if line.starts_with("md") {
+				match parsers::header(line) {
+					Ok((_, array)) => arrays.push(array),
+					Err(e) => return Err(crate::ParseError::new(line, e).with_line_number(number + 1).into())
+				}
+			}
+		}
+		Ok(MdStat { arrays })
+	}
+
+	/// Reads and parses [MDSTAT_PATH].  Returns an empty list if the kernel has no MD support.
+	#[cfg(feature = "io")]
+	pub fn read() -> std::result::Result<MdStat, BoxError> {
+		match std::fs::read_to_string(MDSTAT_PATH) {
+			Ok(text) => MdStat::parse(&text),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(MdStat::default()),
+			Err(e) => Err(e.into())
+		}
+	}
+
+	/// Looks up an array by its kernel name, e.g. md0
+	pub fn get(&self, name: &str) -> std::option::Option<&MdArray> {
+		self.arrays.iter().find(|array| array.name == name)
+	}
+}
+
+impl Mount {
+	/// The MD array the mount is on, if its device is `/dev/mdN` or a link such as `/dev/md/<name>`.  Returns `None` for any other device, or if the array isn't in [MDSTAT_PATH].
+	/// # Examples
+	/// ```no_run
+	/// for mount in nom_tutorial::mounts().unwrap() {
+	///     let mount = mount.unwrap();
+	///     if let Some(array) = mount.md_array().unwrap().filter(|array| array.is_degraded()) {
+	///         eprintln!("{} is on degraded array {}", mount.mount_point, array.name);
+	///     }
+	/// }
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn md_array(&self) -> std::result::Result<std::option::Option<MdArray>, BoxError> {
+		let name = if self.device.starts_with("/dev/md/") {
+			match std::fs::canonicalize(&self.device) {
+				Ok(path) => path.file_name().map(|name| name.to_string_lossy().into_owned()),
+				Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+				Err(e) => return Err(e.into())
+			}
+		} else {
+			self.device.strip_prefix("/dev/").map(|name| name.to_string())
+		};
+		match name {
+			Some(name) if name.starts_with("md") => Ok(MdStat::read()?.get(&name).cloned()),
+			_ => Ok(None)
+		}
+	}
+}
+
+mod parsers {
+	use super::{MdArray, MdMember};
+
+	// Parses a member such as `sda1[0]` or `sdc1[2](F)`.
+	fn member(i: &str) -> nom::IResult<&str, MdMember> {
+		let (i, device) = nom::bytes::complete::is_not("[ ")(i)?;
+		let (i, role) = nom::sequence::delimited(
+			nom::character::complete::char('['),
+			nom::combinator::map_res(nom::character::complete::digit1, str::parse::<u32>),
+			nom::character::complete::char(']')
+		)(i)?;
+		let (i, flags) = nom::multi::many0(nom::sequence::delimited(
+			nom::character::complete::char('('),
+			nom::character::complete::alpha1,
+			nom::character::complete::char(')')
+		))(i)?;
+		let flag = |name: &str| flags.contains(&name);
+		Ok((i, MdMember { device: device.to_string(), role, faulty: flag("F"), spare: flag("S"), write_mostly: flag("W") }))
+	}
+
+	// Parses the first line of an array, such as `md0 : active (auto-read-only) raid1 sdb1[1] sda1[0]`.
+	pub fn header(i: &str) -> nom::IResult<&str, MdArray> {
+		let (i, name) = nom::bytes::complete::is_not(" ")(i)?;
+		let (i, _) = nom::bytes::complete::tag(" : ")(i)?;
+		let (i, active) = nom::branch::alt((
+			nom::combinator::value(true, nom::bytes::complete::tag("active")),
+			nom::combinator::value(false, nom::bytes::complete::tag("inactive"))
+		))(i)?;
+		let (i, read_only) = nom::combinator::opt(nom::sequence::preceded(
+			nom::character::complete::space1,
+			nom::sequence::delimited(nom::character::complete::char('('), nom::bytes::complete::is_not(")"), nom::character::complete::char(')'))
+		))(i)?;
+		// Inactive arrays have no level, so it is recognized by not looking like a member.
+		let (i, level) = nom::combinator::opt(nom::sequence::preceded(
+			nom::character::complete::space1,
+			nom::combinator::verify(crate::parsers::not_whitespace, |token: &str| !token.contains('['))
+		))(i)?;
+		let (i, members) = nom::combinator::all_consuming(nom::sequence::terminated(
+			nom::multi::many0(nom::sequence::preceded(nom::character::complete::space1, member)),
+			nom::character::complete::space0
+		))(i)?;
+		Ok((i, MdArray {
+			name: name.to_string(),
+			active,
+			read_only: read_only.is_some_and(|state| state.ends_with("read-only")),
+			level: level.map(|level| level.to_string()),
+			members,
+			raid_disks: None,
+			working_disks: None,
+		}))
+	}
+
+	// Parses the disk counts such as `[2/1]`.
+	pub fn disk_counts(i: &str) -> nom::IResult<&str, (u32, u32)> {
+		let number = || nom::combinator::map_res(nom::character::complete::digit1, str::parse::<u32>);
+		nom::combinator::all_consuming(nom::sequence::delimited(
+			nom::character::complete::char('['),
+			nom::sequence::separated_pair(number(), nom::character::complete::char('/'), number()),
+			nom::character::complete::char(']')
+		))(i)
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_header() {
+			let (_, array) = header("md1 : active (auto-read-only) raid5 sde1[3](S) sdd1[1](W) sdc1[0]").unwrap();
+			assert_eq!((array.active, array.read_only, array.level.as_deref()), (true, true, Some("raid5")));
+			assert_eq!(array.members.iter().map(|m| (&m.device[..], m.role, m.spare, m.write_mostly)).collect::<std::vec::Vec<_>>(), [("sde1", 3, true, false), ("sdd1", 1, false, true), ("sdc1", 0, false, false)]);
+			let (_, array) = header("md127 : inactive sdf1[0](S)").unwrap();
+			assert_eq!((array.active, array.level, array.members.len()), (false, None, 1));
+			assert!(header("md0 : broken raid1").is_err());
+		}
+
+		#[test]
+		fn test_disk_counts() {
+			assert_eq!(disk_counts("[3/2]"), Ok(("", (3, 2))));
+			assert!(disk_counts("[UU_]").is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Progress lines and bitmaps between arrays don't confuse the parser.
+	#[test]
+	fn test_parse() {
+		let mdstat = MdStat::parse("Personalities : [raid1] [raid6] [raid5] [raid4]
+md1 : active raid5 sdc1[2] sdd1[3](F) sde1[0]
+      20953088 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [U_U]
+      [==>..................]  recovery = 12.6% (1321984/10476544) finish=3.1min speed=48960K/sec
+      bitmap: 0/1 pages [0KB], 65536KB chunk
+
+md0 : active raid1 sdb1[1] sda1[0]
+      1046528 blocks super 1.2 [2/2] [UU]
+
+unused devices: <none>
+").unwrap();
+		assert_eq!(mdstat.arrays.iter().map(|array| (&array.name[..], array.is_degraded())).collect::<std::vec::Vec<_>>(), [("md1", true), ("md0", false)]);
+		assert_eq!(mdstat.get("md1").unwrap().raid_disks, Some(3));
+		assert!(MdStat::parse("md0 : ???\n").unwrap_err().to_string().starts_with("line 1, "));
+	}
+}
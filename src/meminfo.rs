@@ -0,0 +1,58 @@
+//! Reads the amount of memory installed from `/proc/meminfo`, which memory-backed filesystems such as tmpfs size themselves against.
+
+use super::BoxError;
+
+/// Where the kernel reports memory statistics.
+pub const MEMINFO_PATH: &str = "/proc/meminfo";
+
+/// Finds the `MemTotal` line in the contents of `/proc/meminfo` and returns the total usable memory in bytes.
+/// # Examples
+/// ```
+/// # use nom_tutorial::meminfo;
+/// let text = "MemTotal:       16318904 kB\nMemFree:         1203952 kB\n";
+/// assert_eq!(meminfo::parse_mem_total(text).unwrap(), 16318904 * 1024);
+/// ```
+pub fn parse_mem_total(text: &str) -> std::result::Result<u64, BoxError> {
+	for (number, line) in text.lines().enumerate() {
+		if !line.starts_with("MemTotal:") {
+			continue;
+		}
+		return match parsers::parse_line(line) {
+			Ok((_, (_, bytes))) => Ok(bytes),
+			Err(e) => Err(crate::ParseError::new(line, e).with_line_number(number + 1).into())
+		};
+	}
+	Err(format!("no MemTotal in {}", MEMINFO_PATH).into())
+}
+
+/// Reads [MEMINFO_PATH] and returns the total usable memory in bytes, see [parse_mem_total()].
+#[cfg(feature = "io")]
+pub fn mem_total() -> std::result::Result<u64, BoxError> {
+	parse_mem_total(&std::fs::read_to_string(MEMINFO_PATH)?)
+}
+
+mod parsers {
+	// Parses a line such as `MemTotal:       16318904 kB` into the key and the value in bytes.  Values without a unit, such as `HugePages_Total`, are counts and are returned as is.
+	pub fn parse_line(i: &str) -> nom::IResult<&str, (&str, u64)> {
+		let (i, key) = nom::sequence::terminated(nom::bytes::complete::is_not(":"), nom::character::complete::char(':'))(i)?;
+		let (i, _) = nom::character::complete::space1(i)?;
+		let (i, value) = nom::combinator::map_res(nom::character::complete::digit1, |digits: &str| digits.parse::<u64>())(i)?;
+		let (i, kilobytes) = nom::combinator::all_consuming(nom::sequence::terminated(
+			nom::combinator::opt(nom::sequence::preceded(nom::character::complete::space1, nom::bytes::complete::tag("kB"))),
+			nom::character::complete::space0
+		))(i)?;
+		Ok((i, (key, if kilobytes.is_some() { value * 1024 } else { value })))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_parse_line() {
+			assert_eq!(parse_line("MemTotal:       16318904 kB"), Ok(("", ("MemTotal", 16318904 * 1024))));
+			assert_eq!(parse_line("HugePages_Total:       0"), Ok(("", ("HugePages_Total", 0))));
+			assert!(parse_line("MemTotal: lots").is_err());
+		}
+	}
+}
@@ -0,0 +1,105 @@
+//! Renders mount tables in the [Prometheus text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/).  Enabled by the `metrics` feature.
+//!
+//! The output is suitable for node_exporter's textfile collector, e.g. by running `nom-tutorial --metrics > /var/lib/node_exporter/mounts.prom` from a timer.
+
+use super::Mount;
+
+// Escapes a label value: backslash, double quote, and newline must be escaped.
+fn escape_label(value: &str) -> std::string::String {
+	let mut escaped = std::string::String::with_capacity(value.len());
+	for c in value.chars() {
+		match c {
+			'\\' => escaped.push_str("\\\\"),
+			'"' => escaped.push_str("\\\""),
+			'\n' => escaped.push_str("\\n"),
+			c => escaped.push(c)
+		}
+	}
+	escaped
+}
+
+/// Renders the given mounts as Prometheus metrics.
+///
+/// Always emits `nom_tutorial_mounts`, the number of mounts per filesystem type.  When the `statvfs` feature is enabled also emits `nom_tutorial_mount_size_bytes`, `nom_tutorial_mount_free_bytes`, and `nom_tutorial_mount_avail_bytes` for each mount whose usage could be read.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// let mounts = vec![Mount {
+///     device: "/dev/sda1".to_string(),
+///     mount_point: "/".to_string(),
+///     file_system_type: "ext4".to_string(),
+///     options: vec!["rw".to_string()]
+/// }];
+/// let text = nom_tutorial::metrics::render(&mounts);
+/// assert!(text.contains("nom_tutorial_mounts{fs_type=\"ext4\"} 1\n"));
+/// ```
+pub fn render(mounts: &[Mount]) -> std::string::String {
+	use std::fmt::Write;
+	let mut out = std::string::String::new();
+
+	// A BTreeMap keeps the output in a stable order.
+	let mut counts = std::collections::BTreeMap::<&str, usize>::new();
+	for mount in mounts {
+		*counts.entry(&mount.file_system_type[..]).or_insert(0) += 1;
+	}
+	out.push_str("# HELP nom_tutorial_mounts Number of mounted filesystems by type.\n");
+	out.push_str("# TYPE nom_tutorial_mounts gauge\n");
+	for (fs_type, count) in counts {
+		// Writing to a String cannot fail.
+		let _ = writeln!(out, "nom_tutorial_mounts{{fs_type=\"{}\"}} {}", escape_label(fs_type), count);
+	}
+
+	#[cfg(feature = "statvfs")]
+	{
+		let usages: std::vec::Vec<(&Mount, crate::usage::Usage)> = mounts.iter()
+			.filter_map(|mount| mount.usage().ok().map(|usage| (mount, usage)))
+			.collect();
+		// Metric name, help text, and the accessor for its value.
+		type Gauge = (&'static str, &'static str, fn(&crate::usage::Usage) -> u64);
+		let gauges: [Gauge; 3] = [
+			("nom_tutorial_mount_size_bytes", "Total size of the filesystem in bytes.", |u| u.total_bytes),
+			("nom_tutorial_mount_free_bytes", "Free space on the filesystem in bytes.", |u| u.free_bytes),
+			("nom_tutorial_mount_avail_bytes", "Free space available to unprivileged users in bytes.", |u| u.available_bytes),
+		];
+		for (name, help, value) in gauges.iter() {
+			let _ = writeln!(out, "# HELP {} {}", name, help);
+			let _ = writeln!(out, "# TYPE {} gauge", name);
+			for (mount, usage) in &usages {
+				let _ = writeln!(out, "{}{{device=\"{}\",mount_point=\"{}\",fs_type=\"{}\"}} {}",
+					name,
+					escape_label(&mount.device),
+					escape_label(&mount.mount_point),
+					escape_label(&mount.file_system_type),
+					value(usage)
+				);
+			}
+		}
+	}
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_escape_label() {
+		assert_eq!(escape_label("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+	}
+
+	// Counts are grouped by filesystem type and sorted by name.
+	#[test]
+	fn test_render_counts() {
+		let mount = |fs_type: &str| Mount {
+			device: "none".to_string(),
+			mount_point: "/nonexistent".to_string(),
+			file_system_type: fs_type.to_string(),
+			options: vec![]
+		};
+		let text = render(&[mount("tmpfs"), mount("ext4"), mount("tmpfs")]);
+		let ext4 = text.find("nom_tutorial_mounts{fs_type=\"ext4\"} 1\n").unwrap();
+		let tmpfs = text.find("nom_tutorial_mounts{fs_type=\"tmpfs\"} 2\n").unwrap();
+		assert!(ext4 < tmpfs);
+	}
+}
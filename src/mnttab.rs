@@ -0,0 +1,121 @@
+//! Parses the Solaris and illumos mount table, `/etc/mnttab`.
+//!
+//! Each line of mnttab has five tab-separated fields: the device, the mount point, the filesystem type, the comma-separated options, and the time the filesystem was mounted in seconds since the epoch.  Fields are not escaped, but can contain spaces since only tabs separate them.  The first four fields map onto a [Mount]; the mount time is kept alongside in a [MnttabEntry].  To read a mnttab like any other mount table, use [Format::Mnttab](crate::model::Format::Mnttab).
+
+use super::{BoxError, Mount};
+
+/// Where Solaris and illumos keep the mount table.
+pub const MNTTAB_PATH: &str = "/etc/mnttab";
+
+/// One line of mnttab.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MnttabEntry {
+	/// The mounted filesystem.
+	pub mount: Mount,
+	/// When the filesystem was mounted.
+	pub mount_time: std::time::SystemTime,
+}
+
+/// Parses the contents of a mnttab file.  Blank lines are skipped.
+/// # Examples
+/// ```
+/// # use nom_tutorial::mnttab;
+/// let entries = mnttab::parse("rpool/ROOT/solaris\t/\tzfs\tdev=4bd0002\t1700000000\n").unwrap();
+/// assert_eq!(entries[0].mount.file_system_type, "zfs");
+/// assert_eq!(entries[0].mount_time, std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000));
+/// ```
+pub fn parse(text: &str) -> std::result::Result<std::vec::Vec<MnttabEntry>, BoxError> {
+	let mut entries = std::vec::Vec::new();
+	for (number, line) in text.lines().enumerate() {
+		if line.trim().is_empty() {
+			continue;
+		}
+		match parsers::parse_line(line) {
+			Ok((_, (mount, seconds))) => entries.push(MnttabEntry { mount, mount_time: std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds) }),
+			Err(e) => return Err(crate::ParseError::new(line, e).with_line_number(number + 1).into())
+		}
+	}
+	Ok(entries)
+}
+
+/// Reads and parses [MNTTAB_PATH].
+#[cfg(feature = "io")]
+pub fn read() -> std::result::Result<std::vec::Vec<MnttabEntry>, BoxError> {
+	parse(&std::fs::read_to_string(MNTTAB_PATH)?)
+}
+
+pub(crate) mod parsers {
+	use super::Mount;
+
+	// Parses a non-empty field, which is anything up to the next tab.
+	fn field(i: &str) -> nom::IResult<&str, &str> {
+		nom::bytes::complete::is_not("\t")(i)
+	}
+
+	// Parses the options field, which may be empty, into a list of options.
+	fn options(i: &str) -> nom::IResult<&str, std::vec::Vec<std::string::String>> {
+		let (i, options) = nom::combinator::opt(field)(i)?;
+		Ok((i, options.into_iter().flat_map(|options| options.split(',')).filter(|option| !option.is_empty()).map(|option| option.to_string()).collect()))
+	}
+
+	// Parses a whole line into a mount and its mount time in seconds since the epoch.
+	pub fn parse_line(i: &str) -> nom::IResult<&str, (Mount, u64)> {
+		let tab = || nom::character::complete::char('\t');
+		let (i, device) = field(i)?;
+		let (i, _) = tab()(i)?;
+		let (i, mount_point) = field(i)?;
+		let (i, _) = tab()(i)?;
+		let (i, file_system_type) = field(i)?;
+		let (i, _) = tab()(i)?;
+		let (i, options) = options(i)?;
+		let (i, _) = tab()(i)?;
+		let (i, seconds) = nom::combinator::all_consuming(nom::combinator::map_res(nom::character::complete::digit1, |digits: &str| digits.parse::<u64>()))(i)?;
+		Ok((i, (Mount {
+			device: device.to_string(),
+			mount_point: mount_point.to_string(),
+			file_system_type: file_system_type.to_string(),
+			options
+		}, seconds)))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_options() {
+			assert_eq!(options("ro,nosuid\t"), Ok(("\t", vec!["ro".to_string(), "nosuid".to_string()])));
+			assert_eq!(options("\t1"), Ok(("\t1", vec![])));
+		}
+
+		#[test]
+		fn test_parse_line() {
+			let (_, (mount, seconds)) = parse_line("/dev/dsk/c0t0d0s7\t/export/My Home\tufs\trw,intr,largefiles,logging,xattr,onerror=panic,dev=1d80007\t1146775416").unwrap();
+			assert_eq!(mount.mount_point, "/export/My Home");
+			assert_eq!(mount.options.len(), 7);
+			assert_eq!(seconds, 1146775416);
+			assert!(parse_line("swap\t/tmp\ttmpfs\txattr,dev=4f00001").is_err());
+			assert!(parse_line("swap /tmp tmpfs xattr 1146775416").is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// An excerpt of mnttab from an illumos host, where the table is generated by the kernel and ends in a newline.
+	#[test]
+	fn test_parse_illumos() {
+		let entries = parse("\
+			rpool/ROOT/openindiana\t/\tzfs\tdev=4010002\t1700000000\n\
+			/devices\t/devices\tdevfs\tdev=8900000\t1700000001\n\
+			\n\
+			swap\t/tmp\ttmpfs\txattr,dev=5300002\t1700000005\n").unwrap();
+		assert_eq!(entries.len(), 3);
+		assert_eq!(entries[2].mount.options, vec!["xattr", "dev=5300002"]);
+		assert_eq!(crate::parsers::parse("swap\t/tmp\ttmpfs\txattr,dev=5300002\t1700000005", crate::model::Format::Mnttab).unwrap(), entries[2].mount);
+		let error = parse("rpool\t/\tzfs\tdev=1\tyesterday\n").unwrap_err();
+		assert_eq!(error.downcast_ref::<crate::ParseError>().unwrap().line_number, Some(1));
+	}
+}
@@ -0,0 +1,402 @@
+//! The mount table as data: a [Mount] for every line, and a [MountEntry] pairing it with the extra [MountInfo] that `/proc/self/mountinfo` provides.
+//!
+//! Nothing in this module reads or writes files, so it is always available, including with default features turned off.
+
+/// The line format of a mount table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+	/// The format of `/proc/mounts`, `/proc/self/mounts`, and `/etc/mtab`.
+	Mounts,
+	/// The format of `/proc/self/mountinfo`, which has mount IDs and separates per-mount from superblock options.
+	MountInfo,
+	/// The format of `/etc/mnttab` on Solaris and illumos, see [mnttab](crate::mnttab).  The mount time is dropped.
+	Mnttab,
+}
+
+/// Describes a mounted filesystem, see `man 8 mount` for more details.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mount {
+	/// The device from which the filesystem is mounted, e.g. /dev/sda1
+	pub device: std::string::String,
+	/// Where in the root filesystem the device is mounted, e.g. /mnt/disk
+	pub mount_point: std::string::String,
+	/// The filesystem type, e.g. ext4
+	pub file_system_type: std::string::String,
+	/// A vector of mount options, e.g. ["ro", "nosuid"]
+	/// Note: This could also be implemented as a set (e.g. std::collections::HashSet)
+	pub options: std::vec::Vec<std::string::String>,
+}
+
+/// The parts of a `/proc/self/mountinfo` line that `/proc/mounts` doesn't have, see `man 5 proc`.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountInfo {
+	/// Unique ID of the mount, which may be reused after it is unmounted.
+	pub mount_id: u32,
+	/// ID of the parent mount, or of itself for the root of the mount tree.
+	pub parent_id: u32,
+	/// Major device number of the filesystem, as in `st_dev`.
+	pub major: u32,
+	/// Minor device number of the filesystem, as in `st_dev`.
+	pub minor: u32,
+	/// The directory within the filesystem that is mounted, e.g. / or the source of a bind mount.
+	pub root: std::string::String,
+	/// Options that apply to this mount only, e.g. ["rw", "nosuid", "relatime"]
+	pub vfs_options: std::vec::Vec<std::string::String>,
+	/// Optional fields describing mount propagation, e.g. ["shared:1"]
+	pub optional_fields: std::vec::Vec<std::string::String>,
+	/// Options that apply to the filesystem (superblock) and therefore to every mount of it, e.g. ["rw", "errors=remount-ro"]
+	pub super_options: std::vec::Vec<std::string::String>,
+}
+
+/// A line of a mount table: the [Mount], plus the [MountInfo] if the line came from `/proc/self/mountinfo`.
+///
+/// The [MountInfo] is kept out of [Mount] itself so that a mount compares equal whichever table it was read from.  Compare whole entries to also compare mount IDs, propagation, and the like.  An entry dereferences to its [Mount], so its fields and methods can be used directly.
+/// # Examples
+/// ```
+/// # use nom_tutorial::parsers;
+/// # use nom_tutorial::model::Format;
+/// let entry = parsers::parse_entry("36 35 98:0 / /mnt rw,noatime - ext4 /dev/sda1 rw", Format::MountInfo).unwrap();
+/// assert_eq!(entry.mount_point, "/mnt");
+/// assert_eq!(entry.mountinfo.as_ref().unwrap().mount_id, 36);
+/// assert_eq!(entry.mount, parsers::parse("/dev/sda1 /mnt ext4 rw,noatime 0 0", Format::Mounts).unwrap());
+/// ```
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountEntry {
+	/// The mount described by the line.
+	pub mount: Mount,
+	/// Additional information only available when the mount was read from `/proc/self/mountinfo`, see [mountinfo()](crate::mountinfo()).
+	pub mountinfo: std::option::Option<MountInfo>,
+}
+
+impl std::ops::Deref for MountEntry {
+	type Target = Mount;
+
+	fn deref(&self) -> &Mount {
+		&self.mount
+	}
+}
+
+impl AsRef<Mount> for MountEntry {
+	fn as_ref(&self) -> &Mount {
+		&self.mount
+	}
+}
+
+impl From<Mount> for MountEntry {
+	/// An entry without [MountInfo].
+	fn from(mount: Mount) -> MountEntry {
+		MountEntry { mount, mountinfo: None }
+	}
+}
+
+impl From<MountEntry> for Mount {
+	fn from(entry: MountEntry) -> Mount {
+		entry.mount
+	}
+}
+
+impl std::fmt::Display for MountEntry {
+	/// Same format as [Mount], including the alternate format.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		std::fmt::Display::fmt(&self.mount, f)
+	}
+}
+
+impl MountEntry {
+	/// The per-mount (VFS) options, or `None` if the mount was not read from mountinfo.  Changing these only requires `mount -o remount,bind`.
+	pub fn vfs_options(&self) -> std::option::Option<&[std::string::String]> {
+		self.mountinfo.as_ref().map(|info| &info.vfs_options[..])
+	}
+
+	/// The superblock options shared by every mount of this filesystem, or `None` if the mount was not read from mountinfo.  Changing these requires a full `mount -o remount`, which affects every mount of the filesystem.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let entry = nom_tutorial::parsers::parse_entry("36 35 98:0 / /mnt rw,noatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro", Format::MountInfo).unwrap();
+	/// assert_eq!(entry.vfs_options().unwrap(), ["rw", "noatime"]);
+	/// assert_eq!(entry.super_options().unwrap(), ["rw", "errors=remount-ro"]);
+	/// assert_eq!(entry.options, ["rw", "noatime", "errors=remount-ro"]);
+	/// ```
+	pub fn super_options(&self) -> std::option::Option<&[std::string::String]> {
+		self.mountinfo.as_ref().map(|info| &info.super_options[..])
+	}
+
+	/// True if the mount can't be written to because either the mount or its filesystem (superblock) is read-only.  The two can differ: ext4 mounted with `errors=remount-ro` switches its superblock to read-only after an error while every mount of it keeps its own `rw` flag.  Like `/proc/mounts`, [Mount::options] shows such a mount as `ro`, so for mounts not read from mountinfo that is all there is to go on.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let entry = nom_tutorial::parsers::parse_entry("36 35 98:0 / /srv rw,relatime - ext4 /dev/sda1 ro,errors=remount-ro", Format::MountInfo).unwrap();
+	/// assert_eq!(entry.vfs_options().unwrap(), ["rw", "relatime"]);
+	/// assert!(entry.effective_read_only());
+	/// ```
+	pub fn effective_read_only(&self) -> bool {
+		let read_only = |options: &[std::string::String]| options.iter().any(|option| option == "ro");
+		match &self.mountinfo {
+			Some(info) => read_only(&info.vfs_options) || read_only(&info.super_options),
+			None => read_only(&self.options)
+		}
+	}
+}
+
+impl Mount {
+	/// The filesystem type without its subtype, e.g. fuse for `fuse.sshfs`.  Filesystems such as FUSE that serve many kinds of mounts report their type as `type.subtype`; for any other filesystem this is the whole [Mount::file_system_type].  FUSE filesystems backed by a block device, such as `fuseblk` for ntfs-3g, are in the fuse family too.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount { file_system_type: "fuse.sshfs".to_string(), ..Default::default() };
+	/// assert_eq!((mount.fs_family(), mount.fs_subtype()), ("fuse", Some("sshfs")));
+	/// let mount = Mount { file_system_type: "ext4".to_string(), ..Default::default() };
+	/// assert_eq!((mount.fs_family(), mount.fs_subtype()), ("ext4", None));
+	/// let mount = Mount { file_system_type: "fuseblk".to_string(), ..Default::default() };
+	/// assert_eq!(mount.fs_family(), "fuse");
+	/// ```
+	pub fn fs_family(&self) -> &str {
+		match self.file_system_type.split('.').next().unwrap_or_default() {
+			"fuseblk" => "fuse",
+			family => family
+		}
+	}
+
+	/// The subtype of the filesystem type, e.g. sshfs for `fuse.sshfs`, or `None` if the type has no subtype.  See [Mount::fs_family()].
+	pub fn fs_subtype(&self) -> std::option::Option<&str> {
+		self.file_system_type.split_once('.').map(|(_, subtype)| subtype).filter(|subtype| !subtype.is_empty())
+	}
+
+	/// True if the mount is idmapped, i.e. file ownership is translated through a user namespace as containers do with `mount_setattr(2)`.  Kernels since 5.12 report this as the per-mount option `idmapped`, which appears in both `/proc/mounts` and `/proc/self/mountinfo`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let mount = nom_tutorial::parsers::parse("612 598 0:45 / /var/lib/container/rootfs rw,relatime,idmapped - ext4 /dev/sda1 rw", Format::MountInfo).unwrap();
+	/// assert!(mount.is_idmapped());
+	/// ```
+	pub fn is_idmapped(&self) -> bool {
+		self.options.iter().any(|option| option == "idmapped")
+	}
+}
+
+/// Implements `Display` for `Mount` to simulate behavior of Unix mount command.  The output can be parsed back with [str::parse()], see [parsers](crate::parsers).  Commas outside double quotes and backslashes in options are escaped as `\054` and `\134`, as in `/proc/mounts`, so that every option comes back unchanged.
+/// 
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use std::string::String;
+/// let mount = Mount {
+///     device: String::from("/dev/sda1"),
+///     mount_point: String::from("/mnt/disk"),
+///     file_system_type: String::from("ext4"),
+///     options: vec![String::from("ro"), String::from("nosuid")]
+/// };
+/// assert!(mount.to_string() == "/dev/sda1 on /mnt/disk type ext4 (ro,nosuid)");
+/// ```
+///
+/// The alternate format, `{:#}`, spreads the mount over several labeled lines for reports and debugging, and lists the options one per line with their descriptions from [MountOption::description()](crate::options::MountOption::description).  Dump and pass are always 0 in the kernel's mount tables.
+/// ```
+/// # use nom_tutorial::Mount;
+/// let mount = Mount { device: "tmpfs".to_string(), mount_point: "/tmp".to_string(), file_system_type: "tmpfs".to_string(), options: vec!["nosuid".to_string(), "x-made-up".to_string()] };
+/// assert_eq!(format!("{:#}", mount), "\
+///     Device:      tmpfs\n\
+///     Mount point: /tmp\n\
+///     Type:        tmpfs\n\
+///     Dump:        0\n\
+///     Pass:        0\n\
+///     Options:\n    nosuid (ignore set-user-ID and set-group-ID bits)\n    x-made-up");
+/// ```
+impl std::fmt::Display for Mount {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if !f.alternate() {
+			return write!(f, "{} on {} type {} ({})", self.device, self.mount_point, self.file_system_type, join_output_options(&self.options));
+		}
+		write!(f, "Device:      {}\nMount point: {}\nType:        {}\nDump:        0\nPass:        0\nOptions:", self.device, self.mount_point, self.file_system_type)?;
+		for option in self.mount_options() {
+			match option.description() {
+				Some(description) => write!(f, "\n    {} ({})", option, description)?,
+				None => write!(f, "\n    {}", option)?
+			}
+		}
+		Ok(())
+	}
+}
+
+// Escapes a field for `/proc/mounts` the same way the kernel does, the inverse of `parsers::transform_escaped()`.
+fn escape_field(field: &str) -> std::string::String {
+	field.replace('\\', "\\134").replace(' ', "\\040").replace('\t', "\\011").replace('\n', "\\012")
+}
+
+// Escapes a mount option like `escape_field()`, and also escapes commas outside double quotes so that they aren't mistaken for the separator between options.
+fn escape_option(option: &str) -> std::string::String {
+	escape_commas(&escape_field(option))
+}
+
+// The parts of `option` outside double quotes, whose commas would be taken for separators.  A quoted comma, such as the one in `context="s0:c1,c2"`, never is.
+fn unquoted_parts(option: &str) -> impl Iterator<Item = (bool, &str)> {
+	option.split('"').enumerate().map(|(index, part)| (index % 2 == 0, part))
+}
+
+// Escapes the commas of `option` outside double quotes as `\054`.
+fn escape_commas(option: &str) -> std::string::String {
+	unquoted_parts(option)
+		.map(|(unquoted, part)| if unquoted { part.replace(',', "\\054") } else { part.to_string() })
+		.collect::<std::vec::Vec<_>>()
+		.join("\"")
+}
+
+// Joins options with commas, escaping the commas inside them so that the list splits back into the same options.
+pub(crate) fn join_options(options: &[std::string::String]) -> std::string::String {
+	options.iter().map(|option| escape_commas(option)).collect::<std::vec::Vec<_>>().join(",")
+}
+
+// Joins options for the one-line `Display` format, escaping backslashes as `\134` as well as the commas, so that parsing the line decodes every option back to what it was.
+pub(crate) fn join_output_options(options: &[std::string::String]) -> std::string::String {
+	options.iter().map(|option| escape_commas(&option.replace('\\', "\\134"))).collect::<std::vec::Vec<_>>().join(",")
+}
+
+// Joins options for the `-o` argument of mount(8), which doesn't know escape sequences but doesn't split inside double quotes either.  The value of an option with a comma outside quotes is quoted, e.g. `subvol="/a,b"`.
+fn join_mount_options(options: &[std::string::String]) -> std::string::String {
+	let quote = |option: &std::string::String| match option.split_once('=') {
+		_ if !unquoted_parts(option).any(|(unquoted, part)| unquoted && part.contains(',')) => option.clone(),
+		Some((name, value)) => format!("{}=\"{}\"", name, value),
+		None => format!("\"{}\"", option)
+	};
+	options.iter().map(quote).collect::<std::vec::Vec<_>>().join(",")
+}
+
+// Quotes an argument for a POSIX shell.  Arguments made only of characters that are never special are left alone for readability; anything else is wrapped in single quotes, inside which only the single quote itself needs escaping.
+fn shell_quote(arg: &str) -> std::string::String {
+	let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+	if !arg.is_empty() && arg.chars().all(safe) {
+		arg.to_string()
+	} else {
+		format!("'{}'", arg.replace('\'', "'\\''"))
+	}
+}
+
+impl Mount {
+	/// Formats the mount as a line in the style of `/proc/mounts`, escaping whitespace and backslashes so that the line can be parsed back into an identical `Mount`.  The line does not end in a newline.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount {
+	///     device: "/dev/sda1".to_string(),
+	///     mount_point: "/mnt/my disk".to_string(),
+	///     file_system_type: "ext4".to_string(),
+	///     options: vec!["ro".to_string(), "nosuid".to_string()]
+	/// };
+	/// assert_eq!(mount.to_proc_line(), "/dev/sda1 /mnt/my\\040disk ext4 ro,nosuid 0 0");
+	/// ```
+	pub fn to_proc_line(&self) -> std::string::String {
+		let options: std::vec::Vec<std::string::String> = self.options.iter().map(|o| escape_option(o)).collect();
+		format!("{} {} {} {} 0 0", escape_field(&self.device), escape_field(&self.mount_point), escape_field(&self.file_system_type), options.join(","))
+	}
+
+	/// The arguments of a `mount(8)` invocation that would recreate this mount, starting with `mount` itself.  The `-o` argument is left out if there are no options, and an option with a comma in its value, such as `subvol=/a,b`, has the value double-quoted the way mount(8) expects.  The arguments are not quoted, so they can be passed directly to [std::process::Command].
+	pub fn to_mount_argv(&self) -> std::vec::Vec<std::string::String> {
+		let mut argv = vec!["mount".to_string(), "-t".to_string(), self.file_system_type.clone()];
+		if !self.options.is_empty() {
+			argv.push("-o".to_string());
+			argv.push(join_mount_options(&self.options));
+		}
+		argv.push(self.device.clone());
+		argv.push(self.mount_point.clone());
+		argv
+	}
+
+	/// A `mount(8)` command line that would recreate this mount, with arguments quoted for a POSIX shell, see [Mount::to_mount_argv()].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount {
+	///     device: "/dev/sdb1".to_string(),
+	///     mount_point: "/mnt/my disk".to_string(),
+	///     file_system_type: "ext4".to_string(),
+	///     options: vec!["ro".to_string(), "noatime".to_string()]
+	/// };
+	/// assert_eq!(mount.to_mount_command(), "mount -t ext4 -o ro,noatime /dev/sdb1 '/mnt/my disk'");
+	/// ```
+	pub fn to_mount_command(&self) -> std::string::String {
+		let argv: std::vec::Vec<std::string::String> = self.to_mount_argv().iter().map(|arg| shell_quote(arg)).collect();
+		argv.join(" ")
+	}
+}
+
+impl MountEntry {
+	/// Formats the entry as a line in the style of `/proc/self/mountinfo`, escaped like [Mount::to_proc_line()], or returns `None` if the entry has no [MountInfo].  The options are taken from [MountInfo::vfs_options] and [MountInfo::super_options] rather than [Mount::options].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let line = "36 35 98:0 / /mnt/my\\040disk rw,noatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro";
+	/// let entry = nom_tutorial::parsers::parse_entry(line, Format::MountInfo).unwrap();
+	/// assert_eq!(entry.to_mountinfo_line().unwrap(), line);
+	/// ```
+	pub fn to_mountinfo_line(&self) -> std::option::Option<std::string::String> {
+		let info = self.mountinfo.as_ref()?;
+		let join = |options: &[std::string::String]| options.iter().map(|o| escape_option(o)).collect::<std::vec::Vec<_>>().join(",");
+		let mut line = format!("{} {} {}:{} {} {} {} ", info.mount_id, info.parent_id, info.major, info.minor, escape_field(&info.root), escape_field(&self.mount_point), join(&info.vfs_options));
+		for field in &info.optional_fields {
+			line.push_str(field);
+			line.push(' ');
+		}
+		line.push_str(&format!("- {} {} {}", escape_field(&self.file_system_type), escape_field(&self.device), join(&info.super_options)));
+		Some(line)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures::mount;
+
+	// The alternate format doesn't change the one-line format, and a mount without options ends at the label.
+	#[test]
+	fn test_display_alternate() {
+		let mount = mount("/dev/sda1", "/", "ext4", &[]);
+		assert_eq!(mount.to_string(), "/dev/sda1 on / type ext4 ()");
+		assert!(format!("{:#}", mount).ends_with("Pass:        0\nOptions:"));
+		let mount = Mount { options: vec!["size=1G".to_string()], ..mount };
+		assert!(format!("{:#}", mount).ends_with("Options:\n    size=1G (maximum size of the filesystem)"));
+	}
+
+	// Either flag makes the mount read-only, and without mountinfo the merged options decide.
+	#[test]
+	fn test_effective_read_only() {
+		let entry = |vfs: &str, superblock: &str| MountEntry {
+			mount: mount("/dev/sda1", "/", "ext4", &[vfs]),
+			mountinfo: Some(MountInfo { vfs_options: vec![vfs.to_string()], super_options: vec![superblock.to_string()], ..Default::default() })
+		};
+		assert!(!entry("rw", "rw").effective_read_only());
+		assert!(entry("ro", "rw").effective_read_only());
+		assert!(entry("rw", "ro").effective_read_only());
+		assert!(MountEntry::from(entry("ro", "rw").mount).effective_read_only());
+		assert!(!MountEntry::from(entry("rw", "ro").mount).effective_read_only());
+	}
+
+	#[test]
+	fn test_shell_quote() {
+		assert_eq!(shell_quote("/dev/sda1"), "/dev/sda1");
+		assert_eq!(shell_quote(""), "''");
+		assert_eq!(shell_quote("it's"), "'it'\\''s'");
+		assert_eq!(shell_quote("$HOME"), "'$HOME'");
+	}
+
+	// The argument vector holds the raw values, unquoted.
+	#[test]
+	fn test_to_mount_argv() {
+		let mount = Mount { device: "tmpfs".to_string(), mount_point: "/a b".to_string(), file_system_type: "tmpfs".to_string(), ..Default::default() };
+		assert_eq!(mount.to_mount_argv(), ["mount", "-t", "tmpfs", "tmpfs", "/a b"]);
+	}
+
+	// An escaped comma in an option survives every way of writing the options back out, and a quoted one is left alone.
+	#[test]
+	fn test_escaped_comma_round_trip() {
+		let line = "/dev/sda1 /data btrfs rw,subvol=/a\\054b,context=\"s0:c1,c2\" 0 0";
+		let mount = crate::parsers::parse(line, Format::Mounts).unwrap();
+		assert_eq!(mount.options, ["rw", "subvol=/a,b", "context=\"s0:c1,c2\""]);
+		assert_eq!(mount.to_proc_line(), line);
+		assert_eq!(crate::parsers::parse(&mount.to_proc_line(), Format::Mounts).unwrap(), mount);
+		assert_eq!(mount.to_string(), "/dev/sda1 on /data type btrfs (rw,subvol=/a\\054b,context=\"s0:c1,c2\")");
+		assert_eq!(mount.to_mount_argv()[4], "rw,subvol=\"/a,b\",context=\"s0:c1,c2\"");
+	}
+}
@@ -0,0 +1,98 @@
+//! Lists the mount namespaces on the system with a mount table for each, like `lsns --type mnt`.
+//!
+//! Every process links to its mount namespace as `/proc/<pid>/ns/mnt`, whose target names the namespace by inode number, e.g. `mnt:[4026531841]`.  Processes with the same inode share a namespace, so the mount table of any one of them stands for all of them.  Unlike [setns](crate::setns) this needs no privileges beyond being allowed to inspect the processes, and processes the caller may not inspect are skipped.
+
+use super::BoxError;
+use super::snapshot::MountSnapshot;
+
+/// A mount namespace found by [all_namespaces()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MountNamespace {
+	/// The inode number that identifies the namespace, as shown by `lsns`.
+	pub inode: u64,
+	/// The processes in the namespace, in ascending order.
+	pub pids: std::vec::Vec<u32>,
+	/// The mount table of the first of `pids` whose table could be read.  It is seen from that process' root directory, so mounts outside a `chroot()` are missing.
+	pub mounts: MountSnapshot,
+}
+
+/// Scans `/proc` for mount namespaces, returning one for each namespace that has a process the caller may inspect, ordered by inode.
+/// # Examples
+/// ```no_run
+/// for namespace in nom_tutorial::namespaces::all_namespaces().unwrap() {
+///     println!("mnt:[{}] {} processes, {} mounts", namespace.inode, namespace.pids.len(), namespace.mounts.len());
+/// }
+/// ```
+pub fn all_namespaces() -> std::result::Result<std::vec::Vec<MountNamespace>, BoxError> {
+	all_namespaces_from(std::path::Path::new("/proc"))
+}
+
+/// Like [all_namespaces()], but with procfs mounted at `proc` instead of `/proc`, e.g. the host's procfs bind-mounted into a container.
+pub fn all_namespaces_from(proc: &std::path::Path) -> std::result::Result<std::vec::Vec<MountNamespace>, BoxError> {
+	let mut pids_by_inode: std::collections::BTreeMap<u64, std::vec::Vec<u32>> = std::collections::BTreeMap::new();
+	for entry in std::fs::read_dir(proc)? {
+		let entry = entry?;
+		let pid = match entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) {
+			Some(pid) => pid,
+			None => continue
+		};
+		// The process may have exited, or belong to someone the caller may not inspect.
+		let inode = match std::fs::read_link(entry.path().join("ns/mnt")) {
+			Ok(target) => parse_inode(&target.to_string_lossy()),
+			Err(_) => None
+		};
+		if let Some(inode) = inode {
+			pids_by_inode.entry(inode).or_default().push(pid);
+		}
+	}
+	let mut namespaces = std::vec::Vec::new();
+	for (inode, mut pids) in pids_by_inode {
+		pids.sort_unstable();
+		let mounts = pids.iter().find_map(|pid| {
+			MountSnapshot::from_source(&crate::source::FileSource::mountinfo(proc.join(pid.to_string()).join("mountinfo"))).ok()
+		});
+		if let Some(mounts) = mounts {
+			namespaces.push(MountNamespace { inode, pids, mounts });
+		}
+	}
+	Ok(namespaces)
+}
+
+// Parses the inode number out of a namespace link target such as `mnt:[4026531841]`.
+fn parse_inode(target: &str) -> std::option::Option<u64> {
+	target.strip_prefix("mnt:[")?.strip_suffix(']')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Processes sharing a namespace are grouped, and processes without a readable link or table are skipped.
+	#[test]
+	fn test_all_namespaces_from() {
+		let proc = std::env::temp_dir().join(format!("nom-tutorial-namespaces-{}", std::process::id()));
+		let process = |pid: u32, namespace: &str, mountinfo: std::option::Option<&str>| {
+			let directory = proc.join(pid.to_string());
+			std::fs::create_dir_all(directory.join("ns")).unwrap();
+			std::os::unix::fs::symlink(namespace, directory.join("ns/mnt")).unwrap();
+			if let Some(mountinfo) = mountinfo {
+				std::fs::write(directory.join("mountinfo"), mountinfo).unwrap();
+			}
+		};
+		process(7, "mnt:[100]", None);
+		process(12, "mnt:[100]", Some("22 1 8:1 / / rw - ext4 /dev/sda1 rw\n"));
+		process(1, "mnt:[200]", Some("30 1 0:5 / / rw - overlay overlay rw\n31 30 0:6 / /tmp rw - tmpfs tmpfs rw\n"));
+		process(3, "mnt:[300]", None);
+		std::fs::create_dir_all(proc.join("self")).unwrap();
+		let namespaces = all_namespaces_from(&proc);
+		std::fs::remove_dir_all(&proc).unwrap();
+		let namespaces = namespaces.unwrap();
+		assert_eq!(namespaces.iter().map(|ns| (ns.inode, &ns.pids[..], ns.mounts.len())).collect::<std::vec::Vec<_>>(), [(100, &[7, 12][..], 1), (200, &[1][..], 2)]);
+	}
+
+	#[test]
+	fn test_parse_inode() {
+		assert_eq!(parse_inode("mnt:[4026531841]"), Some(4026531841));
+		assert_eq!(parse_inode("net:[4026531840]"), None);
+	}
+}
@@ -0,0 +1,92 @@
+//! Splits the device of an NFS mount into the server and the exported path.
+//!
+//! NFS mounts show up in the mount table with a device of the form `server:/export/path`.  When the server is given as an IPv6 address it is wrapped in brackets, e.g. `[fd00::2]:/export`, because the address itself contains colons.
+
+use super::Mount;
+
+/// The server and exported path of an NFS mount, see [Mount::nfs_source()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NfsSource<'a> {
+	/// The host name or address of the server, without brackets, e.g. nas.example.com or fd00::2
+	pub server: &'a str,
+	/// The path exported by the server, e.g. /export/home
+	pub export_path: &'a str,
+}
+
+impl<'a> NfsSource<'a> {
+	/// Splits a device of the form `server:/path` or `[ipv6-address]:/path`.  Returns `None` if `device` isn't in either form or the path isn't absolute.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::nfs::NfsSource;
+	/// assert_eq!(NfsSource::parse("[fd00::2]:/srv/exports"), Some(NfsSource { server: "fd00::2", export_path: "/srv/exports" }));
+	/// assert_eq!(NfsSource::parse("/dev/sda1"), None);
+	/// ```
+	pub fn parse(device: &'a str) -> std::option::Option<NfsSource<'a>> {
+		parsers::nfs_source(device).ok().map(|(_, source)| source)
+	}
+}
+
+impl std::fmt::Display for NfsSource<'_> {
+	/// Formats the source the way it appears in the mount table, with IPv6 addresses in brackets.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if self.server.contains(':') {
+			write!(f, "[{}]:{}", self.server, self.export_path)
+		} else {
+			write!(f, "{}:{}", self.server, self.export_path)
+		}
+	}
+}
+
+impl Mount {
+	/// The server and exported path of an `nfs` or `nfs4` mount.  Returns `None` for other filesystem types, or if the device isn't of the form `server:/path`, see [NfsSource::parse()].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount { device: "nas.example.com:/export/home".to_string(), file_system_type: "nfs4".to_string(), ..Default::default() };
+	/// let source = mount.nfs_source().unwrap();
+	/// assert_eq!((source.server, source.export_path), ("nas.example.com", "/export/home"));
+	/// ```
+	pub fn nfs_source(&self) -> std::option::Option<NfsSource<'_>> {
+		match &self.file_system_type[..] {
+			"nfs" | "nfs4" => NfsSource::parse(&self.device),
+			_ => None
+		}
+	}
+}
+
+mod parsers {
+	use super::NfsSource;
+
+	// Parses the server part of an NFS device, which is either a bracketed IPv6 address or anything up to the first colon.
+	fn server(i: &str) -> nom::IResult<&str, &str> {
+		nom::branch::alt((
+			nom::sequence::delimited(nom::character::complete::char('['), nom::bytes::complete::is_not("]"), nom::character::complete::char(']')),
+			nom::bytes::complete::is_not(":[]/")
+		))(i)
+	}
+
+	// Parses a whole NFS device such as `server:/export/path`.  The export path is everything after the colon and has to be absolute.
+	pub fn nfs_source(i: &str) -> nom::IResult<&str, NfsSource<'_>> {
+		let (i, (server, export_path)) = nom::combinator::all_consuming(nom::sequence::separated_pair(
+			server,
+			nom::character::complete::char(':'),
+			nom::combinator::recognize(nom::sequence::preceded(nom::character::complete::char('/'), nom::combinator::rest))
+		))(i)?;
+		Ok((i, NfsSource { server, export_path }))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_nfs_source() {
+			assert_eq!(nfs_source("nas:/export"), Ok(("", NfsSource { server: "nas", export_path: "/export" })));
+			assert_eq!(nfs_source("[fd00::2]:/srv/a:b"), Ok(("", NfsSource { server: "fd00::2", export_path: "/srv/a:b" })));
+			assert!(nfs_source("nas:").is_err());
+			assert!(nfs_source("nas:export").is_err());
+			assert!(nfs_source("fd00::2:/export").is_err());
+			assert!(nfs_source("/dev/sda1").is_err());
+		}
+	}
+}
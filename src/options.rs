@@ -0,0 +1,410 @@
+//! Individual mount options and what they mean.
+
+use super::Mount;
+
+// Pairs of option name and description, sorted by name.  Generated by build.rs from data/mount_options.tsv.
+const EXPLANATIONS: &[(&str, &str)] = include!(concat!(env!("OUT_DIR"), "/option_explanations.rs"));
+
+/// Constants for the names of well-known mount options, e.g. [names::NOSUID], so that typos in option names are caught by the compiler.
+///
+/// Generated by build.rs from the same table as [MountOption::description()], a curated list of the generic options from mount(8) and the common options of the most widespread filesystems.  It is not exhaustive: filesystems accept many options that have no constant here, so don't use [names::ALL] to decide whether an option is valid.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::options::names;
+/// let mount = Mount { options: vec!["rw".to_string(), "nosuid".to_string()], ..Default::default() };
+/// assert!(mount.options.iter().any(|option| option == names::NOSUID));
+/// assert!(names::ALL.contains(&names::NETDEV));
+/// ```
+pub mod names {
+	include!(concat!(env!("OUT_DIR"), "/option_names.rs"));
+}
+
+/// A single mount option such as `nosuid` or `size=1G`, split into its name and value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MountOption<'a> {
+	/// The name of the option, e.g. size
+	pub name: &'a str,
+	/// The value after the `=`, if any, e.g. 1G
+	pub value: std::option::Option<&'a str>,
+}
+
+impl<'a> MountOption<'a> {
+	/// Splits an option at the first `=`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::options::MountOption;
+	/// assert_eq!(MountOption::parse("size=1G"), MountOption { name: "size", value: Some("1G") });
+	/// assert_eq!(MountOption::parse("nosuid"), MountOption { name: "nosuid", value: None });
+	/// ```
+	pub fn parse(option: &'a str) -> MountOption<'a> {
+		let mut split = option.splitn(2, '=');
+		MountOption { name: split.next().unwrap_or_default(), value: split.next() }
+	}
+
+	/// A short description of what the option does, e.g. "ignore set-user-ID and set-group-ID bits" for `nosuid`.  Returns `None` for options that aren't in the table, which covers the common generic options and those of the most widespread filesystems.
+	pub fn description(&self) -> std::option::Option<&'static str> {
+		EXPLANATIONS.binary_search_by_key(&self.name, |(name, _)| name).ok().map(|index| EXPLANATIONS[index].1)
+	}
+
+	/// Explains the option for a tooltip or help text, prefixed by its name.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::options::MountOption;
+	/// assert_eq!(MountOption::parse("nosuid").explain().unwrap(), "nosuid: ignore set-user-ID and set-group-ID bits");
+	/// assert_eq!(MountOption::parse("size=1G").explain().unwrap(), "size: maximum size of the filesystem");
+	/// assert_eq!(MountOption::parse("x-made-up").explain(), None);
+	/// ```
+	pub fn explain(&self) -> std::option::Option<std::string::String> {
+		self.description().map(|description| format!("{}: {}", self.name, description))
+	}
+}
+
+impl std::fmt::Display for MountOption<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.value {
+			Some(value) => write!(f, "{}={}", self.name, value),
+			None => write!(f, "{}", self.name)
+		}
+	}
+}
+
+/// An option that should have a numeric value had a missing or malformed one, e.g. `uid=root` or `mode=999`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OptionValueError {
+	/// The name of the option, e.g. uid
+	pub name: std::string::String,
+	/// The value that couldn't be parsed, or `None` if the option had no value.
+	pub value: std::option::Option<std::string::String>,
+	// Why the value couldn't be parsed.
+	source: std::option::Option<std::num::ParseIntError>,
+}
+
+impl OptionValueError {
+	// Reports that the value of `option` couldn't be parsed, optionally because of `source`.
+	pub(crate) fn new(option: &MountOption<'_>, source: std::option::Option<std::num::ParseIntError>) -> OptionValueError {
+		OptionValueError { name: option.name.to_string(), value: option.value.map(str::to_string), source }
+	}
+}
+
+impl std::fmt::Display for OptionValueError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.value {
+			Some(value) => write!(f, "invalid value for option {}: {:?}", self.name, value),
+			None => write!(f, "option {} has no value", self.name)
+		}
+	}
+}
+
+impl std::error::Error for OptionValueError {
+	fn source(&self) -> std::option::Option<&(dyn std::error::Error + 'static)> {
+		self.source.as_ref().map(|e| e as &(dyn std::error::Error + 'static))
+	}
+}
+
+/// The value of a size option such as tmpfs' `size=`, see [MountOption::size_value()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SizeValue {
+	/// An absolute size in bytes, e.g. from `size=2G`.
+	Bytes(u64),
+	/// A percentage of the memory installed, e.g. from `size=50%`.
+	Percent(u64),
+}
+
+impl SizeValue {
+	/// The size in bytes, resolving a percentage against `mem_total` bytes of memory, e.g. as returned by [mem_total()](crate::meminfo::mem_total).
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::options::SizeValue;
+	/// assert_eq!(SizeValue::Percent(25).to_bytes(8 << 30), 2 << 30);
+	/// assert_eq!(SizeValue::Bytes(1024).to_bytes(8 << 30), 1024);
+	/// ```
+	pub fn to_bytes(self, mem_total: u64) -> u64 {
+		match self {
+			SizeValue::Bytes(bytes) => bytes,
+			SizeValue::Percent(percent) => (mem_total as u128 * percent as u128 / 100).min(u64::MAX as u128) as u64
+		}
+	}
+}
+
+impl MountOption<'_> {
+	/// Parses the value as a decimal number, e.g. for `uid=1000`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::options::MountOption;
+	/// assert_eq!(MountOption::parse("uid=1000").decimal_value(), Ok(1000));
+	/// assert!(MountOption::parse("uid=root").decimal_value().is_err());
+	/// ```
+	pub fn decimal_value(&self) -> std::result::Result<u32, OptionValueError> {
+		self.numeric_value(10)
+	}
+
+	/// Parses the value as an octal number, e.g. for `mode=0755`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::options::MountOption;
+	/// assert_eq!(MountOption::parse("mode=0755").octal_value(), Ok(0o755));
+	/// assert!(MountOption::parse("mode=999").octal_value().is_err());
+	/// ```
+	pub fn octal_value(&self) -> std::result::Result<u32, OptionValueError> {
+		self.numeric_value(8)
+	}
+
+	/// Parses the value as a size in the style of tmpfs' `size=` option: a number of bytes with an optional binary suffix `k`, `m`, `g`, `t`, `p`, or `e` (in either case), or a percentage of memory such as `50%`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::options::{MountOption, SizeValue};
+	/// assert_eq!(MountOption::parse("size=512M").size_value(), Ok(SizeValue::Bytes(512 << 20)));
+	/// assert_eq!(MountOption::parse("size=50%").size_value(), Ok(SizeValue::Percent(50)));
+	/// assert!(MountOption::parse("size=1Q").size_value().is_err());
+	/// ```
+	pub fn size_value(&self) -> std::result::Result<SizeValue, OptionValueError> {
+		let error = |source| OptionValueError::new(self, source);
+		let value = self.value.ok_or_else(|| error(None))?;
+		let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+		let number = value[..split].parse::<u64>().map_err(|e| error(Some(e)))?;
+		let shift = match &value[split..] {
+			"%" => return Ok(SizeValue::Percent(number)),
+			"" => 0,
+			"k" | "K" => 10,
+			"m" | "M" => 20,
+			"g" | "G" => 30,
+			"t" | "T" => 40,
+			"p" | "P" => 50,
+			"e" | "E" => 60,
+			_ => return Err(error(None))
+		};
+		number.checked_mul(1 << shift).map(SizeValue::Bytes).ok_or_else(|| error(None))
+	}
+
+	// Parses the value as a number in `radix`.
+	fn numeric_value(&self, radix: u32) -> std::result::Result<u32, OptionValueError> {
+		let error = |source| OptionValueError::new(self, source);
+		let value = self.value.ok_or_else(|| error(None))?;
+		u32::from_str_radix(value, radix).map_err(|e| error(Some(e)))
+	}
+}
+
+impl Mount {
+	/// Iterates over the mount's options, split into names and values.
+	pub fn mount_options(&self) -> impl Iterator<Item = MountOption<'_>> {
+		self.options.iter().map(|option| MountOption::parse(option))
+	}
+
+	// The last option called `name`, which is the one that takes effect if it is given more than once.
+	pub(crate) fn last_option(&self, name: &str) -> std::option::Option<MountOption<'_>> {
+		self.mount_options().filter(|option| option.name == name).last()
+	}
+
+	/// The user that owns every file, from the `uid=` option of filesystems such as vfat and tmpfs.  Returns `Ok(None)` if the option isn't set and an error if its value isn't a number.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount { options: vec!["rw".to_string(), "uid=1000".to_string(), "mode=1777".to_string()], ..Default::default() };
+	/// assert_eq!(mount.uid().unwrap(), Some(1000));
+	/// assert_eq!(mount.gid().unwrap(), None);
+	/// assert_eq!(mount.mode().unwrap(), Some(0o1777));
+	/// ```
+	pub fn uid(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("uid").map(|option| option.decimal_value()).transpose()
+	}
+
+	/// The user that mounted a FUSE filesystem, from the `user_id=` option the kernel records for it.  See [Mount::uid()].
+	pub fn fuse_user_id(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("user_id").map(|option| option.decimal_value()).transpose()
+	}
+
+	/// The name of the user who mounted a filesystem listed with the `user` option in `/etc/fstab`, from the `user=` option mount(8) records in utab.  Only present after merging utab, see [utab::merge()](crate::utab::merge).
+	pub fn user_name(&self) -> std::option::Option<&str> {
+		self.last_option("user").and_then(|option| option.value)
+	}
+
+	/// The group that owns every file, from the `gid=` option.  See [Mount::uid()].
+	pub fn gid(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("gid").map(|option| option.decimal_value()).transpose()
+	}
+
+	/// The permission bits of the root directory, from the octal `mode=` option of filesystems such as tmpfs and devpts.
+	pub fn mode(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("mode").map(|option| option.octal_value()).transpose()
+	}
+
+	/// The permission bits cleared from files, from the octal `fmask=` option of filesystems such as vfat and ntfs.
+	pub fn fmask(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("fmask").map(|option| option.octal_value()).transpose()
+	}
+
+	/// The permission bits cleared from directories, from the octal `dmask=` option.  See [Mount::fmask()].
+	pub fn dmask(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("dmask").map(|option| option.octal_value()).transpose()
+	}
+
+	/// The maximum size of a tmpfs mount in bytes, from its `size=` option, with percentages resolved against the memory reported by [mem_total()](crate::meminfo::mem_total).  A tmpfs mount without a `size=` option is limited to half of memory, which is what the kernel defaults to.  Returns `Ok(None)` if the mount isn't a tmpfs mount or has `size=0`, which means it is unlimited.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount { file_system_type: "tmpfs".to_string(), options: vec!["size=1631280k".to_string()], ..Default::default() };
+	/// assert_eq!(mount.tmpfs_size().unwrap(), Some(1631280 * 1024));
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn tmpfs_size(&self) -> std::result::Result<std::option::Option<u64>, crate::BoxError> {
+		self.tmpfs_size_with(crate::meminfo::mem_total)
+	}
+
+	// Like [Mount::tmpfs_size()], but calls `mem_total` for the amount of memory, and only if a percentage needs it.
+	#[cfg(feature = "io")]
+	fn tmpfs_size_with<F: FnOnce() -> std::result::Result<u64, crate::BoxError>>(&self, mem_total: F) -> std::result::Result<std::option::Option<u64>, crate::BoxError> {
+		if self.file_system_type != "tmpfs" {
+			return Ok(None);
+		}
+		let size = match self.last_option("size") {
+			Some(option) => option.size_value()?,
+			None => SizeValue::Percent(50)
+		};
+		let bytes = match size {
+			SizeValue::Bytes(bytes) => bytes,
+			SizeValue::Percent(_) => size.to_bytes(mem_total()?)
+		};
+		Ok(if bytes == 0 { None } else { Some(bytes) })
+	}
+
+	/// How long NFS waits before retrying a request, in tenths of a second, from the `timeo=` option.
+	pub fn timeo(&self) -> std::result::Result<std::option::Option<u32>, OptionValueError> {
+		self.last_option("timeo").map(|option| option.decimal_value()).transpose()
+	}
+}
+
+// Pairs of options that undo each other, the one that isn't the default first.  Options that affect access times are resolved separately, see [crate::atime].
+pub(crate) const OPPOSITES: &[(&str, &str)] = &[
+	("nosuid", "suid"), ("nodev", "dev"), ("noexec", "exec"), ("sync", "async"), ("mand", "nomand"),
+	("lazytime", "nolazytime"), ("iversion", "noiversion"), ("nosymfollow", "symfollow"),
+	("noauto", "auto"), ("user", "nouser"), ("silent", "loud"),
+];
+
+// The options that affect access times, see [crate::atime].
+pub(crate) const ATIME_OPTIONS: &[&str] = &["noatime", "atime", "relatime", "norelatime", "strictatime", "nostrictatime", "nodiratime", "diratime"];
+
+/// Rewrites `options` in a canonical form, so that two lists that mean the same thing compare equal, e.g. when diffing mount tables or comparing `/etc/fstab` with `/proc/mounts`.
+///
+/// - Of options that undo each other, such as `nosuid` and `suid`, only the last one counts, and it is dropped if it is the default.  `defaults` is dropped too.
+/// - Access time options are resolved as in [Mount::atime_mode()] and written the way the kernel reports them: `noatime`, or `relatime` followed by `nodiratime` if directories are excluded, or nothing for strict access times.  This drops `nodiratime` when `noatime` already implies it.
+/// - Options repeated verbatim are listed once.  Options with a value, such as `uid=0`, are only merged with exact duplicates, since some filesystems accept the same option several times.
+/// - `ro` or `rw` comes first, followed by the other options sorted by name.  Options with the same name keep their order, since for most of them the last one wins.  Unlike the other defaults `rw` is kept rather than dropped: the kernel lists one of `ro` and `rw` for every mount, so keeping it makes the lists of writable and read-only mounts line up, and a list without either means the entry didn't say.
+/// # Examples
+/// ```
+/// # use nom_tutorial::options::normalize_options;
+/// assert_eq!(normalize_options(&["nodiratime", "noatime", "rw", "suid", "nosuid", "uid=0", "uid=0"]), ["rw", "noatime", "nosuid", "uid=0"]);
+/// assert_eq!(normalize_options(&["defaults", "exec", "relatime"]), normalize_options(&["relatime"]));
+/// ```
+pub fn normalize_options<S: AsRef<str>>(options: &[S]) -> std::vec::Vec<std::string::String> {
+	let mut read_only = None;
+	let mut opposites: std::vec::Vec<std::option::Option<&str>> = vec![None; OPPOSITES.len()];
+	let mut rest: std::vec::Vec<std::string::String> = std::vec::Vec::new();
+	for option in options.iter().map(AsRef::as_ref) {
+		match option {
+			"ro" => read_only = Some(true),
+			"rw" => read_only = Some(false),
+			"defaults" => (),
+			_ if ATIME_OPTIONS.contains(&option) => (),
+			_ => match OPPOSITES.iter().position(|&(set, unset)| option == set || option == unset) {
+				Some(index) => opposites[index] = Some(option),
+				None => if !rest.iter().any(|kept| kept == option) {
+					rest.push(option.to_string());
+				}
+			}
+		}
+	}
+	for (&(set, _), last) in OPPOSITES.iter().zip(opposites) {
+		if last == Some(set) {
+			rest.push(set.to_string());
+		}
+	}
+	match crate::atime::resolve(options.iter().map(AsRef::as_ref)) {
+		crate::atime::AtimeMode::Never => rest.push("noatime".to_string()),
+		crate::atime::AtimeMode::Relative { directories, .. } => {
+			rest.push("relatime".to_string());
+			if !directories {
+				rest.push("nodiratime".to_string());
+			}
+		},
+		crate::atime::AtimeMode::Strict { directories, .. } => if !directories {
+			rest.push("nodiratime".to_string());
+		}
+	}
+	rest.sort_by(|a, b| MountOption::parse(a).name.cmp(MountOption::parse(b).name));
+	let mut normalized: std::vec::Vec<std::string::String> = read_only.map(|read_only| if read_only { "ro" } else { "rw" }.to_string()).into_iter().collect();
+	normalized.extend(rest);
+	normalized
+}
+
+impl Mount {
+	/// The mount's options in canonical form, see [normalize_options()].
+	pub fn normalized_options(&self) -> std::vec::Vec<std::string::String> {
+		normalize_options(&self.options)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The options the kernel reports for common mounts are all explained.
+	#[test]
+	fn test_common_options_explained() {
+		let line = "rw,nosuid,nodev,noexec,relatime,size=65536k,mode=755,uid=0,gid=0,errors=remount-ro,subvol=/@";
+		for option in line.split(',').map(MountOption::parse) {
+			assert!(option.explain().is_some(), "{} is not explained", option);
+		}
+	}
+
+	// Every name constant has an explanation, and the list of names is in the same order as the table.
+	#[test]
+	fn test_names() {
+		assert_eq!(names::ALL, EXPLANATIONS.iter().map(|(name, _)| *name).collect::<std::vec::Vec<_>>());
+		assert_eq!((names::RO, names::NETDEV, names::USER_ID), ("ro", "_netdev", "user_id"));
+		assert!(names::ALL.iter().all(|name| MountOption::parse(name).description().is_some()));
+	}
+
+	// Ordering noise and redundant options disappear, but real differences survive.
+	#[test]
+	fn test_normalize_options() {
+		assert_eq!(normalize_options(&["size=1G", "nodev", "rw", "mode=755", "nodev"]), normalize_options(&["rw", "mode=755", "nodev", "size=1G"]));
+		assert_eq!(normalize_options(&["ro", "rw", "nosuid", "suid", "strictatime", "relatime", "nodiratime"]), ["rw", "nodiratime", "relatime"]);
+		assert_eq!(normalize_options(&["noatime", "atime", "lazytime"]), ["lazytime"]);
+		assert_eq!(normalize_options(&["lowerdir=/a", "lowerdir=/b"]), ["lowerdir=/a", "lowerdir=/b"]);
+		assert_eq!(normalize_options(&["uid=1", "gid=0", "uid=0"]), ["gid=0", "uid=1", "uid=0"]);
+		assert_ne!(normalize_options(&["rw", "nosuid"]), normalize_options(&["rw", "suid"]));
+		assert!(normalize_options::<&str>(&[]).is_empty());
+	}
+
+	#[test]
+	fn test_display_roundtrip() {
+		assert_eq!(MountOption::parse("lowerdir=/a=b").to_string(), "lowerdir=/a=b");
+		assert_eq!(MountOption::parse("lowerdir=/a=b").value, Some("/a=b"));
+	}
+
+	// Malformed or missing values are errors that name the option, and the last occurrence of an option wins.
+	#[test]
+	fn test_numeric_options() {
+		let mount = |options: &[&str]| Mount { options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() };
+		assert_eq!(mount(&["fmask=0022", "dmask=0077", "fmask=0133"]).fmask(), Ok(Some(0o133)));
+		assert_eq!(mount(&["fmask=0022", "dmask=0077"]).dmask(), Ok(Some(0o77)));
+		assert_eq!(mount(&["timeo=600"]).timeo(), Ok(Some(600)));
+		let error = mount(&["gid=wheel"]).gid().unwrap_err();
+		assert_eq!((&error.name[..], error.value.as_deref()), ("gid", Some("wheel")));
+		assert_eq!(mount(&["mode"]).mode().unwrap_err().to_string(), "option mode has no value");
+	}
+
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_tmpfs_size() {
+		let tmpfs = |options: &[&str]| Mount { file_system_type: "tmpfs".to_string(), options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() };
+		assert_eq!(tmpfs(&["size=2g"]).tmpfs_size().unwrap(), Some(2 << 30));
+		assert_eq!(tmpfs(&["size=0"]).tmpfs_size().unwrap(), None);
+		let with_8g = |options: &[&str]| tmpfs(options).tmpfs_size_with(|| Ok(8 << 30)).unwrap();
+		assert_eq!((with_8g(&[]), with_8g(&["size=25%"]), with_8g(&["size=1g"])), (Some(4 << 30), Some(2 << 30), Some(1 << 30)));
+		assert_eq!(tmpfs(&["size=1g"]).tmpfs_size_with(|| Err("unreadable".into())).unwrap(), Some(1 << 30));
+		assert!(tmpfs(&["size=99999999999E"]).tmpfs_size().is_err());
+		assert_eq!(Mount { options: vec!["size=1G".to_string()], ..Default::default() }.tmpfs_size().unwrap(), None);
+	}
+}
@@ -0,0 +1,510 @@
+//! The nom parsers for the lines of `/proc/mounts` and `/proc/self/mountinfo`.
+//!
+//! [parse()] turns a single line into a [Mount] without any I/O, which is all that is needed to analyze a mount table that arrives some other way, e.g. uploaded to a web service.  The individual nom parsers it is built from are public within the crate only, so that other file formats, such as fstab, can share the building blocks such as `transform_escaped()`.
+
+use super::{Mount, MountEntry, MountInfo};
+use super::model::Format;
+
+/// Error parsing a line of a mount table, or of another file format read by this crate.
+///
+/// The failing nom parser and the position where it failed are available as fields, and the original nom error is the error's [source()](std::error::Error::source).
+/// # Examples
+/// ```
+/// # use nom_tutorial::model::Format;
+/// let error = nom_tutorial::parsers::parse("/dev/sda1 /mnt", Format::Mounts).unwrap_err();
+/// assert_eq!(error.offset, 14);
+/// assert_eq!(error.kind, nom::error::ErrorKind::Space);
+/// ```
+#[derive(Debug)]
+pub struct ParseError {
+	/// The line that couldn't be parsed, without its newline.
+	pub line: std::string::String,
+	/// The line number, starting from 1, if known.
+	pub line_number: std::option::Option<usize>,
+	/// Byte offset within the line where the failing parser was applied.
+	pub offset: usize,
+	/// The nom parser that failed.
+	pub kind: nom::error::ErrorKind,
+	// The original nom error.
+	source: nom::Err<(std::string::String, nom::error::ErrorKind)>,
+}
+
+impl ParseError {
+	/// Wraps the error returned by a nom parser applied to `line`.
+	pub fn new(line: &str, error: nom::Err<(&str, nom::error::ErrorKind)>) -> ParseError {
+		let (offset, kind) = match &error {
+			nom::Err::Error((rest, kind)) | nom::Err::Failure((rest, kind)) => (line.len() - rest.len(), *kind),
+			// Only streaming parsers ask for more input, which means they ran out at the end of the line.
+			nom::Err::Incomplete(_) => (line.len(), nom::error::ErrorKind::Eof)
+		};
+		ParseError { line: line.to_string(), line_number: None, offset, kind, source: error.to_owned() }
+	}
+
+	/// Records the line number of the line that failed to parse.
+	pub fn with_line_number(mut self, line_number: usize) -> ParseError {
+		self.line_number = Some(line_number);
+		self
+	}
+}
+
+impl std::fmt::Display for ParseError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if let Some(line_number) = self.line_number {
+			write!(f, "line {}, ", line_number)?;
+		}
+		write!(f, "byte {}: failed to parse {:?} ({})", self.offset, self.line, self.kind.description())
+	}
+}
+
+impl std::error::Error for ParseError {
+	fn source(&self) -> std::option::Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.source)
+	}
+}
+
+/// Parses a single line of a mount table in the given format, without its newline.
+/// # Examples
+/// ```
+/// # use nom_tutorial::model::Format;
+/// let mount = nom_tutorial::parsers::parse("/dev/sda1 /mnt/my\\040disk ext4 rw 0 0", Format::Mounts).unwrap();
+/// assert_eq!(mount.mount_point, "/mnt/my disk");
+/// assert!(nom_tutorial::parsers::parse("/dev/sda1 /mnt", Format::Mounts).is_err());
+/// ```
+pub fn parse(line: &str, format: Format) -> std::result::Result<Mount, ParseError> {
+	parse_entry(line, format).map(|entry| entry.mount)
+}
+
+/// Like [parse()], but keeps the [MountInfo] of a line in the format of `/proc/self/mountinfo`.
+pub fn parse_entry(line: &str, format: Format) -> std::result::Result<MountEntry, ParseError> {
+	let parsed = match format {
+		Format::Mounts => parse_line(line).map(|(i, mount)| (i, mount.into())),
+		Format::MountInfo => parse_mountinfo_line(line),
+		Format::Mnttab => crate::mnttab::parsers::parse_line(line).map(|(i, (mount, _))| (i, mount.into()))
+	};
+	parsed.map(|(_, entry)| entry).map_err(|e| ParseError::new(line, e))
+}
+
+impl std::str::FromStr for Mount {
+	type Err = ParseError;
+
+	/// Parses a line of `mount(8)` output, `DEVICE on TARGET type FSTYPE (OPTIONS)`, the inverse of the `Display` implementation.  A trailing label in brackets, as printed by `mount -l`, is ignored.  Options are split at commas outside double quotes and their escape sequences such as `\054` are decoded, like those of `/proc/mounts`.  Since `mount` doesn't escape the other fields, a device containing " on " or a filesystem type containing a space can't be told apart and fails to parse as expected.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount: Mount = "/dev/sdb1 on /mnt/my disk type vfat (rw,relatime,uid=1000) [USB]".parse().unwrap();
+	/// assert_eq!(mount.mount_point, "/mnt/my disk");
+	/// assert_eq!(mount.to_string().parse::<Mount>().unwrap(), mount);
+	/// ```
+	fn from_str(line: &str) -> std::result::Result<Mount, ParseError> {
+		parse_mount_output_line(line).map(|(_, mount)| mount).map_err(|e| ParseError::new(line, e))
+	}
+}
+
+/// Extracts every line of `mount(8)` output from `text`, such as a log or a ticket with `mount` output pasted into it, skipping the lines that aren't mount output.  Leading and trailing whitespace on each line is ignored.
+/// # Examples
+/// ```
+/// let ticket = "After the reboot mount showed:\n  /dev/sda1 on / type ext4 (ro,relatime)\n  proc on /proc type proc (rw)\nwhich is wrong.\n";
+/// let mounts = nom_tutorial::parsers::extract_mount_output(ticket);
+/// assert_eq!(mounts.len(), 2);
+/// assert_eq!(mounts[0].options, vec!["ro", "relatime"]);
+/// ```
+pub fn extract_mount_output(text: &str) -> std::vec::Vec<Mount> {
+	text.lines().filter_map(|line| line.trim().parse().ok()).collect()
+}
+
+// Extract a string that does not contain whitespace (space or tab).  Anything else goes.
+pub(crate) fn not_whitespace(i: &str) -> nom::IResult<&str, &str> {
+	nom::bytes::complete::is_not(" \t")(i)
+}
+
+// Replace the sequence 040 with a space.
+fn escaped_space(i: &str) -> nom::IResult<&str, &str> {
+	nom::combinator::value(" ", nom::bytes::complete::tag("040"))(i)
+}
+
+// Replace the escaped sequence \ with a \.  The inner parser `nom::character::complete::char` returns a `char` instead of a `&str`, so we wrap it in a `nom::combinator::recognize`, which returns that `char` as an `&str` if the inner parser succeeds, and returns an error if the inner parser fails.
+fn escaped_backslash(i: &str) -> nom::IResult<&str, &str> {
+	nom::combinator::recognize(nom::character::complete::char('\\'))(i)
+}
+
+// The kernel also escapes tabs, newlines, and backslashes as octal sequences, and commas in option values such as btrfs' `subvol=`.  Replace 011 with a tab, 012 with a newline, 054 with a comma, and 134 with a \.
+fn escaped_octal(i: &str) -> nom::IResult<&str, &str> {
+	nom::branch::alt((
+		nom::combinator::value("\t", nom::bytes::complete::tag("011")),
+		nom::combinator::value("\n", nom::bytes::complete::tag("012")),
+		nom::combinator::value(",", nom::bytes::complete::tag("054")),
+		nom::combinator::value("\\", nom::bytes::complete::tag("134")),
+	))(i)
+}
+
+// Replace all instances of \040 in a string with a space.
+// Replace \\ with a \.
+// Replace \011, \012, and \134 with a tab, newline, and \ respectively.
+pub(crate) fn transform_escaped(i: &str) -> nom::IResult<&str, std::string::String> {
+	nom::bytes::complete::escaped_transform(nom::bytes::complete::is_not("\\"), '\\', nom::branch::alt((escaped_backslash, escaped_space, escaped_octal)))(i)
+}
+
+// Extract a single mount option, which ends at a comma or whitespace.  Commas inside double quotes don't end the option, since that is how the kernel shows SELinux contexts such as `context="system_u:object_r:tmp_t:s0:c1,c2"`.  An unbalanced quote is taken literally.
+fn mount_opt(i: &str) -> nom::IResult<&str, &str> {
+	nom::combinator::recognize(nom::multi::many1(nom::branch::alt((
+		nom::combinator::recognize(nom::sequence::delimited(nom::character::complete::char('"'), nom::bytes::complete::is_not("\" \t"), nom::character::complete::char('"'))),
+		nom::bytes::complete::is_not(", \t\""),
+		nom::bytes::complete::tag("\""),
+	))))(i)
+}
+
+// Parse the options of a mount into a comma separated vector of strings.  The options string is terminated by a whitespace.
+// Here we use `nom::combinator::map_parser` to extract each option with `mount_opt` and then pass it in to `transform_escaped` to process escaped characters.  The options are collected into a comma-delimited vector of strings by `nom::multi::separated_list`.
+pub(crate) fn mount_opts(i: &str) -> nom::IResult<&str, std::vec::Vec<std::string::String>> {
+	nom::multi::separated_list(nom::character::complete::char(','), nom::combinator::map_parser(mount_opt, transform_escaped))(i)
+}
+
+// Parse a line from `/proc/mounts` into a Mount struct.  This is perhaps the most complex looking parser, but it is actually one of the simplest because we build upon each of the parsers defined above.  Let's break it down parser by parser:
+// # `nom::combinator::all_consuming` generates an error if there is any leftover input.  This will force nom to generate an error if there is unexpected input at the end of a line in `/proc/mounts`, for example:
+// ```ignore
+// /dev/sda1 /mnt/disk ext4 defaults 0 0 this_last_part_shouldn't_be_here
+// ```
+// 
+// `nom::sequence::tuple` generates a `Result<Ok(remaining_input: &str, output_tuple), Error>`.  Although it looks complicated, we can very easily destructure that tuple.  Each sub/inner parser we pass to `nom::sequence::tuple` generates its own element within the tuple.  We can ignore the whitespace by matching it with `_` and destructure the other elements of the tuple as the variabels we are insterested such as `device`, `mount_point`, etc.  If everything goes as planned we return a new instance of the mount `Mount` structure populated with the variables we destructured from the tuple.
+// ```ignore
+// let (device, _, mount_point /*, ...*/)  =  /* tuple returned by all_consuming(tuple()) --> */  ("/dev/sda1", " ", "/mnt/disk" /*, ...*/);
+// let mount = Mount { device: device.to_string(), mount_point: mount_point.to_string() /*, ...*/ };
+// ```
+pub(crate) fn parse_line(i: &str) -> nom::IResult<&str, Mount> {
+	match nom::combinator::all_consuming(nom::sequence::tuple((
+		nom::combinator::map_parser(not_whitespace, transform_escaped), // device
+		nom::character::complete::space1,
+		nom::combinator::map_parser(not_whitespace, transform_escaped), // mount_point
+		nom::character::complete::space1,
+		not_whitespace, // file_system_type
+		nom::character::complete::space1,
+		mount_opts, // options
+		nom::character::complete::space1,
+		nom::character::complete::char('0'),
+		nom::character::complete::space1,
+		nom::character::complete::char('0'),
+		nom::character::complete::space0,
+	)))(i) {
+			Ok((remaining_input, (
+			device,
+			_, // whitespace
+			mount_point,
+			_, // whitespace
+			file_system_type,
+			_, // whitespace
+			options,
+			_, // whitespace
+			_, // 0
+			_, // whitespace
+			_, // 0
+			_, // optional whitespace
+		))) => {
+			Ok((remaining_input, Mount { 
+				device,
+				mount_point,
+				file_system_type: file_system_type.to_string(),
+				options
+			}))
+		}
+		Err(e) => Err(e)
+	}
+}
+
+// Alternative version of `parse_line()` above that performs the same
+// function using a different style.  Rather than parsing the entire line at
+// once with one big `nom::sequence::tuple` we break the parsing up into
+// multiple separate statements.  Each statement runs a parser that returns
+// an `Ok(remaining_input, value)`.  At the end of each statement we have
+// the `?` operator, which unwraps the result and returns early with an
+// error if parsing failed.  The remaining input from each parser is used as
+// the input of each subsequent parser.  Values are assigned to temporary
+// variables that are used to construct a `Mount` object at the end of the
+// function.  Values that are not needed are discarded by assigning to `_`. 
+#[allow(unused)]
+pub(crate) fn parse_line_alternate(i: &str) -> nom::IResult<&str, Mount> {
+	let (i, device) = nom::combinator::map_parser(not_whitespace, transform_escaped)(i)?; // device
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, mount_point) = nom::combinator::map_parser(not_whitespace, transform_escaped)(i)?; // mount_point
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, file_system_type) = not_whitespace(i)?; // file_system_type
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, options) = mount_opts(i)?; // options
+	let (i, _) = nom::combinator::all_consuming(nom::sequence::tuple((
+		nom::character::complete::space1,
+		nom::character::complete::char('0'),
+		nom::character::complete::space1,
+		nom::character::complete::char('0'),
+		nom::character::complete::space0
+	)))(i)?;
+	Ok((i, Mount {
+		device,
+		mount_point,
+		file_system_type: file_system_type.to_string(),
+		options
+	}))
+}
+
+// Parse a decimal number such as a mount ID.
+fn number(i: &str) -> nom::IResult<&str, u32> {
+	nom::combinator::map_res(nom::character::complete::digit1, |digits: &str| digits.parse::<u32>())(i)
+}
+
+// Parse the optional fields of a mountinfo line, e.g. `shared:1 master:2`, up to and including the ` - ` separator.  Each optional field is followed by whitespace, and the list ends at a field consisting of a single hyphen.
+fn optional_fields(i: &str) -> nom::IResult<&str, std::vec::Vec<&str>> {
+	nom::sequence::terminated(
+		nom::multi::many0(nom::sequence::terminated(nom::combinator::verify(not_whitespace, |field: &str| field != "-"), nom::character::complete::space1)),
+		nom::sequence::pair(nom::character::complete::char('-'), nom::character::complete::space1)
+	)(i)
+}
+
+// Combine the per-mount and superblock options into one list like the one in `/proc/mounts`.  Options present in both lists are only listed once.  Like the kernel, the mount's own `rw` is shown as `ro` if the superblock is read-only, so a read-only filesystem never looks writable.
+fn merge_options(vfs_options: &[std::string::String], super_options: &[std::string::String]) -> std::vec::Vec<std::string::String> {
+	let super_read_only = super_options.iter().any(|option| option == "ro");
+	let mut options: std::vec::Vec<std::string::String> = vfs_options.iter()
+		.map(|option| if super_read_only && option == "rw" { "ro".to_string() } else { option.clone() })
+		.collect();
+	for option in super_options {
+		if option != "rw" && !options.contains(option) {
+			options.push(option.clone());
+		}
+	}
+	options
+}
+
+// Parse a line from `/proc/self/mountinfo` into a MountEntry, written in the same style as `parse_line_alternate()`.  A mountinfo line looks like this:
+// ```ignore
+// 36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw,errors=continue
+// ```
+// The fields are the mount ID, parent ID, major:minor device number, root, mount point, per-mount options, zero or more optional fields terminated by a hyphen, filesystem type, device, and superblock options.
+pub(crate) fn parse_mountinfo_line(i: &str) -> nom::IResult<&str, MountEntry> {
+	let (i, mount_id) = number(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, parent_id) = number(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, (major, _, minor)) = nom::sequence::tuple((number, nom::character::complete::char(':'), number))(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, root) = nom::combinator::map_parser(not_whitespace, transform_escaped)(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, mount_point) = nom::combinator::map_parser(not_whitespace, transform_escaped)(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, vfs_options) = mount_opts(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, optional_fields) = optional_fields(i)?;
+	let (i, file_system_type) = not_whitespace(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, device) = nom::combinator::map_parser(not_whitespace, transform_escaped)(i)?;
+	let (i, _) = nom::character::complete::space1(i)?;
+	let (i, (super_options, _)) = nom::combinator::all_consuming(nom::sequence::pair(mount_opts, nom::character::complete::space0))(i)?;
+	Ok((i, MountEntry {
+		mount: Mount {
+			device,
+			mount_point,
+			file_system_type: file_system_type.to_string(),
+			options: merge_options(&vfs_options, &super_options)
+		},
+		mountinfo: Some(MountInfo {
+			mount_id,
+			parent_id,
+			major,
+			minor,
+			root,
+			vfs_options,
+			optional_fields: optional_fields.into_iter().map(|field| field.to_string()).collect(),
+			super_options
+		})
+	}))
+}
+
+// Extract a single option from the parenthesized list of `mount(8)` output.  Like `mount_opt()`, commas inside double quotes don't end the option, but spaces don't either, since they aren't escaped in this format; the option ends at a comma or the closing parenthesis.
+fn mount_output_opt(i: &str) -> nom::IResult<&str, &str> {
+	nom::combinator::recognize(nom::multi::many1(nom::branch::alt((
+		nom::combinator::recognize(nom::sequence::delimited(nom::character::complete::char('"'), nom::bytes::complete::is_not("\""), nom::character::complete::char('"'))),
+		nom::bytes::complete::is_not(",)\""),
+		nom::bytes::complete::tag("\""),
+	))))(i)
+}
+
+// Parses the part of a line of `mount(8)` output after ` on `: `TARGET type FSTYPE (OPTIONS)`, optionally followed by a label in brackets.  The mount point is everything up to the last ` type `, since it can contain spaces.
+fn mount_output_tail(i: &str) -> nom::IResult<&str, (&str, &str, std::vec::Vec<std::string::String>)> {
+	let split = i.rfind(" type ").ok_or(nom::Err::Error((i, nom::error::ErrorKind::TakeUntil)))?;
+	let (mount_point, i) = i.split_at(split);
+	let (i, _) = nom::bytes::complete::tag(" type ")(i)?;
+	let (i, file_system_type) = not_whitespace(i)?;
+	let (i, _) = nom::bytes::complete::tag(" (")(i)?;
+	let (i, options) = nom::multi::separated_list(nom::character::complete::char(','), nom::combinator::map_parser(mount_output_opt, transform_escaped))(i)?;
+	let (i, _) = nom::combinator::all_consuming(nom::sequence::pair(
+		nom::character::complete::char(')'),
+		nom::combinator::opt(nom::sequence::delimited(nom::bytes::complete::tag(" ["), nom::bytes::complete::is_not("]"), nom::character::complete::char(']')))
+	))(i)?;
+	Ok((i, (mount_point, file_system_type, options)))
+}
+
+// Parses a line of `mount(8)` output, which is what `Display` for `Mount` produces.  Only the options are escaped, as in `/proc/mounts`.
+// ```text
+// /dev/sda1 on /mnt/my disk type ext4 (rw,relatime) [LABEL]
+// ```
+pub(crate) fn parse_mount_output_line(i: &str) -> nom::IResult<&str, Mount> {
+	let (i, device) = nom::bytes::complete::take_until(" on ")(i)?;
+	let (i, _) = nom::bytes::complete::tag(" on ")(i)?;
+	let (i, (mount_point, file_system_type, options)) = mount_output_tail(i)?;
+	Ok((i, Mount {
+		device: device.to_string(),
+		mount_point: mount_point.to_string(),
+		file_system_type: file_system_type.to_string(),
+		options
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	
+	#[test]
+	fn test_parse_mount_output_line() {
+		let (_, mount) = parse_mount_output_line("//nas/Shared Docs on /mnt/a type b type cifs (rw,vers=3.1.1)").unwrap();
+		assert_eq!((&mount.device[..], &mount.mount_point[..], &mount.file_system_type[..]), ("//nas/Shared Docs", "/mnt/a type b", "cifs"));
+		assert_eq!(mount.options, vec!["rw", "vers=3.1.1"]);
+		assert_eq!(parse_mount_output_line("none on /x type tmpfs ()").unwrap().1.options, std::vec::Vec::<std::string::String>::new());
+		assert!(parse_mount_output_line("/dev/sda1 on / type ext4").is_err());
+		assert!(parse_mount_output_line("/dev/sda1 on / type ext4 (rw) trailing").is_err());
+		assert!(parse_mount_output_line("/dev/sda1 / ext4 rw 0 0").is_err());
+	}
+
+	// Quoted commas, escaped commas, spaces, and backslashes in options survive a round trip through `Display`.
+	#[test]
+	fn test_mount_output_round_trip() {
+		let mount = crate::test_fixtures::mount("//nas/share", "/mnt/nas", "cifs", &["rw", "context=\"s0:c1,c2\"", "subvol=/a,b", "prefixpath=a b", "domain=A\\B"]);
+		assert_eq!(mount.to_string(), "//nas/share on /mnt/nas type cifs (rw,context=\"s0:c1,c2\",subvol=/a\\054b,prefixpath=a b,domain=A\\134B)");
+		assert_eq!(mount.to_string().parse::<Mount>().unwrap(), mount);
+	}
+
+	// The nom error is reachable as the source, and utab errors carry their line number.
+	#[test]
+	fn test_parse_error_source() {
+		let error = crate::utab::parse("TARGET=/mnt\nTARGET=/a =b\n").unwrap_err();
+		let error = error.downcast_ref::<ParseError>().unwrap();
+		assert_eq!(error.line_number, Some(2));
+		let source = std::error::Error::source(error).unwrap();
+		assert!(source.downcast_ref::<nom::Err<(std::string::String, nom::error::ErrorKind)>>().is_some());
+	}
+	
+	// Extracts a string that does not contain whitespace, i.e. comma or tab.
+	#[test]
+	fn test_not_whitespace() {
+		assert_eq!(not_whitespace("abcd efg"), Ok((" efg", "abcd")));
+		assert_eq!(not_whitespace("abcd\tefg"), Ok(("\tefg", "abcd")));
+		assert_eq!(not_whitespace(" abcdefg"), Err(nom::Err::Error((" abcdefg", nom::error::ErrorKind::IsNot))));
+	}
+	
+	// Converts 040 to a space.  Does not actually recognize a literal space.
+	#[test]
+	fn test_escaped_space() {
+		assert_eq!(escaped_space("040"), Ok(("", " ")));
+		assert_eq!(escaped_space(" "), Err(nom::Err::Error((" ", nom::error::ErrorKind::Tag))));
+	}
+	
+	// Converts `char` \ to `&str` \.
+	#[test]
+	fn test_escaped_backslash() {
+		assert_eq!(escaped_backslash("\\"), Ok(("", "\\")));
+		assert_eq!(escaped_backslash("not a backslash"), Err(nom::Err::Error(("not a backslash", nom::error::ErrorKind::Char))));
+	}
+	
+	// Converts the octal escapes for tab, newline, and backslash.
+	#[test]
+	fn test_escaped_octal() {
+		assert_eq!(escaped_octal("011"), Ok(("", "\t")));
+		assert_eq!(escaped_octal("012"), Ok(("", "\n")));
+		assert_eq!(escaped_octal("054"), Ok(("", ",")));
+		assert_eq!(escaped_octal("134"), Ok(("", "\\")));
+		assert_eq!(escaped_octal("040"), Err(nom::Err::Error(("040", nom::error::ErrorKind::Tag))));
+	}
+	
+	// Recognizes each escape sequence and transfoms it to the escaped literal.
+	// For example, each \040 is transformed into a space.
+	#[test]
+	fn test_transform_escaped() {
+		assert_eq!(transform_escaped("abc\\040def\\\\g\\040h"), Ok(("", std::string::String::from("abc def\\g h"))));
+		assert_eq!(transform_escaped("\\bad"), Err(nom::Err::Error(("bad", nom::error::ErrorKind::Tag))));
+	}
+	
+	// Parses a comma separated list of mount options, which might contain spaces.
+	#[test]
+	fn test_mount_opts() {
+		assert_eq!(mount_opts("a,bc,d\\040e"), Ok(("", vec!["a".to_string(), "bc".to_string(), "d e".to_string()])));
+		assert_eq!(mount_opts("context=\"u:r:t:s0:c1,c2\",seclabel"), Ok(("", vec!["context=\"u:r:t:s0:c1,c2\"".to_string(), "seclabel".to_string()])));
+		assert_eq!(mount_opts("a\"b,c"), Ok(("", vec!["a\"b".to_string(), "c".to_string()])));
+	}
+	
+	// Parses a line from /proc/mounts
+	#[test]
+	fn test_parse_line() {
+		let mount1 = Mount{
+			device: "device".to_string(),
+			mount_point: "mount_point".to_string(),
+			file_system_type: "file_system_type".to_string(),
+			options: vec!["options".to_string(), "a".to_string(), "b=c".to_string(), "d e".to_string()]
+		};
+		let (_, mount2) = parse_line("device mount_point file_system_type options,a,b=c,d\\040e 0 0").unwrap();
+		assert_eq!(mount1.device, mount2.device);
+		assert_eq!(mount1.mount_point, mount2.mount_point);
+		assert_eq!(mount1.file_system_type, mount2.file_system_type);
+		assert_eq!(mount1.options, mount2.options);
+	}
+	
+	// Parses a line from /proc/mounts
+	#[test]
+	fn test_parse_line_alternate() {
+		let mount1 = Mount{
+			device: "device".to_string(),
+			mount_point: "mount_point".to_string(),
+			file_system_type: "file_system_type".to_string(),
+			options: vec!["options".to_string(), "a".to_string(), "b=c".to_string(), "d e".to_string()]
+		};
+		let (_, mount2) = parse_line_alternate("device mount_point file_system_type options,a,b=c,d\\040e 0 0").unwrap();
+		assert_eq!(mount1.device, mount2.device);
+		assert_eq!(mount1.mount_point, mount2.mount_point);
+		assert_eq!(mount1.file_system_type, mount2.file_system_type);
+		assert_eq!(mount1.options, mount2.options);
+	}
+	
+	// Consumes optional fields up to and including the hyphen separator.
+	#[test]
+	fn test_optional_fields() {
+		assert_eq!(optional_fields("- ext4"), Ok(("ext4", vec![])));
+		assert_eq!(optional_fields("shared:1 master:2 - ext4"), Ok(("ext4", vec!["shared:1", "master:2"])));
+		assert!(optional_fields("shared:1 ext4").is_err());
+	}
+	
+	// Parses a line from /proc/self/mountinfo
+	#[test]
+	fn test_parse_mountinfo_line() {
+		let (_, mount) = parse_mountinfo_line("36 35 98:0 /mnt1 /mnt\\0402 ro,noatime master:1 - ext3 /dev/root rw,errors=continue").unwrap();
+		assert_eq!(mount.device, "/dev/root");
+		assert_eq!(mount.mount_point, "/mnt 2");
+		assert_eq!(mount.file_system_type, "ext3");
+		assert_eq!(mount.options, vec!["ro", "noatime", "errors=continue"]);
+		assert_eq!(mount.mountinfo, Some(MountInfo {
+			mount_id: 36,
+			parent_id: 35,
+			major: 98,
+			minor: 0,
+			root: "/mnt1".to_string(),
+			vfs_options: vec!["ro".to_string(), "noatime".to_string()],
+			optional_fields: vec!["master:1".to_string()],
+			super_options: vec!["rw".to_string(), "errors=continue".to_string()]
+		}));
+		// The same mount read from /proc/mounts compares equal.
+		assert_eq!(mount.mount, parse_line("/dev/root /mnt\\0402 ext3 ro,noatime,errors=continue 0 0").unwrap().1);
+		assert!(parse_mountinfo_line("36 35 98:0 /mnt1 /mnt2 rw - ext3").is_err());
+	}
+
+	// A read-only superblock makes the merged options read-only, as in /proc/mounts.
+	#[test]
+	fn test_merge_options() {
+		let options = |list: &[&str]| list.iter().map(|o| o.to_string()).collect::<std::vec::Vec<_>>();
+		assert_eq!(merge_options(&options(&["rw", "relatime"]), &options(&["ro", "errors=remount-ro"])), ["ro", "relatime", "errors=remount-ro"]);
+		assert_eq!(merge_options(&options(&["ro"]), &options(&["rw", "ro"])), ["ro"]);
+		assert_eq!(merge_options(&options(&["rw", "noatime"]), &options(&["rw", "noatime"])), ["rw", "noatime"]);
+	}
+}
\ No newline at end of file
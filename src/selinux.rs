@@ -0,0 +1,152 @@
+//! Parses the SELinux labeling options of a mount.
+//!
+//! On SELinux systems the kernel shows how each mount is labeled: `seclabel` means the filesystem supports per-file labels, while `context=`, `fscontext=`, `defcontext=`, and `rootcontext=` override the labels of the files, the filesystem itself, unlabeled files, and the root directory.  A context whose MLS level contains commas is wrapped in double quotes.
+
+use super::Mount;
+use super::options::{MountOption, OptionValueError};
+
+/// A security context of the form `user:role:type` or `user:role:type:level`, e.g. system_u:object_r:tmp_t:s0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SelinuxContext<'a> {
+	/// The SELinux user, e.g. system_u
+	pub user: &'a str,
+	/// The role, e.g. object_r
+	pub role: &'a str,
+	/// The type, e.g. tmp_t
+	pub type_: &'a str,
+	/// The MLS/MCS level, e.g. s0 or s0:c1,c2, if the policy has one.
+	pub level: std::option::Option<&'a str>,
+}
+
+impl<'a> SelinuxContext<'a> {
+	/// Parses a context, with or without surrounding double quotes.  Returns `None` if `context` doesn't have at least a user, role, and type.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::selinux::SelinuxContext;
+	/// let context = SelinuxContext::parse("\"system_u:object_r:container_file_t:s0:c1,c2\"").unwrap();
+	/// assert_eq!(context.type_, "container_file_t");
+	/// assert_eq!(context.level, Some("s0:c1,c2"));
+	/// assert_eq!(SelinuxContext::parse("unconfined_u:object_r"), None);
+	/// ```
+	pub fn parse(context: &'a str) -> std::option::Option<SelinuxContext<'a>> {
+		parsers::context(context).ok().map(|(_, context)| context)
+	}
+}
+
+impl std::fmt::Display for SelinuxContext<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}:{}:{}", self.user, self.role, self.type_)?;
+		if let Some(level) = self.level {
+			write!(f, ":{}", level)?;
+		}
+		Ok(())
+	}
+}
+
+/// The SELinux labeling of a mount, see [Mount::selinux()].
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub struct SelinuxLabels<'a> {
+	/// The label of every file, from `context=`.
+	pub context: std::option::Option<SelinuxContext<'a>>,
+	/// The label of the filesystem itself, from `fscontext=`.
+	pub fscontext: std::option::Option<SelinuxContext<'a>>,
+	/// The label of files that don't have one, from `defcontext=`.
+	pub defcontext: std::option::Option<SelinuxContext<'a>>,
+	/// The label of the root directory, from `rootcontext=`.
+	pub rootcontext: std::option::Option<SelinuxContext<'a>>,
+	/// True if the filesystem supports per-file labels (`seclabel`).
+	pub seclabel: bool,
+}
+
+impl Mount {
+	/// The SELinux labeling options of the mount.  Options that aren't set are `None`, so a mount on a system without SELinux has no labels at all.  Returns an error if a context option can't be parsed.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// let mount = nom_tutorial::parsers::parse("tmpfs /tmp tmpfs rw,seclabel,context=\"system_u:object_r:tmp_t:s0:c1,c2\" 0 0", Format::Mounts).unwrap();
+	/// let labels = mount.selinux().unwrap();
+	/// assert!(labels.seclabel);
+	/// assert_eq!(labels.context.unwrap().to_string(), "system_u:object_r:tmp_t:s0:c1,c2");
+	/// ```
+	pub fn selinux(&self) -> std::result::Result<SelinuxLabels<'_>, OptionValueError> {
+		let mut labels = SelinuxLabels::default();
+		for option in self.mount_options() {
+			let label = match option.name {
+				"context" => &mut labels.context,
+				"fscontext" => &mut labels.fscontext,
+				"defcontext" => &mut labels.defcontext,
+				"rootcontext" => &mut labels.rootcontext,
+				"seclabel" => {
+					labels.seclabel = true;
+					continue;
+				},
+				_ => continue
+			};
+			*label = Some(parse_option(&option)?);
+		}
+		Ok(labels)
+	}
+}
+
+// Parses the value of a context option.
+fn parse_option<'a>(option: &MountOption<'a>) -> std::result::Result<SelinuxContext<'a>, OptionValueError> {
+	option.value.and_then(SelinuxContext::parse).ok_or_else(|| OptionValueError::new(option, None))
+}
+
+mod parsers {
+	use super::SelinuxContext;
+
+	// Parses one of the colon-separated components before the level.
+	fn component(i: &str) -> nom::IResult<&str, &str> {
+		nom::bytes::complete::is_not(":\"")(i)
+	}
+
+	// Parses an unquoted context such as `user:role:type:level`.  The level is everything after the third colon, since it can contain colons and commas itself.
+	fn unquoted(i: &str) -> nom::IResult<&str, SelinuxContext<'_>> {
+		let (i, user) = component(i)?;
+		let (i, _) = nom::character::complete::char(':')(i)?;
+		let (i, role) = component(i)?;
+		let (i, _) = nom::character::complete::char(':')(i)?;
+		let (i, type_) = component(i)?;
+		let (i, level) = nom::combinator::opt(nom::sequence::preceded(nom::character::complete::char(':'), nom::bytes::complete::is_not("\"")))(i)?;
+		Ok((i, SelinuxContext { user, role, type_, level }))
+	}
+
+	// Parses a context that may be wrapped in double quotes.
+	pub fn context(i: &str) -> nom::IResult<&str, SelinuxContext<'_>> {
+		nom::combinator::all_consuming(nom::branch::alt((
+			nom::sequence::delimited(nom::character::complete::char('"'), unquoted, nom::character::complete::char('"')),
+			unquoted
+		)))(i)
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_context() {
+			assert_eq!(context("system_u:object_r:tmp_t"), Ok(("", SelinuxContext { user: "system_u", role: "object_r", type_: "tmp_t", level: None })));
+			assert_eq!(context("\"u:r:t:s0-s0:c0.c1023\"").unwrap().1.level, Some("s0-s0:c0.c1023"));
+			assert!(context("\"u:r:t").is_err());
+			assert!(context("u::t").is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_selinux_labels() {
+		let mount = |options: &[&str]| Mount { options: options.iter().map(|o| o.to_string()).collect(), ..Default::default() };
+		assert_eq!(mount(&["rw", "relatime"]).selinux(), Ok(SelinuxLabels::default()));
+		let nfs = mount(&["fscontext=system_u:object_r:nfs_t:s0", "defcontext=system_u:object_r:default_t:s0"]);
+		let labels = nfs.selinux().unwrap();
+		assert_eq!(labels.fscontext.unwrap().type_, "nfs_t");
+		assert_eq!(labels.defcontext.unwrap().type_, "default_t");
+		assert!(labels.context.is_none() && !labels.seclabel);
+		assert_eq!(mount(&["context=bogus"]).selinux().unwrap_err().name, "context");
+	}
+}
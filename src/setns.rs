@@ -0,0 +1,65 @@
+//! Lists the mounts of another mount namespace by entering it with `setns(2)`.  Enabled by the `unsafe-setns` feature.
+//!
+//! `/proc/<pid>/mounts` shows the mounts of a process as seen from its root directory, so mounts outside its root, e.g. after `pivot_root()` in a container, are hidden, and the file disappears with the process.  Entering the namespace itself shows every mount in it.  This is done in a scratch thread that first stops sharing its filesystem attributes with the rest of the process, because the kernel refuses to move a thread that shares them, and which exits when it is done, so the calling thread never changes namespace.  Entering a mount namespace requires `CAP_SYS_ADMIN` and `CAP_SYS_CHROOT`.
+
+use super::BoxError;
+use super::snapshot::MountSnapshot;
+
+/// Enters the mount namespace referred to by `namespace`, an open file such as `/proc/<pid>/ns/mnt`, in a scratch thread and reads its mount table.
+pub fn snapshot_in<F: std::os::unix::io::AsRawFd>(namespace: &F) -> std::result::Result<MountSnapshot, BoxError> {
+	let namespace = namespace.as_raw_fd();
+	// The mount table is opened relative to this thread's directory in the current /proc, since the target namespace may not have /proc mounted at all.
+	std::thread::scope(|scope| scope.spawn(move || -> std::result::Result<MountSnapshot, BoxError> {
+		let thread = std::fs::File::open("/proc/thread-self")?;
+		// Safe because these calls only change attributes of the current thread, which exits afterwards.
+		if unsafe { libc::unshare(libc::CLONE_FS) } != 0 || unsafe { libc::setns(namespace, libc::CLONE_NEWNS) } != 0 {
+			return Err(std::io::Error::last_os_error().into());
+		}
+		// The kernel picks the namespace of a mount table when it is opened, so it has to be opened after setns().
+		let fd = unsafe { libc::openat(std::os::unix::io::AsRawFd::as_raw_fd(&thread), b"mounts\0".as_ptr() as *const libc::c_char, libc::O_RDONLY | libc::O_CLOEXEC) };
+		if fd < 0 {
+			return Err(std::io::Error::last_os_error().into());
+		}
+		// Safe because `fd` was just opened and nothing else owns it.
+		let mut file = unsafe { <std::fs::File as std::os::unix::io::FromRawFd>::from_raw_fd(fd) };
+		let mut text = std::string::String::new();
+		std::io::Read::read_to_string(&mut file, &mut text)?;
+		Ok(MountSnapshot::parse(&text, crate::model::Format::Mounts)?)
+	}).join().map_err(|_| BoxError::from("thread listing the mount namespace panicked"))?)
+}
+
+/// Like [snapshot_in()], but enters the mount namespace of the process with the given PID.
+/// # Examples
+/// ```no_run
+/// let snapshot = nom_tutorial::setns::snapshot_in_pid(1).unwrap();
+/// for mount in snapshot.iter() {
+///     println!("{}", mount);
+/// }
+/// ```
+pub fn snapshot_in_pid(pid: u32) -> std::result::Result<MountSnapshot, BoxError> {
+	snapshot_in(&std::fs::File::open(format!("/proc/{}/ns/mnt", pid))?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A process that doesn't exist has no namespace to enter.
+	#[test]
+	fn test_snapshot_in_missing_pid() {
+		assert!(snapshot_in_pid(u32::MAX).is_err());
+	}
+
+	// Entering our own namespace lists the same mounts, if we are privileged enough to do so.
+	#[test]
+	#[ignore = "compares against the host's mount table, which other processes may change between the two reads"]
+	fn test_snapshot_in_own_namespace() {
+		let snapshot = match snapshot_in_pid(std::process::id()) {
+			Ok(snapshot) => snapshot,
+			Err(e) if e.downcast_ref::<std::io::Error>().map(|e| e.kind()) == Some(std::io::ErrorKind::PermissionDenied) => return,
+			Err(e) => panic!("{}", e)
+		};
+		let own = crate::snapshot().unwrap();
+		assert_eq!(snapshot.iter().map(|m| &m.mount_point).collect::<std::vec::Vec<_>>(), own.iter().map(|m| &m.mount_point).collect::<std::vec::Vec<_>>());
+	}
+}
@@ -0,0 +1,132 @@
+//! Recognizes the squashfs images that snapd mounts for every installed snap package.
+//!
+//! snapd loop-mounts each revision of each snap read-only at `/snap/<name>/<revision>`, or at `/var/lib/snapd/snap/<name>/<revision>` on distributions that keep `/` clean, from a file named `/var/lib/snapd/snaps/<name>_<revision>.snap`.  A desktop with a few snaps easily has dozens of these mounts, which drown out the real filesystems in any listing.  [group_snaps()] sets them apart so that they can be shown as one collapsed category, and [Snap::backing_file()] finds the file each one is mounted from, which is what actually takes up disk space.
+
+use super::Mount;
+
+/// The directories snapd mounts snaps under.
+pub const SNAP_MOUNT_DIRS: &[&str] = &["/snap", "/var/lib/snapd/snap"];
+
+/// The directory snapd keeps the `.snap` files in.
+pub const SNAPS_DIR: &str = "/var/lib/snapd/snaps";
+
+/// A revision of a snap package mounted by snapd, see [Mount::snap()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Snap<'a> {
+	/// The name of the snap, e.g. core22
+	pub name: &'a str,
+	/// The revision, e.g. 1122, or e.g. x1 for a snap installed from a local file.
+	pub revision: &'a str,
+	/// The mount of the revision.
+	pub mount: &'a Mount,
+}
+
+impl Snap<'_> {
+	/// The file snapd mounts the revision from by convention, e.g. /var/lib/snapd/snaps/core22_1122.snap
+	pub fn expected_backing_file(&self) -> std::path::PathBuf {
+		std::path::Path::new(SNAPS_DIR).join(format!("{}_{}.snap", self.name, self.revision))
+	}
+
+	/// The file the loop device of the mount is backed by, as recorded in sysfs, or [Snap::expected_backing_file()] if the device isn't a loop device that sysfs knows about.
+	#[cfg(feature = "io")]
+	pub fn backing_file(&self) -> std::result::Result<std::path::PathBuf, std::io::Error> {
+		self.backing_file_from(std::path::Path::new(crate::sysfs::SYSFS_PATH))
+	}
+
+	/// Like [Snap::backing_file()], but with sysfs mounted at `sysfs` instead of `/sys`, e.g. in a container or a saved copy.
+	#[cfg(feature = "io")]
+	pub fn backing_file_from(&self, sysfs: &std::path::Path) -> std::result::Result<std::path::PathBuf, std::io::Error> {
+		let name = match self.mount.device.strip_prefix("/dev/").filter(|name| name.starts_with("loop")) {
+			Some(name) => name,
+			None => return Ok(self.expected_backing_file())
+		};
+		match std::fs::read_to_string(sysfs.join("block").join(name).join("loop/backing_file")) {
+			Ok(path) => Ok(std::path::PathBuf::from(path.trim_end_matches('\n'))),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(self.expected_backing_file()),
+			Err(e) => Err(e)
+		}
+	}
+}
+
+impl Mount {
+	/// The snap package this is a mount of: a squashfs mount whose mount point is `<name>/<revision>` directly inside one of the [SNAP_MOUNT_DIRS].  Returns `None` for every other mount.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount { device: "/dev/loop3".to_string(), mount_point: "/snap/core22/1122".to_string(), file_system_type: "squashfs".to_string(), ..Default::default() };
+	/// let snap = mount.snap().unwrap();
+	/// assert_eq!((snap.name, snap.revision), ("core22", "1122"));
+	/// assert_eq!(snap.expected_backing_file(), std::path::Path::new("/var/lib/snapd/snaps/core22_1122.snap"));
+	/// ```
+	pub fn snap(&self) -> std::option::Option<Snap<'_>> {
+		if self.file_system_type != "squashfs" {
+			return None;
+		}
+		let relative = SNAP_MOUNT_DIRS.iter().find_map(|dir| self.mount_point.strip_prefix(dir)?.strip_prefix('/'))?;
+		let (name, revision) = relative.split_once('/')?;
+		if name.is_empty() || revision.is_empty() || revision.contains('/') {
+			return None;
+		}
+		Some(Snap { name, revision, mount: self })
+	}
+}
+
+/// Separates the snap mounts among `mounts` from the others.  The snaps are sorted by name and then by revision, with numeric revisions in numeric order; the other mounts keep their order.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// let mount = |device: &str, mount_point: &str, file_system_type: &str| Mount { device: device.to_string(), mount_point: mount_point.to_string(), file_system_type: file_system_type.to_string(), ..Default::default() };
+/// let mounts = [mount("/dev/sda1", "/", "ext4"), mount("/dev/loop4", "/snap/firefox/4173", "squashfs"), mount("/dev/loop1", "/snap/core22/1122", "squashfs")];
+/// let (snaps, others) = nom_tutorial::snap::group_snaps(&mounts);
+/// assert_eq!(snaps.iter().map(|snap| snap.name).collect::<Vec<_>>(), ["core22", "firefox"]);
+/// assert_eq!(others.len(), 1);
+/// ```
+pub fn group_snaps<'a, I: IntoIterator<Item = &'a Mount>>(mounts: I) -> (std::vec::Vec<Snap<'a>>, std::vec::Vec<&'a Mount>) {
+	let mut snaps = std::vec::Vec::new();
+	let mut others = std::vec::Vec::new();
+	for mount in mounts {
+		match mount.snap() {
+			Some(snap) => snaps.push(snap),
+			None => others.push(mount)
+		}
+	}
+	snaps.sort_by_key(|snap| (snap.name, snap.revision.parse::<u64>().ok(), snap.revision));
+	(snaps, others)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures::mount;
+
+	// Only squashfs mounts exactly two levels below a snap directory count, and revisions sort numerically.
+	#[test]
+	fn test_group_snaps() {
+		let mounts = [
+			mount("/dev/loop2", "/snap/core22/864", "squashfs", &[]),
+			mount("tmpfs", "/snap/core22", "tmpfs", &[]),
+			mount("/dev/loop5", "/var/lib/snapd/snap/core22/1122", "squashfs", &[]),
+			mount("/dev/loop6", "/snap/core22/1122/extra", "squashfs", &[]),
+			mount("/dev/loop7", "/snapshots/a/1", "squashfs", &[]),
+			mount("/dev/loop8", "/snap/hello/x1", "squashfs", &[]),
+		];
+		let (snaps, others) = group_snaps(&mounts);
+		assert_eq!(snaps.iter().map(|snap| (snap.name, snap.revision)).collect::<std::vec::Vec<_>>(), [("core22", "864"), ("core22", "1122"), ("hello", "x1")]);
+		assert_eq!(others.iter().map(|mount| &mount.mount_point[..]).collect::<std::vec::Vec<_>>(), ["/snap/core22", "/snap/core22/1122/extra", "/snapshots/a/1"]);
+	}
+
+	// sysfs wins over the naming convention, which is the fallback for devices sysfs doesn't know.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_backing_file_from() {
+		let sysfs = std::env::temp_dir().join(format!("nom-tutorial-snap-{}", std::process::id()));
+		std::fs::create_dir_all(sysfs.join("block/loop3/loop")).unwrap();
+		std::fs::write(sysfs.join("block/loop3/loop/backing_file"), "/var/lib/snapd/snaps/core22_1122.snap.partial\n").unwrap();
+		let known = mount("/dev/loop3", "/snap/core22/1122", "squashfs", &[]);
+		let unknown = mount("/dev/loop9", "/snap/core22/1122", "squashfs", &[]);
+		let (known, unknown) = (known.snap().unwrap().backing_file_from(&sysfs), unknown.snap().unwrap().backing_file_from(&sysfs));
+		std::fs::remove_dir_all(&sysfs).unwrap();
+		assert_eq!(known.unwrap(), std::path::Path::new("/var/lib/snapd/snaps/core22_1122.snap.partial"));
+		assert_eq!(unknown.unwrap(), std::path::Path::new("/var/lib/snapd/snaps/core22_1122.snap"));
+	}
+}
@@ -0,0 +1,836 @@
+//! An owned, in-memory copy of a mount table.
+
+#[cfg(feature = "io")]
+use super::BoxError;
+use super::{Mount, MountEntry};
+
+/// An owned copy of a mount table that can be iterated over as many times as needed and queried in various ways.
+///
+/// Unlike [Mounts](crate::Mounts), which reads from a file and can only be consumed once, a snapshot owns all of its data and remembers when it was taken.  With the `serde` feature it can be serialized, e.g. to send it to another machine or to compare it with a later snapshot.  It is `Send + Sync + Clone`, so it can be shared between threads or cached in an [Arc](std::sync::Arc).  Take one with [snapshot()](crate::snapshot()) or [MountSnapshot::from_source()].
+/// # Examples
+/// ```
+/// # use nom_tutorial::snapshot::MountSnapshot;
+/// # use nom_tutorial::model::Format;
+/// let snapshot = MountSnapshot::parse("/dev/sda1 / ext4 rw 0 0\ntmpfs /run tmpfs rw 0 0\ntmpfs /tmp tmpfs rw 0 0\n", Format::Mounts).unwrap();
+/// for (fs_type, mounts) in snapshot.group_by_fs_type() {
+///     println!("{}: {} mounts", fs_type, mounts.len());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MountSnapshot {
+	taken_at: std::time::SystemTime,
+	mounts: std::vec::Vec<MountEntry>,
+	#[cfg_attr(feature = "serde", serde(default))]
+	source: std::option::Option<SnapshotSource>,
+}
+
+/// Where the mount table of a snapshot was read from, see [MountSnapshot::source()].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnapshotSource {
+	/// The file at this path, parsed by this crate.
+	File(std::path::PathBuf),
+	/// The file at this path, read with the C library's `getmntent_r(3)`, see [getmntent](crate::getmntent).
+	Getmntent(std::path::PathBuf),
+}
+
+impl std::fmt::Display for SnapshotSource {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			SnapshotSource::File(path) => write!(f, "{}", path.display()),
+			SnapshotSource::Getmntent(path) => write!(f, "{} (via getmntent)", path.display()),
+		}
+	}
+}
+
+impl PartialEq for MountSnapshot {
+	/// Two snapshots are equal if they hold the same mounts in the same order, whenever and wherever they were taken.
+	fn eq(&self, other: &MountSnapshot) -> bool {
+		self.mounts == other.mounts
+	}
+}
+
+impl Eq for MountSnapshot {}
+
+impl MountSnapshot {
+	/// Creates a snapshot holding `mounts`, in order, taken now.  On `wasm32-unknown-unknown`, which has no clock, the snapshot is stamped with the Unix epoch instead; use [MountSnapshot::with_time()] to record the real time there.
+	pub fn new(mounts: std::vec::Vec<Mount>) -> MountSnapshot {
+		MountSnapshot::with_time(mounts, now())
+	}
+
+	/// Creates a snapshot holding `mounts`, in order, that was taken at `taken_at`.
+	pub fn with_time(mounts: std::vec::Vec<Mount>, taken_at: std::time::SystemTime) -> MountSnapshot {
+		MountSnapshot::with_entries(mounts.into_iter().map(MountEntry::from).collect(), taken_at)
+	}
+
+	/// Like [MountSnapshot::with_time()], but keeps the [MountInfo](crate::MountInfo) of entries read from mountinfo.
+	pub fn with_entries(mounts: std::vec::Vec<MountEntry>, taken_at: std::time::SystemTime) -> MountSnapshot {
+		MountSnapshot { taken_at, mounts, source: None }
+	}
+
+	/// Parses a whole mount table held in memory, e.g. the contents of `/proc/mounts` uploaded from another machine, into a snapshot taken now.  Unlike [MountSnapshot::from_source()] this doesn't need the `io` feature.  Parsing errors have the line number set.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// let snapshot = MountSnapshot::parse("/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n", Format::Mounts).unwrap();
+	/// assert_eq!(snapshot.len(), 2);
+	/// assert_eq!(MountSnapshot::parse("/dev/sda1 / ext4 rw 0 0\nbad\n", Format::Mounts).unwrap_err().line_number, Some(2));
+	/// ```
+	pub fn parse(text: &str, format: crate::model::Format) -> std::result::Result<MountSnapshot, crate::ParseError> {
+		Ok(MountSnapshot::with_entries(parse_lines(text.lines(), format, 1)?, now()))
+	}
+
+	/// Reads every mount from `source` into a snapshot, stopping at the first error.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// # use nom_tutorial::source::StringSource;
+	/// let source = StringSource::new("/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n");
+	/// let snapshot = MountSnapshot::from_source(&source).unwrap();
+	/// assert_eq!(snapshot.len(), 2);
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn from_source<S: crate::source::MountSource>(source: &S) -> std::result::Result<MountSnapshot, BoxError> {
+		let taken_at = std::time::SystemTime::now();
+		let mounts = crate::Mounts::from_source(source)?.entries().collect::<std::result::Result<_, _>>()?;
+		Ok(MountSnapshot::with_entries(mounts, taken_at))
+	}
+
+	/// Reads `source` repeatedly until two consecutive reads agree, which guarantees the snapshot wasn't taken while the table was changing.  Gives up with an error if the table is still changing after `max_retries` re-reads.  With `max_retries` of 0 the source is read only once and the snapshot is returned unchecked, as by [MountSnapshot::from_source()].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// # use nom_tutorial::source::StringSource;
+	/// let source = StringSource::new("/dev/sda1 / ext4 rw 0 0\n");
+	/// assert_eq!(MountSnapshot::from_source_consistent(&source, 0).unwrap().len(), 1);
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn from_source_consistent<S: crate::source::MountSource>(source: &S, max_retries: usize) -> std::result::Result<MountSnapshot, BoxError> {
+		let mut previous = MountSnapshot::from_source(source)?;
+		if max_retries == 0 {
+			return Ok(previous);
+		}
+		for _ in 0..max_retries {
+			let current = MountSnapshot::from_source(source)?;
+			if current.mounts == previous.mounts {
+				return Ok(current);
+			}
+			previous = current;
+		}
+		Err(format!("mount table was still changing after {} retries", max_retries).into())
+	}
+
+	/// Memory-maps the regular file at `path`, e.g. a saved capture or a large generated table, and parses it in `format` from the mapping.  This saves reading the file through a buffer, but the parsed mounts still own copies of their fields, so the snapshot takes as much memory as one from [MountSnapshot::from_source()].  Parsing errors are [ParseError](crate::ParseError)s with the line number set.  Needs the `mmap` feature.
+	///
+	/// Files that report a size of zero, such as `/proc/mounts` and the other files in procfs, can't be mapped and are read into memory instead.
+	/// # Safety
+	/// The file must not be truncated or modified, by this or any other process, until this function returns.  Truncating it makes reading the mapping raise `SIGBUS`, and modifying it can change text that has already been checked to be UTF-8, see [memmap2::Mmap::map()].  Use [MountSnapshot::from_source()] for files that may change, such as the ones in `/etc`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// let path = std::env::temp_dir().join(format!("nom-tutorial-doctest-mmap-{}", std::process::id()));
+	/// std::fs::write(&path, "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n").unwrap();
+	/// // Safety: nothing else knows about the file.
+	/// let snapshot = unsafe { MountSnapshot::from_mmap(&path, Format::Mounts) };
+	/// assert_eq!(snapshot.unwrap().len(), 2);
+	/// # std::fs::remove_file(&path).unwrap();
+	/// ```
+	#[cfg(feature = "mmap")]
+	pub unsafe fn from_mmap<P: AsRef<std::path::Path>>(path: P, format: crate::model::Format) -> std::result::Result<MountSnapshot, BoxError> {
+		let taken_at = now();
+		let mut file = std::fs::File::open(path)?;
+		let metadata = file.metadata()?;
+		if !metadata.is_file() {
+			return Err("only regular files can be memory-mapped".into());
+		}
+		// Mapping an empty file fails on some platforms, and procfs files are empty until read.
+		if metadata.len() == 0 {
+			let mut text = std::string::String::new();
+			std::io::Read::read_to_string(&mut file, &mut text)?;
+			return Ok(MountSnapshot::with_entries(parse_lines(text.lines(), format, 1)?, taken_at));
+		}
+		// Safety: the mapping is only read while `map` is alive, and the caller promises not to modify the file in the meantime.
+		let map = memmap2::Mmap::map(&file)?;
+		let text = std::str::from_utf8(&map)?;
+		Ok(MountSnapshot::with_entries(parse_lines(text.lines(), format, 1)?, taken_at))
+	}
+
+	/// Writes the snapshot to `path` so it can be read back with [MountSnapshot::load()], e.g. by a monitoring agent that wants to remember the last mount table it saw across restarts.
+	///
+	/// The file starts with a header line recording the format version and [MountSnapshot::taken_at()], followed by one escaped line per mount.  If every mount has [MountInfo](crate::MountInfo), and its options are the ones that would be merged from the per-mount and superblock options when the line is read back, the lines are in the format of `/proc/self/mountinfo`.  Otherwise they are in the format of `/proc/mounts`, so that options changed after parsing aren't lost.  The file is written to a temporary file with a unique name next to `path`, flushed to disk, and then renamed over it, so a crash never leaves a half-written snapshot behind and concurrent saves don't clobber each other's temporary files.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// # use nom_tutorial::source::StringSource;
+	/// let snapshot = MountSnapshot::from_source(&StringSource::new("/dev/sda1 /mnt/my\\040disk ext4 rw 0 0\n")).unwrap();
+	/// let path = std::env::temp_dir().join(format!("nom-tutorial-doctest-save-{}", std::process::id()));
+	/// snapshot.save(&path).unwrap();
+	/// assert_eq!(MountSnapshot::load(&path).unwrap(), snapshot);
+	/// # std::fs::remove_file(&path).unwrap();
+	/// ```
+	#[cfg(feature = "io")]
+	pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::result::Result<(), BoxError> {
+		let path = path.as_ref();
+		let since_epoch = self.taken_at.duration_since(std::time::UNIX_EPOCH)?;
+		// Lines in the format of mountinfo, if every mount reads back from one unchanged.
+		let mountinfo_lines: std::option::Option<std::vec::Vec<std::string::String>> = self.mounts.iter()
+			.map(|entry| entry.to_mountinfo_line().filter(|line| crate::parsers::parse_entry(line, crate::model::Format::MountInfo).ok().as_ref() == Some(entry)))
+			.collect();
+		let mountinfo_lines = mountinfo_lines.filter(|lines| !lines.is_empty());
+		let mut contents = format!("{} format={} taken_at={}.{:09}\n", parsers::MAGIC, if mountinfo_lines.is_some() { "mountinfo" } else { "mounts" }, since_epoch.as_secs(), since_epoch.subsec_nanos());
+		let lines = mountinfo_lines.unwrap_or_else(|| self.mounts.iter().map(|entry| entry.to_proc_line()).collect());
+		for line in lines {
+			contents.push_str(&line);
+			contents.push('\n');
+		}
+		let directory = match path.parent() {
+			Some(parent) if !parent.as_os_str().is_empty() => parent,
+			_ => std::path::Path::new(".")
+		};
+		static SAVES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+		let mut temp_name = std::ffi::OsString::from(".");
+		temp_name.push(path.file_name().unwrap_or_else(|| std::ffi::OsStr::new("snapshot")));
+		temp_name.push(format!(".tmp.{}.{}", std::process::id(), SAVES.fetch_add(1, std::sync::atomic::Ordering::Relaxed)));
+		let temp_path = directory.join(temp_name);
+		let result = (|| {
+			let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&temp_path)?;
+			std::io::Write::write_all(&mut file, contents.as_bytes())?;
+			file.sync_all()?;
+			std::fs::rename(&temp_path, path)?;
+			// Make the rename itself durable.
+			std::fs::File::open(directory)?.sync_all()
+		})();
+		if result.is_err() {
+			let _ = std::fs::remove_file(&temp_path);
+		}
+		Ok(result?)
+	}
+
+	/// Reads a snapshot written by [MountSnapshot::save()], including the time it was originally taken.  Parsing errors are [ParseError](crate::ParseError)s with the line number set.
+	#[cfg(feature = "io")]
+	pub fn load<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<MountSnapshot, BoxError> {
+		let contents = std::fs::read_to_string(path)?;
+		let mut lines = contents.lines();
+		let header = lines.next().unwrap_or("");
+		let (format, since_epoch) = match parsers::header(header) {
+			Ok((_, header)) => header,
+			Err(e) => return Err(crate::ParseError::new(header, e).with_line_number(1).into())
+		};
+		let mounts = parse_lines(lines, format, 2)?;
+		Ok(MountSnapshot::with_entries(mounts, std::time::UNIX_EPOCH + since_epoch))
+	}
+
+	/// Writes the snapshot to `w` in [bincode](https://docs.rs/bincode), a compact binary encoding for storing many snapshots, e.g. the history of a monitoring agent that takes one every few seconds.  Unlike [MountSnapshot::save()] it keeps every field, including [MountSnapshot::source()].  Snapshots written one after another can be read back one at a time with [MountSnapshot::read_bincode()].  Enabled by the `bincode` feature.
+	///
+	/// The encoding follows the layout of the structs, so it isn't meant to be read by other versions of this crate.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// # use nom_tutorial::model::Format;
+	/// let snapshot = MountSnapshot::parse("/dev/sda1 / ext4 rw 0 0\n", Format::Mounts).unwrap();
+	/// let mut history = Vec::new();
+	/// snapshot.write_bincode(&mut history).unwrap();
+	/// snapshot.write_bincode(&mut history).unwrap();
+	/// let mut reader = &history[..];
+	/// while !reader.is_empty() {
+	///     assert_eq!(MountSnapshot::read_bincode(&mut reader).unwrap(), snapshot);
+	/// }
+	/// ```
+	#[cfg(feature = "bincode")]
+	pub fn write_bincode<W: std::io::Write>(&self, w: W) -> std::result::Result<(), crate::BoxError> {
+		Ok(bincode::Options::serialize_into(bincode_options(), w, self)?)
+	}
+
+	/// Reads a snapshot written by [MountSnapshot::write_bincode()], leaving `r` at the start of the next one.  A snapshot larger than 64 MiB is rejected as corrupt rather than allocated.  Enabled by the `bincode` feature.
+	#[cfg(feature = "bincode")]
+	pub fn read_bincode<R: std::io::Read>(r: R) -> std::result::Result<MountSnapshot, crate::BoxError> {
+		Ok(bincode::Options::deserialize_from(bincode_options(), r)?)
+	}
+
+	/// When the snapshot was taken.  For a snapshot read from a [MountSource](crate::source::MountSource) this is when reading started.
+	pub fn taken_at(&self) -> std::time::SystemTime {
+		self.taken_at
+	}
+
+	/// Records where the mount table was read from.
+	pub fn with_source(mut self, source: SnapshotSource) -> MountSnapshot {
+		self.source = Some(source);
+		self
+	}
+
+	/// Where the mount table was read from, if known.  Set by [snapshot_with_fallback()](crate::snapshot_with_fallback) to the source it settled on.
+	pub fn source(&self) -> std::option::Option<&SnapshotSource> {
+		self.source.as_ref()
+	}
+
+	/// The mounts in the snapshot, in the order they appear in the mount table.
+	pub fn mounts(&self) -> &[MountEntry] {
+		&self.mounts
+	}
+
+	/// Iterates over the mounts in mount table order.  Unlike [Mounts](crate::Mounts), a snapshot can be iterated over any number of times.
+	pub fn iter(&self) -> std::slice::Iter<'_, MountEntry> {
+		self.mounts.iter()
+	}
+
+	/// Number of mounts in the snapshot.
+	pub fn len(&self) -> usize {
+		self.mounts.len()
+	}
+
+	/// True if the snapshot holds no mounts.
+	pub fn is_empty(&self) -> bool {
+		self.mounts.is_empty()
+	}
+
+	/// Consumes the snapshot, returning the mounts.
+	pub fn into_mounts(self) -> std::vec::Vec<Mount> {
+		self.mounts.into_iter().map(Mount::from).collect()
+	}
+
+	/// Consumes the snapshot, returning the mounts along with their [MountInfo](crate::MountInfo).
+	pub fn into_entries(self) -> std::vec::Vec<MountEntry> {
+		self.mounts
+	}
+
+	/// Groups mounts by device, sorted by device.  Within each group mounts stay in mount table order, so e.g. all bind mounts of `/dev/sda1` are listed together.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// let mount = |device: &str, mount_point: &str| Mount { device: device.to_string(), mount_point: mount_point.to_string(), ..Default::default() };
+	/// let snapshot = MountSnapshot::new(vec![mount("/dev/sda1", "/"), mount("/dev/sdb1", "/data"), mount("/dev/sda1", "/srv")]);
+	/// let groups = snapshot.group_by_device();
+	/// let mount_points: Vec<&str> = groups["/dev/sda1"].iter().map(|m| &m.mount_point[..]).collect();
+	/// assert_eq!(mount_points, ["/", "/srv"]);
+	/// ```
+	pub fn group_by_device(&self) -> std::collections::BTreeMap<&str, std::vec::Vec<&MountEntry>> {
+		self.group_by(|mount| &mount.device)
+	}
+
+	/// Groups mounts by filesystem type, sorted by type.  Within each group mounts stay in mount table order.
+	pub fn group_by_fs_type(&self) -> std::collections::BTreeMap<&str, std::vec::Vec<&MountEntry>> {
+		self.group_by(|mount| &mount.file_system_type)
+	}
+
+	/// Summarizes the snapshot: how many mounts there are of each filesystem type and how many are read-only.  With the `statvfs` feature, also adds up the capacity of each filesystem type.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// let mount = |fs_type: &str, option: &str| Mount { file_system_type: fs_type.to_string(), options: vec![option.to_string()], ..Default::default() };
+	/// let snapshot = MountSnapshot::new(vec![mount("ext4", "rw"), mount("squashfs", "ro"), mount("squashfs", "ro")]);
+	/// let summary = snapshot.summary();
+	/// assert_eq!((summary.read_only, summary.read_write), (2, 1));
+	/// assert_eq!(summary.by_fs_type["squashfs"].count, 2);
+	/// ```
+	pub fn summary(&self) -> Summary {
+		let mut summary = Summary::default();
+		for mount in &self.mounts {
+			let read_only = mount.options.iter().any(|option| option == crate::options::names::RO);
+			let fs_type = summary.by_fs_type.entry(mount.file_system_type.clone()).or_default();
+			fs_type.count += 1;
+			if read_only {
+				fs_type.read_only += 1;
+				summary.read_only += 1;
+			} else {
+				fs_type.read_write += 1;
+				summary.read_write += 1;
+			}
+			#[cfg(feature = "statvfs")]
+			{
+				if let Ok(usage) = mount.usage() {
+					fs_type.capacity.total_bytes += usage.total_bytes;
+					fs_type.capacity.free_bytes += usage.free_bytes;
+					fs_type.capacity.available_bytes += usage.available_bytes;
+				}
+			}
+		}
+		summary.total = self.mounts.len();
+		summary
+	}
+
+	// Groups mounts by the key returned by `key`.
+	fn group_by<'a, F: Fn(&'a MountEntry) -> &'a str>(&'a self, key: F) -> std::collections::BTreeMap<&'a str, std::vec::Vec<&'a MountEntry>> {
+		let mut groups = std::collections::BTreeMap::<&str, std::vec::Vec<&MountEntry>>::new();
+		for mount in &self.mounts {
+			groups.entry(key(mount)).or_default().push(mount);
+		}
+		groups
+	}
+}
+
+/// Statistics about a [MountSnapshot], see [MountSnapshot::summary()].
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Summary {
+	/// Total number of mounts.
+	pub total: usize,
+	/// Number of mounts with the `ro` option.
+	pub read_only: usize,
+	/// Number of mounts without the `ro` option.
+	pub read_write: usize,
+	/// Statistics for each filesystem type, sorted by type.
+	pub by_fs_type: std::collections::BTreeMap<std::string::String, FsTypeSummary>,
+}
+
+/// Statistics about all the mounts of one filesystem type, see [Summary].
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct FsTypeSummary {
+	/// Number of mounts of this type.
+	pub count: usize,
+	/// Number of mounts of this type with the `ro` option.
+	pub read_only: usize,
+	/// Number of mounts of this type without the `ro` option.
+	pub read_write: usize,
+	/// Combined capacity of every mount of this type whose usage could be read.  A filesystem that is mounted more than once, e.g. with bind mounts, is counted each time.
+	#[cfg(feature = "statvfs")]
+	pub capacity: crate::usage::Usage,
+}
+
+// True if `a` has to be unmounted before `b`.
+fn unmount_before(a: (usize, &MountEntry), b: (usize, &MountEntry)) -> bool {
+	let ((a_index, a), (b_index, b)) = (a, b);
+	match (&a.mountinfo, &b.mountinfo) {
+		(Some(a_info), Some(b_info)) => {
+			// Children before parents, except for the root of the tree which is its own parent.
+			let child = a_info.parent_id == b_info.mount_id && a_info.mount_id != b_info.mount_id;
+			// A bind mount exposes a subdirectory of a filesystem that is also mounted whole elsewhere.
+			let bind = a_info.root != "/" && b_info.root == "/" && (a_info.major, a_info.minor) == (b_info.major, b_info.minor);
+			child || bind
+		},
+		// Without mountinfo, nesting has to be guessed from the mount points.  Mounts later in the table are on top of earlier ones at the same mount point.
+		_ => {
+			let (a_path, b_path) = (std::path::Path::new(&a.mount_point), std::path::Path::new(&b.mount_point));
+			(a_path != b_path && a_path.starts_with(b_path)) || (a_path == b_path && a_index > b_index)
+		}
+	}
+}
+
+/// Orders the mounts of `snapshot` so that they can be unmounted one by one without any `umount` failing with `EBUSY` because of another mount: every mount comes before the mount it is mounted on, and bind mounts come before the whole filesystem they expose.
+///
+/// Mounts read from `/proc/self/mountinfo` are ordered exactly using their parent IDs.  Otherwise nesting is inferred from mount points, which can't detect bind mounts.  Mounts that don't depend on each other are unmounted in reverse mount table order.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::snapshot::{unmount_order, MountSnapshot};
+/// let mount = |mount_point: &str| Mount { mount_point: mount_point.to_string(), ..Default::default() };
+/// let snapshot = MountSnapshot::new(vec![mount("/mnt/a/b"), mount("/mnt"), mount("/mnt/a")]);
+/// let order: Vec<&str> = unmount_order(&snapshot).iter().map(|m| &m.mount_point[..]).collect();
+/// assert_eq!(order, ["/mnt/a/b", "/mnt/a", "/mnt"]);
+/// ```
+pub fn unmount_order(snapshot: &MountSnapshot) -> std::vec::Vec<&MountEntry> {
+	let mounts = snapshot.mounts();
+	// blockers[i] counts the mounts that have to go before mount i.
+	let mut blockers = vec![0usize; mounts.len()];
+	let mut blocks = vec![std::vec::Vec::new(); mounts.len()];
+	for a in 0..mounts.len() {
+		for b in 0..mounts.len() {
+			if a != b && unmount_before((a, &mounts[a]), (b, &mounts[b])) {
+				blockers[b] += 1;
+				blocks[a].push(b);
+			}
+		}
+	}
+	let mut order = std::vec::Vec::with_capacity(mounts.len());
+	let mut done = vec![false; mounts.len()];
+	while order.len() < mounts.len() {
+		// Take the last ready mount in table order.  If nothing is ready the table has a cycle, which a real mount table can't, so break it by taking the last remaining mount.
+		let next = (0..mounts.len()).rev().find(|&i| !done[i] && blockers[i] == 0)
+			.or_else(|| (0..mounts.len()).rev().find(|&i| !done[i]))
+			.unwrap();
+		done[next] = true;
+		for &blocked in &blocks[next] {
+			blockers[blocked] = blockers[blocked].saturating_sub(1);
+		}
+		order.push(&mounts[next]);
+	}
+	order
+}
+
+/// Identifies the filesystem (superblock) behind a mount, see [MountEntry::device_id()].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DeviceId {
+	/// The device number from [MountInfo](crate::MountInfo), which is the same for every mount of a filesystem, including bind mounts and btrfs subvolumes.
+	Number(u32, u32),
+	/// The device as written in the mount table, for mounts without [MountInfo](crate::MountInfo).
+	Name(std::string::String),
+}
+
+impl std::fmt::Display for DeviceId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			DeviceId::Number(major, minor) => write!(f, "{}:{}", major, minor),
+			DeviceId::Name(name) => write!(f, "{}", name),
+		}
+	}
+}
+
+impl MountEntry {
+	/// Identifies the filesystem behind the mount: its device number if the mount was read from mountinfo, and otherwise its device name.  Unlike [MountEntry::device_number()](crate::MountEntry::device_number) this never touches the filesystem, so it works on snapshots taken on other machines.
+	pub fn device_id(&self) -> DeviceId {
+		match &self.mountinfo {
+			Some(info) => DeviceId::Number(info.major, info.minor),
+			None => DeviceId::Name(self.device.clone())
+		}
+	}
+}
+
+/// Counts how many times each filesystem in `snapshot` is mounted, bind mounts included, so that unmount tooling can tell when it is about to remove the last mount of a device.  Filesystems are identified by [MountEntry::device_id()], so use a snapshot read from mountinfo to tell bind mounts apart from unrelated filesystems that happen to have the same device name, such as `tmpfs`.
+/// # Examples
+/// ```
+/// # use nom_tutorial::snapshot::{device_mount_counts, DeviceId, MountSnapshot};
+/// # use nom_tutorial::model::Format;
+/// let snapshot = MountSnapshot::parse("\
+///     22 1 8:1 / / rw - ext4 /dev/sda1 rw\n\
+///     23 22 8:1 /srv /export rw - ext4 /dev/sda1 rw\n\
+///     24 22 0:40 / /tmp rw - tmpfs tmpfs rw\n", Format::MountInfo).unwrap();
+/// let counts = device_mount_counts(&snapshot);
+/// assert_eq!(counts[&DeviceId::Number(8, 1)], 2);
+/// assert_eq!(counts[&DeviceId::Number(0, 40)], 1);
+/// ```
+pub fn device_mount_counts(snapshot: &MountSnapshot) -> std::collections::BTreeMap<DeviceId, usize> {
+	let mut counts = std::collections::BTreeMap::new();
+	for mount in snapshot {
+		*counts.entry(mount.device_id()).or_insert(0) += 1;
+	}
+	counts
+}
+
+/// Groups the mounts of `snapshot` that share a filesystem (superblock), such as bind mounts, btrfs subvolumes, or a device mounted twice, so that usage reporting can count the capacity of each filesystem once.  Only groups of two or more mounts are returned, ordered by their first mount, and each group is in mount table order.
+///
+/// Mounts read from mountinfo are grouped by their device number, which is the `st_dev` that `stat(2)` reports for every file on the filesystem.  Other mounts are grouped by device path, and mounts of pseudo filesystems without mountinfo, whose device is just a name such as `tmpfs`, are never grouped, since that name says nothing about which superblock they are on.
+/// # Examples
+/// ```
+/// # use nom_tutorial::snapshot::{shared_superblock_groups, MountSnapshot};
+/// # use nom_tutorial::model::Format;
+/// let snapshot = MountSnapshot::parse("\
+///     22 1 8:1 / / rw - ext4 /dev/sda1 rw\n\
+///     23 22 0:40 / /tmp rw - tmpfs tmpfs rw\n\
+///     24 22 8:1 /srv /export rw - ext4 /dev/sda1 rw\n", Format::MountInfo).unwrap();
+/// let groups = shared_superblock_groups(&snapshot);
+/// assert_eq!(groups.len(), 1);
+/// assert_eq!(groups[0].iter().map(|m| &m.mount_point[..]).collect::<Vec<_>>(), ["/", "/export"]);
+/// ```
+pub fn shared_superblock_groups(snapshot: &MountSnapshot) -> std::vec::Vec<std::vec::Vec<&MountEntry>> {
+	let mut groups: std::vec::Vec<(DeviceId, std::vec::Vec<&MountEntry>)> = std::vec::Vec::new();
+	for mount in snapshot {
+		if mount.mountinfo.is_none() && !mount.device.starts_with('/') {
+			continue;
+		}
+		let id = mount.device_id();
+		match groups.iter_mut().find(|(group_id, _)| *group_id == id) {
+			Some((_, group)) => group.push(mount),
+			None => groups.push((id, vec![mount]))
+		}
+	}
+	groups.into_iter().map(|(_, group)| group).filter(|group| group.len() > 1).collect()
+}
+
+// The bincode settings for snapshots: variable-length integers, which shrink the many small numbers in mountinfo, and a size limit so that a corrupt length can't exhaust memory.
+#[cfg(feature = "bincode")]
+fn bincode_options() -> impl bincode::Options {
+	bincode::Options::with_limit(bincode::DefaultOptions::new(), 64 * 1024 * 1024)
+}
+
+// Parses every line in `lines`, numbering them from `first_line_number` for error messages.
+fn parse_lines<'a, I: Iterator<Item = &'a str>>(lines: I, format: crate::model::Format, first_line_number: usize) -> std::result::Result<std::vec::Vec<MountEntry>, crate::ParseError> {
+	lines.enumerate()
+		.map(|(number, line)| crate::parsers::parse_entry(line, format).map_err(|e| e.with_line_number(number + first_line_number)))
+		.collect()
+}
+
+// The current time, or the epoch on targets where `SystemTime::now()` panics.
+fn now() -> std::time::SystemTime {
+	if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+		std::time::UNIX_EPOCH
+	} else {
+		std::time::SystemTime::now()
+	}
+}
+
+impl Default for MountSnapshot {
+	/// An empty snapshot taken now.
+	fn default() -> MountSnapshot {
+		MountSnapshot::new(std::vec::Vec::new())
+	}
+}
+
+impl From<std::vec::Vec<Mount>> for MountSnapshot {
+	fn from(mounts: std::vec::Vec<Mount>) -> MountSnapshot {
+		MountSnapshot::new(mounts)
+	}
+}
+
+impl From<std::vec::Vec<MountEntry>> for MountSnapshot {
+	fn from(mounts: std::vec::Vec<MountEntry>) -> MountSnapshot {
+		MountSnapshot::with_entries(mounts, now())
+	}
+}
+
+impl std::iter::FromIterator<Mount> for MountSnapshot {
+	fn from_iter<I: IntoIterator<Item = Mount>>(iter: I) -> MountSnapshot {
+		MountSnapshot::new(iter.into_iter().collect())
+	}
+}
+
+impl std::iter::FromIterator<MountEntry> for MountSnapshot {
+	fn from_iter<I: IntoIterator<Item = MountEntry>>(iter: I) -> MountSnapshot {
+		MountSnapshot::with_entries(iter.into_iter().collect(), now())
+	}
+}
+
+impl IntoIterator for MountSnapshot {
+	type Item = MountEntry;
+	type IntoIter = std::vec::IntoIter<MountEntry>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.mounts.into_iter()
+	}
+}
+
+impl<'a> IntoIterator for &'a MountSnapshot {
+	type Item = &'a MountEntry;
+	type IntoIter = std::slice::Iter<'a, MountEntry>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.mounts.iter()
+	}
+}
+
+#[cfg(feature = "io")]
+mod parsers {
+	use crate::model::Format;
+
+	// The start of the header line of a saved snapshot, including the format version.
+	pub const MAGIC: &str = "# nom-tutorial snapshot 1";
+
+	// Parses the header line of a saved snapshot, e.g. `# nom-tutorial snapshot 1 format=mounts taken_at=1760000000.000000000`, into the line format and the time since the epoch.
+	pub fn header(i: &str) -> nom::IResult<&str, (Format, std::time::Duration)> {
+		let (i, _) = nom::bytes::complete::tag(MAGIC)(i)?;
+		let (i, _) = nom::character::complete::space1(i)?;
+		let (i, format) = nom::sequence::preceded(nom::bytes::complete::tag("format="), nom::branch::alt((
+			nom::combinator::value(Format::MountInfo, nom::bytes::complete::tag("mountinfo")),
+			nom::combinator::value(Format::Mounts, nom::bytes::complete::tag("mounts")),
+		)))(i)?;
+		let (i, _) = nom::character::complete::space1(i)?;
+		let (i, (secs, nanos)) = nom::combinator::all_consuming(nom::sequence::preceded(nom::bytes::complete::tag("taken_at="), nom::sequence::separated_pair(
+			nom::combinator::map_res(nom::character::complete::digit1, |digits: &str| digits.parse::<u64>()),
+			nom::character::complete::char('.'),
+			nom::combinator::map_res(nom::character::complete::digit1, |digits: &str| digits.parse::<u32>())
+		)))(i)?;
+		Ok((i, (format, std::time::Duration::new(secs, nanos))))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_header() {
+			assert_eq!(header("# nom-tutorial snapshot 1 format=mountinfo taken_at=12.000000034"), Ok(("", (Format::MountInfo, std::time::Duration::new(12, 34)))));
+			assert!(header("# nom-tutorial snapshot 2 format=mounts taken_at=12.0").is_err());
+			assert!(header("# nom-tutorial snapshot 1 format=mounts taken_at=12").is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_group_by_fs_type() {
+		let mount = |fs_type: &str, mount_point: &str| Mount { file_system_type: fs_type.to_string(), mount_point: mount_point.to_string(), ..Default::default() };
+		let snapshot: MountSnapshot = vec![mount("tmpfs", "/run"), mount("ext4", "/"), mount("tmpfs", "/tmp")].into_iter().collect();
+		let groups = snapshot.group_by_fs_type();
+		assert_eq!(groups.keys().copied().collect::<std::vec::Vec<_>>(), vec!["ext4", "tmpfs"]);
+		assert_eq!(groups["tmpfs"], vec![&snapshot.mounts()[0], &snapshot.mounts()[2]]);
+	}
+
+	// Equality ignores when and where a snapshot was taken.
+	#[test]
+	fn test_eq() {
+		let mounts = vec![Mount { mount_point: "/".to_string(), ..Default::default() }];
+		let earlier = MountSnapshot::with_time(mounts.clone(), std::time::UNIX_EPOCH);
+		let later = MountSnapshot::with_time(mounts, std::time::UNIX_EPOCH + std::time::Duration::from_secs(60)).with_source(SnapshotSource::File("/proc/self/mountinfo".into()));
+		assert_eq!(earlier, later);
+		assert_ne!(earlier, MountSnapshot::with_time(std::vec::Vec::new(), std::time::UNIX_EPOCH));
+	}
+
+	// Snapshots can be shared between threads and iterated over repeatedly.
+	#[test]
+	fn test_shared_between_threads() {
+		fn assert_send_sync<T: Send + Sync + Clone>() {}
+		assert_send_sync::<MountSnapshot>();
+		let snapshot = MountSnapshot::parse("/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\ntmpfs /run tmpfs rw 0 0\n", crate::model::Format::Mounts).unwrap();
+		let snapshot = std::sync::Arc::new(snapshot);
+		let shared = std::sync::Arc::clone(&snapshot);
+		let count = std::thread::spawn(move || shared.iter().count()).join().unwrap();
+		assert_eq!((count, snapshot.len()), (3, 3));
+		assert_eq!((&*snapshot).into_iter().count(), snapshot.len());
+	}
+
+	#[cfg(all(feature = "io", feature = "serde"))]
+	#[test]
+	fn test_serde_roundtrip() {
+		let source = crate::source::StringSource::mountinfo("22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw\n");
+		let snapshot = MountSnapshot::from_source(&source).unwrap();
+		let json = serde_json::to_string(&snapshot).unwrap();
+		assert!(json.contains("\"taken_at\""));
+		assert_eq!(serde_json::from_str::<MountSnapshot>(&json).unwrap(), snapshot);
+	}
+
+	// Mountinfo, escapes, and the time the snapshot was taken all survive saving and loading, and a bad line is reported with its line number.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_save_load_mountinfo() {
+		let source = crate::source::StringSource::mountinfo("\
+			1 1 0:1 / / rw - rootfs rootfs rw\n\
+			22 1 8:1 /srv /mnt/my\\040disk rw,relatime shared:1 master:2 - ext4 /dev/sda1 rw,errors=remount-ro\n");
+		let snapshot = MountSnapshot::from_source(&source).unwrap();
+		let path = std::env::temp_dir().join(format!("nom-tutorial-snapshot-{}", std::process::id()));
+		snapshot.save(&path).unwrap();
+		let loaded = MountSnapshot::load(&path);
+		std::fs::write(&path, "# nom-tutorial snapshot 1 format=mounts taken_at=0.0\n/dev/sda1 / ext4 rw 0 0\nbad line\n").unwrap();
+		let error = MountSnapshot::load(&path).unwrap_err();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(loaded.unwrap(), snapshot);
+		assert_eq!(error.downcast_ref::<crate::ParseError>().unwrap().line_number, Some(3));
+	}
+
+	// Options that no longer match the mountinfo fields are saved in the format of /proc/mounts rather than dropped, and no temporary file is left behind.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_save_changed_options() {
+		let mut entry = crate::parsers::parse_entry("22 1 8:1 / /data rw - ext4 /dev/sda1 rw", crate::model::Format::MountInfo).unwrap();
+		entry.mount.options.push("x-mine".to_string());
+		let snapshot = MountSnapshot::with_entries(vec![entry], std::time::UNIX_EPOCH);
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-snapshot-options-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		snapshot.save(directory.join("snapshot")).unwrap();
+		let loaded = MountSnapshot::load(directory.join("snapshot"));
+		let files = std::fs::read_dir(&directory).unwrap().count();
+		std::fs::remove_dir_all(&directory).unwrap();
+		assert_eq!(loaded.unwrap().mounts()[0].options, ["rw", "x-mine"]);
+		assert_eq!(files, 1);
+	}
+
+	// A mapped file parses the same as one read through a buffer, and a file with a size of zero is read instead.
+	#[cfg(feature = "mmap")]
+	#[test]
+	fn test_from_mmap() {
+		// Safety: the files aren't changed while they are mapped.
+		let from_mmap = |path: &std::path::Path, format| unsafe { MountSnapshot::from_mmap(path, format) };
+		let path = std::env::temp_dir().join(format!("nom-tutorial-mmap-{}", std::process::id()));
+		std::fs::write(&path, "1 1 0:1 / / rw - rootfs rootfs rw\n22 1 8:1 /srv /mnt/my\\040disk rw shared:1 - ext4 /dev/sda1 rw\n").unwrap();
+		let mapped = from_mmap(&path, crate::model::Format::MountInfo).unwrap();
+		let read = MountSnapshot::from_source(&crate::source::FileSource::mountinfo(&path)).unwrap();
+		std::fs::write(&path, "").unwrap();
+		let empty = from_mmap(&path, crate::model::Format::Mounts).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(mapped.mounts(), read.mounts());
+		assert_eq!(mapped.mounts()[1].mount_point, "/mnt/my disk");
+		assert!(empty.is_empty());
+		assert!(from_mmap(&std::env::temp_dir(), crate::model::Format::Mounts).is_err());
+	}
+
+	// A source that returns a different table the first `changes` times it is opened.
+	#[cfg(feature = "io")]
+	struct ChangingSource {
+		opened: std::sync::atomic::AtomicUsize,
+		changes: usize,
+	}
+
+	#[cfg(feature = "io")]
+	impl crate::source::MountSource for ChangingSource {
+		type Reader = std::io::Cursor<std::string::String>;
+
+		fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+			let opened = self.opened.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+			Ok(std::io::Cursor::new(format!("/dev/sda1 /mnt{} ext4 rw 0 0\n", opened.min(self.changes))))
+		}
+	}
+
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_from_source_consistent() {
+		// The table settles on the fourth re-read.
+		let source = ChangingSource { opened: Default::default(), changes: 3 };
+		let snapshot = MountSnapshot::from_source_consistent(&source, 4).unwrap();
+		assert_eq!(snapshot.mounts()[0].mount_point, "/mnt3");
+		let source = ChangingSource { opened: Default::default(), changes: 3 };
+		assert!(MountSnapshot::from_source_consistent(&source, 3).is_err());
+	}
+
+	// No retries means only the initial read.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_from_source_consistent_no_retries() {
+		let source = ChangingSource { opened: Default::default(), changes: 3 };
+		let snapshot = MountSnapshot::from_source_consistent(&source, 0).unwrap();
+		assert_eq!(snapshot.mounts()[0].mount_point, "/mnt0");
+		assert_eq!(source.opened.load(std::sync::atomic::Ordering::SeqCst), 1);
+	}
+
+	// Parent IDs and bind mounts are honored when mountinfo is available, regardless of table order.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_unmount_order_mountinfo() {
+		let source = crate::source::StringSource::mountinfo("\
+			1 1 0:1 / / rw - rootfs rootfs rw\n\
+			5 1 8:1 /srv /export rw - ext4 /dev/sda1 rw\n\
+			4 1 8:1 / /data rw - ext4 /dev/sda1 rw\n\
+			3 4 0:2 / /data/tmp rw - tmpfs tmpfs rw\n");
+		let snapshot = MountSnapshot::from_source(&source).unwrap();
+		let order: std::vec::Vec<&str> = unmount_order(&snapshot).iter().map(|m| &m.mount_point[..]).collect();
+		assert_eq!(order, vec!["/data/tmp", "/export", "/data", "/"]);
+	}
+
+	// Stacked mounts come off top first.
+	#[test]
+	fn test_unmount_order_stacked() {
+		let mount = |device: &str| Mount { device: device.to_string(), mount_point: "/mnt".to_string(), ..Default::default() };
+		let snapshot = MountSnapshot::new(vec![mount("lower"), mount("upper")]);
+		let order: std::vec::Vec<&str> = unmount_order(&snapshot).iter().map(|m| &m.device[..]).collect();
+		assert_eq!(order, vec!["upper", "lower"]);
+	}
+
+	// Without mountinfo, mounts are counted by device name.
+	#[test]
+	fn test_device_mount_counts_by_name() {
+		let mount = |device: &str| crate::test_fixtures::mount(device, "", "", &[]);
+		let snapshot = MountSnapshot::new(vec![mount("/dev/sda1"), mount("tmpfs"), mount("/dev/sda1")]);
+		let counts = device_mount_counts(&snapshot);
+		assert_eq!(counts.into_iter().map(|(id, count)| (id.to_string(), count)).collect::<std::vec::Vec<_>>(), [("/dev/sda1".to_string(), 2), ("tmpfs".to_string(), 1)]);
+	}
+
+	// Without mountinfo, tmpfs mounts aren't taken for one filesystem just because they share a name.
+	#[test]
+	fn test_shared_superblock_groups_by_name() {
+		let mount = |device: &str, mount_point: &str| crate::test_fixtures::mount(device, mount_point, "", &[]);
+		let snapshot = MountSnapshot::new(vec![mount("/dev/sda1", "/"), mount("tmpfs", "/tmp"), mount("/dev/sdb1", "/home"), mount("tmpfs", "/run"), mount("/dev/sda1", "/var")]);
+		let groups = shared_superblock_groups(&snapshot);
+		assert_eq!(groups.iter().map(|group| group.iter().map(|m| &m.mount_point[..]).collect::<std::vec::Vec<_>>()).collect::<std::vec::Vec<_>>(), [["/", "/var"]]);
+	}
+
+	// The source survives the round trip, and truncated or garbled input is an error rather than a panic.
+	#[cfg(feature = "bincode")]
+	#[test]
+	fn test_bincode_roundtrip() {
+		let snapshot = MountSnapshot::parse("36 35 98:0 / /mnt rw,noatime shared:1 - ext4 /dev/sda1 rw\n", crate::model::Format::MountInfo).unwrap()
+			.with_source(SnapshotSource::File("/proc/self/mountinfo".into()));
+		let mut bytes = std::vec::Vec::new();
+		snapshot.write_bincode(&mut bytes).unwrap();
+		assert_eq!(MountSnapshot::read_bincode(&bytes[..]).unwrap(), snapshot);
+		assert!(MountSnapshot::read_bincode(&bytes[..bytes.len() - 1]).is_err());
+		assert!(MountSnapshot::read_bincode(&[0xff; 32][..]).is_err());
+	}
+
+	#[test]
+	fn test_summary_empty() {
+		assert_eq!(MountSnapshot::default().summary(), Summary::default());
+	}
+}
@@ -0,0 +1,127 @@
+//! Orderings of mounts other than the kernel's, for arranging output.
+
+use super::{BoxError, Mount};
+
+/// A field to sort mounts by, named as in the `--sort` option of `nom-tutorial`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SortKey {
+	/// [Mount::device]
+	Device,
+	/// [Mount::mount_point]
+	Target,
+	/// [Mount::file_system_type]
+	FsType,
+	/// Total size of the filesystem, see [Mount::usage()].  Mounts whose size can't be queried come first.
+	#[cfg(feature = "statvfs")]
+	Size,
+}
+
+impl SortKey {
+	/// The name of the key, e.g. fstype.
+	pub fn name(self) -> &'static str {
+		match self {
+			SortKey::Device => "device",
+			SortKey::Target => "target",
+			SortKey::FsType => "fstype",
+			#[cfg(feature = "statvfs")]
+			SortKey::Size => "size",
+		}
+	}
+
+	/// Compares two mounts by this key.  Sorting by [SortKey::Size] queries the filesystem on every comparison, so prefer [sort()] for sorting many mounts.
+	pub fn compare(self, a: &Mount, b: &Mount) -> std::cmp::Ordering {
+		match self {
+			SortKey::Device => by_device(a, b),
+			SortKey::Target => by_target(a, b),
+			SortKey::FsType => by_file_system_type(a, b),
+			#[cfg(feature = "statvfs")]
+			SortKey::Size => size(a).cmp(&size(b)),
+		}
+	}
+}
+
+impl std::fmt::Display for SortKey {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+impl std::str::FromStr for SortKey {
+	type Err = BoxError;
+
+	/// Parses the name returned by [SortKey::name()].
+	fn from_str(s: &str) -> std::result::Result<SortKey, BoxError> {
+		match s {
+			"device" => Ok(SortKey::Device),
+			"target" => Ok(SortKey::Target),
+			"fstype" => Ok(SortKey::FsType),
+			#[cfg(feature = "statvfs")]
+			"size" => Ok(SortKey::Size),
+			#[cfg(not(feature = "statvfs"))]
+			"size" => Err("sorting by size requires the `statvfs` feature".into()),
+			_ => Err(format!("unknown sort key: {}", s).into())
+		}
+	}
+}
+
+/// Compares mounts by device, then by mount point.
+pub fn by_device(a: &Mount, b: &Mount) -> std::cmp::Ordering {
+	(&a.device, &a.mount_point).cmp(&(&b.device, &b.mount_point))
+}
+
+/// Compares mounts by mount point, then by device.
+pub fn by_target(a: &Mount, b: &Mount) -> std::cmp::Ordering {
+	(&a.mount_point, &a.device).cmp(&(&b.mount_point, &b.device))
+}
+
+/// Compares mounts by filesystem type, then by mount point.
+pub fn by_file_system_type(a: &Mount, b: &Mount) -> std::cmp::Ordering {
+	(&a.file_system_type, &a.mount_point).cmp(&(&b.file_system_type, &b.mount_point))
+}
+
+// Total size of the filesystem, or `None` if it can't be queried.
+#[cfg(feature = "statvfs")]
+fn size(mount: &Mount) -> std::option::Option<u64> {
+	mount.usage().ok().map(|usage| usage.total_bytes)
+}
+
+/// Sorts mounts by `key`, keeping mounts that compare equal in their original order.  Reverse the result for descending order.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mount;
+/// # use nom_tutorial::sort::{sort, SortKey};
+/// let mount = |device: &str, mount_point: &str| Mount { device: device.to_string(), mount_point: mount_point.to_string(), ..Default::default() };
+/// let mut mounts = vec![mount("/dev/sdb1", "/a"), mount("/dev/sda1", "/b")];
+/// sort(&mut mounts, SortKey::Device);
+/// assert_eq!(mounts[0].device, "/dev/sda1");
+/// ```
+pub fn sort(mounts: &mut [Mount], key: SortKey) {
+	match key {
+		#[cfg(feature = "statvfs")]
+		SortKey::Size => mounts.sort_by_cached_key(size),
+		key => mounts.sort_by(|a, b| key.compare(a, b))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sort() {
+		let mount = |device: &str, mount_point: &str, file_system_type: &str| Mount {
+			device: device.to_string(),
+			mount_point: mount_point.to_string(),
+			file_system_type: file_system_type.to_string(),
+			..Default::default()
+		};
+		let mut mounts = vec![mount("tmpfs", "/tmp", "tmpfs"), mount("/dev/sda1", "/", "ext4"), mount("/dev/sda2", "/home", "ext4")];
+		sort(&mut mounts, SortKey::Target);
+		assert_eq!(mounts.iter().map(|m| &m.mount_point[..]).collect::<std::vec::Vec<_>>(), ["/", "/home", "/tmp"]);
+		sort(&mut mounts, SortKey::FsType);
+		assert_eq!(mounts.iter().map(|m| &m.device[..]).collect::<std::vec::Vec<_>>(), ["/dev/sda1", "/dev/sda2", "tmpfs"]);
+		assert_eq!("fstype".parse::<SortKey>().unwrap(), SortKey::FsType);
+		assert!("bogus".parse::<SortKey>().is_err());
+	}
+}
@@ -0,0 +1,418 @@
+//! Places a mount table can be read from.
+//!
+//! [Mounts](super::Mounts) is generic over a [MountSource], so code that enumerates mounts can be pointed at `/proc/mounts` in production and at a fabricated mount table in tests without touching the filesystem.
+
+use super::Mount;
+
+pub use super::model::Format;
+
+/// Something that can be opened to read a mount table in the format of `/proc/mounts` or `/proc/self/mountinfo`.
+pub trait MountSource {
+	/// The reader returned by [MountSource::open()].
+	type Reader: std::io::BufRead;
+
+	/// Opens the mount table for reading from the beginning.
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error>;
+
+	/// The format of the lines returned by the reader.  Defaults to [Format::Mounts].
+	fn format(&self) -> Format {
+		Format::Mounts
+	}
+
+	/// The number of lines left in `reader`, if that can be told without reading from it because the whole table is already in memory.  Used for the [size_hint()](Iterator::size_hint) of the iterators over [Mounts](crate::Mounts).  Defaults to `None`.
+	fn remaining_lines(&self, _reader: &Self::Reader) -> std::option::Option<usize> {
+		None
+	}
+}
+
+// Counts the lines between the position of `reader` and the end of its contents, including a last line without a newline.
+fn count_lines(reader: &std::io::Cursor<std::string::String>) -> usize {
+	let rest = reader.get_ref().as_bytes().get(reader.position() as usize..).unwrap_or_default();
+	let newlines = rest.iter().filter(|&&byte| byte == b'\n').count();
+	match rest.last() {
+		Some(b'\n') | None => newlines,
+		Some(_) => newlines + 1
+	}
+}
+
+// Some Android versions deny apps access to `/proc/mounts` but not to the identical table of their own mount namespace.  Returns the path to try when opening `path` was denied, if there is one.
+fn fallback_path(path: &std::path::Path) -> std::option::Option<&'static std::path::Path> {
+	if path == std::path::Path::new("/proc/mounts") {
+		Some(std::path::Path::new("/proc/self/mounts"))
+	} else {
+		None
+	}
+}
+
+// Calls `open` with `path`, and again with its fallback path if access was denied.
+fn with_fallback<T, F: Fn(&std::path::Path) -> std::result::Result<T, std::io::Error>>(path: &std::path::Path, open: F) -> std::result::Result<T, std::io::Error> {
+	match (open(path), fallback_path(path)) {
+		(Err(e), Some(fallback)) if e.kind() == std::io::ErrorKind::PermissionDenied => open(fallback),
+		(result, _) => result
+	}
+}
+
+/// Capacity of the buffer used to read mount table files, unless changed with [FileSource::with_capacity()].  Lines longer than the buffer are read in several pieces, so this only affects performance.
+pub const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+// Opens a file for buffered reading with a buffer of `capacity` bytes.
+fn open_file(path: &std::path::Path, capacity: usize) -> std::result::Result<std::io::BufReader<std::fs::File>, std::io::Error> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::debug_span!("open", path = %path.display()).entered();
+	let file = with_fallback(path, |path| std::fs::File::open(path));
+	#[cfg(feature = "tracing")]
+	match &file {
+		Ok(_) => tracing::debug!("opened mount table"),
+		Err(e) => tracing::debug!(error = %e, "failed to open mount table"),
+	}
+	Ok(std::io::BufReader::with_capacity(capacity, file?))
+}
+
+/// The kernel's mount table at `/proc/mounts`.  This is the default source.  If access to `/proc/mounts` is denied, as it is for apps on some Android versions, `/proc/self/mounts` is read instead.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ProcMounts;
+
+impl MountSource for ProcMounts {
+	type Reader = std::io::BufReader<std::fs::File>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		open_file(std::path::Path::new("/proc/mounts"), DEFAULT_CAPACITY)
+	}
+}
+
+/// The current process's mount table at `/proc/self/mountinfo`.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ProcMountInfo;
+
+impl MountSource for ProcMountInfo {
+	type Reader = std::io::BufReader<std::fs::File>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		open_file(std::path::Path::new("/proc/self/mountinfo"), DEFAULT_CAPACITY)
+	}
+
+	fn format(&self) -> Format {
+		Format::MountInfo
+	}
+}
+
+/// A mount table stored in a file, e.g. `/proc/self/mounts` or a capture saved from another machine.
+#[derive(Clone, Debug)]
+pub struct FileSource {
+	path: std::path::PathBuf,
+	format: Format,
+	capacity: usize
+}
+
+impl FileSource {
+	/// Reads the mount table in the format of `/proc/mounts` from the file at `path`.
+	pub fn new<P: Into<std::path::PathBuf>>(path: P) -> FileSource {
+		FileSource { path: path.into(), format: Format::Mounts, capacity: DEFAULT_CAPACITY }
+	}
+
+	/// Reads the mount table in the format of `/proc/self/mountinfo` from the file at `path`.
+	pub fn mountinfo<P: Into<std::path::PathBuf>>(path: P) -> FileSource {
+		FileSource { path: path.into(), format: Format::MountInfo, capacity: DEFAULT_CAPACITY }
+	}
+
+	/// Reads the mount table in the format of Solaris' `/etc/mnttab` from the file at `path`, see [mnttab](crate::mnttab).
+	pub fn mnttab<P: Into<std::path::PathBuf>>(path: P) -> FileSource {
+		FileSource { path: path.into(), format: Format::Mnttab, capacity: DEFAULT_CAPACITY }
+	}
+
+	/// Reads the file through a buffer of `capacity` bytes instead of [DEFAULT_CAPACITY].  A bigger buffer means fewer `read()` calls for tables with very long lines, such as overlay mounts with hundreds of lower layers.  A capacity of 0, with which nothing could ever be read, is taken as 1.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mounts;
+	/// # use nom_tutorial::source::FileSource;
+	/// let path = std::env::temp_dir().join(format!("nom-tutorial-doctest-capacity-{}", std::process::id()));
+	/// std::fs::write(&path, "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n").unwrap();
+	/// let source = FileSource::new(&path).with_capacity(256 * 1024);
+	/// assert_eq!(Mounts::from_source(&source).unwrap().into_iter().count(), 2);
+	/// # std::fs::remove_file(&path).unwrap();
+	/// ```
+	pub fn with_capacity(mut self, capacity: usize) -> FileSource {
+		self.capacity = capacity.max(1);
+		self
+	}
+}
+
+impl MountSource for FileSource {
+	type Reader = std::io::BufReader<std::fs::File>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		open_file(&self.path, self.capacity)
+	}
+
+	fn format(&self) -> Format {
+		self.format
+	}
+}
+
+/// A mount table held in memory as a string of lines.
+#[derive(Clone, Debug)]
+pub struct StringSource {
+	contents: std::string::String,
+	format: Format
+}
+
+impl StringSource {
+	/// Reads the mount table in the format of `/proc/mounts` from `contents`.
+	pub fn new<S: Into<std::string::String>>(contents: S) -> StringSource {
+		StringSource { contents: contents.into(), format: Format::Mounts }
+	}
+
+	/// Reads the mount table in the format of `/proc/self/mountinfo` from `contents`.
+	pub fn mountinfo<S: Into<std::string::String>>(contents: S) -> StringSource {
+		StringSource { contents: contents.into(), format: Format::MountInfo }
+	}
+}
+
+impl MountSource for StringSource {
+	type Reader = std::io::Cursor<std::string::String>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		Ok(std::io::Cursor::new(self.contents.clone()))
+	}
+
+	fn format(&self) -> Format {
+		self.format
+	}
+
+	fn remaining_lines(&self, reader: &Self::Reader) -> std::option::Option<usize> {
+		Some(count_lines(reader))
+	}
+}
+
+// Size of the first buffer tried by `read_whole()`, enough for a few hundred mounts.
+const SINGLE_READ_CAPACITY: usize = 64 * 1024;
+
+// Reads the whole file with a single `read()` call into a buffer of `capacity` bytes.  The kernel generates as much of a procfs mount table as fits in the buffer while holding the namespace lock, so one read sees the table as it was at one instant.  If the buffer fills up the table may have been cut short, so the file is read again from the start with a buffer twice the size.
+fn read_whole(path: &std::path::Path, mut capacity: usize) -> std::result::Result<std::string::String, std::io::Error> {
+	use std::io::Read;
+	loop {
+		let mut buffer = vec![0; capacity];
+		let length = std::fs::File::open(path)?.read(&mut buffer)?;
+		if length < capacity {
+			buffer.truncate(length);
+			return std::string::String::from_utf8(buffer).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+		}
+		capacity *= 2;
+	}
+}
+
+/// A mount table file that is read in full with a single `read()` call when opened, and then parsed from memory.
+///
+/// Reading `/proc/mounts` line by line takes many system calls, and the kernel releases its lock on the mount table between them, so a table that changes mid-iteration can be observed half old and half new, with mounts missing or repeated.  A single read sees a consistent table.  This is what [snapshot()](crate::snapshot()) uses.
+/// # Examples
+/// ```
+/// # use nom_tutorial::Mounts;
+/// # use nom_tutorial::source::SingleReadSource;
+/// let mounts = Mounts::from_source(&SingleReadSource::proc_mounts()).unwrap();
+/// assert!(mounts.into_iter().count() > 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SingleReadSource {
+	path: std::path::PathBuf,
+	format: Format
+}
+
+impl SingleReadSource {
+	/// Reads the mount table in the format of `/proc/mounts` from the file at `path`.
+	pub fn new<P: Into<std::path::PathBuf>>(path: P) -> SingleReadSource {
+		SingleReadSource { path: path.into(), format: Format::Mounts }
+	}
+
+	/// Reads the mount table in the format of `/proc/self/mountinfo` from the file at `path`.
+	pub fn mountinfo<P: Into<std::path::PathBuf>>(path: P) -> SingleReadSource {
+		SingleReadSource { path: path.into(), format: Format::MountInfo }
+	}
+
+	/// Reads `/proc/mounts`.
+	pub fn proc_mounts() -> SingleReadSource {
+		SingleReadSource::new("/proc/mounts")
+	}
+
+	/// Reads `/proc/self/mountinfo`.
+	pub fn proc_mountinfo() -> SingleReadSource {
+		SingleReadSource::mountinfo("/proc/self/mountinfo")
+	}
+}
+
+impl MountSource for SingleReadSource {
+	type Reader = std::io::Cursor<std::string::String>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::debug_span!("open", path = %self.path.display()).entered();
+		let contents = with_fallback(&self.path, |path| read_whole(path, SINGLE_READ_CAPACITY));
+		#[cfg(feature = "tracing")]
+		match &contents {
+			Ok(contents) => tracing::debug!(bytes = contents.len(), "read mount table"),
+			Err(e) => tracing::debug!(error = %e, "failed to read mount table"),
+		}
+		Ok(std::io::Cursor::new(contents?))
+	}
+
+	fn format(&self) -> Format {
+		self.format
+	}
+
+	fn remaining_lines(&self, reader: &Self::Reader) -> std::option::Option<usize> {
+		Some(count_lines(reader))
+	}
+}
+
+/// A fabricated mount table for unit tests.
+///
+/// Mounts are serialized with [Mount::to_proc_line()] when opened, so they are parsed back exactly as they would be from `/proc/mounts`.  The source can also be made to fail on open to exercise error handling.
+/// # Examples
+/// ```
+/// # use nom_tutorial::{Mount, Mounts};
+/// # use nom_tutorial::source::MockSource;
+/// let mount = Mount {
+///     device: "/dev/sda1".to_string(),
+///     mount_point: "/mnt/disk".to_string(),
+///     file_system_type: "ext4".to_string(),
+///     options: vec!["rw".to_string()]
+/// };
+/// let source = MockSource::new(vec![mount.clone()]);
+/// let parsed: Vec<Mount> = Mounts::from_source(&source).unwrap().into_iter().map(Result::unwrap).collect();
+/// assert_eq!(parsed, vec![mount]);
+///
+/// let failing = MockSource::failing(std::io::ErrorKind::PermissionDenied);
+/// assert!(Mounts::from_source(&failing).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MockSource {
+	mounts: std::vec::Vec<Mount>,
+	error: std::option::Option<std::io::ErrorKind>
+}
+
+impl MockSource {
+	/// Serves the given mounts.
+	pub fn new(mounts: std::vec::Vec<Mount>) -> MockSource {
+		MockSource { mounts, error: None }
+	}
+
+	/// Fails to open with an error of the given kind.
+	pub fn failing(kind: std::io::ErrorKind) -> MockSource {
+		MockSource { mounts: std::vec::Vec::new(), error: Some(kind) }
+	}
+}
+
+impl MountSource for MockSource {
+	type Reader = std::io::Cursor<std::string::String>;
+
+	fn open(&self) -> std::result::Result<Self::Reader, std::io::Error> {
+		if let Some(kind) = self.error {
+			return Err(std::io::Error::new(kind, "mock mount source failure"));
+		}
+		let mut contents = std::string::String::new();
+		for mount in &self.mounts {
+			contents.push_str(&mount.to_proc_line());
+			contents.push('\n');
+		}
+		Ok(std::io::Cursor::new(contents))
+	}
+
+	fn remaining_lines(&self, reader: &Self::Reader) -> std::option::Option<usize> {
+		Some(count_lines(reader))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Mounts;
+
+	#[test]
+	fn test_string_source() {
+		let source = StringSource::new("a /b c d 0 0\nbad line\n");
+		let mut mounts = Mounts::from_source(&source).unwrap();
+		let mut iter = mounts.iter_mut();
+		assert_eq!(iter.next().unwrap().unwrap().mount_point, "/b");
+		assert!(iter.next().unwrap().is_err());
+		assert!(iter.next().is_none());
+	}
+
+	// Fields that need escaping survive a round trip through the mock.
+	#[test]
+	fn test_mock_source_escapes() {
+		let mount = Mount {
+			device: "\\\\server\\share".to_string(),
+			mount_point: "/mnt/my disk".to_string(),
+			file_system_type: "cifs".to_string(),
+			options: vec!["rw".to_string(), "prefixpath=a b".to_string()]
+		};
+		let parsed: std::vec::Vec<Mount> = Mounts::from_source(&MockSource::new(vec![mount.clone()])).unwrap().into_iter().map(|m| m.unwrap()).collect();
+		assert_eq!(parsed, vec![mount]);
+	}
+
+	// A buffer that fills up is retried with a bigger one until the whole file fits.
+	#[test]
+	fn test_read_whole_grows() {
+		let path = std::env::temp_dir().join(format!("nom-tutorial-read-whole-{}", std::process::id()));
+		let contents = "/dev/sda1 /mnt ext4 rw 0 0\n".repeat(100);
+		std::fs::write(&path, &contents).unwrap();
+		let read = read_whole(&path, 16);
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(read.unwrap(), contents);
+	}
+
+	#[test]
+	fn test_file_source_missing() {
+		assert!(Mounts::from_source(&FileSource::new("/this/path/does/not/exist")).is_err());
+	}
+
+	// Only a denied `/proc/mounts` falls back to `/proc/self/mounts`; other errors and other paths are returned as they are.
+	#[test]
+	fn test_with_fallback() {
+		let denied = |path: &std::path::Path| if path == std::path::Path::new("/proc/mounts") {
+			Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+		} else {
+			Ok(path.to_path_buf())
+		};
+		assert_eq!(with_fallback(std::path::Path::new("/proc/mounts"), denied).unwrap(), std::path::Path::new("/proc/self/mounts"));
+		let missing = |_: &std::path::Path| std::result::Result::<(), _>::Err(std::io::Error::from(std::io::ErrorKind::NotFound));
+		assert_eq!(with_fallback(std::path::Path::new("/proc/mounts"), missing).unwrap_err().kind(), std::io::ErrorKind::NotFound);
+		let always_denied = |_: &std::path::Path| std::result::Result::<(), _>::Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+		assert!(with_fallback(std::path::Path::new("/etc/mtab"), always_denied).is_err());
+	}
+
+	// An overlay line far longer than the read buffer, as produced by containers with many image layers, is read whole and parsed without corruption.
+	#[test]
+	fn test_line_longer_than_buffer() {
+		let lowerdir: std::vec::Vec<std::string::String> = (0..4000).map(|layer| format!("/var/lib/docker/overlay2/l/LAYER{:05}", layer)).collect();
+		let line = format!("overlay /merged overlay rw,relatime,lowerdir={},upperdir=/upper,workdir=/work 0 0", lowerdir.join(":"));
+		assert!(line.len() > 128 * 1024);
+		let path = std::env::temp_dir().join(format!("nom-tutorial-long-line-{}", std::process::id()));
+		std::fs::write(&path, format!("/dev/sda1 / ext4 rw 0 0\n{}\nproc /proc proc rw 0 0\n", line)).unwrap();
+		let mounts: std::result::Result<std::vec::Vec<Mount>, _> = Mounts::from_source(&FileSource::new(&path).with_capacity(64)).unwrap().into_iter().collect();
+		std::fs::remove_file(&path).unwrap();
+		let mounts = mounts.unwrap();
+		assert_eq!(mounts.len(), 3);
+		assert_eq!(mounts[1].to_proc_line(), line);
+		assert_eq!(mounts[2].mount_point, "/proc");
+	}
+
+	// A zero capacity still reads every mount.
+	#[test]
+	fn test_zero_capacity() {
+		let path = std::env::temp_dir().join(format!("nom-tutorial-zero-capacity-{}", std::process::id()));
+		std::fs::write(&path, "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n").unwrap();
+		let count = Mounts::from_source(&FileSource::new(&path).with_capacity(0)).unwrap().into_iter().count();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(count, 2);
+	}
+
+	#[test]
+	fn test_count_lines() {
+		let mut reader = std::io::Cursor::new("a\nb\nc".to_string());
+		assert_eq!(count_lines(&reader), 3);
+		reader.set_position(2);
+		assert_eq!(count_lines(&reader), 2);
+		assert_eq!(count_lines(&std::io::Cursor::new("a\n\n".to_string())), 2);
+		assert_eq!(count_lines(&std::io::Cursor::new(std::string::String::new())), 0);
+	}
+}
@@ -0,0 +1,111 @@
+//! Ownership, permissions, and birth time of mount points via `statx(2)`.  Enabled by the `statx` feature, which needs Linux with glibc.
+//!
+//! A mount point shows the root directory of the filesystem mounted on it, so its owner and mode are those of that root, not of the directory underneath.  A world-writable mount point or one owned by the wrong user is easy to miss because it only appears once the filesystem is mounted.  `statx(2)` is called with `AT_NO_AUTOMOUNT`, so auditing an autofs mount point doesn't mount it.
+
+use super::Mount;
+
+/// The metadata of a mount point as reported by `statx(2)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MountPointMetadata {
+	/// The user that owns the mount point.
+	pub uid: u32,
+	/// The group that owns the mount point.
+	pub gid: u32,
+	/// The permission bits, including the set-user-ID, set-group-ID, and sticky bits, e.g. 0o1777 for /tmp
+	pub mode: u32,
+	/// When the root directory of the filesystem was created, or `None` if the filesystem doesn't record it.
+	pub birth_time: std::option::Option<std::time::SystemTime>,
+	/// The ID of the mount the path resolved to, as in [MountInfo::mount_id](crate::MountInfo::mount_id), or `None` on kernels before 5.8.
+	pub mount_id: std::option::Option<u64>,
+}
+
+impl MountPointMetadata {
+	/// True if anyone may create or delete files in the mount point, i.e. it is world-writable without the sticky bit that would stop users from deleting each other's files.
+	pub fn is_world_writable(&self) -> bool {
+		self.mode & 0o002 != 0 && self.mode & 0o1000 == 0
+	}
+}
+
+/// Calls `statx(2)` on `path` without triggering automounts.
+pub fn statx<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<MountPointMetadata, std::io::Error> {
+	use std::os::unix::ffi::OsStrExt;
+	let path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+		.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+	let mask = libc::STATX_UID | libc::STATX_GID | libc::STATX_MODE | libc::STATX_BTIME | libc::STATX_MNT_ID;
+	let mut buf = std::mem::MaybeUninit::<libc::statx>::uninit();
+	// Safe because `path` is a valid NUL-terminated string and `buf` is large enough to hold the result.
+	if unsafe { libc::statx(libc::AT_FDCWD, path.as_ptr(), libc::AT_NO_AUTOMOUNT, mask, buf.as_mut_ptr()) } != 0 {
+		return Err(std::io::Error::last_os_error());
+	}
+	// Safe because statx() returned success and therefore initialized `buf`.
+	let buf = unsafe { buf.assume_init() };
+	// The kernel clears the bits of the mask for fields it couldn't fill in.
+	let birth_time = if buf.stx_mask & libc::STATX_BTIME != 0 && buf.stx_btime.tv_sec >= 0 {
+		Some(std::time::UNIX_EPOCH + std::time::Duration::new(buf.stx_btime.tv_sec as u64, buf.stx_btime.tv_nsec))
+	} else {
+		None
+	};
+	Ok(MountPointMetadata {
+		uid: buf.stx_uid,
+		gid: buf.stx_gid,
+		mode: u32::from(buf.stx_mode) & 0o7777,
+		birth_time,
+		mount_id: if buf.stx_mask & libc::STATX_MNT_ID != 0 { Some(buf.stx_mnt_id) } else { None },
+	})
+}
+
+impl Mount {
+	/// Returns the owner, permissions, and birth time of the mount point by calling `statx(2)` on it.
+	pub fn mount_point_metadata(&self) -> std::result::Result<MountPointMetadata, std::io::Error> {
+		statx(&self.mount_point)
+	}
+}
+
+/// Calls `statx(2)` on the mount point of every mount in `mounts`, pairing each mount with its metadata, or with the error if its mount point couldn't be examined, e.g. because it is hidden by another mount or the caller may not search its parent directory.
+/// # Examples
+/// ```no_run
+/// let mounts = nom_tutorial::mounts().unwrap().into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+/// for (mount, metadata) in nom_tutorial::statx::enrich(&mounts) {
+///     if let Ok(metadata) = metadata {
+///         if metadata.is_world_writable() {
+///             println!("{} is world-writable without the sticky bit", mount.mount_point);
+///         }
+///     }
+/// }
+/// ```
+pub fn enrich(mounts: &[Mount]) -> std::vec::Vec<(&Mount, std::result::Result<MountPointMetadata, std::io::Error>)> {
+	mounts.iter().map(|mount| (mount, mount.mount_point_metadata())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// statx() agrees with stat(2) as wrapped by the standard library, and enrich() pairs each mount with its own result.
+	#[test]
+	fn test_statx_matches_metadata() {
+		use std::os::unix::fs::{MetadataExt, PermissionsExt};
+		let directory = std::env::temp_dir().join(format!("nom-tutorial-statx-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+		std::fs::set_permissions(&directory, std::fs::Permissions::from_mode(0o1777)).unwrap();
+		let metadata = std::fs::metadata(&directory).unwrap();
+		let mounts = [
+			crate::test_fixtures::mount("tmpfs", directory.to_str().unwrap(), "tmpfs", &[]),
+			crate::test_fixtures::mount("tmpfs", directory.join("missing").to_str().unwrap(), "tmpfs", &[]),
+		];
+		let enriched = enrich(&mounts);
+		std::fs::remove_dir_all(&directory).unwrap();
+		let found = enriched[0].1.as_ref().unwrap();
+		assert_eq!((found.uid, found.gid, found.mode), (metadata.uid(), metadata.gid(), 0o1777));
+		assert!(!found.is_world_writable());
+		assert_eq!(enriched[1].1.as_ref().unwrap_err().kind(), std::io::ErrorKind::NotFound);
+	}
+
+	#[test]
+	fn test_is_world_writable() {
+		let metadata = |mode: u32| MountPointMetadata { uid: 0, gid: 0, mode, birth_time: None, mount_id: None };
+		assert!(metadata(0o777).is_world_writable());
+		assert!(!metadata(0o1777).is_world_writable());
+		assert!(!metadata(0o755).is_world_writable());
+	}
+}
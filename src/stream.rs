@@ -0,0 +1,101 @@
+//! Runtime-agnostic asynchronous parsing of mount tables.
+//!
+//! [MountStream] parses lines from any [AsyncBufRead](futures_lite::io::AsyncBufRead), the trait from the `futures` family of crates that async-std, smol, and others implement for their files.  The crate doesn't depend on any particular runtime, so open the mount table with the file type of whichever runtime you already use.
+//!
+//! # Examples
+//! ```ignore
+//! use futures_lite::StreamExt;
+//! let file = async_std::fs::File::open("/proc/mounts").await?;
+//! let mut mounts = nom_tutorial::stream::MountStream::new(async_std::io::BufReader::new(file));
+//! while let Some(mount) = mounts.next().await {
+//!     println!("{}", mount?);
+//! }
+//! ```
+
+use super::{BoxError, Mount, MountEntry};
+use futures_lite::io::{AsyncBufRead, AsyncBufReadExt};
+use futures_lite::stream::{Stream, StreamExt};
+
+/// Asynchronous counterpart of [MountEntries](crate::MountEntries), yielding one parsed [MountEntry] per line of the reader.
+pub struct MountStream<R: AsyncBufRead + Unpin> {
+	lines: futures_lite::io::Lines<R>,
+	format: crate::model::Format
+}
+
+impl<R: AsyncBufRead + Unpin> MountStream<R> {
+	/// Returns a stream that parses `reader` in the format of `/proc/mounts`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::stream::MountStream;
+	/// use futures_lite::StreamExt;
+	/// let reader = futures_lite::io::Cursor::new("/dev/sda1 /mnt ext4 rw 0 0\n");
+	/// let mounts: Vec<_> = futures_lite::future::block_on(MountStream::new(reader).collect());
+	/// assert_eq!(mounts[0].as_ref().unwrap().mount_point, "/mnt");
+	/// ```
+	pub fn new(reader: R) -> MountStream<R> {
+		MountStream::with_format(reader, crate::model::Format::Mounts)
+	}
+
+	/// Returns a stream that parses `reader` in the format of `/proc/self/mountinfo`.
+	pub fn mountinfo(reader: R) -> MountStream<R> {
+		MountStream::with_format(reader, crate::model::Format::MountInfo)
+	}
+
+	/// Returns a stream that parses `reader` in the given format.
+	pub fn with_format(reader: R, format: crate::model::Format) -> MountStream<R> {
+		MountStream { lines: reader.lines(), format }
+	}
+}
+
+impl<R: AsyncBufRead + Unpin> Stream for MountStream<R> {
+	type Item = std::result::Result<MountEntry, BoxError>;
+
+	/// Returns the next line as a [MountEntry], or an error if the line couldn't be read or parsed.
+	fn poll_next(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<std::option::Option<Self::Item>> {
+		let format = self.format;
+		self.lines.poll_next(cx).map(|line| line.map(|line| Ok(crate::parsers::parse_entry(&line?, format)?)))
+	}
+}
+
+/// Reads every mount from `reader` in the format of `/proc/mounts`, stopping at the first error.
+pub async fn read_mounts<R: AsyncBufRead + Unpin>(reader: R) -> std::result::Result<std::vec::Vec<Mount>, BoxError> {
+	let mut stream = MountStream::new(reader);
+	let mut mounts = std::vec::Vec::new();
+	while let Some(mount) = stream.next().await {
+		mounts.push(mount?.mount);
+	}
+	Ok(mounts)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The stream yields the same mounts as the blocking iterator, in both formats.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_matches_blocking() {
+		let mountinfo = "22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro\n\
+			23 22 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:12 - proc proc rw\n\
+			45 22 8:17 /backups /media/My\\040Disk rw,nosuid master:3 propagate_from:2 - vfat /dev/sdb1 rw,fmask=0022\n";
+		let source = crate::source::StringSource::mountinfo(mountinfo.to_string());
+		let blocking: std::vec::Vec<MountEntry> = crate::Mounts::from_source(&source).unwrap().entries().map(|m| m.unwrap()).collect();
+		let stream = MountStream::mountinfo(futures_lite::io::Cursor::new(mountinfo));
+		let asynchronous: std::vec::Vec<MountEntry> = futures_lite::future::block_on(stream.map(|m| m.unwrap()).collect());
+		assert_eq!(blocking, asynchronous);
+		assert_eq!(asynchronous.len(), 3);
+
+		use crate::test_fixtures;
+		let mounts = test_fixtures::table(test_fixtures::ALL);
+		let blocking: std::vec::Vec<MountEntry> = test_fixtures::mounts(test_fixtures::ALL).entries().map(|m| m.unwrap()).collect();
+		let asynchronous: std::vec::Vec<MountEntry> = futures_lite::future::block_on(MountStream::new(futures_lite::io::Cursor::new(mounts)).map(|m| m.unwrap()).collect());
+		assert_eq!(blocking, asynchronous);
+		assert_eq!(asynchronous.len(), test_fixtures::ALL.len());
+	}
+
+	#[test]
+	fn test_read_mounts_error() {
+		let reader = futures_lite::io::Cursor::new("/dev/sda1 /mnt ext4 rw 0 0\ngarbage\n");
+		assert!(futures_lite::future::block_on(read_mounts(reader)).is_err());
+	}
+}
@@ -0,0 +1,111 @@
+//! Identifies the disk behind a mount by walking sysfs, without udev.
+//!
+//! `/sys/dev/block/<major>:<minor>` links to the device's directory in sysfs.  For a partition that directory sits inside the directory of the whole disk, which is where the kernel exposes the drive's model, serial number, and world wide identifier.  Not every driver provides all three, e.g. virtio disks have a serial number but no model.
+
+use super::Mount;
+
+/// Where sysfs is normally mounted.
+pub const SYSFS_PATH: &str = "/sys";
+
+/// The identity of a disk, see [Mount::disk_identity()].
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+pub struct DiskIdentity {
+	/// The kernel's name for the whole disk, e.g. sda or nvme0n1, even if the mount is on a partition.
+	pub disk: std::string::String,
+	/// The drive's model, e.g. Samsung SSD 860.
+	pub model: std::option::Option<std::string::String>,
+	/// The drive's serial number.
+	pub serial: std::option::Option<std::string::String>,
+	/// The drive's world wide identifier, e.g. naa.5002538e40a1b2c3 or eui.0025388b71b2c3d4.
+	pub wwid: std::option::Option<std::string::String>,
+}
+
+impl DiskIdentity {
+	/// Reads the identity of the disk containing the block device with the given major and minor number.  Returns `None` if sysfs doesn't know the device.
+	pub fn read(major: u32, minor: u32) -> std::result::Result<std::option::Option<DiskIdentity>, std::io::Error> {
+		DiskIdentity::read_from(std::path::Path::new(SYSFS_PATH), major, minor)
+	}
+
+	/// Like [DiskIdentity::read()], but with sysfs mounted at `sysfs` instead of `/sys`, e.g. in a container or a saved copy.
+	pub fn read_from(sysfs: &std::path::Path, major: u32, minor: u32) -> std::result::Result<std::option::Option<DiskIdentity>, std::io::Error> {
+		let device = match std::fs::canonicalize(sysfs.join("dev/block").join(format!("{}:{}", major, minor))) {
+			Ok(device) => device,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(e)
+		};
+		// Partitions have a `partition` attribute and live inside their disk's directory.
+		let disk = match device.parent() {
+			Some(parent) if device.join("partition").exists() => parent.to_path_buf(),
+			_ => device
+		};
+		let name = disk.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+		Ok(Some(DiskIdentity {
+			disk: name,
+			model: read_attribute(&disk, &["device/model"])?,
+			serial: read_attribute(&disk, &["device/serial", "serial"])?,
+			wwid: read_attribute(&disk, &["wwid", "device/wwid"])?,
+		}))
+	}
+}
+
+// Reads the first of `candidates` that exists under `directory`, with the padding drivers like to add trimmed off.  Empty attributes count as missing.
+fn read_attribute(directory: &std::path::Path, candidates: &[&str]) -> std::result::Result<std::option::Option<std::string::String>, std::io::Error> {
+	for candidate in candidates {
+		match std::fs::read_to_string(directory.join(candidate)) {
+			Ok(value) if value.trim().is_empty() => continue,
+			Ok(value) => return Ok(Some(value.trim().to_string())),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+			Err(e) => return Err(e)
+		}
+	}
+	Ok(None)
+}
+
+impl Mount {
+	/// Identifies the disk behind the mount from sysfs, using the device number from [Mount::device_number()].  Returns `None` for mounts without a backing block device.
+	/// # Examples
+	/// ```no_run
+	/// for mount in nom_tutorial::mountinfo().unwrap() {
+	///     let mount = mount.unwrap();
+	///     if let Some(identity) = mount.disk_identity().unwrap() {
+	///         println!("{} is on {} (model {:?}, serial {:?})", mount.mount_point, identity.disk, identity.model, identity.serial);
+	///     }
+	/// }
+	/// ```
+	pub fn disk_identity(&self) -> std::result::Result<std::option::Option<DiskIdentity>, std::io::Error> {
+		match self.device_number() {
+			// Major 0 is reserved for anonymous devices, which have no disk.
+			Some((major, minor)) if major != 0 => DiskIdentity::read(major, minor),
+			_ => Ok(None)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// A partition is resolved to its disk, whose padded attributes are trimmed.
+	#[test]
+	fn test_read_from_partition() {
+		let sysfs = std::env::temp_dir().join(format!("nom-tutorial-sysfs-{}", std::process::id()));
+		let disk = sysfs.join("devices/pci0000:00/ata1/block/sda");
+		std::fs::create_dir_all(disk.join("sda1")).unwrap();
+		std::fs::create_dir_all(disk.join("device")).unwrap();
+		std::fs::create_dir_all(sysfs.join("dev/block")).unwrap();
+		std::fs::write(disk.join("sda1/partition"), "1\n").unwrap();
+		std::fs::write(disk.join("device/model"), "Samsung SSD 860 \n").unwrap();
+		std::fs::write(disk.join("device/wwid"), "naa.5002538e40a1b2c3\n").unwrap();
+		std::os::unix::fs::symlink("../../devices/pci0000:00/ata1/block/sda/sda1", sysfs.join("dev/block/8:1")).unwrap();
+		let identity = DiskIdentity::read_from(&sysfs, 8, 1);
+		let missing = DiskIdentity::read_from(&sysfs, 8, 2);
+		std::fs::remove_dir_all(&sysfs).unwrap();
+		assert_eq!(identity.unwrap(), Some(DiskIdentity {
+			disk: "sda".to_string(),
+			model: Some("Samsung SSD 860".to_string()),
+			serial: None,
+			wwid: Some("naa.5002538e40a1b2c3".to_string()),
+		}));
+		assert_eq!(missing.unwrap(), None);
+	}
+}
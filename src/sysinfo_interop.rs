@@ -0,0 +1,126 @@
+//! Conversions to and from the [sysinfo](https://docs.rs/sysinfo) crate.  Enabled by the `sysinfo` feature.
+//!
+//! sysinfo lists disks with their capacity but not their mount options.  [compare()] lines up sysinfo's disks with a mount table read by this crate so the two can be cross-checked, and each pair can be used to enrich sysinfo's view with the full option list.
+
+use super::Mount;
+
+impl From<&::sysinfo::Disk> for Mount {
+	/// Converts a sysinfo disk to a [Mount].  sysinfo only knows whether the disk is read-only, so the options are just `ro` or `rw`.
+	fn from(disk: &::sysinfo::Disk) -> Mount {
+		Mount {
+			device: disk.name().to_string_lossy().into_owned(),
+			mount_point: disk.mount_point().to_string_lossy().into_owned(),
+			file_system_type: disk.file_system().to_string_lossy().into_owned(),
+			options: vec![if disk.is_read_only() { "ro" } else { "rw" }.to_string()]
+		}
+	}
+}
+
+/// A way in which a sysinfo disk and its mount disagree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+	/// The devices differ, e.g. because one of them reports a symlink.
+	Device {
+		/// The device according to sysinfo.
+		sysinfo: std::string::String,
+		/// The device according to the mount table.
+		mount: std::string::String
+	},
+	/// The filesystem types differ.
+	FileSystemType {
+		/// The filesystem type according to sysinfo.
+		sysinfo: std::string::String,
+		/// The filesystem type according to the mount table.
+		mount: std::string::String
+	},
+	/// One says the filesystem is read-only and the other doesn't.
+	ReadOnly {
+		/// Whether sysinfo says the disk is read-only.
+		sysinfo: bool,
+		/// Whether the mount has the `ro` option.
+		mount: bool
+	},
+}
+
+/// A sysinfo disk paired with its mount, see [compare()].
+#[derive(Debug)]
+pub struct DiskMatch<'a> {
+	/// The disk as reported by sysinfo.
+	pub disk: &'a ::sysinfo::Disk,
+	/// The mount with the same mount point, or `None` if there isn't one.
+	pub mount: std::option::Option<&'a Mount>,
+	/// Every disagreement between the disk and the mount.
+	pub mismatches: std::vec::Vec<Mismatch>,
+}
+
+/// Pairs each sysinfo disk with the mount at the same mount point and reports where they disagree.  If several mounts share the mount point the last, visible, one is used.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::sysinfo_interop::compare;
+/// let disks = sysinfo::Disks::new_with_refreshed_list();
+/// let mounts = nom_tutorial::mounts().unwrap().into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+/// for pair in compare(disks.list(), &mounts) {
+///     match pair.mount {
+///         Some(mount) => println!("{:?} has options {:?}, mismatches: {:?}", pair.disk.mount_point(), mount.options, pair.mismatches),
+///         None => println!("{:?} is not in the mount table", pair.disk.mount_point())
+///     }
+/// }
+/// ```
+pub fn compare<'a>(disks: &'a [::sysinfo::Disk], mounts: &'a [Mount]) -> std::vec::Vec<DiskMatch<'a>> {
+	disks.iter().map(|disk| {
+		let (mount, mismatches) = find_match(&Mount::from(disk), mounts);
+		DiskMatch { disk, mount, mismatches }
+	}).collect()
+}
+
+// Finds the mount of a disk converted with `Mount::from()`, and where the two disagree.
+fn find_match<'a>(disk: &Mount, mounts: &'a [Mount]) -> (std::option::Option<&'a Mount>, std::vec::Vec<Mismatch>) {
+	match mounts.iter().rev().find(|mount| mount.mount_point == disk.mount_point) {
+		Some(mount) => (Some(mount), mismatches(disk, mount)),
+		None => (None, std::vec::Vec::new())
+	}
+}
+
+// Compares a mount converted from a sysinfo disk with one from the mount table.
+fn mismatches(disk: &Mount, mount: &Mount) -> std::vec::Vec<Mismatch> {
+	let mut mismatches = std::vec::Vec::new();
+	if disk.device != mount.device {
+		mismatches.push(Mismatch::Device { sysinfo: disk.device.clone(), mount: mount.device.clone() });
+	}
+	if disk.file_system_type != mount.file_system_type {
+		mismatches.push(Mismatch::FileSystemType { sysinfo: disk.file_system_type.clone(), mount: mount.file_system_type.clone() });
+	}
+	let disk_ro = disk.options.iter().any(|option| option == "ro");
+	let mount_ro = mount.options.iter().any(|option| option == "ro");
+	if disk_ro != mount_ro {
+		mismatches.push(Mismatch::ReadOnly { sysinfo: disk_ro, mount: mount_ro });
+	}
+	mismatches
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_mismatches() {
+		let disk = Mount { device: "/dev/sda1".to_string(), file_system_type: "ext4".to_string(), options: vec!["rw".to_string()], ..Default::default() };
+		assert!(mismatches(&disk, &disk).is_empty());
+		let mount = Mount { device: "/dev/disk/by-uuid/x".to_string(), options: vec!["ro".to_string()], ..disk.clone() };
+		assert_eq!(mismatches(&disk, &mount), vec![
+			Mismatch::Device { sysinfo: "/dev/sda1".to_string(), mount: "/dev/disk/by-uuid/x".to_string() },
+			Mismatch::ReadOnly { sysinfo: false, mount: true },
+		]);
+	}
+
+	// Disks are matched with the last, visible, mount at their mount point, and a disk without a mount has no mismatches.
+	#[test]
+	fn test_find_match() {
+		let mount = |device: &str, mount_point: &str, options: &[&str]| crate::test_fixtures::mount(device, mount_point, "ext4", options);
+		let mounts = [mount("/dev/sda1", "/", &["rw"]), mount("/dev/sdb1", "/data", &["rw"]), mount("/dev/sdc1", "/data", &["ro"])];
+		let (found, mismatches) = find_match(&mount("/dev/sdc1", "/data", &["rw"]), &mounts);
+		assert_eq!(found.map(|mount| &mount.device[..]), Some("/dev/sdc1"));
+		assert_eq!(mismatches, [Mismatch::ReadOnly { sysinfo: false, mount: true }]);
+		assert_eq!(find_match(&mount("/dev/sdd1", "/backup", &["rw"]), &mounts), (None, vec![]));
+	}
+}
@@ -0,0 +1,183 @@
+//! Typed access to the `x-systemd.*` options of fstab entries, see `systemd.mount(5)`.
+//!
+//! systemd turns every line of `fstab` into a mount unit, and the `x-systemd.*` options are how that unit is configured: whether an automount unit is put in front of it, how long to wait for the device, and which other units it depends on.  The kernel never sees them because mount(8) drops `x-*` options before calling `mount(2)`.  Timeouts are time spans in the syntax of `systemd.time(7)`, e.g. `90`, `5min 20s`, or `infinity`.
+
+use super::fstab::FstabEntry;
+use super::options::{MountOption, OptionValueError};
+
+/// The `x-systemd.*` options of an fstab entry, see [FstabEntry::systemd_options()].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemdOptions {
+	/// True if the mount is started on first access through an automount unit (`x-systemd.automount`).
+	pub automount: bool,
+	/// How long an automounted filesystem may stay unused before it is unmounted (`x-systemd.idle-timeout=`).  Zero means never.
+	pub idle_timeout: std::option::Option<std::time::Duration>,
+	/// How long to wait for the device to show up (`x-systemd.device-timeout=`).  [Duration::MAX](std::time::Duration::MAX) stands for `infinity`.
+	pub device_timeout: std::option::Option<std::time::Duration>,
+	/// How long to wait for the mount command to finish (`x-systemd.mount-timeout=`).  [Duration::MAX](std::time::Duration::MAX) stands for `infinity`.
+	pub mount_timeout: std::option::Option<std::time::Duration>,
+	/// Units or device paths the mount requires and is ordered after (`x-systemd.requires=`).
+	pub requires: std::vec::Vec<std::string::String>,
+	/// Paths whose mounts the mount requires and is ordered after (`x-systemd.requires-mounts-for=`).
+	pub requires_mounts_for: std::vec::Vec<std::string::String>,
+	/// Units the mount is ordered before (`x-systemd.before=`).
+	pub before: std::vec::Vec<std::string::String>,
+	/// Units the mount is ordered after (`x-systemd.after=`).
+	pub after: std::vec::Vec<std::string::String>,
+	/// Units that want the mount (`x-systemd.wanted-by=`).
+	pub wanted_by: std::vec::Vec<std::string::String>,
+	/// Units that require the mount (`x-systemd.required-by=`).
+	pub required_by: std::vec::Vec<std::string::String>,
+	/// True if a filesystem is created on the device if it has none (`x-systemd.makefs`).
+	pub makefs: bool,
+	/// True if the filesystem is grown to fill the device when mounted (`x-systemd.growfs`).
+	pub growfs: bool,
+	/// True if the mount fails rather than falling back to read-only (`x-systemd.rw-only`).
+	pub rw_only: bool,
+}
+
+impl FstabEntry {
+	/// Collects the entry's `x-systemd.*` options.  Options that may be given more than once accumulate in order, and for the others the last one wins.  Unknown `x-systemd.*` options are ignored so that entries written for newer versions of systemd still parse.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::fstab::FstabEntry;
+	/// # use std::time::Duration;
+	/// let entry = FstabEntry::new("server:/export", "/mnt/data", "nfs", &["noauto", "x-systemd.automount", "x-systemd.idle-timeout=5min", "x-systemd.requires=network-online.target"]);
+	/// let systemd = entry.systemd_options().unwrap();
+	/// assert!(systemd.automount);
+	/// assert_eq!(systemd.idle_timeout, Some(Duration::from_secs(300)));
+	/// assert_eq!(systemd.requires, ["network-online.target"]);
+	/// assert!(FstabEntry::new("/dev/sdb1", "/mnt", "ext4", &["x-systemd.device-timeout=soon"]).systemd_options().is_err());
+	/// ```
+	pub fn systemd_options(&self) -> std::result::Result<SystemdOptions, OptionValueError> {
+		let mut systemd = SystemdOptions::default();
+		for option in self.options.iter().map(|option| MountOption::parse(option)) {
+			let name = match option.name.strip_prefix("x-systemd.") {
+				Some(name) => name,
+				None => continue
+			};
+			let value = || option.value.map(str::to_string).ok_or_else(|| OptionValueError::new(&option, None));
+			let timeout = || option.value.and_then(time_span).ok_or_else(|| OptionValueError::new(&option, None));
+			match name {
+				"automount" => systemd.automount = true,
+				"idle-timeout" => systemd.idle_timeout = Some(timeout()?),
+				"device-timeout" => systemd.device_timeout = Some(timeout()?),
+				"mount-timeout" => systemd.mount_timeout = Some(timeout()?),
+				"requires" => systemd.requires.push(value()?),
+				"requires-mounts-for" => systemd.requires_mounts_for.extend(value()?.split_whitespace().map(str::to_string)),
+				"before" => systemd.before.push(value()?),
+				"after" => systemd.after.push(value()?),
+				"wanted-by" => systemd.wanted_by.push(value()?),
+				"required-by" => systemd.required_by.push(value()?),
+				"makefs" => systemd.makefs = true,
+				"growfs" => systemd.growfs = true,
+				"rw-only" => systemd.rw_only = true,
+				_ => ()
+			}
+		}
+		Ok(systemd)
+	}
+}
+
+/// Parses a time span in the syntax of `systemd.time(7)`: numbers with units, optionally separated by spaces and summed, e.g. `1h 30min`.  A number without a unit is in seconds, and `infinity` becomes [Duration::MAX](std::time::Duration::MAX).
+/// # Examples
+/// ```
+/// # use nom_tutorial::systemd::time_span;
+/// # use std::time::Duration;
+/// assert_eq!(time_span("90"), Some(Duration::from_secs(90)));
+/// assert_eq!(time_span("1h 30min"), Some(Duration::from_secs(5400)));
+/// assert_eq!(time_span("1.5s"), Some(Duration::from_millis(1500)));
+/// assert_eq!(time_span("3 fortnights"), None);
+/// ```
+pub fn time_span(value: &str) -> std::option::Option<std::time::Duration> {
+	let value = value.trim();
+	if value == "infinity" {
+		return Some(std::time::Duration::MAX);
+	}
+	let (rest, parts) = parsers::time_span(value).ok()?;
+	if !rest.is_empty() {
+		return None;
+	}
+	let mut seconds = 0.0;
+	for (number, unit) in parts {
+		seconds += number.parse::<f64>().ok()? * unit_seconds(unit)?;
+	}
+	std::time::Duration::try_from_secs_f64(seconds).ok()
+}
+
+// The length of a time span unit in seconds, with months and years averaged as systemd does.
+fn unit_seconds(unit: &str) -> std::option::Option<f64> {
+	Some(match unit {
+		"nsec" | "ns" => 1e-9,
+		"usec" | "us" | "µs" | "μs" => 1e-6,
+		"msec" | "ms" => 1e-3,
+		"seconds" | "second" | "sec" | "s" | "" => 1.0,
+		"minutes" | "minute" | "min" | "m" => 60.0,
+		"hours" | "hour" | "hr" | "h" => 60.0 * 60.0,
+		"days" | "day" | "d" => 24.0 * 60.0 * 60.0,
+		"weeks" | "week" | "w" => 7.0 * 24.0 * 60.0 * 60.0,
+		"months" | "month" | "M" => 30.44 * 24.0 * 60.0 * 60.0,
+		"years" | "year" | "y" => 365.25 * 24.0 * 60.0 * 60.0,
+		_ => return None
+	})
+}
+
+mod parsers {
+	// A number with an optional fraction, e.g. 1.5
+	fn number(i: &str) -> nom::IResult<&str, &str> {
+		nom::combinator::recognize(nom::sequence::pair(
+			nom::character::complete::digit1,
+			nom::combinator::opt(nom::sequence::pair(nom::character::complete::char('.'), nom::character::complete::digit1))
+		))(i)
+	}
+
+	// A unit such as min or µs, possibly empty.
+	fn unit(i: &str) -> nom::IResult<&str, &str> {
+		nom::bytes::complete::take_while(|c: char| c.is_alphabetic())(i)
+	}
+
+	// Splits a time span into numbers and their units.
+	pub fn time_span(i: &str) -> nom::IResult<&str, std::vec::Vec<(&str, &str)>> {
+		nom::multi::many1(nom::sequence::delimited(
+			nom::character::complete::space0,
+			nom::sequence::separated_pair(number, nom::character::complete::space0, unit),
+			nom::character::complete::space0
+		))(i)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Repeatable options accumulate, the last timeout wins, and unknown options are ignored.
+	#[test]
+	fn test_systemd_options() {
+		let entry = FstabEntry::new("/dev/sdb1", "/srv", "ext4", &[
+			"defaults", "x-systemd.requires=a.service", "x-systemd.device-timeout=10", "x-systemd.requires=/dev/sdc1",
+			"x-systemd.device-timeout=infinity", "x-systemd.requires-mounts-for=/var /home", "x-systemd.makefs", "x-systemd.wanted-by=b.target", "x-systemd.frobnicate",
+		]);
+		let expected = SystemdOptions {
+			device_timeout: Some(std::time::Duration::MAX),
+			requires: vec!["a.service".to_string(), "/dev/sdc1".to_string()],
+			requires_mounts_for: vec!["/var".to_string(), "/home".to_string()],
+			wanted_by: vec!["b.target".to_string()],
+			makefs: true,
+			..Default::default()
+		};
+		assert_eq!(entry.systemd_options(), Ok(expected));
+		assert_eq!(FstabEntry::new("/dev/sdb1", "/srv", "ext4", &["defaults"]).systemd_options(), Ok(SystemdOptions::default()));
+		assert!(FstabEntry::new("/dev/sdb1", "/srv", "ext4", &["x-systemd.requires"]).systemd_options().is_err());
+	}
+
+	#[test]
+	fn test_time_span() {
+		assert_eq!(time_span("0"), Some(std::time::Duration::ZERO));
+		assert_eq!(time_span("2d4h"), Some(std::time::Duration::from_secs(2 * 86400 + 4 * 3600)));
+		assert_eq!(time_span(" 5 min 20 s "), Some(std::time::Duration::from_secs(320)));
+		assert_eq!(time_span("300ms"), Some(std::time::Duration::from_millis(300)));
+		assert_eq!(time_span(""), None);
+		assert_eq!(time_span("-5s"), None);
+		assert_eq!(time_span("5s-"), None);
+	}
+}
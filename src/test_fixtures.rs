@@ -0,0 +1,119 @@
+//! A corpus of real-world `/proc/mounts` lines for testing code built on this crate.  Enabled by the `test-utils` feature, and always available to the crate's own tests.
+//!
+//! Each constant is a single line as the kernel would print it, without the trailing newline.  [ALL] lists every fixture, and the helper functions turn any selection of lines into a [Mounts] or a vector of parsed [Mount]s.
+//! # Examples
+//! ```
+//! # use nom_tutorial::test_fixtures;
+//! let mounts = test_fixtures::parsed(&[test_fixtures::NFS4, test_fixtures::CIFS]);
+//! assert_eq!(mounts[1].device, "//fileserver/Shared Docs");
+//! ```
+
+#[cfg(feature = "io")]
+use super::{Mount, Mounts};
+#[cfg(feature = "io")]
+use super::source::StringSource;
+
+/// The root filesystem on an ext4 partition.
+pub const EXT4_ROOT: &str = "/dev/sda2 / ext4 rw,relatime,errors=remount-ro 0 0";
+/// The proc pseudo-filesystem.
+pub const PROC: &str = "proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0";
+/// The sysfs pseudo-filesystem.
+pub const SYSFS: &str = "sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0";
+/// The unified cgroup v2 hierarchy.
+pub const CGROUP2: &str = "cgroup2 /sys/fs/cgroup cgroup2 rw,nosuid,nodev,noexec,relatime,nsdelegate,memory_recursiveprot 0 0";
+/// A size-limited tmpfs.
+pub const TMPFS: &str = "tmpfs /run tmpfs rw,nosuid,nodev,noexec,relatime,size=1631280k,mode=755,inode64 0 0";
+/// An NFSv4 export.
+pub const NFS4: &str = "nas.example.com:/export/home /home nfs4 rw,relatime,vers=4.2,rsize=1048576,wsize=1048576,namlen=255,hard,proto=tcp,timeo=600,retrans=2,sec=sys,clientaddr=192.168.1.10,local_lock=none,addr=192.168.1.2 0 0";
+/// An NFSv3 export from a server addressed by IPv6 literal.
+pub const NFS3_IPV6: &str = "[fd00::2]:/srv/exports/scratch /mnt/scratch nfs rw,relatime,vers=3,rsize=524288,wsize=524288,namlen=255,hard,proto=tcp6,timeo=600,retrans=2,sec=sys,mountaddr=fd00::2,mountvers=3,mountport=20048,mountproto=udp6,local_lock=none,addr=fd00::2 0 0";
+/// A CIFS share whose name contains a space.
+pub const CIFS: &str = "//fileserver/Shared\\040Docs /mnt/shared cifs rw,relatime,vers=3.1.1,cache=strict,username=alice,domain=CORP,uid=1000,forceuid,gid=1000,forcegid,addr=10.0.0.5,file_mode=0755,dir_mode=0755,soft,nounix,serverino,mapposix,rsize=4194304,wsize=4194304,bsize=1048576,echo_interval=60,actimeo=1 0 0";
+/// A Docker overlay filesystem with several lower layers.
+pub const OVERLAY: &str = "overlay /var/lib/docker/overlay2/3f9c2a/merged overlay rw,relatime,lowerdir=/var/lib/docker/overlay2/l/AAAA:/var/lib/docker/overlay2/l/BBBB:/var/lib/docker/overlay2/l/CCCC,upperdir=/var/lib/docker/overlay2/3f9c2a/diff,workdir=/var/lib/docker/overlay2/3f9c2a/work 0 0";
+/// A btrfs subvolume.
+pub const BTRFS_SUBVOL: &str = "/dev/nvme0n1p3 /home btrfs rw,relatime,ssd,space_cache=v2,subvolid=257,subvol=/@home 0 0";
+/// An sshfs filesystem mounted through FUSE.
+pub const FUSE_SSHFS: &str = "alice@build.example.com:/home/alice /home/alice/remote fuse.sshfs rw,nosuid,nodev,relatime,user_id=1000,group_id=1000 0 0";
+/// A removable vfat drive with a space and a tab in its label.
+pub const ESCAPED_WHITESPACE: &str = "/dev/sdb1 /media/alice/My\\040Drive\\011(2) vfat rw,nosuid,nodev,relatime,uid=1000,gid=1000,fmask=0022,dmask=0022,codepage=437,iocharset=ascii,shortname=mixed,showexec,utf8,flush,errors=remount-ro 0 0";
+/// A mount point containing a backslash and a newline, escaped by the kernel in octal.
+pub const ESCAPED_OCTAL: &str = "/dev/sdc1 /mnt/back\\134slash\\012newline ext4 rw,relatime 0 0";
+/// An Android-style f2fs data partition with a very long option list.
+pub const HUGE_OPTIONS: &str = "/dev/block/dm-5 /data f2fs rw,lazytime,seclabel,nosuid,nodev,noatime,background_gc=on,discard,no_heap,user_xattr,inline_xattr,acl,inline_data,inline_dentry,flush_merge,extent_cache,mode=adaptive,active_logs=6,reserve_root=32768,resuid=0,resgid=1065,inlinecrypt,alloc_mode=default,checkpoint_merge,fsync_mode=nobarrier,compress_algorithm=lz4,compress_log_size=2,compress_extension=apk,compress_extension=so,compress_mode=fs,atgc,discard_unit=block,memory=normal 0 0";
+/// Android shared storage, an sdcardfs view of the data partition.
+pub const ANDROID_SDCARDFS: &str = "/data/media /storage/emulated sdcardfs rw,nosuid,nodev,noexec,noatime,fsuid=1023,fsgid=1023,gid=1015,multiuser,mask=6,derive_gid,default_normal 0 0";
+/// An Android vendor firmware partition labeled with a `context=` option.
+pub const ANDROID_CONTEXT: &str = "/dev/block/bootdevice/by-name/modem_a /vendor/firmware_mnt vfat ro,context=u:object_r:firmware_file:s0,relatime,uid=1000,gid=1000,fmask=0337,dmask=0227,codepage=437,iocharset=iso8859-1,shortname=lower,errors=remount-ro 0 0";
+/// A snap package mounted from a squashfs loop device.
+pub const SNAP_SQUASHFS: &str = "/dev/loop3 /snap/core22/1122 squashfs ro,nodev,relatime,errors=continue,threads=single 0 0";
+/// A ZFS dataset.
+pub const ZFS: &str = "tank/data/projects /tank/projects zfs rw,xattr,posixacl 0 0";
+/// A virtiofs share exported by a hypervisor.
+pub const VIRTIOFS: &str = "hostshare /mnt/host virtiofs rw,relatime 0 0";
+
+/// Every fixture in this module.
+pub const ALL: &[&str] = &[
+	EXT4_ROOT, PROC, SYSFS, CGROUP2, TMPFS, NFS4, NFS3_IPV6, CIFS, OVERLAY, BTRFS_SUBVOL, FUSE_SSHFS,
+	ESCAPED_WHITESPACE, ESCAPED_OCTAL, HUGE_OPTIONS, ANDROID_SDCARDFS, ANDROID_CONTEXT, SNAP_SQUASHFS, ZFS, VIRTIOFS,
+];
+
+/// Joins `lines` into a newline-terminated mount table.
+pub fn table(lines: &[&str]) -> std::string::String {
+	let mut table = std::string::String::new();
+	for line in lines {
+		table.push_str(line);
+		table.push('\n');
+	}
+	table
+}
+
+/// Returns a [StringSource] serving `lines`.
+#[cfg(feature = "io")]
+pub fn source(lines: &[&str]) -> StringSource {
+	StringSource::new(table(lines))
+}
+
+/// Returns a [Mounts] that iterates over `lines`.
+#[cfg(feature = "io")]
+pub fn mounts(lines: &[&str]) -> Mounts<StringSource> {
+	// Opening a StringSource cannot fail.
+	Mounts::from_source(&source(lines)).unwrap()
+}
+
+// A mount with the given fields, for the crate's own tests.
+#[cfg(test)]
+pub(crate) fn mount(device: &str, mount_point: &str, file_system_type: &str, options: &[&str]) -> super::Mount {
+	super::Mount {
+		device: device.to_string(),
+		mount_point: mount_point.to_string(),
+		file_system_type: file_system_type.to_string(),
+		options: options.iter().map(|o| o.to_string()).collect()
+	}
+}
+
+/// Parses `lines`, panicking if any of them fail to parse.
+#[cfg(feature = "io")]
+pub fn parsed(lines: &[&str]) -> std::vec::Vec<Mount> {
+	mounts(lines).into_iter().map(|mount| mount.expect("fixture failed to parse")).collect()
+}
+
+#[cfg(all(test, feature = "io"))]
+mod tests {
+	use super::*;
+
+	// Every fixture parses and survives a round trip through `Mount::to_proc_line()`.
+	#[test]
+	fn test_all_fixtures_round_trip() {
+		for (line, mount) in ALL.iter().zip(parsed(ALL)) {
+			assert_eq!(&mount.to_proc_line(), line);
+		}
+	}
+
+	#[test]
+	fn test_escaped_fixtures() {
+		let mounts = parsed(&[ESCAPED_WHITESPACE, ESCAPED_OCTAL]);
+		assert_eq!(mounts[0].mount_point, "/media/alice/My Drive\t(2)");
+		assert_eq!(mounts[1].mount_point, "/mnt/back\\slash\nnewline");
+	}
+}
@@ -0,0 +1,222 @@
+//! The hierarchy of mounts, each mounted on top of another.
+
+use super::MountEntry;
+use super::snapshot::MountSnapshot;
+
+/// The mounts of a [MountSnapshot] arranged as a tree, where each mount's parent is the mount it is mounted on.
+///
+/// For mounts read from `/proc/self/mountinfo` the tree follows the kernel's parent IDs.  Otherwise each mount's parent is guessed to be the most recent mount before it in the table at its mount point, or failing that at the closest directory above it.  Mounts whose parent isn't in the snapshot, such as the root, are roots of the tree.
+/// # Examples
+/// ```
+/// # use nom_tutorial::tree::MountTree;
+/// # use nom_tutorial::snapshot::MountSnapshot;
+/// # use nom_tutorial::model::Format;
+/// let snapshot = MountSnapshot::parse("1 0 8:1 / / rw - ext4 /dev/sda1 rw\n2 1 0:30 / /tmp rw - tmpfs tmpfs rw\n", Format::MountInfo).unwrap();
+/// let tree = MountTree::new(&snapshot);
+/// println!("{}", tree.to_dot());
+/// ```
+#[derive(Clone, Debug)]
+pub struct MountTree<'a> {
+	mounts: &'a [MountEntry],
+	parents: std::vec::Vec<std::option::Option<usize>>,
+}
+
+impl<'a> MountTree<'a> {
+	/// Arranges the mounts of `snapshot` in a tree.
+	pub fn new(snapshot: &'a MountSnapshot) -> MountTree<'a> {
+		let mounts = snapshot.mounts();
+		let parents = (0..mounts.len()).map(|index| find_parent(mounts, index)).collect();
+		MountTree { mounts, parents }
+	}
+
+	/// The mounts in the tree, in mount table order.  Mounts are identified by their index in this slice.
+	pub fn mounts(&self) -> &'a [MountEntry] {
+		self.mounts
+	}
+
+	/// The index of the mount that mount `index` is mounted on, if it is in the tree.
+	pub fn parent(&self, index: usize) -> std::option::Option<usize> {
+		self.parents[index]
+	}
+
+	/// The indices of the mounts mounted on mount `index`, in mount table order.
+	pub fn children(&self, index: usize) -> std::vec::Vec<usize> {
+		(0..self.mounts.len()).filter(|&child| self.parents[child] == Some(index)).collect()
+	}
+
+	/// The indices of the mounts without a parent in the tree, in mount table order.
+	pub fn roots(&self) -> std::vec::Vec<usize> {
+		(0..self.mounts.len()).filter(|&index| self.parents[index].is_none()).collect()
+	}
+
+	/// True if mount `ancestor` is mount `index`'s parent, its parent's parent, and so on.
+	pub fn is_ancestor(&self, ancestor: usize, index: usize) -> bool {
+		let mut current = self.parents[index];
+		// Guard against cycles, which a real mount table can't have but a damaged one might.
+		for _ in 0..self.mounts.len() {
+			match current {
+				Some(parent) if parent == ancestor => return true,
+				Some(parent) => current = self.parents[parent],
+				None => return false
+			}
+		}
+		false
+	}
+
+	/// Finds the mounts that are hidden because another mount was later mounted on their mount point or on a directory above it, which makes them unreachable from the root.  Hidden mounts still hold on to their filesystem, so they confuse disk usage accounting: the space is used, but `du` can't find it.
+	///
+	/// A mount is hidden by any mount over its mount point that comes later in the mount table.  For mounts read from `/proc/self/mountinfo` the tree decides instead where it can: a mount is never hidden by one of its ancestors, and always by a descendant mounted over it.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// # use nom_tutorial::tree::{MountTree, Shadowed};
+	/// let mount = |device: &str, mount_point: &str| Mount { device: device.to_string(), mount_point: mount_point.to_string(), ..Default::default() };
+	/// let snapshot = MountSnapshot::new(vec![mount("a", "/"), mount("b", "/srv"), mount("c", "/srv/www"), mount("d", "/srv")]);
+	/// assert_eq!(MountTree::new(&snapshot).shadowed(), [Shadowed { index: 1, by: 3 }, Shadowed { index: 2, by: 3 }]);
+	/// ```
+	pub fn shadowed(&self) -> std::vec::Vec<Shadowed> {
+		let mut shadowed = std::vec::Vec::new();
+		for (index, mount) in self.mounts.iter().enumerate() {
+			let path = std::path::Path::new(&mount.mount_point);
+			let by = (0..self.mounts.len()).rev().find(|&other| {
+				let hides = match (&mount.mountinfo, &self.mounts[other].mountinfo) {
+					(Some(_), Some(_)) => other != index && !self.is_ancestor(other, index) && (other > index || self.is_ancestor(index, other)),
+					_ => other > index
+				};
+				hides && path.starts_with(&self.mounts[other].mount_point)
+			});
+			if let Some(by) = by {
+				shadowed.push(Shadowed { index, by });
+			}
+		}
+		shadowed
+	}
+
+	/// Renders the tree as a Graphviz graph with an arrow from each mount to the mounts on top of it.  Render it with e.g. `dot -Tsvg`.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::snapshot::MountSnapshot;
+	/// # use nom_tutorial::tree::MountTree;
+	/// let mount = |mount_point: &str| Mount { device: "/dev/sda1".to_string(), mount_point: mount_point.to_string(), file_system_type: "ext4".to_string(), ..Default::default() };
+	/// let snapshot = MountSnapshot::new(vec![mount("/"), mount("/home")]);
+	/// assert!(MountTree::new(&snapshot).to_dot().contains("\tm0 -> m1;\n"));
+	/// ```
+	pub fn to_dot(&self) -> std::string::String {
+		self.render_dot(false)
+	}
+
+	/// Like [MountTree::to_dot()], but also joins mounts of the same device with dashed lines, which makes bind mounts and filesystems mounted in several places stand out.
+	pub fn to_dot_with_devices(&self) -> std::string::String {
+		self.render_dot(true)
+	}
+
+	fn render_dot(&self, devices: bool) -> std::string::String {
+		let mut dot = std::string::String::from("digraph mounts {\n\tnode [shape=box];\n");
+		for (index, mount) in self.mounts.iter().enumerate() {
+			let label = format!("{}\n{} on {}", mount.mount_point, mount.file_system_type, mount.device);
+			dot.push_str(&format!("\tm{} [label=\"{}\"];\n", index, escape_dot(&label)));
+		}
+		for (index, parent) in self.parents.iter().enumerate() {
+			if let Some(parent) = parent {
+				dot.push_str(&format!("\tm{} -> m{};\n", parent, index));
+			}
+		}
+		if devices {
+			// Join each mount to the first other mount of its device, rather than every pair, to keep the graph readable.
+			let mut first = std::collections::HashMap::<&str, usize>::new();
+			for (index, mount) in self.mounts.iter().enumerate() {
+				match first.get(&mount.device[..]) {
+					Some(&other) => dot.push_str(&format!("\tm{} -> m{} [style=dashed, dir=none, constraint=false];\n", other, index)),
+					None => { first.insert(&mount.device, index); }
+				}
+			}
+		}
+		dot.push_str("}\n");
+		dot
+	}
+}
+
+/// A mount hidden by another mount, see [MountTree::shadowed()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Shadowed {
+	/// The index of the hidden mount.
+	pub index: usize,
+	/// The index of the mount hiding it.  If several do, the last one in the mount table.
+	pub by: usize,
+}
+
+// Finds the parent of mount `index`, see [MountTree].
+fn find_parent(mounts: &[MountEntry], index: usize) -> std::option::Option<usize> {
+	let mount = &mounts[index];
+	if let Some(info) = &mount.mountinfo {
+		return mounts.iter().position(|other| other.mountinfo.as_ref().is_some_and(|other| other.mount_id == info.parent_id && other.mount_id != info.mount_id));
+	}
+	let path = std::path::Path::new(&mount.mount_point);
+	// A mount at the same mount point that was mounted earlier is underneath this one.
+	if let Some(below) = (0..index).rev().find(|&other| mounts[other].mount_point == mount.mount_point) {
+		return Some(below);
+	}
+	// Otherwise the parent is the most recent earlier mount at the closest directory above.  A mount can't be on top of one made after it, whereas mountinfo parent IDs are exact whatever the table order.
+	mounts[..index].iter().enumerate()
+		.filter(|(_, other)| {
+			let other = std::path::Path::new(&other.mount_point);
+			other != path && path.starts_with(other)
+		})
+		.max_by_key(|(other, parent)| (parent.mount_point.len(), *other))
+		.map(|(other, _)| other)
+}
+
+// Escapes a string for a double-quoted Graphviz ID.
+fn escape_dot(text: &str) -> std::string::String {
+	text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Mount;
+
+	#[test]
+	fn test_tree_from_mount_points() {
+		let mount = |device: &str, mount_point: &str| Mount { device: device.to_string(), mount_point: mount_point.to_string(), ..Default::default() };
+		let snapshot = MountSnapshot::new(vec![mount("a", "/"), mount("b", "/srv"), mount("c", "/srv/www"), mount("d", "/srv"), mount("a", "/mnt")]);
+		let tree = MountTree::new(&snapshot);
+		assert_eq!(tree.roots(), vec![0]);
+		assert_eq!(tree.children(0), vec![1, 4]);
+		assert_eq!(tree.parent(3), Some(1));
+		// /srv/www is on the /srv that was on top when it was mounted, not on the one mounted over it later.
+		assert_eq!(tree.parent(2), Some(1));
+		assert!(tree.to_dot_with_devices().contains("\tm0 -> m4 [style=dashed"));
+	}
+
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_tree_from_mountinfo() {
+		let source = crate::source::StringSource::mountinfo("\
+			20 1 0:1 / / rw - rootfs rootfs rw\n\
+			22 20 0:2 / /a\"b rw - tmpfs tmpfs rw\n");
+		let snapshot = MountSnapshot::from_source(&source).unwrap();
+		let tree = MountTree::new(&snapshot);
+		assert_eq!(tree.roots(), vec![0]);
+		assert_eq!(tree.parent(1), Some(0));
+		assert!(tree.to_dot().contains("label=\"/a\\\"b\\ntmpfs on tmpfs\""));
+	}
+
+	// Overmounting /srv hides the old /srv and everything below it, but not the mounts made on top of the new one.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_shadowed_mountinfo() {
+		let source = crate::source::StringSource::mountinfo("\
+			20 1 0:1 / / rw - rootfs rootfs rw\n\
+			30 20 0:3 / /srv rw - tmpfs tmpfs rw\n\
+			21 30 0:2 / /srv/www rw - tmpfs tmpfs rw\n\
+			22 30 0:4 / /srv rw - tmpfs tmpfs rw\n\
+			23 22 0:5 / /srv/www rw - tmpfs tmpfs rw\n");
+		let snapshot = MountSnapshot::from_source(&source).unwrap();
+		let tree = MountTree::new(&snapshot);
+		assert!(tree.is_ancestor(1, 4) && !tree.is_ancestor(3, 2));
+		assert_eq!(tree.shadowed(), [Shadowed { index: 1, by: 3 }, Shadowed { index: 2, by: 4 }]);
+	}
+}
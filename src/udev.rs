@@ -0,0 +1,98 @@
+//! Reads properties of a mount's backing device from the udev database.
+//!
+//! udevd records what it knows about every device in `/run/udev/data`, one file per device named after its major and minor number, e.g. `b8:1` for /dev/sda1.  This is the same database libudev and `udevadm info` read, so looking up a label or serial number needs neither libudev nor a subprocess.
+
+use super::Mount;
+
+/// Where udevd keeps its database.
+pub const UDEV_DATA_DIR: &str = "/run/udev/data";
+
+/// The udev properties of a block device, see [Mount::device_properties()].
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct DeviceProperties {
+	/// The filesystem label with unsafe characters replaced, e.g. My_Disk  (`ID_FS_LABEL`)
+	pub label: std::option::Option<std::string::String>,
+	/// The serial number of the drive, usually prefixed by its model, e.g. Samsung_SSD_860_S3Z9NB0K123456  (`ID_SERIAL`)
+	pub serial: std::option::Option<std::string::String>,
+	/// The bus the drive is attached to, e.g. ata, usb, or scsi  (`ID_BUS`)
+	pub bus: std::option::Option<std::string::String>,
+	/// Every property in the database entry, sorted by name.
+	pub all: std::collections::BTreeMap<std::string::String, std::string::String>,
+}
+
+impl DeviceProperties {
+	/// Parses the contents of a udev database entry.  Only the `E:` (environment) lines hold properties; the other lines record symlinks, tags, and internal state and are skipped.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::udev::DeviceProperties;
+	/// let properties = DeviceProperties::parse("S:disk/by-label/Backup\nE:ID_FS_LABEL=Backup\nE:ID_BUS=usb\n");
+	/// assert_eq!(properties.label.as_deref(), Some("Backup"));
+	/// assert_eq!(properties.bus.as_deref(), Some("usb"));
+	/// assert_eq!(properties.serial, None);
+	/// ```
+	pub fn parse(text: &str) -> DeviceProperties {
+		let all: std::collections::BTreeMap<std::string::String, std::string::String> = text.lines()
+			.filter_map(|line| line.strip_prefix("E:"))
+			.filter_map(|property| {
+				let mut split = property.splitn(2, '=');
+				Some((split.next()?.to_string(), split.next()?.to_string()))
+			})
+			.collect();
+		DeviceProperties {
+			label: all.get("ID_FS_LABEL").cloned(),
+			serial: all.get("ID_SERIAL").cloned(),
+			bus: all.get("ID_BUS").cloned(),
+			all
+		}
+	}
+
+	/// Reads the database entry of the block device with the given major and minor number.  Returns `None` if udev has no entry for it.
+	pub fn read(major: u32, minor: u32) -> std::result::Result<std::option::Option<DeviceProperties>, std::io::Error> {
+		let path = std::path::Path::new(UDEV_DATA_DIR).join(format!("b{}:{}", major, minor));
+		match std::fs::read_to_string(path) {
+			Ok(text) => Ok(Some(DeviceProperties::parse(&text))),
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+			Err(e) => Err(e)
+		}
+	}
+}
+
+impl Mount {
+	/// Looks up the udev properties of the mount's backing device, found with [Mount::device_number()].  Returns `None` for mounts without a backing device and for devices udev doesn't know about, e.g. when udevd isn't running.
+	/// # Examples
+	/// ```no_run
+	/// for mount in nom_tutorial::mounts().unwrap() {
+	///     let mount = mount.unwrap();
+	///     if let Some(properties) = mount.device_properties().unwrap() {
+	///         println!("{} label={:?} serial={:?} bus={:?}", mount.mount_point, properties.label, properties.serial, properties.bus);
+	///     }
+	/// }
+	/// ```
+	pub fn device_properties(&self) -> std::result::Result<std::option::Option<DeviceProperties>, std::io::Error> {
+		match self.device_number() {
+			// Major 0 is reserved for anonymous devices, which udev never sees.
+			Some((major, minor)) if major != 0 => DeviceProperties::read(major, minor),
+			_ => Ok(None)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		let properties = DeviceProperties::parse("I:123456\nE:ID_SERIAL=WDC_WD40EFRX-68N32N0_WD-WCC7K1234567\nE:ID_FS_UUID=a=b\nG:systemd\n");
+		assert_eq!(properties.serial.as_deref(), Some("WDC_WD40EFRX-68N32N0_WD-WCC7K1234567"));
+		assert_eq!(properties.all["ID_FS_UUID"], "a=b");
+		assert_eq!(properties.all.len(), 2);
+	}
+
+	// Pseudo-filesystems have no backing device to look up.
+	#[test]
+	fn test_no_backing_device() {
+		let mount = Mount { device: "tmpfs".to_string(), ..Default::default() };
+		assert_eq!(mount.device_properties().unwrap(), None);
+	}
+}
@@ -0,0 +1,130 @@
+//! Enriches mounts with drive metadata from UDisks2 over D-Bus.
+//!
+//! Desktop tools usually want to know more about a mount than the kernel's mount table says, e.g. whether the drive behind it is a USB stick that can be ejected.  UDisks2 keeps track of this, and [UDisks] asks it about the device of each [Mount].
+
+use super::{BoxError, Mount};
+
+// Well-known bus name of the UDisks2 daemon.
+const SERVICE: &str = "org.freedesktop.UDisks2";
+
+/// Metadata about the drive behind a mount, as reported by UDisks2's `org.freedesktop.UDisks2.Drive` interface.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash)]
+pub struct Drive {
+	/// The drive's vendor, e.g. SanDisk, or empty if unknown.
+	pub vendor: std::string::String,
+	/// The drive's model, e.g. Ultra Fit, or empty if unknown.
+	pub model: std::string::String,
+	/// The drive's serial number, or empty if unknown.
+	pub serial: std::string::String,
+	/// True if the drive itself can be removed, e.g. a USB stick.
+	pub removable: bool,
+	/// True if the media in the drive can be removed, e.g. a card reader or optical drive.
+	pub media_removable: bool,
+	/// True if the drive can be ejected.
+	pub ejectable: bool,
+}
+
+/// A connection to UDisks2 on the system bus.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::udisks::UDisks;
+/// let udisks = UDisks::connect().unwrap();
+/// for mount in nom_tutorial::mounts().unwrap() {
+///     let mount = mount.unwrap();
+///     if let Some(drive) = mount.drive(&udisks).unwrap() {
+///         println!("{} is on a {} {} (removable: {})", mount.mount_point, drive.vendor, drive.model, drive.removable);
+///     }
+/// }
+/// ```
+pub struct UDisks {
+	connection: zbus::blocking::Connection,
+}
+
+impl UDisks {
+	/// Connects to the system bus.  Fails if there is no system bus, but not if UDisks2 isn't running; that is only noticed by the first query.
+	pub fn connect() -> std::result::Result<UDisks, BoxError> {
+		Ok(UDisks { connection: zbus::blocking::Connection::system()? })
+	}
+
+	/// Looks up the drive behind `device`, e.g. /dev/sda1.  Returns `None` for devices UDisks2 doesn't know about and for block devices that aren't backed by a drive, such as loop devices.
+	pub fn drive_for_device(&self, device: &str) -> std::result::Result<std::option::Option<Drive>, BoxError> {
+		if !is_device_path(device) {
+			return Ok(None);
+		}
+		let manager = self.proxy("/org/freedesktop/UDisks2/Manager", "org.freedesktop.UDisks2.Manager")?;
+		let mut spec = std::collections::HashMap::<&str, zbus::zvariant::Value>::new();
+		spec.insert("path", device.into());
+		let options = std::collections::HashMap::<&str, zbus::zvariant::Value>::new();
+		let blocks: std::vec::Vec<zbus::zvariant::OwnedObjectPath> = manager.call("ResolveDevice", &(spec, options))?;
+		let block = match blocks.into_iter().next() {
+			Some(block) => block,
+			None => return Ok(None)
+		};
+		let drive: zbus::zvariant::OwnedObjectPath = self.proxy(block, "org.freedesktop.UDisks2.Block")?.get_property("Drive")?;
+		// UDisks2 uses the root path to mean "no drive".
+		if drive.as_str() == "/" {
+			return Ok(None);
+		}
+		let drive = self.proxy(drive, "org.freedesktop.UDisks2.Drive")?;
+		Ok(Some(Drive {
+			vendor: drive.get_property("Vendor")?,
+			model: drive.get_property("Model")?,
+			serial: drive.get_property("Serial")?,
+			removable: drive.get_property("Removable")?,
+			media_removable: drive.get_property("MediaRemovable")?,
+			ejectable: drive.get_property("Ejectable")?,
+		}))
+	}
+
+	/// Pairs each mount with the drive behind it, see [UDisks::drive_for_device()].  Each device is only looked up once, however many times it is mounted.
+	pub fn enrich<'a>(&self, mounts: &'a [Mount]) -> std::result::Result<std::vec::Vec<(&'a Mount, std::option::Option<Drive>)>, BoxError> {
+		let mut drives = std::collections::HashMap::<&str, std::option::Option<Drive>>::new();
+		let mut enriched = std::vec::Vec::with_capacity(mounts.len());
+		for mount in mounts {
+			let drive = match drives.get(&mount.device[..]) {
+				Some(drive) => drive.clone(),
+				None => {
+					let drive = self.drive_for_device(&mount.device)?;
+					drives.insert(&mount.device, drive.clone());
+					drive
+				}
+			};
+			enriched.push((mount, drive));
+		}
+		Ok(enriched)
+	}
+
+	// Creates a proxy for an interface of an object owned by UDisks2.
+	fn proxy<'p, P>(&self, path: P, interface: &'static str) -> std::result::Result<zbus::blocking::Proxy<'p>, BoxError>
+	where
+		P: std::convert::TryInto<zbus::zvariant::ObjectPath<'p>>,
+		P::Error: Into<zbus::Error>,
+	{
+		Ok(zbus::blocking::Proxy::new(&self.connection, SERVICE, path, interface)?)
+	}
+}
+
+// Only devices named by a path under /dev can be block devices.  Pseudo-filesystems use arbitrary names like "tmpfs" and network filesystems use "host:/export", neither of which UDisks2 can resolve.
+fn is_device_path(device: &str) -> bool {
+	device.starts_with("/dev/")
+}
+
+impl Mount {
+	/// Looks up the drive behind this mount's device with UDisks2, see [UDisks::drive_for_device()].
+	pub fn drive(&self, udisks: &UDisks) -> std::result::Result<std::option::Option<Drive>, BoxError> {
+		udisks.drive_for_device(&self.device)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_is_device_path() {
+		assert!(is_device_path("/dev/sda1"));
+		assert!(is_device_path("/dev/mapper/vg-root"));
+		assert!(!is_device_path("tmpfs"));
+		assert!(!is_device_path("server:/export"));
+	}
+}
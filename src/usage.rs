@@ -0,0 +1,78 @@
+//! Filesystem capacity and usage via `statvfs(3)`.  Enabled by the `statvfs` feature.
+
+use super::Mount;
+
+/// Capacity and usage of a mounted filesystem as reported by `statvfs(3)`.  All values are in bytes.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Usage {
+	/// Total size of the filesystem.
+	pub total_bytes: u64,
+	/// Free space, including space reserved for the superuser.
+	pub free_bytes: u64,
+	/// Free space available to unprivileged users.
+	pub available_bytes: u64,
+}
+
+impl Usage {
+	/// Space in use, i.e. `total_bytes - free_bytes`.
+	pub fn used_bytes(&self) -> u64 {
+		self.total_bytes.saturating_sub(self.free_bytes)
+	}
+}
+
+/// Calls `statvfs(3)` on `path` and returns the capacity of the filesystem containing it.
+pub fn statvfs<P: AsRef<std::path::Path>>(path: P) -> std::result::Result<Usage, std::io::Error> {
+	use std::os::unix::ffi::OsStrExt;
+	let path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+		.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+	let mut buf = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+	// Safe because `path` is a valid NUL-terminated string and `buf` is large enough to hold the result.
+	if unsafe { libc::statvfs(path.as_ptr(), buf.as_mut_ptr()) } != 0 {
+		return Err(std::io::Error::last_os_error());
+	}
+	// Safe because statvfs() returned success and therefore initialized `buf`.
+	let buf = unsafe { buf.assume_init() };
+	#[allow(clippy::unnecessary_cast)] // field widths differ between platforms
+	let fragment_size = buf.f_frsize as u64;
+	#[allow(clippy::unnecessary_cast)]
+	Ok(Usage {
+		total_bytes: buf.f_blocks as u64 * fragment_size,
+		free_bytes: buf.f_bfree as u64 * fragment_size,
+		available_bytes: buf.f_bavail as u64 * fragment_size,
+	})
+}
+
+impl Mount {
+	/// Returns the capacity and usage of this filesystem by calling `statvfs(3)` on its mount point.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial;
+	/// for mount in nom_tutorial::mounts().unwrap() {
+	///     let mount = mount.unwrap();
+	///     if let Ok(usage) = mount.usage() {
+	///         println!("{}: {} of {} bytes used", mount.mount_point, usage.used_bytes(), usage.total_bytes);
+	///     }
+	/// }
+	/// ```
+	pub fn usage(&self) -> std::result::Result<Usage, std::io::Error> {
+		statvfs(&self.mount_point)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The root filesystem always exists and has a nonzero size.
+	#[test]
+	fn test_statvfs_root() {
+		let usage = statvfs("/").unwrap();
+		assert!(usage.total_bytes > 0);
+		assert!(usage.used_bytes() <= usage.total_bytes);
+	}
+
+	#[test]
+	fn test_statvfs_missing() {
+		assert!(statvfs("/this/path/does/not/exist").is_err());
+	}
+}
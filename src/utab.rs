@@ -0,0 +1,179 @@
+//! Parses libmount's `/run/mount/utab`, where userspace mount options are kept.
+//!
+//! The kernel doesn't know about options like `x-gvfs-show` or `user=alice`, so mount(8) records them in utab instead.  Tools such as findmnt merge them back into the kernel's view; [merge()] does the same for a mount table read by this crate.
+
+use super::{BoxError, Mount};
+
+/// Where libmount keeps the utab file.
+pub const UTAB_PATH: &str = "/run/mount/utab";
+
+/// One line of utab.  Every field is optional because libmount only writes the ones it needs.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct UtabEntry {
+	/// The kernel's mount ID, as in `/proc/self/mountinfo`.  (`ID=`)
+	pub id: std::option::Option<u64>,
+	/// The mount source, e.g. /dev/sda1.  (`SRC=`)
+	pub source: std::option::Option<std::string::String>,
+	/// The mount point.  (`TARGET=`)
+	pub target: std::option::Option<std::string::String>,
+	/// The root of the mount within its filesystem, for bind mounts and btrfs subvolumes.  (`ROOT=`)
+	pub root: std::option::Option<std::string::String>,
+	/// The source of a bind mount.  (`BINDSRC=`)
+	pub bind_source: std::option::Option<std::string::String>,
+	/// The userspace mount options, e.g. ["x-gvfs-show", "user=alice"]  (`OPTS=`)
+	pub options: std::vec::Vec<std::string::String>,
+	/// Additional libmount attributes.  (`ATTRS=`)
+	pub attributes: std::vec::Vec<std::string::String>,
+}
+
+/// Parses the contents of a utab file.  Blank lines are skipped and unknown keys are ignored.
+/// # Examples
+/// ```
+/// # use nom_tutorial::utab;
+/// let entries = utab::parse("ID=74 SRC=/dev/sdb1 TARGET=/media/alice/USB\\040Stick OPTS=uhelper=udisks2,x-gvfs-show\n").unwrap();
+/// assert_eq!(entries[0].target.as_deref(), Some("/media/alice/USB Stick"));
+/// assert_eq!(entries[0].options, vec!["uhelper=udisks2", "x-gvfs-show"]);
+/// ```
+pub fn parse(text: &str) -> std::result::Result<std::vec::Vec<UtabEntry>, BoxError> {
+	let mut entries = std::vec::Vec::new();
+	for (number, line) in text.lines().enumerate() {
+		if line.trim().is_empty() {
+			continue;
+		}
+		match parsers::parse_line(line) {
+			Ok((_, entry)) => entries.push(entry),
+			Err(e) => return Err(crate::ParseError::new(line, e).with_line_number(number + 1).into())
+		}
+	}
+	Ok(entries)
+}
+
+/// Reads and parses [UTAB_PATH].  Returns an empty list if the file doesn't exist, which is normal when no mount has userspace options.
+#[cfg(feature = "io")]
+pub fn read() -> std::result::Result<std::vec::Vec<UtabEntry>, BoxError> {
+	match std::fs::read_to_string(UTAB_PATH) {
+		Ok(text) => parse(&text),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(std::vec::Vec::new()),
+		Err(e) => Err(e.into())
+	}
+}
+
+/// Returns a copy of `mounts` with the userspace options from `utab` appended, matching what findmnt shows.
+///
+/// Entries are matched by mount point.  If several mounts share a mount point the options go to the last one, which is the one visible to users.  Options that a mount already has are not repeated.
+/// # Examples
+/// ```
+/// # use nom_tutorial::{Mount, utab};
+/// let mounts = vec![Mount {
+///     device: "/dev/sdb1".to_string(),
+///     mount_point: "/media/usb".to_string(),
+///     file_system_type: "vfat".to_string(),
+///     options: vec!["rw".to_string()]
+/// }];
+/// let entries = utab::parse("SRC=/dev/sdb1 TARGET=/media/usb OPTS=x-gvfs-show").unwrap();
+/// assert_eq!(utab::merge(&mounts, &entries)[0].options, vec!["rw", "x-gvfs-show"]);
+/// ```
+pub fn merge(mounts: &[Mount], utab: &[UtabEntry]) -> std::vec::Vec<Mount> {
+	let mut merged = mounts.to_vec();
+	for entry in utab {
+		let target = match &entry.target {
+			Some(target) => target,
+			None => continue
+		};
+		if let Some(mount) = merged.iter_mut().rev().find(|mount| &mount.mount_point == target) {
+			for option in &entry.options {
+				if !mount.options.contains(option) {
+					mount.options.push(option.clone());
+				}
+			}
+		}
+	}
+	merged
+}
+
+mod parsers {
+	use super::UtabEntry;
+
+	// Splits a comma-separated list, which utab leaves unescaped apart from whitespace.
+	fn split_list(value: std::string::String) -> std::vec::Vec<std::string::String> {
+		value.split(',').filter(|item| !item.is_empty()).map(|item| item.to_string()).collect()
+	}
+
+	// Parses a single `KEY=value` pair, unescaping the value.
+	fn key_value(i: &str) -> nom::IResult<&str, (&str, std::string::String)> {
+		nom::sequence::separated_pair(
+			nom::character::complete::alpha1,
+			nom::character::complete::char('='),
+			nom::combinator::map_parser(crate::parsers::not_whitespace, crate::parsers::transform_escaped)
+		)(i)
+	}
+
+	// Parses a line of space-separated `KEY=value` pairs.
+	pub fn parse_line(i: &str) -> nom::IResult<&str, UtabEntry> {
+		let (i, pairs) = nom::combinator::all_consuming(nom::sequence::delimited(
+			nom::character::complete::space0,
+			nom::multi::separated_list(nom::character::complete::space1, key_value),
+			nom::character::complete::space0
+		))(i)?;
+		let mut entry = UtabEntry::default();
+		for (key, value) in pairs {
+			match key {
+				"ID" => entry.id = value.parse().ok(),
+				"SRC" => entry.source = Some(value),
+				"TARGET" => entry.target = Some(value),
+				"ROOT" => entry.root = Some(value),
+				"BINDSRC" => entry.bind_source = Some(value),
+				"OPTS" => entry.options = split_list(value),
+				"ATTRS" => entry.attributes = split_list(value),
+				_ => ()
+			}
+		}
+		Ok((i, entry))
+	}
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn test_key_value() {
+			assert_eq!(key_value("TARGET=/a\\040b rest"), Ok((" rest", ("TARGET", "/a b".to_string()))));
+			assert!(key_value("=value").is_err());
+		}
+
+		#[test]
+		fn test_parse_line() {
+			let (_, entry) = parse_line("ID=29 SRC=/dev/sda1 TARGET=/mnt ROOT=/ OPTS=user=bob,noauto UNKNOWN=1 ATTRS=a").unwrap();
+			assert_eq!(entry, UtabEntry {
+				id: Some(29),
+				source: Some("/dev/sda1".to_string()),
+				target: Some("/mnt".to_string()),
+				root: Some("/".to_string()),
+				bind_source: None,
+				options: vec!["user=bob".to_string(), "noauto".to_string()],
+				attributes: vec!["a".to_string()]
+			});
+			assert!(parse_line("TARGET=/mnt garbage").is_err());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Options go to the topmost of several mounts at the same mount point and aren't duplicated.
+	#[test]
+	fn test_merge_overmounted() {
+		let mount = |device: &str| Mount {
+			device: device.to_string(),
+			mount_point: "/mnt".to_string(),
+			file_system_type: "ext4".to_string(),
+			options: vec!["rw".to_string()]
+		};
+		let entries = parse("TARGET=/mnt OPTS=rw,x-mine\nTARGET=/elsewhere OPTS=x-other\n").unwrap();
+		let merged = merge(&[mount("lower"), mount("upper")], &entries);
+		assert_eq!(merged[0].options, vec!["rw"]);
+		assert_eq!(merged[1].options, vec!["rw", "x-mine"]);
+	}
+}
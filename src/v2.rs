@@ -0,0 +1,219 @@
+//! An encapsulated version of [Mount](super::Mount) whose values are guaranteed to be well-formed.
+//!
+//! The original [Mount](super::Mount) has public fields so it is easy to construct and destructure, but that means code receiving one cannot assume anything about its contents.  [Mount] in this module keeps its fields private and can only be constructed through [Mount::new()] or `TryFrom`, which check the following invariants:
+//!
+//! - The device and filesystem type are not empty.
+//! - The mount point is an absolute path.
+//! - The filesystem type contains no whitespace.
+//! - Each option is non-empty.  It may contain a comma, e.g. in a btrfs `subvol=` path, which is escaped again when the options are joined.
+//! - No field contains a NUL byte.
+//! - No field still contains an escape sequence from `/proc/mounts` such as `\040`, i.e. escapes have been decoded.  A field that genuinely contains the text `\040` (which the kernel would have written as `\134040`) is therefore rejected too.
+
+use super::MountInfo;
+pub use super::validate::Field;
+
+/// Error returned when a value would violate one of the invariants of [Mount].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvariantError {
+	/// The offending field.
+	pub field: Field,
+	/// What is wrong with it.
+	pub reason: std::string::String,
+}
+
+impl std::fmt::Display for InvariantError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "invalid {}: {}", self.field, self.reason)
+	}
+}
+
+impl std::error::Error for InvariantError {}
+
+impl From<super::validate::ValidationError> for InvariantError {
+	fn from(error: super::validate::ValidationError) -> InvariantError {
+		InvariantError { field: error.field, reason: error.problem.to_string() }
+	}
+}
+
+// Escape sequences the parser decodes; finding one in a field means it wasn't decoded.
+const ESCAPES: [&str; 5] = ["\\040", "\\011", "\\012", "\\054", "\\134"];
+
+// Checks the invariants shared with `Mount::validate()`, which include that the field is an absolute path if `path` is true, and then that every escape sequence was decoded.
+fn check_field(field: Field, value: &str, path: bool) -> std::result::Result<(), InvariantError> {
+	super::validate::check(field, value, path)?;
+	if let Some(escape) = ESCAPES.iter().find(|escape| value.contains(*escape)) {
+		return Err(InvariantError { field, reason: format!("contains the undecoded escape sequence {}", escape) });
+	}
+	Ok(())
+}
+
+/// A mounted filesystem whose values are known to be well-formed.  See the [module documentation](self) for the invariants.
+/// # Examples
+/// ```
+/// # use nom_tutorial::v2::{Field, Mount};
+/// let mount = Mount::new("/dev/sda1", "/mnt/my disk", "ext4", vec!["rw".to_string()]).unwrap();
+/// assert_eq!(mount.mount_point(), std::path::Path::new("/mnt/my disk"));
+///
+/// let error = Mount::new("/dev/sda1", "/mnt/my\\040disk", "ext4", vec![]).unwrap_err();
+/// assert_eq!(error.field, Field::MountPoint);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mount {
+	device: std::string::String,
+	mount_point: std::string::String,
+	file_system_type: std::string::String,
+	options: std::vec::Vec<std::string::String>,
+	mountinfo: std::option::Option<MountInfo>,
+}
+
+impl Mount {
+	/// Creates a new mount, checking its invariants.
+	pub fn new<D, M, T>(device: D, mount_point: M, file_system_type: T, options: std::vec::Vec<std::string::String>) -> std::result::Result<Mount, InvariantError>
+	where
+		D: Into<std::string::String>,
+		M: Into<std::string::String>,
+		T: Into<std::string::String>,
+	{
+		let mount = Mount {
+			device: device.into(),
+			mount_point: mount_point.into(),
+			file_system_type: file_system_type.into(),
+			options,
+			mountinfo: None
+		};
+		mount.check()?;
+		Ok(mount)
+	}
+
+	// Checks every invariant.
+	fn check(&self) -> std::result::Result<(), InvariantError> {
+		check_field(Field::Device, &self.device, false)?;
+		check_field(Field::MountPoint, &self.mount_point, true)?;
+		check_field(Field::FileSystemType, &self.file_system_type, false)?;
+		if self.file_system_type.contains(char::is_whitespace) {
+			return Err(InvariantError { field: Field::FileSystemType, reason: "contains whitespace".to_string() });
+		}
+		for (index, option) in self.options.iter().enumerate() {
+			check_field(Field::Option(index), option, false)?;
+		}
+		Ok(())
+	}
+
+	/// The device from which the filesystem is mounted, e.g. /dev/sda1
+	pub fn device(&self) -> &str {
+		&self.device
+	}
+
+	/// Where in the root filesystem the device is mounted, e.g. /mnt/disk.  Always absolute.
+	pub fn mount_point(&self) -> &std::path::Path {
+		std::path::Path::new(&self.mount_point)
+	}
+
+	/// The filesystem type, e.g. ext4
+	pub fn file_system_type(&self) -> &str {
+		&self.file_system_type
+	}
+
+	/// The mount options, e.g. ["ro", "nosuid"]
+	pub fn options(&self) -> &[std::string::String] {
+		&self.options
+	}
+
+	/// Returns `true` if `option` is one of the mount options.
+	pub fn has_option(&self, option: &str) -> bool {
+		self.options.iter().any(|o| o == option)
+	}
+
+	/// Additional information if the mount was read from `/proc/self/mountinfo`.
+	pub fn mountinfo(&self) -> std::option::Option<&MountInfo> {
+		self.mountinfo.as_ref()
+	}
+}
+
+impl std::convert::TryFrom<super::Mount> for Mount {
+	type Error = InvariantError;
+
+	/// Checks the invariants of a [Mount](super::Mount), e.g. one returned by [mounts()](super::mounts).
+	fn try_from(mount: super::Mount) -> std::result::Result<Mount, InvariantError> {
+		Mount::try_from(super::MountEntry::from(mount))
+	}
+}
+
+impl std::convert::TryFrom<super::MountEntry> for Mount {
+	type Error = InvariantError;
+
+	/// Checks the invariants of a [MountEntry](super::MountEntry), keeping its [MountInfo].
+	fn try_from(entry: super::MountEntry) -> std::result::Result<Mount, InvariantError> {
+		let mount = Mount {
+			device: entry.mount.device,
+			mount_point: entry.mount.mount_point,
+			file_system_type: entry.mount.file_system_type,
+			options: entry.mount.options,
+			mountinfo: entry.mountinfo
+		};
+		mount.check()?;
+		Ok(mount)
+	}
+}
+
+impl From<Mount> for super::MountEntry {
+	fn from(mount: Mount) -> super::MountEntry {
+		super::MountEntry {
+			mount: super::Mount {
+				device: mount.device,
+				mount_point: mount.mount_point,
+				file_system_type: mount.file_system_type,
+				options: mount.options
+			},
+			mountinfo: mount.mountinfo
+		}
+	}
+}
+
+impl From<Mount> for super::Mount {
+	/// Drops the [MountInfo], see the conversion into [MountEntry](super::MountEntry) to keep it.
+	fn from(mount: Mount) -> super::Mount {
+		super::MountEntry::from(mount).mount
+	}
+}
+
+impl std::fmt::Display for Mount {
+	/// Same format as [Mount](super::Mount).
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} on {} type {} ({})", self.device, self.mount_point, self.file_system_type, super::model::join_output_options(&self.options))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn field_of(result: std::result::Result<Mount, InvariantError>) -> Field {
+		result.unwrap_err().field
+	}
+
+	#[test]
+	fn test_invariants() {
+		let options = || vec!["rw".to_string()];
+		assert!(Mount::new("/dev/sda1", "/", "ext4", options()).is_ok());
+		assert_eq!(field_of(Mount::new("", "/", "ext4", options())), Field::Device);
+		assert_eq!(field_of(Mount::new("a\0b", "/", "ext4", options())), Field::Device);
+		assert_eq!(field_of(Mount::new("/dev/sda1", "mnt", "ext4", options())), Field::MountPoint);
+		assert_eq!(field_of(Mount::new("/dev/sda1", "/a\\134b", "ext4", options())), Field::MountPoint);
+		assert_eq!(field_of(Mount::new("/dev/sda1", "/", "ext 4", options())), Field::FileSystemType);
+		assert_eq!(field_of(Mount::new("/dev/sda1", "/", "ext4", vec!["a\\054b".to_string()])), Field::Option(0));
+		assert_eq!(Mount::new("/dev/sda1", "/", "btrfs", vec!["subvol=/a,b".to_string()]).unwrap().to_string(), "/dev/sda1 on / type btrfs (subvol=/a\\054b)");
+		assert_eq!(field_of(Mount::new("/dev/sda1", "/", "ext4", vec![String::new()])), Field::Option(0));
+	}
+
+	// Everything the parser produces from the fixtures satisfies the invariants.
+	#[test]
+	fn test_try_from_parsed() {
+		use std::convert::TryFrom;
+		for line in crate::test_fixtures::ALL.iter().chain(&["/dev/sda2 /data btrfs rw,subvol=/a\\054b 0 0"]) {
+			let mount = crate::parsers::parse(line, crate::model::Format::Mounts).unwrap();
+			let checked = Mount::try_from(mount.clone()).unwrap();
+			assert_eq!(crate::Mount::from(checked), mount);
+		}
+	}
+}
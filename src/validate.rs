@@ -0,0 +1,136 @@
+//! Checks that the fields of a parsed mount make sense, see [Mount::validate()].
+//!
+//! The parsers only check the layout of a line, so a damaged or forged mount table can still produce a mount with an empty device or a relative mount point.  Unescaping can also produce characters that no real path contains, such as a NUL byte.  Validation is opt-in because the kernel's own tables never need it.  [v2::Mount](crate::v2::Mount) runs the same checks on every mount it constructs.
+
+use super::{Mount, MountEntry};
+
+/// A field of a [Mount] that failed validation, or that violates an invariant of [v2::Mount](crate::v2::Mount).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+	/// [Mount::device]
+	Device,
+	/// [Mount::mount_point]
+	MountPoint,
+	/// [Mount::file_system_type]
+	FileSystemType,
+	/// The option with this index in [Mount::options].
+	Option(usize),
+	/// [MountInfo::root](crate::MountInfo::root)
+	Root,
+}
+
+impl std::fmt::Display for Field {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Field::Device => write!(f, "device"),
+			Field::MountPoint => write!(f, "mount point"),
+			Field::FileSystemType => write!(f, "filesystem type"),
+			Field::Option(index) => write!(f, "option {}", index + 1),
+			Field::Root => write!(f, "root"),
+		}
+	}
+}
+
+/// What is wrong with a [Field].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Problem {
+	/// The field is empty.
+	Empty,
+	/// The field contains a NUL byte, which can't be part of a path or passed to the kernel.
+	Nul,
+	/// The field should be an absolute path but doesn't start with `/`.
+	NotAbsolute,
+}
+
+/// A mount failed [Mount::validate()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ValidationError {
+	/// The field that failed.
+	pub field: Field,
+	/// What is wrong with it.
+	pub problem: Problem,
+}
+
+impl std::fmt::Display for Problem {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(match self {
+			Problem::Empty => "is empty",
+			Problem::Nul => "contains a NUL byte",
+			Problem::NotAbsolute => "is not an absolute path",
+		})
+	}
+}
+
+impl std::fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} {}", self.field, self.problem)
+	}
+}
+
+impl std::error::Error for ValidationError {}
+
+impl Mount {
+	/// Checks that every decoded field is non-empty and free of NUL bytes, and that the mount point is an absolute path.  Returns an error for the first field that fails, in the order of the line.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::model::Format;
+	/// # use nom_tutorial::validate::{Field, Problem};
+	/// let mount = nom_tutorial::parsers::parse("/dev/sda1 /mnt ext4 rw 0 0", Format::Mounts).unwrap();
+	/// assert!(mount.validate().is_ok());
+	/// let mount = nom_tutorial::parsers::parse("/dev/sda1 mnt ext4 rw 0 0", Format::Mounts).unwrap();
+	/// let error = mount.validate().unwrap_err();
+	/// assert_eq!((error.field, error.problem), (Field::MountPoint, Problem::NotAbsolute));
+	/// ```
+	pub fn validate(&self) -> std::result::Result<(), ValidationError> {
+		check(Field::Device, &self.device, false)?;
+		check(Field::MountPoint, &self.mount_point, true)?;
+		check(Field::FileSystemType, &self.file_system_type, false)?;
+		for (index, option) in self.options.iter().enumerate() {
+			check(Field::Option(index), option, false)?;
+		}
+		Ok(())
+	}
+}
+
+impl MountEntry {
+	/// Like [Mount::validate()], but also checks that the root of a mount read from mountinfo is an absolute path.
+	pub fn validate(&self) -> std::result::Result<(), ValidationError> {
+		self.mount.validate()?;
+		if let Some(info) = &self.mountinfo {
+			check(Field::Root, &info.root, true)?;
+		}
+		Ok(())
+	}
+}
+
+// Checks a single field, which has to be an absolute path if `path` is true.  Shared with the invariants of `v2::Mount`.
+pub(crate) fn check(field: Field, value: &str, path: bool) -> std::result::Result<(), ValidationError> {
+	let problem = if value.is_empty() {
+		Problem::Empty
+	} else if value.contains('\0') {
+		Problem::Nul
+	} else if path && !value.starts_with('/') {
+		Problem::NotAbsolute
+	} else {
+		return Ok(());
+	};
+	Err(ValidationError { field, problem })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_validate() {
+		let mount = Mount { device: "tmpfs".to_string(), mount_point: "/tmp".to_string(), file_system_type: "tmpfs".to_string(), options: vec!["rw".to_string()] };
+		assert_eq!(mount.validate(), Ok(()));
+		let error = |mount: Mount| mount.validate().map_err(|e| (e.field, e.problem)).unwrap_err();
+		assert_eq!(error(Mount { device: std::string::String::new(), ..mount.clone() }), (Field::Device, Problem::Empty));
+		assert_eq!(error(Mount { mount_point: "tmp".to_string(), ..mount.clone() }), (Field::MountPoint, Problem::NotAbsolute));
+		assert_eq!(error(Mount { options: vec!["rw".to_string(), "".to_string()], ..mount.clone() }), (Field::Option(1), Problem::Empty));
+		let info = crate::MountInfo { root: "sub\0dir".to_string(), ..Default::default() };
+		assert_eq!(MountEntry { mount: mount.clone(), mountinfo: Some(info) }.validate().map_err(|e| (e.field, e.problem)), Err((Field::Root, Problem::Nul)));
+		assert_eq!(ValidationError { field: Field::Option(0), problem: Problem::Empty }.to_string(), "option 1 is empty");
+	}
+}
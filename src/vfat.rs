@@ -0,0 +1,123 @@
+//! Works out the permissions that vfat, exfat, and NTFS mounts present to Linux.
+//!
+//! These filesystems don't store an owner or mode for their files, so the kernel makes them up from mount options: every file and directory belongs to `uid=` and `gid=`, and its mode is 0777 minus `fmask=` for files or `dmask=` for directories, both of which default to `umask=`.  Without any of them the mode comes from the umask of the process that mounted the filesystem, which is why the kernel lists `fmask=` and `dmask=` in `/proc/mounts` even when they weren't given.
+
+use super::Mount;
+use super::options::{MountOption, OptionValueError};
+
+/// The filesystem types whose permissions come from mount options.
+pub const FAKE_PERMISSION_TYPES: &[&str] = &["exfat", "msdos", "ntfs", "ntfs3", "vfat"];
+
+/// The umask assumed when a mount has neither the mask for a file type nor `umask=`, i.e. the usual umask of root.
+pub const DEFAULT_UMASK: u32 = 0o022;
+
+/// Whether [FatPermissions::effective_mode()] is asked about a file or a directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FileType {
+	/// A regular file, masked by `fmask=`.
+	File,
+	/// A directory, masked by `dmask=`.
+	Directory,
+}
+
+/// The permission options of a vfat, exfat, or NTFS mount, see [Mount::fat_permissions()].  Options that aren't given are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FatPermissions<'a> {
+	/// The owner of every file (`uid=`), or `None` for the user that mounted the filesystem.
+	pub uid: std::option::Option<u32>,
+	/// The group of every file (`gid=`), or `None` for the group of the user that mounted the filesystem.
+	pub gid: std::option::Option<u32>,
+	/// The permission bits removed from files and directories (`umask=`), in octal.
+	pub umask: std::option::Option<u32>,
+	/// The permission bits removed from files (`fmask=`), overriding `umask=`.  A later `umask=` overrides it in turn, leaving it `None`.
+	pub fmask: std::option::Option<u32>,
+	/// The permission bits removed from directories (`dmask=`), overriding `umask=`.  A later `umask=` overrides it in turn, leaving it `None`.
+	pub dmask: std::option::Option<u32>,
+	/// The character set file names are translated to (`iocharset=`), e.g. utf8
+	pub iocharset: std::option::Option<&'a str>,
+}
+
+impl<'a> FatPermissions<'a> {
+	/// Interprets the permission options among `options`, ignoring the others.  Later options override earlier ones, so `umask=` replaces any `fmask=` or `dmask=` before it, as it does in the kernel.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::vfat::FatPermissions;
+	/// let permissions = FatPermissions::parse(&["rw", "uid=1000", "umask=0077", "iocharset=utf8"]).unwrap();
+	/// assert_eq!((permissions.uid, permissions.umask, permissions.iocharset), (Some(1000), Some(0o077), Some("utf8")));
+	/// assert!(FatPermissions::parse(&["umask=0999"]).is_err());
+	/// ```
+	pub fn parse<S: AsRef<str>>(options: &'a [S]) -> std::result::Result<FatPermissions<'a>, OptionValueError> {
+		let mut parsed = FatPermissions::default();
+		for option in options.iter().map(|option| MountOption::parse(option.as_ref())) {
+			match option.name {
+				"uid" => parsed.uid = Some(option.decimal_value()?),
+				"gid" => parsed.gid = Some(option.decimal_value()?),
+				"umask" => parsed = FatPermissions { umask: Some(option.octal_value()?), fmask: None, dmask: None, ..parsed },
+				"fmask" => parsed.fmask = Some(option.octal_value()?),
+				"dmask" => parsed.dmask = Some(option.octal_value()?),
+				"iocharset" => parsed.iocharset = Some(option.value.ok_or_else(|| OptionValueError::new(&option, None))?),
+				_ => ()
+			}
+		}
+		Ok(parsed)
+	}
+
+	/// The mode every file or directory appears to have: 0777 without the bits of `fmask=` or `dmask=`, falling back to `umask=` and then to [DEFAULT_UMASK].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::vfat::{FatPermissions, FileType};
+	/// let permissions = FatPermissions::parse(&["umask=0022", "fmask=0133"]).unwrap();
+	/// assert_eq!(permissions.effective_mode(FileType::File), 0o644);
+	/// assert_eq!(permissions.effective_mode(FileType::Directory), 0o755);
+	/// ```
+	pub fn effective_mode(&self, file_type: FileType) -> u32 {
+		let mask = match file_type {
+			FileType::File => self.fmask,
+			FileType::Directory => self.dmask
+		};
+		0o777 & !mask.or(self.umask).unwrap_or(DEFAULT_UMASK)
+	}
+}
+
+impl Mount {
+	/// The permission options of a vfat, exfat, or NTFS mount, see [FAKE_PERMISSION_TYPES].  Returns `Ok(None)` for other filesystem types, and an error if a uid, gid, or mask isn't a number.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::vfat::FileType;
+	/// let mount = Mount { file_system_type: "vfat".to_string(), options: vec!["rw".to_string(), "fmask=0077".to_string(), "dmask=0077".to_string()], ..Default::default() };
+	/// assert_eq!(mount.fat_permissions().unwrap().unwrap().effective_mode(FileType::File), 0o700);
+	/// ```
+	pub fn fat_permissions(&self) -> std::result::Result<std::option::Option<FatPermissions<'_>>, OptionValueError> {
+		if !FAKE_PERMISSION_TYPES.contains(&&self.file_system_type[..]) {
+			return Ok(None);
+		}
+		FatPermissions::parse(&self.options).map(Some)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// The specific masks win over an earlier umask, and a later umask replaces them.
+	#[test]
+	fn test_effective_mode() {
+		let mode = |options: &[&str], file_type| FatPermissions::parse(options).unwrap().effective_mode(file_type);
+		assert_eq!(mode(&[], FileType::File), 0o755);
+		assert_eq!(mode(&["dmask=0027", "umask=0077"], FileType::Directory), 0o700);
+		assert_eq!(mode(&["umask=0077", "dmask=0027"], FileType::Directory), 0o750);
+		assert_eq!(mode(&["dmask=0027", "umask=0077"], FileType::File), 0o700);
+		assert_eq!(mode(&["umask=0"], FileType::File), 0o777);
+		assert_eq!(mode(&["fmask=7777"], FileType::File), 0);
+	}
+
+	#[test]
+	fn test_fat_permissions() {
+		let mount = |file_system_type: &str, options: &[&str]| crate::test_fixtures::mount("/dev/sdb1", "/media/usb", file_system_type, options);
+		let ntfs = mount("ntfs3", &["uid=1000", "gid=1000", "iocharset=utf8"]);
+		assert_eq!(ntfs.fat_permissions(), Ok(Some(FatPermissions { uid: Some(1000), gid: Some(1000), iocharset: Some("utf8"), ..Default::default() })));
+		assert_eq!(mount("ext4", &["uid=1000"]).fat_permissions(), Ok(None));
+		assert!(mount("exfat", &["uid=alice"]).fat_permissions().is_err());
+	}
+}
@@ -0,0 +1,113 @@
+//! Maps `9p` and `virtiofs` mounts in a virtual machine back to the host directories they share.
+//!
+//! A hypervisor such as QEMU exports a host directory to the guest under a tag, e.g. `-virtfs local,path=/srv/data,mount_tag=data` or a virtiofsd socket with `tag=data`, and the guest mounts it with `mount -t 9p -o trans=virtio data /mnt` or `mount -t virtiofs data /mnt`.  The tag is the only link between the two sides, and it shows up as the device of the mount.  For 9p the `trans=`, `msize=`, and `version=` options decide how the share is reached and how fast it is; `msize=` in particular limits the size of every request.
+
+use super::Mount;
+use super::options::{MountOption, OptionValueError};
+
+/// How a 9p share is reached, from the `trans=` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transport {
+	/// A virtio channel from the hypervisor (`trans=virtio`), where the device is the share tag.
+	Virtio,
+	/// A TCP connection (`trans=tcp`), where the device is the address of the server.
+	Tcp,
+	/// A Unix domain socket (`trans=unix`), where the device is the path of the socket.
+	Unix,
+	/// File descriptors passed with `rfdno=` and `wfdno=` (`trans=fd`).
+	Fd,
+	/// A Xen grant table channel (`trans=xen`).
+	Xen,
+	/// An RDMA connection (`trans=rdma`).
+	Rdma,
+	/// A USB gadget channel (`trans=usbg`).
+	Usbg,
+}
+
+/// The dialect of the 9p protocol, from the `version=` option.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Version {
+	/// The original protocol (`version=9p2000`).
+	P9p2000,
+	/// The Unix extensions (`version=9p2000.u`).
+	P9p2000U,
+	/// The Linux extensions (`version=9p2000.L`), the kernel's default.
+	P9p2000L,
+}
+
+/// A directory shared with a virtual machine by its host, see [Mount::virt_share()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VirtShare<'a> {
+	/// The device of the mount: the tag the host exported the directory under for virtio and virtiofs, or the server address or socket path for the other transports.
+	pub tag: &'a str,
+	/// The transport of a 9p mount (`trans=`), or `None` if not given, in which case the kernel tries virtio first.  Always virtio for virtiofs.
+	pub transport: std::option::Option<Transport>,
+	/// The largest message in bytes (`msize=`), for 9p only.
+	pub msize: std::option::Option<u32>,
+	/// The protocol dialect (`version=`, or `noextend` for the original protocol), for 9p only.
+	pub version: std::option::Option<Version>,
+}
+
+impl Mount {
+	/// The share behind a `9p` or `virtiofs` mount.  Returns `Ok(None)` for other filesystem types, and an error if `trans=`, `msize=`, or `version=` has a value the kernel doesn't know.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::virtfs::{Transport, Version};
+	/// let mount = Mount { device: "data".to_string(), mount_point: "/mnt/data".to_string(), file_system_type: "9p".to_string(), options: vec!["rw".to_string(), "trans=virtio".to_string(), "version=9p2000.L".to_string(), "msize=512000".to_string()], ..Default::default() };
+	/// let share = mount.virt_share().unwrap().unwrap();
+	/// assert_eq!((share.tag, share.transport, share.msize, share.version), ("data", Some(Transport::Virtio), Some(512000), Some(Version::P9p2000L)));
+	/// ```
+	pub fn virt_share(&self) -> std::result::Result<std::option::Option<VirtShare<'_>>, OptionValueError> {
+		let mut share = VirtShare { tag: &self.device, transport: None, msize: None, version: None };
+		match &self.file_system_type[..] {
+			"virtiofs" => return Ok(Some(VirtShare { transport: Some(Transport::Virtio), ..share })),
+			"9p" => (),
+			_ => return Ok(None)
+		}
+		for option in self.mount_options() {
+			let invalid = |option: &MountOption<'_>| OptionValueError::new(option, None);
+			match option.name {
+				"trans" => share.transport = Some(match option.value {
+					Some("virtio") => Transport::Virtio,
+					Some("tcp") => Transport::Tcp,
+					Some("unix") => Transport::Unix,
+					Some("fd") => Transport::Fd,
+					Some("xen") => Transport::Xen,
+					Some("rdma") => Transport::Rdma,
+					Some("usbg") => Transport::Usbg,
+					_ => return Err(invalid(&option))
+				}),
+				"msize" => share.msize = Some(option.decimal_value()?),
+				"version" => share.version = Some(match option.value {
+					Some("9p2000") => Version::P9p2000,
+					Some("9p2000.u") => Version::P9p2000U,
+					Some("9p2000.L") => Version::P9p2000L,
+					_ => return Err(invalid(&option))
+				}),
+				// The kernel treats noextend as version=9p2000, which has no extensions to turn off.
+				"noextend" => share.version = Some(Version::P9p2000),
+				_ => ()
+			}
+		}
+		Ok(Some(share))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_virt_share() {
+		let mount = |file_system_type: &str, options: &[&str]| crate::test_fixtures::mount("hostshare", "/mnt/share", file_system_type, options);
+		assert_eq!(mount("virtiofs", &["rw", "relatime"]).virt_share(), Ok(Some(VirtShare { tag: "hostshare", transport: Some(Transport::Virtio), msize: None, version: None })));
+		assert_eq!(mount("9p", &["rw", "trans=tcp"]).virt_share().unwrap().unwrap().transport, Some(Transport::Tcp));
+		assert_eq!(mount("9p", &["rw"]).virt_share().unwrap().unwrap().transport, None);
+		assert_eq!(mount("9p", &["version=9p2000.L", "noextend"]).virt_share().unwrap().unwrap().version, Some(Version::P9p2000));
+		assert_eq!(mount("ext4", &["rw"]).virt_share(), Ok(None));
+		assert!(mount("9p", &["trans=carrier-pigeon"]).virt_share().is_err());
+		assert!(mount("9p", &["version=9p2000.l"]).virt_share().is_err());
+		assert!(mount("9p", &["msize=big"]).virt_share().is_err());
+	}
+}
@@ -0,0 +1,354 @@
+//! Detects changes to the mount table.
+//!
+//! The [Watcher] re-reads the mount table on a fixed interval in a background thread and reports the differences between consecutive reads as [WatchEvent]s.  The differences themselves are computed by [diff()], which can also be used directly to compare two saved mount tables, and [classify()] infers what kind of [Action] caused each of them.
+//!
+//! [diff()] and [classify()] are always available, the [Watcher] needs the `watch` feature.
+
+use super::{BoxError, MountEntry};
+
+/// A single difference between two mount tables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)] // events are infrequent, boxing would only make them harder to match on
+pub enum MountEvent {
+	/// A filesystem was mounted.
+	Added(MountEntry),
+	/// A filesystem was unmounted.
+	Removed(MountEntry),
+	/// A mount with the same device and mount point changed, e.g. it was remounted with different options.
+	Changed {
+		/// The mount as it was before the change.
+		old: MountEntry,
+		/// The mount as it is after the change.
+		new: MountEntry
+	},
+}
+
+/// Computes the differences between two mount tables.
+///
+/// Mounts are matched up by their device and mount point.  A matched pair whose filesystem type or options differ is reported as [MountEvent::Changed].  If the same device is mounted at the same mount point more than once the duplicates are matched in order.  Events for mounts present in `new` are returned first, in the order they appear in `new`, followed by removals in the order they appear in `old`.
+/// # Examples
+/// ```
+/// # use nom_tutorial::{Mount, MountEntry};
+/// # use nom_tutorial::watch::{diff, MountEvent};
+/// let sda1 = MountEntry::from(Mount {
+///     device: "/dev/sda1".to_string(),
+///     mount_point: "/mnt/disk".to_string(),
+///     file_system_type: "ext4".to_string(),
+///     options: vec!["rw".to_string()]
+/// });
+/// assert_eq!(diff(&[], &[sda1.clone()]), vec![MountEvent::Added(sda1)]);
+/// ```
+pub fn diff(old: &[MountEntry], new: &[MountEntry]) -> std::vec::Vec<MountEvent> {
+	let mut unmatched = std::collections::HashMap::<(&str, &str), std::collections::VecDeque<&MountEntry>>::new();
+	for mount in old {
+		unmatched.entry((&mount.device[..], &mount.mount_point[..])).or_default().push_back(mount);
+	}
+	let mut events = std::vec::Vec::new();
+	for mount in new {
+		match unmatched.get_mut(&(&mount.device[..], &mount.mount_point[..])).and_then(|candidates| candidates.pop_front()) {
+			Some(previous) => if previous != mount {
+				events.push(MountEvent::Changed { old: previous.clone(), new: mount.clone() });
+			},
+			None => events.push(MountEvent::Added(mount.clone()))
+		}
+	}
+	// Whatever was not matched has been removed.  Walk `old` again rather than the map so that removals come out in a deterministic order.
+	for mount in old {
+		if let Some(candidates) = unmatched.get_mut(&(&mount.device[..], &mount.mount_point[..])) {
+			if candidates.front().is_some_and(|candidate| std::ptr::eq(*candidate, mount)) {
+				candidates.pop_front();
+				events.push(MountEvent::Removed(mount.clone()));
+			}
+		}
+	}
+	events
+}
+
+/// What happened to a mount, named as in `findmnt --poll`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+	/// A filesystem was mounted.
+	Mount,
+	/// A filesystem was unmounted.
+	Umount,
+	/// A mount's options or filesystem type changed.
+	Remount,
+	/// A mount was moved to another mount point with `mount --move`.
+	Move,
+}
+
+impl Action {
+	/// Every action, in the order above.
+	pub const ALL: [Action; 4] = [Action::Mount, Action::Umount, Action::Remount, Action::Move];
+
+	/// The lowercase name of the action, e.g. umount.
+	pub fn name(self) -> &'static str {
+		match self {
+			Action::Mount => "mount",
+			Action::Umount => "umount",
+			Action::Remount => "remount",
+			Action::Move => "move",
+		}
+	}
+}
+
+impl std::fmt::Display for Action {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name())
+	}
+}
+
+impl std::str::FromStr for Action {
+	type Err = BoxError;
+
+	/// Parses the name returned by [Action::name()].
+	fn from_str(s: &str) -> std::result::Result<Action, BoxError> {
+		Action::ALL.iter().copied().find(|action| action.name() == s).ok_or_else(|| format!("unknown action: {}", s).into())
+	}
+}
+
+// True if `added` is `removed` at a new mount point.  The kernel keeps a mount's ID when it is moved, so this can only be told for mounts read from mountinfo.  IDs are reused after an unmount, so the device and the root of the mount have to match as well.
+fn is_move(removed: &MountEntry, added: &MountEntry) -> bool {
+	match (&removed.mountinfo, &added.mountinfo) {
+		(Some(old), Some(new)) => old.mount_id == new.mount_id
+			&& (old.major, old.minor) == (new.major, new.minor)
+			&& old.root == new.root
+			&& removed.device == added.device,
+		_ => false
+	}
+}
+
+/// Infers the [Action] behind each event returned by one call to [diff()].
+///
+/// [MountEvent::Added] is a mount, [MountEvent::Removed] an unmount, and [MountEvent::Changed] a remount.  A removal and an addition with the same mount ID, device, and root are a move, which is returned as a single [MountEvent::Changed] whose old and new mount differ in their mount point.  Moves can only be detected in mount tables read from `/proc/self/mountinfo`, see [Watcher::mountinfo()]; otherwise they appear as an unmount and a mount.
+/// # Examples
+/// ```
+/// # use nom_tutorial::model::Format;
+/// # use nom_tutorial::snapshot::MountSnapshot;
+/// # use nom_tutorial::watch::{classify, diff, Action};
+/// let before = MountSnapshot::parse("40 1 0:50 / /mnt/a rw - tmpfs tmpfs rw\n", Format::MountInfo).unwrap();
+/// let after = MountSnapshot::parse("40 1 0:50 / /mnt/b rw - tmpfs tmpfs rw\n", Format::MountInfo).unwrap();
+/// let actions = classify(diff(before.mounts(), after.mounts()));
+/// assert_eq!(actions.len(), 1);
+/// assert_eq!(actions[0].0, Action::Move);
+/// ```
+pub fn classify(events: std::vec::Vec<MountEvent>) -> std::vec::Vec<(Action, MountEvent)> {
+	let mut removed: std::vec::Vec<MountEntry> = events.iter().filter_map(|event| match event {
+		MountEvent::Removed(mount) => Some(mount.clone()),
+		_ => None
+	}).collect();
+	let mut actions = std::vec::Vec::with_capacity(events.len());
+	for event in events {
+		match event {
+			MountEvent::Added(mount) => match removed.iter().position(|old| is_move(old, &mount)) {
+				Some(index) => actions.push((Action::Move, MountEvent::Changed { old: removed.remove(index), new: mount })),
+				None => actions.push((Action::Mount, MountEvent::Added(mount)))
+			},
+			event @ MountEvent::Changed { .. } => actions.push((Action::Remount, event)),
+			MountEvent::Removed(_) => ()
+		}
+	}
+	actions.extend(removed.into_iter().map(|mount| (Action::Umount, MountEvent::Removed(mount))));
+	actions
+}
+
+/// A change reported by a [Watcher], with when it was observed.  Since the mount table is polled this is up to one polling interval after the change actually happened.  All the changes found by the same read share the same timestamps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchEvent {
+	/// What kind of change this is.
+	pub action: Action,
+	/// The change itself.  For [Action::Move] this is a [MountEvent::Changed] from the old to the new mount point.
+	pub event: MountEvent,
+	/// When the change was observed, for measuring intervals between events.
+	pub instant: std::time::Instant,
+	/// When the change was observed, for logging and for correlating with other sources.
+	pub time: std::time::SystemTime,
+}
+
+// Reads the entire mount table into a vector.
+#[cfg(feature = "watch")]
+pub(crate) fn read_mounts() -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> {
+	super::mounts()?.entries().collect()
+}
+
+/// Boxed function used by a [Watcher] to read the current mount table.
+#[cfg(feature = "watch")]
+pub type ReadTable = std::boxed::Box<dyn FnMut() -> std::result::Result<std::vec::Vec<MountEntry>, BoxError> + Send>;
+
+/// Watches the mount table for changes.
+///
+/// A background thread re-reads the mount table every `interval` and sends a [WatchEvent] for every difference from the previous read.  Errors encountered while reading are sent as well, after which watching continues.  The thread exits when the `Watcher` is dropped.
+/// # Examples
+/// ```no_run
+/// # use nom_tutorial::watch::Watcher;
+/// let watcher = Watcher::new(std::time::Duration::from_secs(1)).unwrap();
+/// for event in watcher {
+///     let event = event.unwrap();
+///     println!("{:?} {}: {:?}", event.time, event.action, event.event);
+/// }
+/// ```
+#[cfg(feature = "watch")]
+pub struct Watcher {
+	receiver: std::sync::mpsc::Receiver<std::result::Result<WatchEvent, BoxError>>,
+	stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(feature = "watch")]
+impl Watcher {
+	/// Starts watching `/proc/mounts`, polling every `interval`.  Fails if the initial read of the mount table fails.
+	pub fn new(interval: std::time::Duration) -> std::result::Result<Watcher, BoxError> {
+		Watcher::with_reader(interval, std::boxed::Box::new(read_mounts))
+	}
+
+	/// Starts watching `/proc/self/mountinfo`, polling every `interval`.  Unlike [Watcher::new()] this can detect moved mounts, see [classify()], but also reports changes to propagation flags as remounts.
+	pub fn mountinfo(interval: std::time::Duration) -> std::result::Result<Watcher, BoxError> {
+		Watcher::with_reader(interval, std::boxed::Box::new(|| super::mountinfo()?.entries().collect()))
+	}
+
+	/// Starts watching the mount table returned by `read`, polling every `interval`.  Useful for watching a mount table other than `/proc/mounts` or for injecting fabricated tables in tests.
+	pub fn with_reader(interval: std::time::Duration, mut read: ReadTable) -> std::result::Result<Watcher, BoxError> {
+		let mut previous = read()?;
+		let (sender, receiver) = std::sync::mpsc::channel();
+		let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+		let thread_stop = stop.clone();
+		std::thread::spawn(move || {
+			#[cfg(feature = "tracing")]
+			let _span = tracing::debug_span!("watch", ?interval).entered();
+			while !thread_stop.load(std::sync::atomic::Ordering::Relaxed) {
+				std::thread::sleep(interval);
+				let events = match read() {
+					Ok(current) => {
+						let (instant, time) = (std::time::Instant::now(), std::time::SystemTime::now());
+						let events = classify(diff(&previous, &current));
+						previous = current;
+						events.into_iter().map(|(action, event)| Ok(WatchEvent { action, event, instant, time })).collect()
+					},
+					Err(e) => vec![Err(e)]
+				};
+				for event in events {
+					#[cfg(feature = "tracing")]
+					match &event {
+						Ok(event) => tracing::debug!(?event, "mount table changed"),
+						Err(e) => tracing::debug!(error = %e, "failed to read mount table"),
+					}
+					// The receiver has hung up, nobody is listening anymore.
+					if sender.send(event).is_err() {
+						return;
+					}
+				}
+			}
+		});
+		Ok(Watcher { receiver, stop })
+	}
+
+	/// Blocks until the next event is available.  Returns `None` if the watcher thread has exited.
+	pub fn recv(&self) -> std::option::Option<std::result::Result<WatchEvent, BoxError>> {
+		self.receiver.recv().ok()
+	}
+
+	/// Returns the next event if one is available without blocking.
+	pub fn try_recv(&self) -> std::option::Option<std::result::Result<WatchEvent, BoxError>> {
+		self.receiver.try_recv().ok()
+	}
+
+	/// Waits up to `timeout` for the next event.  Returns `Err` if the timeout elapsed or the watcher thread has exited.
+	pub fn recv_timeout(&self, timeout: std::time::Duration) -> std::result::Result<std::result::Result<WatchEvent, BoxError>, std::sync::mpsc::RecvTimeoutError> {
+		self.receiver.recv_timeout(timeout)
+	}
+}
+
+#[cfg(feature = "watch")]
+impl std::iter::Iterator for Watcher {
+	type Item = std::result::Result<WatchEvent, BoxError>;
+
+	/// Blocks until the next event, see [Watcher::recv()].
+	fn next(&mut self) -> std::option::Option<Self::Item> {
+		self.recv()
+	}
+}
+
+// Once the background thread has exited the channel stays disconnected, so `recv()` keeps returning `None`.
+#[cfg(feature = "watch")]
+impl std::iter::FusedIterator for Watcher {}
+
+#[cfg(feature = "watch")]
+impl Drop for Watcher {
+	fn drop(&mut self) {
+		self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_fixtures::mount;
+
+	fn entry(device: &str, mount_point: &str, options: &[&str]) -> MountEntry {
+		mount(device, mount_point, "ext4", options).into()
+	}
+
+	#[test]
+	fn test_diff() {
+		let a = entry("/dev/sda1", "/", &["rw"]);
+		let b = entry("/dev/sdb1", "/data", &["rw"]);
+		let b_ro = entry("/dev/sdb1", "/data", &["ro"]);
+		let c = entry("/dev/sdc1", "/backup", &["rw"]);
+		assert_eq!(diff(&[a.clone(), b.clone()], &[a.clone(), b.clone()]), vec![]);
+		assert_eq!(diff(&[a.clone(), b.clone()], &[a.clone(), b_ro.clone(), c.clone()]), vec![
+			MountEvent::Changed { old: b, new: b_ro },
+			MountEvent::Added(c.clone())
+		]);
+		assert_eq!(diff(&[c.clone(), a.clone()], &[a]), vec![MountEvent::Removed(c)]);
+	}
+
+	// Stacked duplicate mounts are matched one for one.
+	#[test]
+	fn test_diff_duplicates() {
+		let a = entry("tmpfs", "/tmp", &["rw"]);
+		let pair = [a.clone(), a.clone()];
+		assert_eq!(diff(&pair, std::slice::from_ref(&a)), vec![MountEvent::Removed(a.clone())]);
+		assert_eq!(diff(std::slice::from_ref(&a), &pair), vec![MountEvent::Added(a)]);
+	}
+
+	#[cfg(feature = "watch")]
+	#[test]
+	fn test_watcher() {
+		let tables = std::sync::Arc::new(std::sync::Mutex::new(vec![
+			vec![entry("/dev/sda1", "/", &["rw"])],
+			vec![],
+		]));
+		let reader_tables = tables.clone();
+		let watcher = Watcher::with_reader(std::time::Duration::from_millis(1), std::boxed::Box::new(move || {
+			let mut tables = reader_tables.lock().unwrap();
+			Ok(if tables.len() > 1 { tables.remove(0) } else { tables[0].clone() })
+		})).unwrap();
+		let event = watcher.recv().unwrap().unwrap();
+		assert_eq!(event.action, Action::Umount);
+		assert_eq!(event.event, MountEvent::Removed(entry("/dev/sda1", "/", &["rw"])));
+	}
+
+	// A move is only recognized by its mount ID; everything else keeps its plain meaning.
+	#[test]
+	fn test_classify() {
+		let with_id = |mount_point: &str, mount_id: u32| MountEntry {
+			mountinfo: Some(crate::MountInfo { mount_id, ..Default::default() }),
+			..entry("tmpfs", mount_point, &["rw"])
+		};
+		let (a, b, c) = (with_id("/a", 1), with_id("/b", 1), with_id("/c", 2));
+		assert_eq!(classify(diff(&[a.clone(), c.clone()], std::slice::from_ref(&b))), vec![
+			(Action::Move, MountEvent::Changed { old: a.clone(), new: b }),
+			(Action::Umount, MountEvent::Removed(c))
+		]);
+		// An unmount followed by a mount of another filesystem that got the freed ID isn't a move.
+		let reused = MountEntry { mountinfo: Some(crate::MountInfo { mount_id: 1, minor: 9, ..Default::default() }), ..entry("/dev/sdb1", "/b", &["rw"]) };
+		assert_eq!(classify(diff(std::slice::from_ref(&a), std::slice::from_ref(&reused))), vec![
+			(Action::Mount, MountEvent::Added(reused)),
+			(Action::Umount, MountEvent::Removed(a))
+		]);
+		let (d, e) = (entry("tmpfs", "/d", &["rw"]), entry("tmpfs", "/e", &["rw"]));
+		assert_eq!(classify(diff(std::slice::from_ref(&d), std::slice::from_ref(&e))), vec![
+			(Action::Mount, MountEvent::Added(e)),
+			(Action::Umount, MountEvent::Removed(d))
+		]);
+	}
+}
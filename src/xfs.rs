@@ -0,0 +1,145 @@
+//! Interprets the log and quota options of XFS, see `xfs(5)`.
+//!
+//! Like those of [ext4](crate::ext4), these options are checked by the kernel at mount time, so an out-of-range `logbufs=` or a misspelt quota option in `fstab` only shows up at the next boot.  [fstab_verify](crate::fstab_verify) checks them with [XfsOptions::parse()].
+
+use super::Mount;
+use super::options::{MountOption, OptionValueError, SizeValue};
+
+/// Whether a kind of quota is enforced or only accounted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum QuotaMode {
+	/// Usage is tracked and limits are enforced, e.g. `usrquota`.
+	Enforced,
+	/// Usage is tracked but limits aren't enforced, e.g. `uqnoenforce`.
+	Accounting,
+}
+
+// The kinds of quota that XFS tracks separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum QuotaKind {
+	User,
+	Group,
+	Project,
+}
+
+// The quota options, with the kind of quota and the mode each one turns on.
+pub(crate) const QUOTA_OPTIONS: &[(&str, QuotaKind, QuotaMode)] = &[
+	("gqnoenforce", QuotaKind::Group, QuotaMode::Accounting),
+	("gquota", QuotaKind::Group, QuotaMode::Enforced),
+	("grpquota", QuotaKind::Group, QuotaMode::Enforced),
+	("pqnoenforce", QuotaKind::Project, QuotaMode::Accounting),
+	("pquota", QuotaKind::Project, QuotaMode::Enforced),
+	("prjquota", QuotaKind::Project, QuotaMode::Enforced),
+	("qnoenforce", QuotaKind::User, QuotaMode::Accounting),
+	("quota", QuotaKind::User, QuotaMode::Enforced),
+	("uqnoenforce", QuotaKind::User, QuotaMode::Accounting),
+	("uquota", QuotaKind::User, QuotaMode::Enforced),
+	("usrquota", QuotaKind::User, QuotaMode::Enforced),
+];
+
+// The options other than the quota options, from `xfs(5)`.
+pub(crate) const OTHER_OPTIONS: &[&str] = &[
+	"allocsize", "attr2", "bsdgroups", "dax", "discard", "filestreams", "grpid", "ikeep", "inode32", "inode64", "largeio", "logbsize", "logbufs", "logdev",
+	"noalign", "noattr2", "nodiscard", "nogrpid", "noikeep", "nolargeio", "noquota", "norecovery", "nouuid", "rtdev", "sunit", "swalloc", "swidth",
+	"sysvgroups", "wsync",
+];
+
+// True if `name` is the name of an XFS option, for [fstab_verify](crate::fstab_verify).
+pub(crate) fn is_option(name: &str) -> bool {
+	OTHER_OPTIONS.contains(&name) || QUOTA_OPTIONS.iter().any(|(option, ..)| *option == name)
+}
+
+/// The log and quota options of an XFS mount, see [Mount::xfs_options()].  Options that aren't given are `None`, leaving the choice to the kernel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct XfsOptions {
+	/// The number of in-memory log buffers (`logbufs=`), from 2 to 8.
+	pub logbufs: std::option::Option<u32>,
+	/// The size of each in-memory log buffer in bytes (`logbsize=`), a power of two from 16 KiB to 256 KiB.
+	pub logbsize: std::option::Option<u64>,
+	/// User quotas (`usrquota`, `uquota`, `quota`, `uqnoenforce`, or `qnoenforce`).
+	pub user_quota: std::option::Option<QuotaMode>,
+	/// Group quotas (`grpquota`, `gquota`, or `gqnoenforce`).
+	pub group_quota: std::option::Option<QuotaMode>,
+	/// Project quotas (`prjquota`, `pquota`, or `pqnoenforce`).
+	pub project_quota: std::option::Option<QuotaMode>,
+	/// True if `noquota` turned off all quotas given before it.
+	pub noquota: bool,
+}
+
+impl XfsOptions {
+	/// Interprets the XFS options among `options`, ignoring the others.  Later options override earlier ones.  Returns an error for a value that the kernel would refuse.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::xfs::XfsOptions;
+	/// assert_eq!(XfsOptions::parse(&["logbufs=8", "logbsize=256k"]).unwrap().logbsize, Some(256 << 10));
+	/// assert!(XfsOptions::parse(&["logbufs=16"]).is_err());
+	/// ```
+	pub fn parse<S: AsRef<str>>(options: &[S]) -> std::result::Result<XfsOptions, OptionValueError> {
+		let mut parsed = XfsOptions::default();
+		for option in options.iter().map(|option| MountOption::parse(option.as_ref())) {
+			match option.name {
+				"logbufs" => match option.decimal_value()? {
+					logbufs @ 2..=8 => parsed.logbufs = Some(logbufs),
+					_ => return Err(OptionValueError::new(&option, None))
+				},
+				"logbsize" => match option.size_value()? {
+					SizeValue::Bytes(logbsize) if logbsize.is_power_of_two() && (16 << 10..=256 << 10).contains(&logbsize) => parsed.logbsize = Some(logbsize),
+					_ => return Err(OptionValueError::new(&option, None))
+				},
+				"noquota" => parsed = XfsOptions { user_quota: None, group_quota: None, project_quota: None, noquota: true, ..parsed },
+				name => if let Some((_, kind, mode)) = QUOTA_OPTIONS.iter().find(|(option, ..)| *option == name) {
+					*match kind {
+						QuotaKind::User => &mut parsed.user_quota,
+						QuotaKind::Group => &mut parsed.group_quota,
+						QuotaKind::Project => &mut parsed.project_quota
+					} = Some(*mode);
+				}
+			}
+		}
+		Ok(parsed)
+	}
+
+	/// True if any kind of quota is tracked.
+	pub fn has_quota(&self) -> bool {
+		self.user_quota.is_some() || self.group_quota.is_some() || self.project_quota.is_some()
+	}
+}
+
+impl Mount {
+	/// The log and quota options of an XFS mount.  Returns `Ok(None)` for other filesystem types, and an error if an option has a value the kernel would refuse.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// # use nom_tutorial::xfs::QuotaMode;
+	/// let mount = Mount { file_system_type: "xfs".to_string(), options: vec!["rw".to_string(), "logbufs=8".to_string(), "usrquota".to_string()], ..Default::default() };
+	/// let options = mount.xfs_options().unwrap().unwrap();
+	/// assert_eq!((options.logbufs, options.user_quota), (Some(8), Some(QuotaMode::Enforced)));
+	/// ```
+	pub fn xfs_options(&self) -> std::result::Result<std::option::Option<XfsOptions>, OptionValueError> {
+		if self.file_system_type != "xfs" {
+			return Ok(None);
+		}
+		XfsOptions::parse(&self.options).map(Some)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// noquota only clears the quotas given before it.
+	#[test]
+	fn test_parse() {
+		assert_eq!(XfsOptions::parse(&["usrquota", "gqnoenforce", "noquota", "pquota"]), Ok(XfsOptions {
+			project_quota: Some(QuotaMode::Enforced),
+			noquota: true,
+			..Default::default()
+		}));
+		assert!(!XfsOptions::parse(&["noquota"]).unwrap().has_quota());
+		assert_eq!(XfsOptions::parse(&["logbsize=32768"]).unwrap().logbsize, Some(32 << 10));
+		assert!(XfsOptions::parse(&["logbsize=48k"]).is_err());
+		assert!(XfsOptions::parse(&["logbsize=512k"]).is_err());
+		assert!(XfsOptions::parse(&["logbufs=1"]).is_err());
+		assert!(XfsOptions::parse(&["logbufs=many"]).is_err());
+	}
+}
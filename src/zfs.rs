@@ -0,0 +1,101 @@
+//! Splits the device of a ZFS mount into the pool and the path of the dataset.
+//!
+//! ZFS mounts show up in the mount table with the name of a dataset as their device, e.g. `rpool/home/alice`, rather than a block device.  The first component names the pool, and a snapshot mounted under `.zfs/snapshot` has its name appended after an `@`, e.g. `rpool/home@daily`.  A pool can span many disks, none of which appear in the mount table, so [Mount::device_number()](crate::Mount::device_number) never looks for a device node by that name.
+
+use super::Mount;
+
+/// The pool and dataset of a ZFS mount, see [Mount::zfs_dataset()].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ZfsDataset<'a> {
+	/// The name of the pool, e.g. rpool
+	pub pool: &'a str,
+	/// The components of the dataset below the pool, e.g. ["home", "alice"], or empty for the root dataset of the pool.
+	pub path: std::vec::Vec<&'a str>,
+	/// The name of the snapshot, e.g. daily, if the mount is a snapshot.
+	pub snapshot: std::option::Option<&'a str>,
+}
+
+impl<'a> ZfsDataset<'a> {
+	/// Splits a dataset name of the form `pool/path/to/dataset`, optionally followed by `@snapshot`.  Returns `None` if `device` can't be a dataset name, e.g. because it is a path or has an empty component.
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::zfs::ZfsDataset;
+	/// assert_eq!(ZfsDataset::parse("rpool/home@daily"), Some(ZfsDataset { pool: "rpool", path: vec!["home"], snapshot: Some("daily") }));
+	/// assert_eq!(ZfsDataset::parse("/dev/sda1"), None);
+	/// ```
+	pub fn parse(device: &'a str) -> std::option::Option<ZfsDataset<'a>> {
+		let (dataset, snapshot) = match device.split_once('@') {
+			Some((_, "")) => return None,
+			Some((dataset, snapshot)) => (dataset, Some(snapshot)),
+			None => (device, None)
+		};
+		let mut components = dataset.split('/');
+		let pool = components.next().filter(|pool| pool.starts_with(|c: char| c.is_ascii_alphabetic()))?;
+		let path: std::vec::Vec<&str> = components.collect();
+		if path.iter().any(|component| component.is_empty()) {
+			return None;
+		}
+		Some(ZfsDataset { pool, path, snapshot })
+	}
+}
+
+impl std::fmt::Display for ZfsDataset<'_> {
+	/// Formats the dataset the way it appears in the mount table.
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.pool)?;
+		for component in &self.path {
+			write!(f, "/{}", component)?;
+		}
+		if let Some(snapshot) = self.snapshot {
+			write!(f, "@{}", snapshot)?;
+		}
+		Ok(())
+	}
+}
+
+impl Mount {
+	/// True if this is a ZFS mount, whose device is a dataset name rather than a block device.
+	pub fn is_zfs(&self) -> bool {
+		self.file_system_type == "zfs"
+	}
+
+	/// The pool and dataset of a `zfs` mount.  Returns `None` for other filesystem types, or if the device isn't a dataset name, see [ZfsDataset::parse()].
+	/// # Examples
+	/// ```
+	/// # use nom_tutorial::Mount;
+	/// let mount = Mount { device: "rpool/home/alice".to_string(), mount_point: "/home/alice".to_string(), file_system_type: "zfs".to_string(), ..Default::default() };
+	/// let dataset = mount.zfs_dataset().unwrap();
+	/// assert_eq!((dataset.pool, &dataset.path[..]), ("rpool", &["home", "alice"][..]));
+	/// ```
+	pub fn zfs_dataset(&self) -> std::option::Option<ZfsDataset<'_>> {
+		if !self.is_zfs() {
+			return None;
+		}
+		ZfsDataset::parse(&self.device)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse() {
+		assert_eq!(ZfsDataset::parse("tank"), Some(ZfsDataset { pool: "tank", path: vec![], snapshot: None }));
+		assert_eq!(ZfsDataset::parse("tank/a/b").unwrap().to_string(), "tank/a/b");
+		assert_eq!(ZfsDataset::parse("tank/a@snap").unwrap().to_string(), "tank/a@snap");
+		assert_eq!(ZfsDataset::parse("tank//a"), None);
+		assert_eq!(ZfsDataset::parse("tank/a@"), None);
+		assert_eq!(ZfsDataset::parse(""), None);
+	}
+
+	// The device number of a ZFS mount is the anonymous one from mountinfo, so the enrichment layers skip it.
+	#[cfg(feature = "io")]
+	#[test]
+	fn test_device_number() {
+		let info = crate::MountInfo { major: 0, minor: 52, ..Default::default() };
+		let mount = crate::test_fixtures::mount("rpool/ROOT", "/", "zfs", &[]);
+		assert_eq!(crate::MountEntry { mount: mount.clone(), mountinfo: Some(info) }.device_number(), Some((0, 52)));
+		assert_eq!(mount.device_number(), None);
+	}
+}